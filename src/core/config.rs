@@ -1,41 +1,780 @@
 use super::error::{Error, Result};
-use std::path::PathBuf;
+use crate::indexing::discovery::DEFAULT_IGNORE_DIRS;
+use crate::indexing::parser::DEFAULT_CONTEXT_DEPTH;
+use crate::search::model::{
+    embedding_scheme, Truncation, DEFAULT_PASSAGE_PREFIX, DEFAULT_QUERY_PREFIX, EMBEDDING_MODEL_ID, MIN_MAX_TOKENS,
+};
+use crate::search::metric::SimilarityMetric;
+use crate::search::options::{
+    DEFAULT_EXPAND_CHUNKS, DEFAULT_TUI_RESULTS, DEFAULT_TUI_RESULTS_PER_FILE, DEFAULT_TWO_STAGE_FILES,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the default base directory
+pub const HOME_ENV: &str = "NOTES2VEC_HOME";
+
+/// Settings file inside the base directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Prefix for environment variables overriding settings (e.g. `NOTES2VEC_SCORE_MIN`)
+pub const ENV_PREFIX: &str = "NOTES2VEC_";
+
+/// Collection stored directly in the base directory
+pub const DEFAULT_COLLECTION: &str = "default";
+
+/// Directory under the base directory holding the other collections, one subdirectory each
+pub const COLLECTIONS_DIR: &str = "collections";
+
+/// Table in config.toml with one `[collections.<name>]` entry per configured collection
+pub const COLLECTIONS_TABLE: &str = "collections";
+
+/// A `[collections.<name>]` entry from config.toml
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionSettings {
+    /// Notes directory indexed into the collection when `index` or `watch` get no path
+    pub path: Option<PathBuf>,
+    /// Multiplier on the collection's similarities when searching several collections together
+    pub weight: f32,
+}
+
+impl Default for CollectionSettings {
+    fn default() -> Self {
+        Self { path: None, weight: 1.0 }
+    }
+}
+
+/// Where a setting's effective value came from, as annotated by `config show`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingSource {
+    Default,
+    /// config.toml
+    File,
+    /// A `NOTES2VEC_*` variable
+    Env,
+    /// A command-line flag
+    Flag,
+}
+
+impl SettingSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingSource::Default => "default",
+            SettingSource::File => "file",
+            SettingSource::Env => "env",
+            SettingSource::Flag => "flag",
+        }
+    }
+}
+
+/// Keys accepted in config.toml, as `NOTES2VEC_<KEY>` variables, and by `config set`
+pub const SETTINGS: &[&str] = &[
+    "model_id",
+    "model_revision",
+    "query_prefix",
+    "passage_prefix",
+    "max_tokens",
+    "truncation",
+    "embed_context",
+    "context_separator",
+    "context_weight",
+    "score_min",
+    "score_max",
+    "lexical_floor",
+    "raw_scores",
+    "confidence_labels",
+    "confidence_strong",
+    "confidence_likely",
+    "tag_case_sensitive",
+    "tag_strip_hash",
+    "show_welcome",
+    "ascii_art",
+    "paint_background",
+    "scratch_file",
+    "export_dir",
+    "preview_lines",
+    "tui_results",
+    "tui_results_per_file",
+    "max_chunks_per_file",
+    "min_chunk_chars",
+    "list_item_chunks",
+    "split_on_rule",
+    "context_depth",
+    "low_content_weight",
+    "similarity_metric",
+    "two_stage_files",
+    "expand_chunks",
+    "include_hidden",
+    "ignore_dirs",
+    "path_signal",
+    "merge_adjacent_chunks",
+    "collection",
+];
 
 /// Configuration for notes2vec
 #[derive(Debug, Clone)]
 pub struct Config {
     /// Base directory for notes2vec data
     pub base_dir: PathBuf,
-    /// Directory for the vector database
+    /// Directory for the vector database (of the selected collection)
     pub database_dir: PathBuf,
     /// Directory for cached models
     pub models_dir: PathBuf,
-    /// Path to the state store
+    /// Path to the state store (of the selected collection)
     pub state_path: PathBuf,
+    /// Collection whose database and state store are used; models and settings are shared
+    pub collection: String,
+    /// Hugging Face id of the embedding model
+    pub model_id: String,
+    /// Branch, tag or commit of the model repository to download (None = its default branch)
+    pub model_revision: Option<String>,
+    /// Text put in front of every query before embedding
+    pub query_prefix: String,
+    /// Text put in front of every chunk before embedding
+    pub passage_prefix: String,
+    /// Tokens per text the model sees, special tokens included (None = the model's own limit,
+    /// `max_position_embeddings`); longer texts are truncated
+    pub max_tokens: Option<usize>,
+    /// Which end of an over-long text is cut off
+    pub truncation: Truncation,
+    /// Prepend the heading context to each chunk before embedding
+    pub embed_context: bool,
+    /// Separator placed between context and chunk text when `embed_context` is set
+    pub context_separator: String,
+    /// Share of each chunk's vector taken from its separately embedded heading context
+    /// (0 embeds the context with the text instead, as `embed_context` says)
+    pub context_weight: f32,
+    /// Cosine mapped to a 0% display score (defaults to the model's reference value)
+    pub score_min: Option<f32>,
+    /// Cosine mapped to a 100% display score (defaults to the model's reference value)
+    pub score_max: Option<f32>,
+    /// Cosine a chunk needs before the TUI's lexical boost applies (defaults to the level the
+    /// model gives unrelated text), so a common word can't lift an off-topic chunk
+    pub lexical_floor: Option<f32>,
+    /// Show raw cosine * 100 instead of calibrated scores
+    pub raw_scores: bool,
+    /// Label results "strong" / "likely" / "weak" next to their score
+    pub confidence_labels: bool,
+    /// Cosine similarity at or above which a result is labelled "strong"
+    pub confidence_strong: f32,
+    /// Cosine similarity at or above which a result is labelled "likely" (below is "weak")
+    pub confidence_likely: f32,
+    /// Treat `#Work` and `#work` as different tags
+    pub tag_case_sensitive: bool,
+    /// Strip a leading `#` from tags so `#work` and `work` match
+    pub tag_strip_hash: bool,
+    /// Start the TUI on the welcome screen
+    pub show_welcome: bool,
+    /// Draw the large logo and character art on the welcome screen (off: a one-line title)
+    pub ascii_art: bool,
+    /// Paint the TUI's RGB background (disable for terminals that render it as solid blocks)
+    pub paint_background: bool,
+    /// Note that the TUI appends selected snippets to
+    pub scratch_file: PathBuf,
+    /// Directory the TUI writes result exports (Ctrl+S) to
+    pub export_dir: PathBuf,
+    /// Lines of a chunk the TUI details panel shows before cutting it off with "(truncated)"
+    pub preview_lines: usize,
+    /// Results the TUI shows per search (`+`/`-` change it for the session)
+    pub tui_results: usize,
+    /// Chunks of any one file among the TUI's results (`[`/`]` change it for the session)
+    pub tui_results_per_file: usize,
+    /// Stop storing chunks for a single file after this many (None = unlimited)
+    pub max_chunks_per_file: Option<usize>,
+    /// Chunks with fewer letters/digits than this are flagged as low-content (0 disables)
+    pub min_chunk_chars: usize,
+    /// Index each top-level Markdown list item (with its sub-items) as its own chunk
+    pub list_item_chunks: bool,
+    /// End a chunk at every horizontal rule, keeping the heading context
+    pub split_on_rule: bool,
+    /// Headings kept under the document title in each chunk's context (None = every level)
+    pub context_depth: Option<usize>,
+    /// Multiplier applied to low-content similarities in search (0 excludes them)
+    pub low_content_weight: f32,
+    /// How query and stored embeddings are compared; recorded with the index
+    pub similarity_metric: SimilarityMetric,
+    /// Files whose chunks `search --two-stage` scores, picked by their document vectors
+    pub two_stage_files: usize,
+    /// Best matches of a first search blended into the query by `search --expand`
+    pub expand_chunks: usize,
+    /// Index hidden files and folders (`.notes2vec` and `.git` are always skipped)
+    pub include_hidden: bool,
+    /// Directory names skipped anywhere in the notes tree, e.g. `node_modules` or `templates`
+    pub ignore_dirs: Vec<String>,
+    /// Blend how well each result's file path matches the query into TUI search scores
+    pub path_signal: bool,
+    /// Show matches on consecutive chunks of a file as one TUI result spanning them
+    pub merge_adjacent_chunks: bool,
+    /// Encrypt database values when creating a new index (passphrase from `NOTES2VEC_PASSPHRASE`)
+    pub encrypt: bool,
+    /// Collections configured in config.toml, by name
+    pub collection_settings: BTreeMap<String, CollectionSettings>,
+    /// Settings not at their default, and where their value came from
+    sources: BTreeMap<&'static str, SettingSource>,
 }
 
 impl Config {
-    /// Get the default configuration directory
+    /// Get the default configuration directory, checking in order:
+    /// 1. `NOTES2VEC_HOME`
+    /// 2. `$XDG_DATA_HOME/notes2vec`, for services and containers without a home directory
+    /// 3. `~/.notes2vec`
     pub fn default_base_dir() -> Result<PathBuf> {
-        dirs::home_dir()
-            .ok_or_else(|| Error::Config("Could not determine home directory".to_string()))
-            .map(|home| home.join(".notes2vec"))
+        Self::base_dir_from(env_dir(HOME_ENV), env_dir("XDG_DATA_HOME"), dirs::home_dir()).ok_or_else(|| {
+            Error::Config(format!(
+                "Could not determine home directory; set {} or pass --base-dir",
+                HOME_ENV
+            ))
+        })
+    }
+
+    /// [`default_base_dir`](Self::default_base_dir) from the values it looks up
+    /// (`NOTES2VEC_HOME`, `XDG_DATA_HOME` and the home directory)
+    pub fn base_dir_from(
+        notes2vec_home: Option<PathBuf>,
+        xdg_data_home: Option<PathBuf>,
+        home: Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        notes2vec_home
+            .or_else(|| xdg_data_home.map(|dir| dir.join("notes2vec")))
+            .or_else(|| home.map(|dir| dir.join(".notes2vec")))
     }
 
     /// Create a new configuration
     pub fn new(base_dir: Option<PathBuf>) -> Result<Self> {
-        let base_dir = base_dir.unwrap_or_else(|| {
-            Self::default_base_dir().unwrap_or_else(|_| PathBuf::from(".notes2vec"))
-        });
+        let base_dir = match base_dir {
+            Some(dir) => dir,
+            None => Self::default_base_dir().unwrap_or_else(|e| {
+                // Last resort: make the working-directory fallback visible instead of silent
+                let fallback = std::env::current_dir()
+                    .unwrap_or_default()
+                    .join(".notes2vec");
+                tracing::warn!("{}. Using {}", e, fallback.display());
+                fallback
+            }),
+        };
 
         Ok(Self {
             database_dir: base_dir.join("database"),
             models_dir: base_dir.join("models"),
             state_path: base_dir.join("state").join("state.redb"),
+            collection: DEFAULT_COLLECTION.to_string(),
+            scratch_file: base_dir.join("scratch.md"),
+            export_dir: base_dir.join("exports"),
             base_dir,
+            model_id: EMBEDDING_MODEL_ID.to_string(),
+            model_revision: None,
+            query_prefix: DEFAULT_QUERY_PREFIX.to_string(),
+            passage_prefix: DEFAULT_PASSAGE_PREFIX.to_string(),
+            max_tokens: None,
+            truncation: Truncation::End,
+            embed_context: true,
+            context_separator: "\n".to_string(),
+            context_weight: 0.0,
+            score_min: None,
+            score_max: None,
+            lexical_floor: None,
+            raw_scores: false,
+            confidence_labels: true,
+            confidence_strong: 0.75,
+            confidence_likely: 0.60,
+            tag_case_sensitive: false,
+            tag_strip_hash: true,
+            show_welcome: true,
+            ascii_art: true,
+            paint_background: true,
+            preview_lines: 200,
+            tui_results: DEFAULT_TUI_RESULTS,
+            tui_results_per_file: DEFAULT_TUI_RESULTS_PER_FILE,
+            max_chunks_per_file: Some(1000),
+            min_chunk_chars: 12,
+            list_item_chunks: false,
+            split_on_rule: true,
+            context_depth: Some(DEFAULT_CONTEXT_DEPTH),
+            low_content_weight: 0.5,
+            similarity_metric: SimilarityMetric::Cosine,
+            two_stage_files: DEFAULT_TWO_STAGE_FILES,
+            expand_chunks: DEFAULT_EXPAND_CHUNKS,
+            include_hidden: false,
+            ignore_dirs: DEFAULT_IGNORE_DIRS.iter().map(|d| d.to_string()).collect(),
+            path_signal: false,
+            merge_adjacent_chunks: false,
+            encrypt: false,
+            collection_settings: BTreeMap::new(),
+            sources: BTreeMap::new(),
         })
     }
 
+    /// Create a configuration with settings from config.toml, then `NOTES2VEC_*` variables, applied.
+    /// Every invalid setting is reported, not just the first.
+    pub fn load(base_dir: Option<PathBuf>) -> Result<Self> {
+        let mut config = Self::new(base_dir)?;
+        let mut problems = Vec::new();
+        let file_source = format!("in {}", config.config_file().display());
+        for (key, value) in config.read_file()? {
+            let applied = if key == COLLECTIONS_TABLE {
+                config.apply_collections(&value)
+            } else {
+                config.apply_setting(&key, &value)
+            };
+            match applied {
+                Ok(()) => config.set_source(&key, SettingSource::File),
+                Err(e) => problems.push(with_source(e, &file_source)),
+            }
+        }
+        for key in SETTINGS {
+            let var = format!("{}{}", ENV_PREFIX, key.to_uppercase());
+            if let Ok(raw) = std::env::var(&var) {
+                let applied = match parse_setting(key, &raw) {
+                    Ok(Some(value)) => config.apply_setting(key, &value).map(|_| true),
+                    Ok(None) => Ok(false),
+                    Err(e) => Err(e),
+                };
+                match applied {
+                    Ok(true) => config.set_source(key, SettingSource::Env),
+                    Ok(false) => {}
+                    Err(e) => problems.push(with_source(e, &format!("from {}", var))),
+                }
+            }
+        }
+        problems.extend(config.conflicts());
+        match problems.len() {
+            0 => Ok(config),
+            1 => Err(problems.remove(0)),
+            _ => {
+                let messages = problems.into_iter().map(|e| match e {
+                    Error::Config(msg) => msg,
+                    other => other.to_string(),
+                });
+                Err(Error::Config(problem_list("Invalid configuration", messages)))
+            }
+        }
+    }
+
+    /// Where `key`'s current value came from
+    pub fn source(&self, key: &str) -> SettingSource {
+        self.sources.get(key).copied().unwrap_or(SettingSource::Default)
+    }
+
+    /// Record where `key`'s value came from, e.g. [`SettingSource::Flag`] after applying a flag
+    pub fn set_source(&mut self, key: &str, source: SettingSource) {
+        let known = SETTINGS.iter().chain([&COLLECTIONS_TABLE]).find(|k| **k == key);
+        if let Some(key) = known {
+            self.sources.insert(key, source);
+        }
+    }
+
+    /// Settings that are valid on their own but not together
+    fn conflicts(&self) -> Vec<Error> {
+        let mut conflicts = Vec::new();
+        if let (Some(min), Some(max)) = (self.score_min, self.score_max) {
+            if min > max {
+                conflicts.push(Error::Config(format!(
+                    "'score_min' ({}) must not be above 'score_max' ({})",
+                    min, max
+                )));
+            }
+        }
+        if self.confidence_likely > self.confidence_strong {
+            conflicts.push(Error::Config(format!(
+                "'confidence_likely' ({}) must not be above 'confidence_strong' ({})",
+                self.confidence_likely, self.confidence_strong
+            )));
+        }
+        conflicts
+    }
+
+    /// Path to config.toml
+    pub fn config_file(&self) -> PathBuf {
+        self.base_dir.join(CONFIG_FILE)
+    }
+
+    /// Validate `value` for `key` and write it to config.toml.
+    /// `auto` removes the key so the built-in default applies again.
+    pub fn persist_setting(&self, key: &str, value: &str) -> Result<()> {
+        let mut table = self.read_file()?;
+        match parse_setting(key, value)? {
+            Some(parsed) => {
+                // Type-check against the real field before touching the file
+                let mut updated = self.clone();
+                updated.apply_setting(key, &parsed)?;
+                if let Some(conflict) = updated.conflicts().into_iter().next() {
+                    return Err(conflict);
+                }
+                table.insert(key.to_string(), parsed);
+            }
+            None => {
+                table.remove(key);
+            }
+        }
+
+        let contents = toml::to_string(&table)
+            .map_err(|e| Error::Config(format!("Failed to serialize config: {}", e)))?;
+        std::fs::create_dir_all(&self.base_dir)?;
+        std::fs::write(self.config_file(), contents)?;
+        Ok(())
+    }
+
+    /// Effective value of every setting, in `SETTINGS` order
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let optional = |v: Option<f32>| v.map(|v| v.to_string()).unwrap_or_else(|| "auto".to_string());
+        SETTINGS
+            .iter()
+            .map(|&key| {
+                let value = match key {
+                    "model_id" => format!("{:?}", self.model_id),
+                    "model_revision" => self
+                        .model_revision
+                        .as_ref()
+                        .map_or_else(|| "auto".to_string(), |revision| format!("{:?}", revision)),
+                    "query_prefix" => format!("{:?}", self.query_prefix),
+                    "passage_prefix" => format!("{:?}", self.passage_prefix),
+                    "max_tokens" => self.max_tokens.unwrap_or(0).to_string(),
+                    "truncation" => format!("{:?}", self.truncation.as_str()),
+                    "embed_context" => self.embed_context.to_string(),
+                    "context_separator" => format!("{:?}", self.context_separator),
+                    "context_weight" => self.context_weight.to_string(),
+                    "score_min" => optional(self.score_min),
+                    "score_max" => optional(self.score_max),
+                    "lexical_floor" => optional(self.lexical_floor),
+                    "raw_scores" => self.raw_scores.to_string(),
+                    "confidence_labels" => self.confidence_labels.to_string(),
+                    "confidence_strong" => self.confidence_strong.to_string(),
+                    "confidence_likely" => self.confidence_likely.to_string(),
+                    "tag_case_sensitive" => self.tag_case_sensitive.to_string(),
+                    "tag_strip_hash" => self.tag_strip_hash.to_string(),
+                    "show_welcome" => self.show_welcome.to_string(),
+                    "ascii_art" => self.ascii_art.to_string(),
+                    "paint_background" => self.paint_background.to_string(),
+                    "scratch_file" => format!("{:?}", self.scratch_file.display().to_string()),
+                    "export_dir" => format!("{:?}", self.export_dir.display().to_string()),
+                    "preview_lines" => self.preview_lines.to_string(),
+                    "tui_results" => self.tui_results.to_string(),
+                    "tui_results_per_file" => self.tui_results_per_file.to_string(),
+                    "max_chunks_per_file" => self.max_chunks_per_file.unwrap_or(0).to_string(),
+                    "min_chunk_chars" => self.min_chunk_chars.to_string(),
+                    "list_item_chunks" => self.list_item_chunks.to_string(),
+                    "split_on_rule" => self.split_on_rule.to_string(),
+                    "context_depth" => self.context_depth.unwrap_or(0).to_string(),
+                    "low_content_weight" => self.low_content_weight.to_string(),
+                    "similarity_metric" => format!("{:?}", self.similarity_metric.as_str()),
+                    "two_stage_files" => self.two_stage_files.to_string(),
+                    "expand_chunks" => self.expand_chunks.to_string(),
+                    "include_hidden" => self.include_hidden.to_string(),
+                    "ignore_dirs" => format!("{:?}", self.ignore_dirs),
+                    "path_signal" => self.path_signal.to_string(),
+                    "merge_adjacent_chunks" => self.merge_adjacent_chunks.to_string(),
+                    "collection" => format!("{:?}", self.collection),
+                    _ => unreachable!("every entry in SETTINGS is handled"),
+                };
+                (key, value)
+            })
+            .collect()
+    }
+
+    /// Fingerprint of the prefixes and pooling vectors are made with (see [`embedding_scheme`]),
+    /// plus `context_weight` once it blends anything in
+    pub fn embedding_scheme(&self) -> String {
+        let scheme = embedding_scheme(&self.query_prefix, &self.passage_prefix);
+        if self.context_weight > 0.0 {
+            format!("{} context_weight={}", scheme, self.context_weight)
+        } else {
+            scheme
+        }
+    }
+
+    /// Directory holding the files for `model_id`; the default model keeps the original location.
+    /// A pinned `model_revision` gets a directory of its own.
+    pub fn model_dir(&self) -> PathBuf {
+        match &self.model_revision {
+            Some(revision) => self.models_dir.join(format!(
+                "{}@{}",
+                self.model_id.replace('/', "--"),
+                revision.replace(['/', '\\'], "--")
+            )),
+            None if self.model_id == EMBEDDING_MODEL_ID => self.models_dir.clone(),
+            None => self.models_dir.join(self.model_id.replace('/', "--")),
+        }
+    }
+
+    fn read_file(&self) -> Result<toml::Table> {
+        let path = self.config_file();
+        if !path.exists() {
+            return Ok(toml::Table::new());
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        contents
+            .parse::<toml::Table>()
+            .map_err(|e| Error::Config(format!("Invalid {}: {}", path.display(), e)))
+    }
+
+    fn apply_setting(&mut self, key: &str, value: &toml::Value) -> Result<()> {
+        let expected = |kind: &str| Error::Config(format!("'{}' must be {}", key, kind));
+        let as_bool = || value.as_bool().ok_or_else(|| expected("true or false"));
+        let as_f32 = || {
+            value
+                .as_float()
+                .or_else(|| value.as_integer().map(|i| i as f64))
+                .map(|f| f as f32)
+                .ok_or_else(|| expected("a number"))
+        };
+        let as_string = || {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| expected("a string"))
+        };
+
+        match key {
+            "model_id" => {
+                let id = as_string()?;
+                if id.trim().is_empty() {
+                    return Err(expected("a non-empty model id"));
+                }
+                self.model_id = id;
+            }
+            "model_revision" => {
+                let revision = as_string()?;
+                if revision.trim().is_empty() {
+                    return Err(expected("a branch, tag or commit"));
+                }
+                self.model_revision = Some(revision.trim().to_string());
+            }
+            "query_prefix" => self.query_prefix = as_string()?,
+            "passage_prefix" => self.passage_prefix = as_string()?,
+            "max_tokens" => {
+                let max = value
+                    .as_integer()
+                    .filter(|n| *n == 0 || *n >= MIN_MAX_TOKENS as i64)
+                    .ok_or_else(|| expected(&format!("0 (the model's limit) or an integer of at least {}", MIN_MAX_TOKENS)))?;
+                self.max_tokens = if max == 0 { None } else { Some(max as usize) };
+            }
+            "truncation" => {
+                self.truncation = Truncation::parse(&as_string()?)
+                    .ok_or_else(|| expected(&format!("one of {}", Truncation::NAMES.join(", "))))?;
+            }
+            "embed_context" => self.embed_context = as_bool()?,
+            "context_separator" => self.context_separator = as_string()?,
+            "context_weight" => {
+                let weight = as_f32()?;
+                if !(0.0..=1.0).contains(&weight) {
+                    return Err(expected("between 0 and 1"));
+                }
+                self.context_weight = weight;
+            }
+            "score_min" => self.score_min = Some(as_f32()?),
+            "score_max" => self.score_max = Some(as_f32()?),
+            "lexical_floor" => {
+                let floor = as_f32()?;
+                if !(-1.0..=1.0).contains(&floor) {
+                    return Err(expected("a similarity between -1 and 1"));
+                }
+                self.lexical_floor = Some(floor);
+            }
+            "raw_scores" => self.raw_scores = as_bool()?,
+            "confidence_labels" => self.confidence_labels = as_bool()?,
+            "confidence_strong" | "confidence_likely" => {
+                let threshold = as_f32()?;
+                if !(-1.0..=1.0).contains(&threshold) {
+                    return Err(expected("a similarity between -1 and 1"));
+                }
+                if key == "confidence_strong" {
+                    self.confidence_strong = threshold;
+                } else {
+                    self.confidence_likely = threshold;
+                }
+            }
+            "tag_case_sensitive" => self.tag_case_sensitive = as_bool()?,
+            "tag_strip_hash" => self.tag_strip_hash = as_bool()?,
+            "show_welcome" => self.show_welcome = as_bool()?,
+            "ascii_art" => self.ascii_art = as_bool()?,
+            "paint_background" => self.paint_background = as_bool()?,
+            "scratch_file" => self.scratch_file = PathBuf::from(as_string()?),
+            "export_dir" => self.export_dir = PathBuf::from(as_string()?),
+            "preview_lines" => {
+                let lines = value
+                    .as_integer()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| expected("a positive integer"))?;
+                self.preview_lines = lines as usize;
+            }
+            "tui_results" => {
+                let results = value
+                    .as_integer()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| expected("a positive integer"))?;
+                self.tui_results = results as usize;
+            }
+            "tui_results_per_file" => {
+                let chunks = value
+                    .as_integer()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| expected("a positive integer"))?;
+                self.tui_results_per_file = chunks as usize;
+            }
+            "max_chunks_per_file" => {
+                let max = value
+                    .as_integer()
+                    .filter(|n| *n >= 0)
+                    .ok_or_else(|| expected("a non-negative integer (0 = unlimited)"))?;
+                self.max_chunks_per_file = if max == 0 { None } else { Some(max as usize) };
+            }
+            "min_chunk_chars" => {
+                let min = value
+                    .as_integer()
+                    .filter(|n| *n >= 0)
+                    .ok_or_else(|| expected("a non-negative integer (0 = disabled)"))?;
+                self.min_chunk_chars = min as usize;
+            }
+            "list_item_chunks" => self.list_item_chunks = as_bool()?,
+            "split_on_rule" => self.split_on_rule = as_bool()?,
+            "context_depth" => {
+                let depth = value
+                    .as_integer()
+                    .filter(|n| *n >= 0)
+                    .ok_or_else(|| expected("a non-negative integer (0 = every level)"))?;
+                self.context_depth = if depth == 0 { None } else { Some(depth as usize) };
+            }
+            "similarity_metric" => {
+                self.similarity_metric = SimilarityMetric::parse(&as_string()?)
+                    .ok_or_else(|| expected(&format!("one of {}", SimilarityMetric::NAMES.join(", "))))?;
+            }
+            "two_stage_files" => {
+                let files = value
+                    .as_integer()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| expected("a positive integer"))?;
+                self.two_stage_files = files as usize;
+            }
+            "expand_chunks" => {
+                let chunks = value
+                    .as_integer()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| expected("a positive integer"))?;
+                self.expand_chunks = chunks as usize;
+            }
+            "low_content_weight" => {
+                let weight = as_f32()?;
+                if !(0.0..=1.0).contains(&weight) {
+                    return Err(expected("between 0 and 1"));
+                }
+                self.low_content_weight = weight;
+            }
+            "include_hidden" => self.include_hidden = as_bool()?,
+            "ignore_dirs" => {
+                let names = value.as_array().ok_or_else(|| expected("a list of directory names"))?;
+                let mut dirs = Vec::new();
+                for name in names {
+                    match name.as_str() {
+                        Some(name) if !name.is_empty() && !name.contains(['/', '\\']) => dirs.push(name.to_string()),
+                        _ => return Err(expected("a list of directory names (no paths)")),
+                    }
+                }
+                self.ignore_dirs = dirs;
+            }
+            "path_signal" => self.path_signal = as_bool()?,
+            "merge_adjacent_chunks" => self.merge_adjacent_chunks = as_bool()?,
+            "collection" => self.select_collection(&as_string()?)?,
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
+    /// Read the `[collections.<name>]` tables
+    fn apply_collections(&mut self, value: &toml::Value) -> Result<()> {
+        let tables = value
+            .as_table()
+            .ok_or_else(|| Error::Config("'collections' must be a table of [collections.<name>] sections".to_string()))?;
+        for (name, table) in tables {
+            validate_collection_name(name)?;
+            let table = table
+                .as_table()
+                .ok_or_else(|| Error::Config(format!("'collections.{}' must be a table", name)))?;
+            let mut settings = CollectionSettings::default();
+            for (key, value) in table {
+                let expected = |kind: &str| Error::Config(format!("'collections.{}.{}' must be {}", name, key, kind));
+                match key.as_str() {
+                    "path" => {
+                        let path = value.as_str().ok_or_else(|| expected("a string"))?;
+                        settings.path = Some(PathBuf::from(path));
+                    }
+                    "weight" => {
+                        let weight = value
+                            .as_float()
+                            .or_else(|| value.as_integer().map(|i| i as f64))
+                            .filter(|w| *w > 0.0)
+                            .ok_or_else(|| expected("a number above 0"))?;
+                        settings.weight = weight as f32;
+                    }
+                    _ => {
+                        return Err(Error::Config(format!(
+                            "Unknown key 'collections.{}.{}'. Known keys: path, weight",
+                            name, key
+                        )))
+                    }
+                }
+            }
+            self.collection_settings.insert(name.clone(), settings);
+        }
+        Ok(())
+    }
+
+    /// Search weight of a collection (1.0 unless configured)
+    pub fn collection_weight(&self, name: &str) -> f32 {
+        self.collection_settings.get(name).map_or(1.0, |settings| settings.weight)
+    }
+
+    /// Configured notes directory of the selected collection
+    pub fn collection_path(&self) -> Option<&Path> {
+        self.collection_settings.get(&self.collection)?.path.as_deref()
+    }
+
+    /// This configuration with `name`'s database and state store
+    pub fn with_collection(mut self, name: &str) -> Result<Self> {
+        self.select_collection(name)?;
+        Ok(self)
+    }
+
+    fn select_collection(&mut self, name: &str) -> Result<()> {
+        validate_collection_name(name)?;
+
+        let root = if name == DEFAULT_COLLECTION {
+            self.base_dir.clone()
+        } else {
+            self.base_dir.join(COLLECTIONS_DIR).join(name)
+        };
+        self.database_dir = root.join("database");
+        self.state_path = root.join("state").join("state.redb");
+        self.collection = name.to_string();
+        Ok(())
+    }
+
+    /// Collections that have been indexed into, `default` first and the rest by name
+    pub fn collections(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        if self.base_dir.join("database").exists() {
+            names.push(DEFAULT_COLLECTION.to_string());
+        }
+
+        let dir = self.base_dir.join(COLLECTIONS_DIR);
+        if dir.is_dir() {
+            let mut named = Vec::new();
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                if !entry.path().join("database").is_dir() {
+                    continue;
+                }
+                if let Some(name) = entry.file_name().to_str() {
+                    if name != DEFAULT_COLLECTION {
+                        named.push(name.to_string());
+                    }
+                }
+            }
+            named.sort();
+            names.extend(named);
+        }
+        Ok(names)
+    }
+
     /// Initialize the configuration directories
     pub fn init(&self) -> Result<()> {
         std::fs::create_dir_all(&self.base_dir)?;
@@ -45,9 +784,129 @@ impl Config {
         Ok(())
     }
 
-    /// Check if the configuration is already initialized
+    /// Check if the configuration is already initialized (see [`initialization_problems`](Self::initialization_problems))
     pub fn is_initialized(&self) -> bool {
-        self.base_dir.exists() && self.database_dir.exists()
+        self.initialization_problems(false).is_empty()
+    }
+
+    /// Everything missing from the data directories, and with `writable` anything that can't be
+    /// written to; empty when notes2vec is ready to use. A half-deleted base directory shows up here
+    /// instead of failing later in a store.
+    pub fn initialization_problems(&self, writable: bool) -> Vec<String> {
+        let state_dir = self.state_path.parent().unwrap_or(&self.base_dir);
+        let dirs = [
+            ("base directory", self.base_dir.as_path()),
+            ("database directory", self.database_dir.as_path()),
+            ("models directory", self.models_dir.as_path()),
+            ("state directory", state_dir),
+        ];
+        let mut problems = Vec::new();
+        for (name, dir) in dirs {
+            match std::fs::metadata(dir) {
+                Ok(meta) if !meta.is_dir() => {
+                    problems.push(format!("{} is not a directory: {}", name, dir.display()))
+                }
+                Ok(meta) if writable && meta.permissions().readonly() => {
+                    problems.push(format!("{} is not writable: {}", name, dir.display()))
+                }
+                Ok(_) => {}
+                Err(_) => problems.push(format!("{} is missing: {}", name, dir.display())),
+            }
+        }
+        problems
+    }
+
+    /// Fail with every [`initialization_problems`](Self::initialization_problems) entry listed
+    pub fn ensure_initialized(&self, writable: bool) -> Result<()> {
+        let problems = self.initialization_problems(writable);
+        if problems.is_empty() {
+            return Ok(());
+        }
+        // Nothing there at all: the usual first-run message
+        if !self.base_dir.exists() {
+            return Err(Error::Config(
+                "notes2vec is not initialized. Run 'notes2vec init' first.".to_string(),
+            ));
+        }
+        Err(Error::Config(format!(
+            "{}\nRun 'notes2vec init' to recreate the missing directories.",
+            problem_list("notes2vec is not initialized correctly", problems)
+        )))
+    }
+}
+
+/// Non-empty directory from an environment variable
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Parse a command-line or environment value for `key`; `None` means reset to the default
+fn parse_setting(key: &str, raw: &str) -> Result<Option<toml::Value>> {
+    let raw = raw.trim();
+    let invalid = |kind: &str| Error::Config(format!("Invalid value '{}' for '{}': expected {}", raw, key, kind));
+
+    let value = match key {
+        _ if !SETTINGS.contains(&key) => return Err(unknown_key(key)),
+        _ if raw.eq_ignore_ascii_case("auto") => return Ok(None),
+        "model_id" | "model_revision" | "query_prefix" | "passage_prefix" | "context_separator" | "scratch_file" | "export_dir" | "collection" | "similarity_metric" | "truncation" => {
+            toml::Value::String(raw.to_string())
+        }
+        "score_min" | "score_max" | "lexical_floor" | "context_weight" | "low_content_weight" | "confidence_strong" | "confidence_likely" => toml::Value::Float(raw.parse().map_err(|_| invalid("a number or 'auto'"))?),
+        "max_chunks_per_file" if raw.eq_ignore_ascii_case("unlimited") => toml::Value::Integer(0),
+        // Comma-separated on the command line and in the environment; empty ignores nothing
+        "ignore_dirs" => toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| toml::Value::String(name.to_string()))
+                .collect(),
+        ),
+        "max_chunks_per_file" | "min_chunk_chars" | "preview_lines" | "tui_results" | "tui_results_per_file" | "two_stage_files" | "expand_chunks" | "context_depth" | "max_tokens" => {
+            toml::Value::Integer(raw.parse().map_err(|_| invalid("a non-negative integer"))?)
+        }
+        _ => toml::Value::Boolean(raw.parse().map_err(|_| invalid("true or false"))?),
+    };
+    Ok(Some(value))
+}
+
+/// `heading:` followed by one problem per line
+fn problem_list(heading: &str, problems: impl IntoIterator<Item = String>) -> String {
+    let mut message = format!("{}:", heading);
+    for problem in problems {
+        message.push_str("\n  - ");
+        message.push_str(&problem);
+    }
+    message
+}
+
+/// Say where a bad setting came from
+fn with_source(error: Error, source: &str) -> Error {
+    match error {
+        Error::Config(msg) => Error::Config(format!("{} ({})", msg, source)),
+        other => other,
     }
 }
 
+/// Collection names become directory names, so only `[A-Za-z0-9_-]` is allowed
+fn validate_collection_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(Error::Config(format!(
+            "Invalid collection name '{}': use letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn unknown_key(key: &str) -> Error {
+    Error::Config(format!(
+        "Unknown config key '{}'. Known keys: {}",
+        key,
+        SETTINGS.join(", ")
+    ))
+}
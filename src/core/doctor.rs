@@ -0,0 +1,424 @@
+//! `notes2vec doctor`: checks for the setup problems that come up most, without changing
+//! anything. Every check ends in pass, warn or fail, with a one-line fix for the last two.
+
+use crate::core::config::Config;
+use crate::core::sample::{sample, SampleRng};
+use crate::search::model::{missing_model_files, EmbeddingModel, MODEL_FILES};
+use crate::storage::lock::{IndexLock, LockState};
+use crate::storage::state::StateStore;
+use crate::storage::vectors::{EmbeddingSource, VectorStore};
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Stored chunks whose embeddings are inspected
+pub const DOCTOR_SAMPLE_SIZE: usize = 20;
+
+/// Sentence embedded to prove the model works end to end
+const PROBE_SENTENCE: &str = "notes2vec doctor probe: does the model embed this sentence?";
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Severity {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Severity::Pass => "✓",
+            Severity::Warn => "⚠",
+            Severity::Fail => "✗",
+        }
+    }
+}
+
+/// One diagnosis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub severity: Severity,
+    /// What was found
+    pub detail: String,
+    /// What to do about it (None when it passed)
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, severity: Severity::Pass, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, severity: Severity::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, severity: Severity::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Every check that ran, in order
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// Worst outcome of any check (pass when nothing ran)
+    pub fn worst(&self) -> Severity {
+        self.checks.iter().map(|check| check.severity).max().unwrap_or(Severity::Pass)
+    }
+
+    /// Process exit status: 0 when everything passed, 1 for warnings, 2 for failures
+    pub fn exit_code(&self) -> i32 {
+        match self.worst() {
+            Severity::Pass => 0,
+            Severity::Warn => 1,
+            Severity::Fail => 2,
+        }
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.checks.iter().filter(|check| check.severity == severity).count()
+    }
+}
+
+/// Run every check against `config`. Stores are opened read-only and the lock is only looked at.
+pub fn run_checks(config: &Config, rng: &mut SampleRng) -> DoctorReport {
+    let mut checks = vec![check_base_dir(config), check_model_files(config)];
+
+    // Loading would download missing files, which is not a doctor's job
+    let model_dim = if checks.iter().all(|check| check.severity != Severity::Fail) {
+        let (check, dim) = check_model(config);
+        checks.push(check);
+        dim
+    } else {
+        None
+    };
+
+    let lock = check_lock(config);
+    // A writer has the stores open; their checks would only fail on its lock
+    let busy = lock.severity != Severity::Pass;
+    checks.push(lock);
+    if busy {
+        checks.push(Check::warn("stores", "not checked while another process has them open", "Run 'notes2vec doctor' again once it's done"));
+    } else {
+        checks.push(check_state_store(config, model_dim));
+        checks.extend(check_vector_store(config, model_dim, rng));
+    }
+    DoctorReport { checks }
+}
+
+fn check_base_dir(config: &Config) -> Check {
+    const NAME: &str = "base directory";
+    let base_dir = &config.base_dir;
+    let metadata = match std::fs::metadata(base_dir) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Check::fail(NAME, format!("{} does not exist", base_dir.display()), "Run 'notes2vec init'");
+        }
+        Err(e) => {
+            return Check::fail(
+                NAME,
+                format!("{} can't be read: {}", base_dir.display(), e),
+                format!("Check the permissions of {}", base_dir.display()),
+            );
+        }
+    };
+    if !metadata.is_dir() {
+        return Check::fail(
+            NAME,
+            format!("{} is not a directory", base_dir.display()),
+            "Move the file away or pass another --base-dir",
+        );
+    }
+    let read_only = [base_dir.as_path(), config.database_dir.as_path()]
+        .into_iter()
+        .find(|dir| std::fs::metadata(dir).is_ok_and(|m| m.permissions().readonly()));
+    match read_only {
+        Some(dir) => Check::warn(
+            NAME,
+            format!("{} is read-only, so the index can be searched but not updated", dir.display()),
+            format!("Make {} writable to index", dir.display()),
+        ),
+        None => Check::pass(NAME, format!("{} is writable", base_dir.display())),
+    }
+}
+
+fn check_model_files(config: &Config) -> Check {
+    const NAME: &str = "model files";
+    let model_dir = config.model_dir();
+    let missing = missing_model_files(config);
+    if !missing.is_empty() {
+        return Check::fail(
+            NAME,
+            format!("{} missing from {}", missing.join(", "), model_dir.display()),
+            "Run 'notes2vec init' to download them",
+        );
+    }
+
+    let incomplete: Vec<String> = MODEL_FILES
+        .iter()
+        .filter_map(|file| {
+            let path = model_dir.join(file);
+            let problem = if file.ends_with(".json") { json_problem(&path) } else { safetensors_problem(&path) };
+            problem.map(|problem| format!("{} ({})", file, problem))
+        })
+        .collect();
+    if incomplete.is_empty() {
+        Check::pass(NAME, format!("{} complete in {}", config.model_id, model_dir.display()))
+    } else {
+        Check::fail(
+            NAME,
+            format!("incomplete or corrupt: {}", incomplete.join(", ")),
+            format!("Delete {} and run 'notes2vec init' to download it again", model_dir.display()),
+        )
+    }
+}
+
+/// Why a JSON model file is unusable, if it is
+fn json_problem(path: &Path) -> Option<String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return Some(e.to_string()),
+    };
+    serde_json::from_slice::<serde_json::Value>(&bytes)
+        .err()
+        .map(|_| "not valid JSON, likely cut off".to_string())
+}
+
+/// Why a safetensors file is unusable, if it is: its header says how long the tensor data
+/// is, so a download cut short is caught without loading the weights
+fn safetensors_problem(path: &Path) -> Option<String> {
+    let check = || -> std::io::Result<Option<String>> {
+        let mut file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut header_len = [0u8; 8];
+        if file.read_exact(&mut header_len).is_err() {
+            return Ok(Some("empty".to_string()));
+        }
+        let header_len = u64::from_le_bytes(header_len);
+        if header_len > len.saturating_sub(8) {
+            return Ok(Some("header cut off".to_string()));
+        }
+        let mut header = vec![0u8; header_len as usize];
+        file.read_exact(&mut header)?;
+        let Ok(serde_json::Value::Object(tensors)) = serde_json::from_slice(&header) else {
+            return Ok(Some("unreadable header".to_string()));
+        };
+        let data_len = tensors
+            .values()
+            .filter_map(|tensor| tensor.get("data_offsets")?.get(1)?.as_u64())
+            .max()
+            .unwrap_or(0);
+        let expected = 8 + header_len + data_len;
+        Ok((len < expected).then(|| format!("{} of {} bytes", len, expected)))
+    };
+    check().unwrap_or_else(|e| Some(e.to_string()))
+}
+
+/// Load the model and embed a probe sentence; the embedding length on success
+fn check_model(config: &Config) -> (Check, Option<usize>) {
+    const NAME: &str = "model";
+    let fix = "Delete the model directory and run 'notes2vec init' to download it again";
+    let model = match EmbeddingModel::init_quiet(config) {
+        Ok(model) if model.is_model_loaded() => model,
+        Ok(_) => return (Check::fail(NAME, "model files present but not loaded", fix), None),
+        Err(e) => return (Check::fail(NAME, format!("failed to load: {}", e), fix), None),
+    };
+    let dim = model.embedding_dim();
+    let probe = match model.embed_queries(&[PROBE_SENTENCE.to_string()]) {
+        Ok(mut embeddings) if !embeddings.is_empty() => embeddings.swap_remove(0),
+        Ok(_) => return (Check::fail(NAME, "embedding the probe sentence returned nothing", fix), Some(dim)),
+        Err(e) => return (Check::fail(NAME, format!("failed to embed a probe sentence: {}", e), fix), Some(dim)),
+    };
+    let norm = probe.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let check = if probe.len() != dim {
+        Check::fail(NAME, format!("probe embedding has {} values, expected {}", probe.len(), dim), fix)
+    } else if !norm.is_finite() || norm == 0.0 {
+        Check::fail(NAME, "probe embedding is all zeros or not finite", fix)
+    } else {
+        Check::pass(NAME, format!("{} loaded, embeds {}-dimensional vectors", config.model_id, dim))
+    };
+    (check, Some(dim))
+}
+
+fn check_lock(config: &Config) -> Check {
+    const NAME: &str = "index lock";
+    match IndexLock::state(&config.database_dir) {
+        Ok(LockState::Free) => Check::pass(NAME, "not held"),
+        Ok(LockState::Stale) => Check::pass(NAME, "left over from a process that didn't exit cleanly; the next writer takes it over"),
+        Ok(LockState::Held(pid)) => Check::warn(
+            NAME,
+            match pid {
+                Some(pid) => format!("held by pid {}, so indexing waits for it", pid),
+                None => "held by another process, so indexing waits for it".to_string(),
+            },
+            "Let the other notes2vec finish, or stop it",
+        ),
+        Err(e) => Check::warn(NAME, format!("can't be checked: {}", e), format!("Check the permissions of {}", config.database_dir.display())),
+    }
+}
+
+fn check_state_store(config: &Config, model_dim: Option<usize>) -> Check {
+    const NAME: &str = "state store";
+    let reindex = "Run 'notes2vec index --force' to re-embed your notes";
+    if !config.state_path.exists() {
+        return Check::warn(NAME, "nothing indexed yet", "Run 'notes2vec index /path/to/notes'");
+    }
+    let store = match StateStore::open_read_only(config) {
+        Ok(store) => store,
+        Err(e) => return Check::fail(NAME, format!("can't be opened: {}", e), "Close other notes2vec processes, or rebuild with 'notes2vec index --force'"),
+    };
+    let recorded = store.get_model_id().ok().flatten();
+    if let Some(recorded) = recorded.filter(|id| *id != config.model_id) {
+        return Check::warn(NAME, format!("index built with {} but model_id is {}", recorded, config.model_id), reindex);
+    }
+    if store.needs_reindex(config).unwrap_or(false) {
+        return Check::warn(NAME, "index built with other embedding prefixes or context_weight", reindex);
+    }
+    if let Some(dim) = model_dim {
+        if let Err(e) = store.check_embedding_dim(dim) {
+            return Check::fail(NAME, e.to_string(), reindex);
+        }
+    }
+    let files = store.list_states().map(|states| states.len()).unwrap_or(0);
+    Check::pass(NAME, format!("{} files tracked, built with the configured model", files))
+}
+
+fn check_vector_store(config: &Config, model_dim: Option<usize>, rng: &mut SampleRng) -> Vec<Check> {
+    const NAME: &str = "vector store";
+    if !config.database_dir.join("vectors.redb").exists() {
+        return vec![Check::warn(NAME, "no vector database yet", "Run 'notes2vec index /path/to/notes'")];
+    }
+    let store = match VectorStore::open_read_only(config) {
+        Ok(store) => store,
+        Err(e) => return vec![Check::fail(NAME, format!("can't be opened: {}", e), "Close other notes2vec processes, or rebuild with 'notes2vec index --force'")],
+    };
+    let chunks = store.chunk_count();
+    if chunks == 0 {
+        return vec![Check::warn(NAME, "the index is empty", "Run 'notes2vec index /path/to/notes'")];
+    }
+    let files = store.get_file_count().unwrap_or(0);
+    vec![
+        Check::pass(NAME, format!("{} chunks from {} files", chunks, files)),
+        check_sampled_embeddings(&store, model_dim, rng),
+    ]
+}
+
+/// Look at a random sample of stored embeddings: all the same length as the model's, and
+/// none written by the old hash fallback
+fn check_sampled_embeddings(store: &VectorStore, model_dim: Option<usize>, rng: &mut SampleRng) -> Check {
+    const NAME: &str = "stored embeddings";
+    let reindex = "Run 'notes2vec index --force' to re-embed your notes";
+
+    let mut keys = Vec::new();
+    if let Err(e) = store.for_each_key(|key| {
+        keys.push(key.to_string());
+        ControlFlow::Continue(())
+    }) {
+        return Check::fail(NAME, format!("can't be read: {}", e), reindex);
+    }
+    let entries: Vec<_> = sample(keys, DOCTOR_SAMPLE_SIZE, rng)
+        .iter()
+        .filter_map(|key| store.get(key).ok().flatten())
+        .collect();
+    let Some(first) = entries.first() else {
+        return Check::warn(NAME, "no readable chunks in the sample", reindex);
+    };
+
+    let dim = model_dim.unwrap_or(first.embedding.len());
+    let wrong_dim = entries.iter().filter(|entry| entry.embedding.len() != dim).count();
+    let hashed = entries
+        .iter()
+        .filter(|entry| entry.embedding_source == EmbeddingSource::Hash || looks_quantized(&entry.embedding))
+        .count();
+    if hashed > 0 {
+        Check::fail(NAME, format!("{} of {} sampled chunks look like hash-fallback embeddings, mixed in with model ones", hashed, entries.len()), reindex)
+    } else if wrong_dim > 0 {
+        Check::fail(NAME, format!("{} of {} sampled chunks are not {}-dimensional", wrong_dim, entries.len(), dim), reindex)
+    } else {
+        Check::pass(NAME, format!("{} sampled chunks hold {}-dimensional model embeddings", entries.len(), dim))
+    }
+}
+
+/// Whether every value sits on a 255-step grid (as-is or scaled by the largest value), the
+/// mark of the byte-derived hash fallback; model output never lines up like that
+pub fn looks_quantized(embedding: &[f32]) -> bool {
+    let max = embedding.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+    if embedding.len() < 8 || max == 0.0 {
+        return false;
+    }
+    let on_grid = |scale: f32| {
+        embedding.iter().all(|v| {
+            let steps = v / scale * 255.0;
+            (steps - steps.round()).abs() < 1e-3
+        })
+    };
+    on_grid(1.0) || on_grid(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_exit_code_follows_worst_check() {
+        let mut report = DoctorReport::default();
+        assert_eq!(report.exit_code(), 0);
+        report.checks.push(Check::pass("a", "fine"));
+        assert_eq!(report.exit_code(), 0);
+        report.checks.push(Check::warn("b", "meh", "do this"));
+        assert_eq!(report.exit_code(), 1);
+        report.checks.push(Check::fail("c", "broken", "do that"));
+        report.checks.push(Check::pass("d", "fine"));
+        assert_eq!(report.worst(), Severity::Fail);
+        assert_eq!(report.exit_code(), 2);
+        assert_eq!(report.count(Severity::Pass), 2);
+    }
+
+    #[test]
+    fn test_quantized_embeddings_are_spotted() {
+        let bytes: Vec<f32> = (0..384).map(|i| ((i * 37) % 256) as f32 / 255.0).collect();
+        assert!(looks_quantized(&bytes));
+        let norm = bytes.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let normalized: Vec<f32> = bytes.iter().map(|v| v / norm).collect();
+        assert!(looks_quantized(&normalized));
+
+        let mut rng = SampleRng::new(7);
+        let model_like: Vec<f32> = (0..384).map(|_| rng.next_u64() as f32 / u64::MAX as f32 - 0.5).collect();
+        assert!(!looks_quantized(&model_like));
+        assert!(!looks_quantized(&[0.0; 384]));
+    }
+
+    #[test]
+    fn test_truncated_safetensors_is_caught() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("model.safetensors");
+        let header = br#"{"w":{"dtype":"F32","shape":[4],"data_offsets":[0,16]}}"#;
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header);
+        bytes.extend_from_slice(&[0u8; 16]);
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(safetensors_problem(&path), None);
+
+        std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+        assert!(safetensors_problem(&path).unwrap().contains("bytes"));
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(safetensors_problem(&path).as_deref(), Some("empty"));
+    }
+
+    #[test]
+    fn test_uninitialized_base_dir_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("missing"))).unwrap();
+        let report = run_checks(&config, &mut SampleRng::new(1));
+        assert_eq!(report.checks[0].severity, Severity::Fail);
+        assert!(report.checks.iter().all(|check| check.name != "model"), "model load must be skipped");
+        assert_eq!(report.exit_code(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+use super::error::{Error, Result};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parse a human duration such as `90m`, `24h`, `7d` or `2w` (a whole number and one unit:
+/// `s`, `m`, `h`, `d` or `w`)
+pub fn parse_duration(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let invalid = || {
+        Error::Config(format!(
+            "Invalid duration '{}': expected a number and a unit, like 30m, 24h, 7d or 2w",
+            raw
+        ))
+    };
+
+    let split = raw.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = raw.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    amount
+        .checked_mul(unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+/// Parse a calendar date written `YYYY-MM-DD` into the Unix time its day starts (UTC)
+pub fn parse_date(raw: &str) -> Result<u64> {
+    let raw = raw.trim();
+    date_start(raw).ok_or_else(|| {
+        Error::Config(format!("Invalid date '{}': expected YYYY-MM-DD, like 2024-03-01", raw))
+    })
+}
+
+/// Start of the day a frontmatter date such as `2024-03-01` or `2024-03-01T09:30` falls on;
+/// None when it doesn't begin with a `YYYY-MM-DD` date
+pub fn frontmatter_date(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    match raw.get(10..) {
+        Some(rest) if rest.is_empty() || rest.starts_with(['T', ' ']) => date_start(&raw[..10]),
+        _ => None,
+    }
+}
+
+fn date_start(date: &str) -> Option<u64> {
+    let bytes = date.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = &date[range];
+        digits.bytes().all(|b| b.is_ascii_digit()).then(|| digits.parse().ok())?
+    };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = [31, if leap { 29 } else { 28 }, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    if year < 1970 || !(1..=12).contains(&month) || day < 1 || day > month_days[month as usize - 1] {
+        return None;
+    }
+    // Days since 1970-01-01 (civil-from-days, shifted so years start in March)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days as u64 * 24 * 60 * 60)
+}
+
+/// Unix time `secs` as `YYYYMMDD-HHMMSS` (UTC), for file names that sort by time
+pub fn compact_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Inverse of the day count in `date_start` (days-from-civil, years starting in March)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Unix time `window` before now (0 if that's before the epoch)
+pub fn unix_cutoff(window: Duration) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+    now.saturating_sub(window).as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("90m").unwrap(), Duration::from_secs(90 * 60));
+        assert_eq!(parse_duration("24h").unwrap(), Duration::from_secs(24 * 3600));
+        assert_eq!(parse_duration(" 7d ").unwrap(), Duration::from_secs(7 * 86400));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::from_secs(14 * 86400));
+        assert_eq!(parse_duration("0d").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_malformed() {
+        for bad in ["", "7", "d", "7x", "7 d", "-3d", "1.5h", "7dd", "99999999999999999999w"] {
+            assert!(parse_duration(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2024-03-01").unwrap(), 1_709_251_200);
+        assert_eq!(parse_date(" 2000-02-29 ").unwrap(), 951_782_400);
+        for bad in ["", "2024-3-1", "2024-02-30", "2023-02-29", "2024-13-01", "1969-12-31", "2024/03/01", "2024-03-01T10:00"] {
+            assert!(parse_date(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_frontmatter_date() {
+        assert_eq!(frontmatter_date("2024-03-01"), Some(1_709_251_200));
+        assert_eq!(frontmatter_date("2024-03-01T09:30:00Z"), Some(1_709_251_200));
+        assert_eq!(frontmatter_date("2024-03-01 09:30"), Some(1_709_251_200));
+        assert_eq!(frontmatter_date("2024-03-015"), None);
+        assert_eq!(frontmatter_date("March 1st"), None);
+    }
+
+    #[test]
+    fn test_compact_timestamp() {
+        assert_eq!(compact_timestamp(0), "19700101-000000");
+        assert_eq!(compact_timestamp(1_709_251_200 + 9 * 3600 + 30 * 60 + 5), "20240301-093005");
+        assert_eq!(compact_timestamp(951_782_400 - 1), "20000228-235959");
+        assert_eq!(compact_timestamp(parse_date("2100-12-31").unwrap()), "21001231-000000");
+    }
+
+    #[test]
+    fn test_unix_cutoff() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let cutoff = unix_cutoff(Duration::from_secs(3600));
+        assert!(cutoff + 3600 >= now && cutoff + 3600 <= now + 1);
+        assert_eq!(unix_cutoff(Duration::from_secs(u64::MAX)), 0);
+    }
+}
@@ -17,6 +17,18 @@ pub enum Error {
     #[error("Parsing error: {0}")]
     Parsing(String),
 
+    #[error("File looks binary, skipping: {0}")]
+    BinaryContent(String),
+
+    #[error("Index mixes {found} and {expected} embeddings, which can't be compared. Run 'notes2vec index --force' to rebuild it.")]
+    EmbeddingSourceMismatch { found: String, expected: String },
+
+    #[error("Encryption error: {0}")]
+    Encryption(String),
+
+    #[error("Query server error: {0}")]
+    Server(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 
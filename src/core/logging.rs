@@ -0,0 +1,334 @@
+//! Diagnostics through `tracing`, installed with `tracing_subscriber`.
+//!
+//! Events go to stderr, where warnings and errors read like the rest of the CLI's messages,
+//! and optionally to a file as JSON lines (`--log-file`). While the TUI owns the terminal,
+//! the stderr side is silenced and warnings and errors are held for its error console.
+//! Results and summaries are plain stdout and never pass through here.
+
+use crate::core::error::{Error, Result};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{Sink, Stderr};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::{self, FormatEvent, FormatFields};
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, EitherWriter, MakeWriter};
+use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Read for the filter when `--log-level` isn't given
+pub const LOG_ENV: &str = "RUST_LOG";
+
+/// Filter without `--log-level` or `RUST_LOG`: warnings and errors, plus the status lines
+/// model loading and `watch` log at info
+const DEFAULT_FILTER: &str = "warn,notes2vec::search::model=info,notes2vec::ui::watch=info";
+/// Default filter under `--quiet`
+const QUIET_FILTER: &str = "warn";
+
+/// Which events are kept, in `RUST_LOG` syntax: a default level and per-target overrides,
+/// e.g. `info` or `warn,notes2vec::indexing=debug`
+pub fn parse_filter(spec: &str) -> Result<EnvFilter> {
+    let directives: Vec<&str> = spec.split(',').map(str::trim).collect();
+    EnvFilter::builder()
+        .parse(directives.join(","))
+        .map_err(|e| Error::Config(format!("Invalid log filter '{}': {}", spec, e)))
+}
+
+/// A warning or error held while the TUI is up
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub level: Level,
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            Level::ERROR => write!(f, "✗ Error: {}", self.message),
+            _ => write!(f, "⚠ Warning: {}", self.message),
+        }
+    }
+}
+
+/// Where the human-readable side of the log goes
+#[derive(Debug)]
+enum Front {
+    Stderr,
+    /// Warnings and errors kept for the TUI's console; anything quieter is dropped
+    Capture(Vec<LogRecord>),
+}
+
+/// Switches the human-readable output between stderr and a buffer (see [`capture`])
+#[derive(Debug, Clone)]
+pub struct LogFront(Arc<Mutex<Front>>);
+
+impl Default for LogFront {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Front::Stderr)))
+    }
+}
+
+impl LogFront {
+    fn lock(&self) -> MutexGuard<'_, Front> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Hold warnings and errors instead of writing them to stderr
+    pub fn capture(&self) {
+        *self.lock() = Front::Capture(Vec::new());
+    }
+
+    /// Warnings and errors held since the last call
+    pub fn drain(&self) -> Vec<LogRecord> {
+        match &mut *self.lock() {
+            Front::Capture(records) => std::mem::take(records),
+            Front::Stderr => Vec::new(),
+        }
+    }
+
+    /// Back to stderr, returning whatever was still held
+    pub fn release(&self) -> Vec<LogRecord> {
+        match std::mem::replace(&mut *self.lock(), Front::Stderr) {
+            Front::Capture(records) => records,
+            Front::Stderr => Vec::new(),
+        }
+    }
+}
+
+/// The stderr writer, silenced while capturing
+impl<'a> MakeWriter<'a> for LogFront {
+    type Writer = EitherWriter<Stderr, Sink>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match &*self.lock() {
+            Front::Stderr => EitherWriter::A(std::io::stderr()),
+            Front::Capture(_) => EitherWriter::B(std::io::sink()),
+        }
+    }
+}
+
+/// Holds warnings and errors while the front is capturing
+struct CaptureLayer(LogFront);
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let level = *event.metadata().level();
+        if level > Level::WARN {
+            return;
+        }
+        if let Front::Capture(records) = &mut *self.0.lock() {
+            let mut message = Message::default();
+            event.record(&mut message);
+            records.push(LogRecord { level, message: message.text });
+        }
+    }
+}
+
+/// An event's message followed by its other fields as `key=value`
+#[derive(Default)]
+struct Message {
+    text: String,
+}
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{}", value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let separator = if self.text.is_empty() { "" } else { " " };
+        if field.name() == "message" {
+            self.text = format!("{:?}{}{}", value, separator, self.text);
+        } else {
+            self.text.push_str(&format!("{}{}={:?}", separator, field.name(), value));
+        }
+    }
+}
+
+/// Warnings and errors read like the CLI's other messages and info is the bare message;
+/// debug and trace add the level, target and spans
+struct HumanFormat {
+    detailed: format::Format<format::Full, ()>,
+}
+
+impl Default for HumanFormat {
+    fn default() -> Self {
+        Self { detailed: format::format().without_time() }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for HumanFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: format::Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        match *event.metadata().level() {
+            Level::ERROR => write!(writer, "✗ Error: ")?,
+            Level::WARN => write!(writer, "⚠ Warning: ")?,
+            Level::INFO => {}
+            _ => return self.detailed.format_event(ctx, writer, event),
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+/// The process-wide subscriber: `filter`, then stderr (or the capture buffer) through `front`,
+/// and every kept event as a JSON line to `json`
+fn subscriber(filter: EnvFilter, front: LogFront, json: Option<BoxMakeWriter>) -> impl Subscriber + Send + Sync {
+    let json = json.map(|writer| {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_writer(writer)
+    });
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .event_format(HumanFormat::default())
+        .with_writer(front.clone())
+        .finish()
+        .with(CaptureLayer(front))
+        .with(json)
+}
+
+static FRONT: OnceLock<LogFront> = OnceLock::new();
+
+/// Install the process-wide logger. `level` (`--log-level`) wins over `RUST_LOG`, and without
+/// either warnings and errors are shown, plus status lines unless `quiet`. `file` (`--log-file`)
+/// is appended to as JSON lines.
+pub fn init(level: Option<&str>, file: Option<&Path>, quiet: bool) -> Result<()> {
+    let spec = level
+        .map(str::to_string)
+        .or_else(|| std::env::var(LOG_ENV).ok())
+        .unwrap_or_else(|| if quiet { QUIET_FILTER } else { DEFAULT_FILTER }.to_string());
+    let filter = parse_filter(&spec)?;
+
+    let json = match file {
+        Some(path) => {
+            let log_file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| {
+                Error::Config(format!("Can't open log file {}: {}", path.display(), e))
+            })?;
+            Some(BoxMakeWriter::new(Mutex::new(log_file)))
+        }
+        None => None,
+    };
+    let front = LogFront::default();
+    tracing::subscriber::set_global_default(subscriber(filter, front.clone(), json))
+        .map_err(|e| Error::Config(format!("Failed to install the logger: {}", e)))?;
+    let _ = FRONT.set(front);
+    Ok(())
+}
+
+/// Hold warnings and errors for the TUI instead of writing them over its screen
+pub fn capture() {
+    if let Some(front) = FRONT.get() {
+        front.capture();
+    }
+}
+
+/// Warnings and errors held since the last call
+pub fn drain() -> Vec<LogRecord> {
+    FRONT.get().map(LogFront::drain).unwrap_or_default()
+}
+
+/// Write to stderr again (once the TUI has restored the terminal), returning what was held
+pub fn release() -> Vec<LogRecord> {
+    FRONT.get().map(LogFront::release).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::io::Write;
+
+    /// A writer the test can read back after the logger has taken it
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn json_lines(filter: &str, log: impl FnOnce()) -> Vec<Value> {
+        let buffer = SharedBuffer::default();
+        let writer = BoxMakeWriter::new(Mutex::new(buffer.clone()));
+        let front = LogFront::default();
+        front.capture();
+        tracing::subscriber::with_default(subscriber(parse_filter(filter).unwrap(), front, Some(writer)), log);
+
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        text.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_filter_directives() {
+        let levels = |filter: &str| {
+            json_lines(filter, || {
+                tracing::warn!(target: "notes2vec::search", "warn");
+                tracing::info!(target: "notes2vec::search", "info");
+                tracing::debug!(target: "notes2vec::search", "debug");
+                tracing::debug!(target: "notes2vec::indexing::pipeline", "pipeline debug");
+                tracing::error!(target: "redb", "redb error");
+            })
+            .iter()
+            .map(|line| line["fields"]["message"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(levels(QUIET_FILTER), vec!["warn", "redb error"]);
+        assert_eq!(
+            levels("info, notes2vec::indexing=debug ,redb=off"),
+            vec!["warn", "info", "pipeline debug"]
+        );
+        assert!(levels("off").is_empty());
+
+        assert!(parse_filter("notes2vec=loud").is_err());
+    }
+
+    #[test]
+    fn test_json_lines_carry_spans_and_fields() {
+        let lines = json_lines("debug", || {
+            let _span = tracing::info_span!("index_file", file = "notes/plan.md").entered();
+            tracing::debug!(chunks = 3, "Parsed");
+            tracing::trace!("Too quiet to keep");
+        });
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "DEBUG");
+        assert_eq!(lines[0]["fields"]["message"], "Parsed");
+        assert_eq!(lines[0]["fields"]["chunks"], 3);
+        assert_eq!(lines[0]["spans"][0]["name"], "index_file");
+        assert_eq!(lines[0]["spans"][0]["file"], "notes/plan.md");
+    }
+
+    #[test]
+    fn test_capture_keeps_warnings_for_the_console() {
+        let front = LogFront::default();
+        front.capture();
+        let logger = subscriber(parse_filter("info").unwrap(), front.clone(), None);
+        tracing::subscriber::with_default(logger, || {
+            tracing::info!("Loaded model");
+            tracing::warn!(file = "a.md", "Could not check for changes");
+            tracing::error!("Failed to index b.md");
+        });
+
+        let records = front.drain();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].to_string(), "⚠ Warning: Could not check for changes file=a.md");
+        assert_eq!(records[1].level, Level::ERROR);
+        assert!(front.drain().is_empty());
+        assert!(front.release().is_empty());
+    }
+}
@@ -1,5 +1,9 @@
 pub mod config;
+pub mod doctor;
+pub mod duration;
 pub mod error;
+pub mod logging;
+pub mod sample;
 
 pub use error::{Error, Result};
 
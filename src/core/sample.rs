@@ -0,0 +1,94 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Small seedable generator (SplitMix64) for picking samples; not for anything cryptographic.
+/// The same seed always yields the same sequence, so `--seed` makes sampled output reproducible.
+#[derive(Debug, Clone)]
+pub struct SampleRng {
+    state: u64,
+}
+
+impl SampleRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeded with `seed` when given, otherwise from the clock
+    pub fn from_seed_or_clock(seed: Option<u64>) -> Self {
+        Self::new(seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        }))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound` (0 when `bound` is 0)
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        // Reject the uneven tail so small bounds aren't biased
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Up to `k` items chosen uniformly from `items` in one pass (reservoir sampling), without
+/// collecting the rest. Picks keep the order they were seen in.
+pub fn sample<T>(items: impl IntoIterator<Item = T>, k: usize, rng: &mut SampleRng) -> Vec<T> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut picked: Vec<(usize, T)> = Vec::with_capacity(k);
+    for (seen, item) in items.into_iter().enumerate() {
+        if picked.len() < k {
+            picked.push((seen, item));
+        } else {
+            let slot = rng.below(seen as u64 + 1) as usize;
+            if slot < k {
+                picked[slot] = (seen, item);
+            }
+        }
+    }
+    picked.sort_by_key(|(seen, _)| *seen);
+    picked.into_iter().map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_samples_the_same_items() {
+        let first = sample(0..10_000, 20, &mut SampleRng::new(42));
+        let second = sample(0..10_000, 20, &mut SampleRng::new(42));
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 20);
+        assert!(first.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let other = sample(0..10_000, 20, &mut SampleRng::new(43));
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_sample_smaller_input_returns_everything() {
+        let mut rng = SampleRng::new(7);
+        assert_eq!(sample(["a", "b", "c"], 5, &mut rng), vec!["a", "b", "c"]);
+        assert!(sample(0..100, 0, &mut rng).is_empty());
+        assert_eq!(rng.below(0), 0);
+        assert!((0..1000).all(|_| rng.below(6) < 6));
+    }
+}
@@ -0,0 +1,199 @@
+//! Indexing notes straight from a `.zip` archive (`notes2vec index vault.zip`).
+//!
+//! Nothing is extracted: each supported entry is read into memory, parsed like a file of the
+//! same name, and stored under its path inside the archive, so results point at
+//! `Projects/roadmap.md` rather than a temporary copy. Entries that escape the archive root
+//! (absolute paths, `..`) are skipped, and hidden and ignored folders are left out as they
+//! would be on disk.
+
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use crate::indexing::discovery::{is_excluded, is_notes_file, DiscoveryOptions};
+use crate::indexing::parser::{parse_note_bytes_with, ChunkingOptions, ParsedDocument};
+use crate::indexing::pipeline::{index_document, IndexReport, WarningKind};
+use crate::search::model::EmbeddingModel;
+use crate::storage::state::get_file_modified_time;
+use crate::storage::vectors::VectorStore;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+/// Largest note read out of an archive. Sizes in the zip's headers aren't trusted, so this
+/// bounds what is actually decompressed.
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// A note read from an archive
+#[derive(Debug)]
+pub struct ArchiveNote {
+    /// Path inside the archive, with `/` separators; the note's key in the index
+    pub path: String,
+    /// The parsed note, or why it couldn't be read
+    pub document: Result<ParsedDocument>,
+}
+
+/// Whether `path` names an archive `index` reads notes from
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Parse every supported note in the zip archive `reader`, sorted by path
+pub fn read_archive_notes<R: Read + Seek>(reader: R, config: &Config) -> Result<Vec<ArchiveNote>> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| Error::Parsing(format!("Failed to open zip archive: {}", e)))?;
+    let discovery = DiscoveryOptions::from_config(config);
+    let chunking = ChunkingOptions::from_config(config);
+
+    let mut notes = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| Error::Parsing(format!("Failed to read zip entry: {}", e)))?;
+        if entry.is_dir() {
+            continue;
+        }
+        // enclosed_name rejects absolute paths and `..` escapes
+        let Some(name) = entry.enclosed_name().map(|p| p.to_path_buf()) else {
+            continue;
+        };
+        if !is_notes_file(&name) || is_excluded(&name, &discovery) {
+            continue;
+        }
+        let Some(path) = name.to_str().map(|p| p.replace('\\', "/")) else {
+            continue;
+        };
+
+        let mut bytes = Vec::new();
+        let document = match (&mut entry).take(MAX_ENTRY_BYTES + 1).read_to_end(&mut bytes) {
+            Ok(_) if bytes.len() as u64 > MAX_ENTRY_BYTES => Err(Error::Parsing(format!(
+                "Note is larger than {} MB",
+                MAX_ENTRY_BYTES / (1024 * 1024)
+            ))),
+            Ok(_) => parse_note_bytes_with(bytes, &name, chunking),
+            Err(e) => Err(e.into()),
+        };
+        notes.push(ArchiveNote { path, document });
+    }
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(notes)
+}
+
+/// Index every supported note in the archive at `archive_path`. Notes that fail are
+/// recorded as skipped in the report rather than stopping the run.
+pub fn index_archive(
+    archive_path: &Path,
+    config: &Config,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+    mut on_note: impl FnMut(&str, &IndexReport),
+) -> Result<IndexReport> {
+    let file = std::fs::File::open(archive_path)?;
+    let notes = read_archive_notes(std::io::BufReader::new(file), config)?;
+    // Entries' own timestamps have no time zone, so `--changed-since` goes by the archive's
+    let modified = get_file_modified_time(archive_path).ok();
+
+    let mut report = IndexReport::default();
+    for note in notes {
+        let indexed = note
+            .document
+            .and_then(|doc| index_document(doc, &note.path, modified, config, model, vector_store, None));
+        match indexed {
+            Ok(indexed) => {
+                report.processed += 1;
+                report.chunks_indexed += indexed.chunks_stored;
+                report.record_truncated(&indexed, model.max_tokens());
+                if indexed.truncated() {
+                    report.warn(
+                        note.path.as_str(),
+                        WarningKind::ChunkLimit,
+                        format!(
+                            "Stored the first {} of {} chunks (max_chunks_per_file)",
+                            indexed.chunks_stored,
+                            indexed.chunks_stored + indexed.chunks_dropped
+                        ),
+                        false,
+                    );
+                }
+                if let Some(error) = &indexed.frontmatter_error {
+                    report.warn(
+                        note.path.as_str(),
+                        WarningKind::InvalidFrontmatter,
+                        format!("Ignored frontmatter that isn't valid YAML: {}", error),
+                        false,
+                    );
+                }
+            }
+            Err(e) => report.warn(note.path.as_str(), WarningKind::from_error(&e), e.to_string(), true),
+        }
+        on_note(&note.path, &report);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::pipeline::store_document;
+    use crate::search::files::best_chunk_per_file_by_embedding;
+    use crate::search::options::SearchOptions;
+    use std::io::{Cursor, Write};
+    use tempfile::TempDir;
+
+    fn zip_of(entries: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, content) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        let mut cursor = writer.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn test_archive_notes_are_searchable_by_their_archive_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        let archive = zip_of(&[
+            ("vault/Projects/roadmap.md", "# Roadmap\n\nShip the sync engine by spring.\n"),
+            ("vault/journal.md", "# Journal\n\nWalked along the river today.\n"),
+            ("vault/cover.png", "not a note"),
+            ("vault/.obsidian/workspace.md", "# Hidden\n"),
+        ]);
+        let notes = read_archive_notes(archive, &config).unwrap();
+        let paths: Vec<&str> = notes.iter().map(|note| note.path.as_str()).collect();
+        assert_eq!(paths, vec!["vault/Projects/roadmap.md", "vault/journal.md"]);
+
+        // Stand-in embeddings (one axis per note) in place of the model
+        for (axis, note) in notes.iter().enumerate() {
+            let doc = note.document.as_ref().unwrap();
+            assert!(!doc.chunks.is_empty());
+            let embedding = if axis == 0 { vec![1.0, 0.0] } else { vec![0.0, 1.0] };
+            let embeddings = vec![embedding; doc.chunks.len()];
+            store_document(doc, &note.path, embeddings, None, &config, &store).unwrap();
+        }
+
+        for (query, expected) in [([1.0, 0.0], "vault/Projects/roadmap.md"), ([0.0, 1.0], "vault/journal.md")] {
+            let results = best_chunk_per_file_by_embedding(&query, &SearchOptions::new(1), &store).unwrap();
+            assert_eq!(results[0].0.file_path, expected);
+        }
+        assert!(store.get_file_vectors("vault/journal.md").unwrap()[0].text.contains("river"));
+    }
+
+    #[test]
+    fn test_unreadable_entry_is_reported_not_fatal() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        let archive = zip_of(&[("binary.md", "\0\0\0\0"), ("ok.txt", "Plain text note\n")]);
+
+        let notes = read_archive_notes(archive, &config).unwrap();
+        assert_eq!(notes.len(), 2);
+        assert!(matches!(notes[0].document, Err(Error::BinaryContent(_))));
+        assert!(notes[1].document.is_ok());
+        assert!(read_archive_notes(Cursor::new(b"not a zip".to_vec()), &config).is_err());
+    }
+}
@@ -1,6 +1,13 @@
+use crate::core::config::Config;
 use crate::core::error::{Error, Result};
 use ignore::WalkBuilder;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+/// Directories never indexed, even with `include_hidden`: notes2vec's own data and git internals
+pub const ALWAYS_EXCLUDED_DIRS: &[&str] = &[".notes2vec", ".git"];
+
+/// Default `ignore_dirs`: folders that hold tooling or app data rather than notes
+pub const DEFAULT_IGNORE_DIRS: &[&str] = &["node_modules", ".obsidian", ".trash"];
 
 /// Represents a discovered file with its metadata
 #[derive(Debug, Clone)]
@@ -8,10 +15,163 @@ pub struct DiscoveredFile {
     pub path: PathBuf,
     pub relative_path: PathBuf,
     pub is_markdown: bool,
+    /// Size in bytes at discovery time (0 if it couldn't be read)
+    pub size: u64,
+}
+
+/// Why discovery left a path out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SkipReason {
+    PermissionDenied,
+    /// A symlink whose target doesn't exist
+    BrokenSymlink,
+    /// A symlink leading back to one of its own ancestors
+    SymlinkLoop,
+    /// Any other error reading the entry
+    Unreadable,
+}
+
+impl SkipReason {
+    /// Short human-readable label used in grouped summaries
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkipReason::PermissionDenied => "permission denied",
+            SkipReason::BrokenSymlink => "broken symlink",
+            SkipReason::SymlinkLoop => "symlink loop",
+            SkipReason::Unreadable => "unreadable",
+        }
+    }
+
+    /// Classify a walk error, with the path it refers to when the error names one
+    fn from_walk_error(error: &ignore::Error) -> (Option<PathBuf>, Self) {
+        match error {
+            ignore::Error::WithPath { path, err } => (Some(path.clone()), Self::from_walk_error(err).1),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                Self::from_walk_error(err)
+            }
+            ignore::Error::Partial(errors) => errors
+                .first()
+                .map(Self::from_walk_error)
+                .unwrap_or((None, SkipReason::Unreadable)),
+            ignore::Error::Loop { child, .. } => (Some(child.clone()), SkipReason::SymlinkLoop),
+            ignore::Error::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied => (None, SkipReason::PermissionDenied),
+            ignore::Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound => (None, SkipReason::BrokenSymlink),
+            _ => (None, SkipReason::Unreadable),
+        }
+    }
 }
 
-/// Discover all note files in a directory, respecting .gitignore rules
+/// Files found by discovery, and the paths it had to skip
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryReport {
+    pub files: Vec<DiscoveredFile>,
+    /// Paths left out because they couldn't be read, with the reason (in walk order)
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+}
+
+impl DiscoveryReport {
+    /// Just the files, for callers that don't report skipped paths
+    pub fn into_files(self) -> Vec<DiscoveredFile> {
+        self.files
+    }
+
+    /// Grouped one-line summary of the skipped paths, e.g.
+    /// "3 paths skipped: 2 permission denied, 1 broken symlink"
+    pub fn skipped_summary(&self) -> Option<String> {
+        if self.skipped.is_empty() {
+            return None;
+        }
+        let mut counts: std::collections::BTreeMap<SkipReason, usize> = std::collections::BTreeMap::new();
+        for (_, reason) in &self.skipped {
+            *counts.entry(*reason).or_insert(0) += 1;
+        }
+        let mut groups: Vec<(SkipReason, usize)> = counts.into_iter().collect();
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let breakdown: Vec<String> = groups
+            .iter()
+            .map(|(reason, n)| format!("{} {}", n, reason.label()))
+            .collect();
+        let total = self.skipped.len();
+        Some(format!(
+            "{} path{} skipped: {}",
+            total,
+            if total == 1 { "" } else { "s" },
+            breakdown.join(", ")
+        ))
+    }
+}
+
+/// Options for directory discovery
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// Sort results by relative path so runs and platforms agree on the order.
+    /// Turn off where order doesn't matter and the extra sort is wasted work.
+    pub sort: bool,
+    /// Also discover hidden files and folders (names starting with `.`).
+    /// The root itself is always walked, even if it's hidden.
+    pub include_hidden: bool,
+    /// Directory names skipped wherever they appear below the root (whole names, not substrings)
+    pub ignore_dirs: Vec<String>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            sort: true,
+            include_hidden: false,
+            ignore_dirs: DEFAULT_IGNORE_DIRS.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    /// Defaults with the `include_hidden` and `ignore_dirs` settings applied
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            include_hidden: config.include_hidden,
+            ignore_dirs: config.ignore_dirs.clone(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether a directory called `name` is skipped along with everything in it
+    fn ignores_dir(&self, name: &str) -> bool {
+        ALWAYS_EXCLUDED_DIRS.contains(&name) || self.ignore_dirs.iter().any(|d| d == name)
+    }
+}
+
+/// Whether a path relative to the discovery root is left out by `options`
+/// (for paths that don't come from a walk, such as watcher events)
+pub fn is_excluded(relative_path: &Path, options: &DiscoveryOptions) -> bool {
+    let components: Vec<Component> = relative_path.components().collect();
+    let last = components.len().saturating_sub(1);
+    components.iter().enumerate().any(|(i, component)| match component {
+        Component::Normal(name) => {
+            let name = name.to_string_lossy();
+            // Every component but the last is a directory
+            (i < last && options.ignores_dir(&name))
+                || ALWAYS_EXCLUDED_DIRS.contains(&name.as_ref())
+                || (!options.include_hidden && name.starts_with('.'))
+        }
+        _ => false,
+    })
+}
+
+/// Discover all note files in a directory, respecting .gitignore rules and skipping
+/// hidden files. Results are sorted by relative path.
 pub fn discover_files(root: &Path) -> Result<Vec<DiscoveredFile>> {
+    discover_files_with(root, DiscoveryOptions::default())
+}
+
+/// Discover all note files in a directory with explicit options.
+/// Unreadable entries are left out silently; see [`discover_files_with_report`] to list them.
+pub fn discover_files_with(root: &Path, options: DiscoveryOptions) -> Result<Vec<DiscoveredFile>> {
+    discover_files_with_report(root, options).map(DiscoveryReport::into_files)
+}
+
+/// Discover all note files in a directory, recording every path that had to be skipped
+/// (permission errors, broken symlinks, symlink loops) instead of stopping at it
+pub fn discover_files_with_report(root: &Path, options: DiscoveryOptions) -> Result<DiscoveryReport> {
     if !root.exists() {
         return Err(Error::Config(format!(
             "Directory does not exist: {}",
@@ -26,13 +186,20 @@ pub fn discover_files(root: &Path) -> Result<Vec<DiscoveredFile>> {
         )));
     }
 
-    let mut files = Vec::new();
+    let mut report = DiscoveryReport::default();
+
+    // filter_entry needs its own copy of the options ('static closure)
+    let ignored = options.clone();
 
     // Use ignore crate to walk directory respecting .gitignore
     let walker = WalkBuilder::new(root)
-        .hidden(false) // We want to process hidden files (like .notes)
+        .hidden(!options.include_hidden)
         .git_ignore(true)
         .git_exclude(true)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            !(is_dir && entry.depth() > 0 && ignored.ignores_dir(&entry.file_name().to_string_lossy()))
+        })
         .build();
 
     for result in walker {
@@ -45,6 +212,12 @@ pub fn discover_files(root: &Path) -> Result<Vec<DiscoveredFile>> {
                     continue;
                 }
 
+                // Links aren't followed, so a dangling one would only fail later when read
+                if entry.path_is_symlink() && std::fs::metadata(path).is_err() {
+                    report.skipped.push((path.to_path_buf(), SkipReason::BrokenSymlink));
+                    continue;
+                }
+
                 // Check if it's a supported notes file
                 let is_markdown = is_notes_file(path);
                 
@@ -57,21 +230,28 @@ pub fn discover_files(root: &Path) -> Result<Vec<DiscoveredFile>> {
                         )))?
                         .to_path_buf();
 
-                    files.push(DiscoveredFile {
+                    report.files.push(DiscoveredFile {
                         path: path.to_path_buf(),
                         relative_path,
                         is_markdown: true,
+                        size: entry.metadata().map(|m| m.len()).unwrap_or(0),
                     });
                 }
             }
             Err(err) => {
-                // Log but continue - some files might be inaccessible
-                eprintln!("Warning: Failed to access file: {}", err);
+                // Keep walking; the caller decides how to report what was skipped
+                let (path, reason) = SkipReason::from_walk_error(&err);
+                report.skipped.push((path.unwrap_or_else(|| root.to_path_buf()), reason));
             }
         }
     }
 
-    Ok(files)
+    // The walk order of the ignore crate varies between runs and platforms
+    if options.sort {
+        report.files.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    }
+
+    Ok(report)
 }
 
 /// Check if a file is a supported notes file based on extension
@@ -81,7 +261,7 @@ pub fn is_notes_file(path: &Path) -> bool {
         .map(|ext| {
             matches!(
                 ext.to_lowercase().as_str(),
-                "md" | "markdown" | "mdown" | "mkd" | "mkdn" | "txt"
+                "md" | "markdown" | "mdown" | "mkd" | "mkdn" | "mdx" | "txt"
             )
         })
         .unwrap_or(false)
@@ -101,6 +281,7 @@ mod tests {
         assert!(is_notes_file(Path::new("test.mdown")));
         assert!(is_notes_file(Path::new("test.mkd")));
         assert!(is_notes_file(Path::new("test.mkdn")));
+        assert!(is_notes_file(Path::new("page.MDX")));
         assert!(is_notes_file(Path::new("test.txt")));
         assert!(!is_notes_file(Path::new("test")));
         assert!(!is_notes_file(Path::new("test.js")));
@@ -196,6 +377,33 @@ mod tests {
                 relative_paths.contains(&"subdir\\nested.md".to_string())); // Windows vs Unix
     }
 
+    #[test]
+    fn test_discover_files_sorted() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_dir = temp_dir.path().join("notes");
+        fs::create_dir_all(test_dir.join("b")).unwrap();
+        fs::create_dir_all(test_dir.join("a")).unwrap();
+
+        for name in ["zeta.md", "b/beta.md", "alpha.md", "a/zulu.md", "m.txt"] {
+            fs::write(test_dir.join(name), "# Test").unwrap();
+        }
+
+        let relative: Vec<PathBuf> = discover_files(&test_dir)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path)
+            .collect();
+        let expected: Vec<PathBuf> = ["a/zulu.md", "alpha.md", "b/beta.md", "m.txt", "zeta.md"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(relative, expected);
+
+        // Unsorted discovery finds the same files
+        let unsorted = discover_files_with(&test_dir, DiscoveryOptions { sort: false, ..DiscoveryOptions::default() }).unwrap();
+        assert_eq!(unsorted.len(), expected.len());
+    }
+
     #[test]
     fn test_discover_files_nonexistent_directory() {
         let result = discover_files(Path::new("/nonexistent/directory"));
@@ -233,5 +441,115 @@ mod tests {
             assert!(file.is_markdown);
         }
     }
-}
 
+    #[test]
+    fn test_hidden_files_follow_include_hidden() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_dir = temp_dir.path().join("notes");
+        fs::create_dir_all(test_dir.join(".drafts")).unwrap();
+        fs::create_dir_all(test_dir.join(".git")).unwrap();
+        fs::create_dir_all(test_dir.join(".notes2vec")).unwrap();
+
+        fs::write(test_dir.join("visible.md"), "# Visible").unwrap();
+        fs::write(test_dir.join(".secret.md"), "# Secret").unwrap();
+        fs::write(test_dir.join(".drafts").join("draft.md"), "# Draft").unwrap();
+        fs::write(test_dir.join(".git").join("notes.md"), "# Git internals").unwrap();
+        fs::write(test_dir.join(".notes2vec").join("scratch.md"), "# Scratch").unwrap();
+
+        let relative = |options: DiscoveryOptions| -> Vec<String> {
+            discover_files_with(&test_dir, options)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.relative_path.to_str().unwrap().replace('\\', "/"))
+                .collect()
+        };
+
+        assert_eq!(relative(DiscoveryOptions::default()), vec!["visible.md"]);
+        assert_eq!(
+            relative(DiscoveryOptions { include_hidden: true, ..DiscoveryOptions::default() }),
+            vec![".drafts/draft.md", ".secret.md", "visible.md"]
+        );
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let options = DiscoveryOptions::default();
+        let hidden = DiscoveryOptions { include_hidden: true, ..options.clone() };
+        assert!(!is_excluded(Path::new("notes/todo.md"), &options));
+        assert!(is_excluded(Path::new(".secret.md"), &options));
+        assert!(!is_excluded(Path::new(".secret.md"), &hidden));
+        assert!(is_excluded(Path::new(".git/notes.md"), &hidden));
+        assert!(is_excluded(Path::new("sub/.notes2vec/scratch.md"), &hidden));
+        assert!(is_excluded(Path::new("web/node_modules/pkg/README.md"), &options));
+        // A note that happens to share an ignored directory's name is still a note
+        assert!(!is_excluded(Path::new("node_modules"), &options));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_symlinks_are_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_dir = temp_dir.path().join("notes");
+        fs::create_dir_all(&test_dir).unwrap();
+        fs::write(test_dir.join("real.md"), "# Real").unwrap();
+        std::os::unix::fs::symlink(test_dir.join("gone.md"), test_dir.join("dangling.md")).unwrap();
+
+        let report = discover_files_with_report(&test_dir, DiscoveryOptions::default()).unwrap();
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.skipped, vec![(test_dir.join("dangling.md"), SkipReason::BrokenSymlink)]);
+        assert_eq!(report.skipped_summary().unwrap(), "1 path skipped: 1 broken symlink");
+        assert_eq!(discover_files(&test_dir).unwrap().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directory_is_reported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_dir = temp_dir.path().join("notes");
+        let locked = test_dir.join("locked");
+        fs::create_dir_all(&locked).unwrap();
+        fs::write(test_dir.join("open.md"), "# Open").unwrap();
+        fs::write(locked.join("secret.md"), "# Secret").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Root reads it anyway; nothing to check then
+        let readable = fs::read_dir(&locked).is_ok();
+        let report = discover_files_with_report(&test_dir, DiscoveryOptions::default());
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        if readable {
+            return;
+        }
+
+        let report = report.unwrap();
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.skipped, vec![(locked, SkipReason::PermissionDenied)]);
+    }
+
+    #[test]
+    fn test_ignored_dir_names_are_pruned() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_dir = temp_dir.path().join("notes");
+        fs::create_dir_all(test_dir.join("templates")).unwrap();
+        fs::create_dir_all(test_dir.join("project").join("node_modules").join("pkg")).unwrap();
+        fs::create_dir_all(test_dir.join("my-templates")).unwrap();
+
+        fs::write(test_dir.join("index.md"), "# Index").unwrap();
+        fs::write(test_dir.join("templates").join("daily.md"), "# {{date}}").unwrap();
+        fs::write(test_dir.join("project").join("node_modules").join("pkg").join("README.md"), "# Package").unwrap();
+        fs::write(test_dir.join("my-templates").join("kept.md"), "# Kept").unwrap();
+
+        let options = DiscoveryOptions {
+            ignore_dirs: vec!["templates".to_string(), "node_modules".to_string()],
+            ..DiscoveryOptions::default()
+        };
+        let relative: Vec<String> = discover_files_with(&test_dir, options)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.relative_path.to_str().unwrap().replace('\\', "/"))
+            .collect();
+        // Matched on the whole directory name, so `my-templates` stays
+        assert_eq!(relative, vec!["index.md", "my-templates/kept.md"]);
+    }
+}
@@ -0,0 +1,513 @@
+use crate::core::error::{Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// Length of the hex id Notion appends to exported page and folder names
+const NOTION_HASH_LEN: usize = 32;
+
+/// Largest file read out of an export zip. Sizes in the zip's headers aren't trusted, so
+/// this bounds what is actually decompressed.
+const MAX_ZIP_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// One file copied out of an export
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ImportMapping {
+    /// Path inside the export
+    pub source: String,
+    /// Path inside the destination notes directory
+    pub dest: String,
+    /// Intra-export links rewritten to cleaned names
+    pub links_rewritten: usize,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    pub mappings: Vec<ImportMapping>,
+    /// Where the mapping was saved (JSON) for auditing
+    pub mapping_file: PathBuf,
+}
+
+/// Copy a Notion zip / export directory or a plain-text dump into `dest`.
+///
+/// - Notion's 32-character hash suffixes are stripped from file and folder names
+/// - Markdown links between exported files are rewritten to the cleaned names
+/// - `.txt` notes become `.md` with their first line as an H1 title
+/// - Existing files in `dest` are never overwritten; clashing names get a numeric suffix
+pub fn import_notes(source: &Path, dest: &Path) -> Result<ImportReport> {
+    let files = read_export(source)?;
+    std::fs::create_dir_all(dest)?;
+
+    let sources: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    let plan = plan_destinations(&sources, dest);
+
+    let mut mappings = Vec::with_capacity(files.len());
+    for (source_rel, bytes) in files {
+        let dest_rel = &plan[&source_rel];
+        let is_txt = has_extension(&source_rel, "txt");
+        let is_markdown = is_txt || crate::indexing::discovery::is_notes_file(&source_rel);
+
+        let mut links_rewritten = 0;
+        let contents = match String::from_utf8(bytes) {
+            Ok(text) if is_markdown => {
+                let text = if is_txt { txt_to_markdown(&text) } else { text };
+                let (text, rewritten) = rewrite_links(&text, &source_rel, dest_rel, &plan);
+                links_rewritten = rewritten;
+                text.into_bytes()
+            }
+            Ok(text) => text.into_bytes(),
+            Err(e) => e.into_bytes(),
+        };
+
+        let dest_path = dest.join(dest_rel);
+        if let Some(parent) = dest_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&dest_path, contents)?;
+
+        mappings.push(ImportMapping {
+            source: to_slash(&source_rel),
+            dest: to_slash(dest_rel),
+            links_rewritten,
+        });
+    }
+    mappings.sort_by(|a, b| a.source.cmp(&b.source));
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mapping_file = dest.join(format!(".notes2vec-import-{}.json", timestamp));
+    let json = serde_json::to_string_pretty(&mappings)
+        .map_err(|e| Error::Parsing(format!("Failed to serialize import mapping: {}", e)))?;
+    std::fs::write(&mapping_file, json)?;
+
+    Ok(ImportReport {
+        mappings,
+        mapping_file,
+    })
+}
+
+/// Read every file of an export (zip archive or directory) as (relative path, bytes)
+fn read_export(source: &Path) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    if source.is_dir() {
+        let mut files = Vec::new();
+        read_dir_recursive(source, source, &mut files)?;
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
+    } else if has_extension(source, "zip") {
+        read_zip(source, MAX_ZIP_ENTRY_BYTES)
+    } else if source.is_file() {
+        // A single exported note
+        let name = source
+            .file_name()
+            .map(PathBuf::from)
+            .ok_or_else(|| Error::Config(format!("Invalid import path: {}", source.display())))?;
+        Ok(vec![(name, std::fs::read(source)?)])
+    } else {
+        Err(Error::Config(format!(
+            "Import source does not exist: {}",
+            source.display()
+        )))
+    }
+}
+
+fn read_dir_recursive(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, Vec<u8>)>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            read_dir_recursive(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push((relative.to_path_buf(), std::fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+fn read_zip(path: &Path, max_entry_bytes: u64) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let file = std::fs::File::open(path)?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| Error::Parsing(format!("Failed to open zip {}: {}", path.display(), e)))?;
+
+    let mut files = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| Error::Parsing(format!("Failed to read zip entry: {}", e)))?;
+        if entry.is_dir() {
+            continue;
+        }
+        // enclosed_name rejects absolute paths and `..` escapes
+        let Some(name) = entry.enclosed_name().map(|p| p.to_path_buf()) else {
+            continue;
+        };
+        let mut bytes = Vec::new();
+        (&mut entry).take(max_entry_bytes + 1).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > max_entry_bytes {
+            return Err(Error::Parsing(format!(
+                "{} in {} is larger than {} MB",
+                name.display(),
+                path.display(),
+                max_entry_bytes / (1024 * 1024)
+            )));
+        }
+        files.push((name, bytes));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// Map every exported path to a cleaned, collision-free destination path
+fn plan_destinations(sources: &[PathBuf], dest: &Path) -> HashMap<PathBuf, PathBuf> {
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+    let mut plan = HashMap::with_capacity(sources.len());
+
+    for source in sources {
+        let cleaned = clean_relative_path(source);
+        let mut candidate = cleaned.clone();
+        let mut n = 2;
+        while taken.contains(&candidate) || dest.join(&candidate).exists() {
+            candidate = with_suffix(&cleaned, n);
+            n += 1;
+        }
+        taken.insert(candidate.clone());
+        plan.insert(source.clone(), candidate);
+    }
+
+    plan
+}
+
+/// Clean every component of an exported path; `.txt` files become `.md`
+pub fn clean_relative_path(path: &Path) -> PathBuf {
+    let components: Vec<&str> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect();
+
+    let mut cleaned = PathBuf::new();
+    for (i, component) in components.iter().enumerate() {
+        if i + 1 == components.len() {
+            cleaned.push(clean_file_name(component));
+        } else {
+            cleaned.push(strip_notion_hash(component));
+        }
+    }
+    cleaned
+}
+
+/// "Roadmap 0123456789abcdef0123456789abcdef.md" -> "Roadmap.md"
+pub fn clean_file_name(name: &str) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or(name);
+    let ext = path.extension().and_then(|e| e.to_str());
+    let stem = strip_notion_hash(stem);
+    match ext {
+        Some(ext) if ext.eq_ignore_ascii_case("txt") => format!("{}.md", stem),
+        Some(ext) => format!("{}.{}", stem, ext),
+        None => stem.to_string(),
+    }
+}
+
+/// Strip a trailing " <32 hex chars>" Notion id, keeping the name if nothing would remain
+pub fn strip_notion_hash(name: &str) -> &str {
+    let Some((base, hash)) = name.rsplit_once(' ') else {
+        return name;
+    };
+    if hash.len() == NOTION_HASH_LEN && hash.chars().all(|c| c.is_ascii_hexdigit()) && !base.trim().is_empty() {
+        base.trim_end()
+    } else {
+        name
+    }
+}
+
+fn with_suffix(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("note");
+    let name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{} {}.{}", stem, n, ext),
+        None => format!("{} {}", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// Plain-text export: the first non-empty line becomes the H1 title
+pub fn txt_to_markdown(content: &str) -> String {
+    let trimmed = content.trim_start_matches(['\r', '\n']);
+    let (first, rest) = match trimmed.split_once('\n') {
+        Some((first, rest)) => (first.trim_end_matches('\r'), rest),
+        None => (trimmed, ""),
+    };
+    if first.trim().is_empty() || first.starts_with('#') {
+        return content.to_string();
+    }
+    let rest = rest.trim_start_matches(['\r', '\n']);
+    if rest.is_empty() {
+        format!("# {}\n", first.trim())
+    } else {
+        format!("# {}\n\n{}", first.trim(), rest)
+    }
+}
+
+/// Rewrite `[text](target)` links that point at other exported files.
+/// Returns the new content and the number of links rewritten.
+pub fn rewrite_links(
+    content: &str,
+    source_rel: &Path,
+    dest_rel: &Path,
+    plan: &HashMap<PathBuf, PathBuf>,
+) -> (String, usize) {
+    let source_dir = source_rel.parent().unwrap_or(Path::new(""));
+    let dest_dir = dest_rel.parent().unwrap_or(Path::new(""));
+
+    let mut out = String::with_capacity(content.len());
+    let mut rewritten = 0;
+    let mut rest = content;
+
+    while let Some(start) = rest.find("](") {
+        let target_start = start + 2;
+        let Some(len) = rest[target_start..].find([')', '\n']) else {
+            break;
+        };
+        let target = &rest[target_start..target_start + len];
+        out.push_str(&rest[..target_start]);
+
+        match rewrite_target(target, source_dir, dest_dir, plan) {
+            Some(new_target) => {
+                out.push_str(&new_target);
+                rewritten += 1;
+            }
+            None => out.push_str(target),
+        }
+        rest = &rest[target_start + len..];
+    }
+    out.push_str(rest);
+
+    (out, rewritten)
+}
+
+fn rewrite_target(
+    target: &str,
+    source_dir: &Path,
+    dest_dir: &Path,
+    plan: &HashMap<PathBuf, PathBuf>,
+) -> Option<String> {
+    if target.is_empty() || target.starts_with('#') || target.contains("://") || target.starts_with("mailto:") {
+        return None;
+    }
+    let (path_part, anchor) = match target.split_once('#') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (target, None),
+    };
+
+    let linked = normalize(&source_dir.join(percent_decode(path_part)));
+    let linked_dest = plan.get(&linked)?;
+
+    let mut new_target = encode_link(&relative_path(dest_dir, linked_dest));
+    if let Some(anchor) = anchor {
+        new_target.push('#');
+        new_target.push_str(anchor);
+    }
+    Some(new_target)
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut parts: Vec<&std::ffi::OsStr> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(s) => parts.push(s),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+    parts.iter().collect()
+}
+
+/// Path to `to` relative to the directory `from_dir` (both relative to the same root)
+fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from_dir.components().collect();
+    let target: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(target.iter()).take_while(|(a, b)| a == b).count();
+
+    let mut result = PathBuf::new();
+    for _ in common..from.len() {
+        result.push("..");
+    }
+    for component in &target[common..] {
+        result.push(component.as_os_str());
+    }
+    result
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(byte) = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Forward slashes, with characters that break Markdown link targets escaped
+fn encode_link(path: &Path) -> String {
+    to_slash(path)
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case(ext))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const HASH_A: &str = "0123456789abcdef0123456789abcdef";
+    const HASH_B: &str = "fedcba9876543210fedcba9876543210";
+
+    #[test]
+    fn test_strip_notion_hash() {
+        assert_eq!(strip_notion_hash(&format!("Projects {}", HASH_A)), "Projects");
+        assert_eq!(strip_notion_hash("Projects"), "Projects");
+        assert_eq!(strip_notion_hash("Release 2024"), "Release 2024");
+        assert_eq!(strip_notion_hash(&format!(" {}", HASH_A)), format!(" {}", HASH_A));
+        assert_eq!(clean_file_name(&format!("Roadmap {}.md", HASH_B)), "Roadmap.md");
+        assert_eq!(clean_file_name("Groceries.txt"), "Groceries.md");
+    }
+
+    #[test]
+    fn test_txt_to_markdown() {
+        assert_eq!(txt_to_markdown("Groceries\nmilk\neggs\n"), "# Groceries\n\nmilk\neggs\n");
+        assert_eq!(txt_to_markdown("\n\nTitle only"), "# Title only\n");
+        assert_eq!(txt_to_markdown("# Already a title\nbody"), "# Already a title\nbody");
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path(Path::new(""), Path::new("a/b.md")), PathBuf::from("a/b.md"));
+        assert_eq!(relative_path(Path::new("a"), Path::new("b.md")), PathBuf::from("../b.md"));
+        assert_eq!(relative_path(Path::new("a/x"), Path::new("a/y/c.md")), PathBuf::from("../y/c.md"));
+    }
+
+    #[test]
+    fn test_import_notion_style_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let export = temp_dir.path().join("export");
+        let page_dir = export.join(format!("Projects {}", HASH_A));
+        fs::create_dir_all(&page_dir).unwrap();
+
+        fs::write(
+            export.join(format!("Projects {}.md", HASH_A)),
+            format!(
+                "# Projects\n\nSee [Roadmap](Projects%20{}/Roadmap%20{}.md#q3) and [site](https://example.com).\n",
+                HASH_A, HASH_B
+            ),
+        )
+        .unwrap();
+        fs::write(
+            page_dir.join(format!("Roadmap {}.md", HASH_B)),
+            format!("# Roadmap\n\nBack to [Projects](../Projects%20{}.md).\n", HASH_A),
+        )
+        .unwrap();
+        fs::write(export.join("Groceries.txt"), "Groceries\nmilk\n").unwrap();
+
+        let dest = temp_dir.path().join("notes");
+        let report = import_notes(&export, &dest).unwrap();
+
+        let projects = fs::read_to_string(dest.join("Projects.md")).unwrap();
+        assert!(projects.contains("[Roadmap](Projects/Roadmap.md#q3)"));
+        assert!(projects.contains("[site](https://example.com)"));
+
+        let roadmap = fs::read_to_string(dest.join("Projects").join("Roadmap.md")).unwrap();
+        assert!(roadmap.contains("[Projects](../Projects.md)"));
+
+        let groceries = fs::read_to_string(dest.join("Groceries.md")).unwrap();
+        assert!(groceries.starts_with("# Groceries\n"));
+
+        assert_eq!(report.mappings.len(), 3);
+        let projects_mapping = report
+            .mappings
+            .iter()
+            .find(|m| m.dest == "Projects.md")
+            .unwrap();
+        assert_eq!(projects_mapping.source, format!("Projects {}.md", HASH_A));
+        assert_eq!(projects_mapping.links_rewritten, 1);
+
+        // The mapping is saved for auditing
+        let saved = fs::read_to_string(&report.mapping_file).unwrap();
+        assert!(saved.contains("Projects/Roadmap.md"));
+    }
+
+    #[test]
+    fn test_import_does_not_overwrite_existing_notes() {
+        let temp_dir = TempDir::new().unwrap();
+        let export = temp_dir.path().join("export");
+        fs::create_dir_all(&export).unwrap();
+        fs::write(export.join(format!("Ideas {}.md", HASH_A)), "# Imported").unwrap();
+
+        let dest = temp_dir.path().join("notes");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("Ideas.md"), "# Mine").unwrap();
+
+        let report = import_notes(&export, &dest).unwrap();
+        assert_eq!(report.mappings[0].dest, "Ideas 2.md");
+        assert_eq!(fs::read_to_string(dest.join("Ideas.md")).unwrap(), "# Mine");
+    }
+
+    #[test]
+    fn test_import_from_zip() {
+        use std::io::Write;
+
+        let temp_dir = TempDir::new().unwrap();
+        let zip_path = temp_dir.path().join("export.zip");
+        {
+            let file = fs::File::create(&zip_path).unwrap();
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            writer
+                .start_file(format!("Journal {}/Day one {}.md", HASH_A, HASH_B), options)
+                .unwrap();
+            writer.write_all(b"# Day one\n\nStarted the journal.\n").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let dest = temp_dir.path().join("notes");
+        let report = import_notes(&zip_path, &dest).unwrap();
+        assert_eq!(report.mappings[0].dest, "Journal/Day one.md");
+        assert!(dest.join("Journal").join("Day one.md").exists());
+
+        // Entries past the size limit are refused, whatever the header claims
+        assert_eq!(read_zip(&zip_path, 64).unwrap().len(), 1);
+        assert!(matches!(read_zip(&zip_path, 16), Err(Error::Parsing(_))));
+    }
+}
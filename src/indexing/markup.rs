@@ -0,0 +1,224 @@
+//! Markup that isn't prose, taken out of a note before its Markdown is parsed.
+//!
+//! HTML comments go from every note. MDX notes (`.mdx`) also lose the `import`/`export`
+//! statements at the top, JSX tags (`<Callout type="tip">`, `</Callout>`, `<Chart />`) and
+//! `{/* */}` comments, keeping the text between tags, and the indentation of every line: MDX has
+//! no indented code blocks, so indented JSX children would otherwise parse as code. Code spans
+//! and fenced code blocks are left alone. Line breaks are always kept, so line numbers still
+//! match the file, and [`Stripped::source_offset`] maps positions back to byte offsets in it.
+
+/// A note's text with markup taken out
+#[derive(Debug, Clone, Default)]
+pub struct Stripped {
+    pub text: String,
+    /// Position in `text` of each removal, with the bytes removed up to and including it
+    removed: Vec<(usize, usize)>,
+}
+
+impl Stripped {
+    /// Byte offset in the original text of position `pos` in [`text`](Self::text). A range
+    /// starting right where something was removed starts after it; one ending there (`end`)
+    /// ends before it.
+    pub fn source_offset(&self, pos: usize, end: bool) -> usize {
+        let before = if end {
+            self.removed.partition_point(|&(at, _)| at < pos)
+        } else {
+            self.removed.partition_point(|&(at, _)| at <= pos)
+        };
+        pos + before.checked_sub(1).map_or(0, |i| self.removed[i].1)
+    }
+
+    fn keep(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Leave out `text` except for its line breaks
+    fn remove(&mut self, text: &str) {
+        for (i, piece) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.text.push('\n');
+            }
+            if !piece.is_empty() {
+                let total = self.removed.last().map_or(0, |&(_, total)| total) + piece.len();
+                self.removed.push((self.text.len(), total));
+            }
+        }
+    }
+}
+
+/// Take HTML comments out of `body`, and with `mdx` the MDX-only syntax described above
+pub fn strip_markup(body: &str, mdx: bool) -> Stripped {
+    let mut out = Stripped::default();
+    let mut rest = body;
+    if mdx {
+        let preamble = mdx_preamble_len(body);
+        out.remove(&body[..preamble]);
+        rest = &body[preamble..];
+    }
+
+    // Marker character and length of the open code fence
+    let mut fence: Option<(char, usize)> = None;
+    let mut line_start = true;
+    while !rest.is_empty() {
+        if line_start {
+            line_start = false;
+            let line_len = rest.find('\n').map_or(rest.len(), |i| i + 1);
+            let line = &rest[..line_len];
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let marker = fence_marker(&line[indent..]);
+            let in_fence = match (fence, marker) {
+                (Some((open, open_len)), Some((ch, len))) => {
+                    if ch == open && len >= open_len && line[indent + len..].trim().is_empty() {
+                        fence = None;
+                    }
+                    true
+                }
+                (Some(_), None) => true,
+                (None, Some(opened)) if indent < 4 || mdx => {
+                    fence = Some(opened);
+                    true
+                }
+                (None, _) => false,
+            };
+            if in_fence {
+                out.keep(line);
+                rest = &rest[line_len..];
+                line_start = true;
+            } else if mdx && indent > 0 {
+                out.remove(&line[..indent]);
+                rest = &rest[indent..];
+            }
+            continue;
+        }
+
+        let Some(i) = rest.find(['\n', '`', '<', '{']) else {
+            out.keep(rest);
+            break;
+        };
+        out.keep(&rest[..i]);
+        rest = &rest[i..];
+
+        let (len, keep) = if rest.starts_with('\n') {
+            line_start = true;
+            (1, true)
+        } else if rest.starts_with('`') {
+            (code_span_len(rest), true)
+        } else if rest.starts_with("<!--") {
+            (rest.find("-->").map_or(rest.len(), |end| end + 3), false)
+        } else if mdx && rest.starts_with("{/*") {
+            (rest.find("*/}").map_or(rest.len(), |end| end + 3), false)
+        } else if mdx && is_jsx_tag_start(rest) {
+            match jsx_tag_len(rest) {
+                Some(len) => (len, false),
+                None => (1, true),
+            }
+        } else {
+            (1, true)
+        };
+        if keep {
+            out.keep(&rest[..len]);
+        } else {
+            out.remove(&rest[..len]);
+        }
+        rest = &rest[len..];
+    }
+    out
+}
+
+/// Length of the `import`/`export` blocks (each running to a blank line) at the top of an MDX body
+fn mdx_preamble_len(body: &str) -> usize {
+    let mut len = 0;
+    let mut in_statement = false;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            in_statement = false;
+        } else if in_statement || ["import ", "import{", "export "].iter().any(|start| trimmed.starts_with(start)) {
+            in_statement = true;
+        } else {
+            break;
+        }
+        len += line.len();
+    }
+    len
+}
+
+/// Character and length of the run of three or more backticks or tildes opening `line`
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let ch = line.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = line.len() - line.trim_start_matches(ch).len();
+    (len >= 3).then_some((ch, len))
+}
+
+/// Length of the code span `text` opens, through its closing backticks; just the opening run
+/// when it isn't closed
+fn code_span_len(text: &str) -> usize {
+    let run = text.len() - text.trim_start_matches('`').len();
+    text[run..]
+        .find(&text[..run])
+        .map_or(run, |close| run + close + run)
+}
+
+/// `<Tag`, `</Tag`, or a fragment's `<>` or `</>`
+fn is_jsx_tag_start(text: &str) -> bool {
+    let after = text[1..].strip_prefix('/').unwrap_or(&text[1..]);
+    after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '>')
+}
+
+/// Length of the JSX tag `text` opens, through its `>`; quotes and `{...}` attribute values
+/// may hold a `>` of their own. None when the tag never closes.
+fn jsx_tag_len(text: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(open), _) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => depth = depth.saturating_sub(1),
+            (None, '>') if depth == 0 => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether `path` is an MDX note
+pub fn is_mdx(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mdx"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_go_from_every_note_and_offsets_map_back() {
+        let body = "Keep this <!-- but\nnot this --> and that.\n\n```html\n<!-- shown in code -->\n```\n";
+        let stripped = strip_markup(body, false);
+        assert_eq!(stripped.text, "Keep this \n and that.\n\n```html\n<!-- shown in code -->\n```\n");
+        let that = stripped.text.find("and that").unwrap();
+        assert_eq!(&body[stripped.source_offset(that, false)..], &body[body.find("and that").unwrap()..]);
+        // JSX is only MDX's
+        assert_eq!(strip_markup("<Callout>Hi</Callout>", false).text, "<Callout>Hi</Callout>");
+    }
+
+    #[test]
+    fn test_mdx_loses_statements_tags_and_indentation() {
+        let body = "import { Callout } from 'nextra/components'\nexport const meta = {\n  title: 'x'\n}\n\nIntro with `<Tag>` in code.\n\n<Callout type=\"warning\" onClose={() => a > b}>\n    Indented child text.\n</Callout>\n{/* editor note */}\nA <Badge /> inline and <>a fragment</>.\n";
+        let stripped = strip_markup(body, true);
+        assert_eq!(
+            stripped.text,
+            "\n\n\n\n\nIntro with `<Tag>` in code.\n\n\nIndented child text.\n\n\nA  inline and a fragment.\n"
+        );
+        assert_eq!(stripped.text.lines().count(), body.lines().count());
+
+        let child = stripped.text.find("Indented").unwrap();
+        let end = child + "Indented child text.".len();
+        let (start, end) = (stripped.source_offset(child, false), stripped.source_offset(end, true));
+        assert_eq!(&body[start..end], "Indented child text.");
+    }
+}
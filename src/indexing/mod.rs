@@ -1,3 +1,10 @@
+pub mod archive;
 pub mod discovery;
+pub mod import;
+pub mod markup;
 pub mod parser;
+pub mod pipeline;
+pub mod remove;
+pub mod text;
+pub mod verify;
 
@@ -1,5 +1,7 @@
-use crate::core::error::Result;
+use crate::core::error::{Error, Result};
+use crate::indexing::markup::{is_mdx, strip_markup};
 use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use std::ops::Range;
 use std::path::Path;
 
 /// Metadata extracted from frontmatter
@@ -7,19 +9,57 @@ use std::path::Path;
 pub struct DocumentMetadata {
     pub title: Option<String>,
     pub tags: Vec<String>,
+    pub aliases: Vec<String>,
     pub created: Option<String>,
     pub modified: Option<String>,
     pub custom: std::collections::HashMap<String, String>,
 }
 
+/// Where a chunk's text came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkKind {
+    /// Regular body text
+    #[default]
+    Content,
+    /// Synthesized from frontmatter because the note has no body
+    Metadata,
+}
+
 /// A chunk of text with its context
 #[derive(Debug, Clone)]
 pub struct TextChunk {
     pub text: String,
     pub context: String, // e.g., "Document Title > Section > Subsection"
+    /// Every enclosing heading, outermost first (`context` may leave middle levels out)
+    pub headings: Vec<String>,
     pub chunk_index: usize,
     pub start_line: usize,
     pub end_line: usize,
+    /// Byte range of the chunk in the source file (`start_offset..end_offset`), for highlighting
+    /// the exact region; both 0 when the chunk has no source (a frontmatter summary)
+    pub start_offset: usize,
+    pub end_offset: usize,
+    pub kind: ChunkKind,
+}
+
+impl TextChunk {
+    /// Apply [`normalize_whitespace`] to the text and context, which are embedded and stored as-is
+    pub fn normalize_whitespace(&mut self) {
+        self.text = normalize_whitespace(&self.text);
+        self.context = normalize_whitespace(&self.context);
+    }
+}
+
+/// Tidy whitespace left by parsing (a space after every text event, markers dropped from
+/// headings, `\r\n` line ends): runs of spaces and tabs become one space, every line is
+/// trimmed, and so is the text as a whole. Line breaks are kept, so line ranges still hold.
+pub fn normalize_whitespace(text: &str) -> String {
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    lines.join("\n").trim_matches('\n').to_string()
 }
 
 /// Parsed document structure
@@ -29,23 +69,222 @@ pub struct ParsedDocument {
     pub title: String,
     pub chunks: Vec<TextChunk>,
     pub header_hierarchy: Vec<String>,
+    /// Why the frontmatter was ignored, if it isn't valid YAML (the body is still parsed)
+    pub frontmatter_error: Option<String>,
+}
+
+/// How tags are normalized before they are stored or compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagNormalization {
+    /// Keep the original casing (`#Work` and `#work` stay distinct)
+    pub case_sensitive: bool,
+    /// Strip a leading `#`
+    pub strip_hash: bool,
 }
 
+impl Default for TagNormalization {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            strip_hash: true,
+        }
+    }
+}
+
+impl TagNormalization {
+    pub fn from_config(config: &crate::core::config::Config) -> Self {
+        Self {
+            case_sensitive: config.tag_case_sensitive,
+            strip_hash: config.tag_strip_hash,
+        }
+    }
+
+    /// Normalize a single tag
+    pub fn normalize(&self, tag: &str) -> String {
+        let tag = tag.trim();
+        let tag = if self.strip_hash { tag.trim_start_matches('#') } else { tag };
+        if self.case_sensitive {
+            tag.to_string()
+        } else {
+            tag.to_lowercase()
+        }
+    }
+}
+
+/// Extract inline `#tags` from text (e.g. "meeting #Work #project/alpha").
+/// Pure numbers like `#1` are not treated as tags.
+pub fn extract_hashtags(text: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for token in text.split_whitespace() {
+        let Some(rest) = token.strip_prefix('#') else {
+            continue;
+        };
+        let tag: String = rest
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+            .collect();
+        if !tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit()) {
+            tags.push(format!("#{}", tag));
+        }
+    }
+    tags
+}
+
+/// Headings kept under the document title in a chunk's context unless `context_depth` says otherwise
+pub const DEFAULT_CONTEXT_DEPTH: usize = 3;
+
+/// Chunking choices taken from the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingOptions {
+    /// Emit each top-level list item (with its sub-items) as its own chunk
+    pub list_items: bool,
+    /// Innermost headings kept in a chunk's context besides the title (None = all of them)
+    pub context_depth: Option<usize>,
+    /// End a chunk at every horizontal rule (`---`, `***`, `___`) outside lists
+    pub split_on_rule: bool,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            list_items: false,
+            context_depth: Some(DEFAULT_CONTEXT_DEPTH),
+            split_on_rule: true,
+        }
+    }
+}
+
+impl ChunkingOptions {
+    pub fn from_config(config: &crate::core::config::Config) -> Self {
+        Self {
+            list_items: config.list_item_chunks,
+            context_depth: config.context_depth,
+            split_on_rule: config.split_on_rule,
+        }
+    }
+}
+
+/// Number of leading bytes inspected when sniffing for binary content
+const BINARY_SNIFF_LEN: usize = 8192;
+/// Share of undecodable bytes in the sniffed prefix above which a file counts as binary
+const MAX_INVALID_UTF8_RATIO: f32 = 0.10;
+
 /// Parse a Markdown file and extract structure
 pub fn parse_markdown_file(path: &Path) -> Result<ParsedDocument> {
-    let content = std::fs::read_to_string(path)?;
+    parse_markdown_file_with(path, ChunkingOptions::default())
+}
+
+/// [`parse_markdown_file`] with non-default chunking
+pub fn parse_markdown_file_with(path: &Path, options: ChunkingOptions) -> Result<ParsedDocument> {
+    let bytes = std::fs::read(path)?;
+    parse_markdown_bytes(bytes, path, options)
+}
+
+fn parse_markdown_bytes(bytes: Vec<u8>, path: &Path, options: ChunkingOptions) -> Result<ParsedDocument> {
+    // A supported extension is no guarantee of text (e.g. an image renamed to .md)
+    if looks_binary(&bytes) {
+        return Err(Error::BinaryContent(path.display().to_string()));
+    }
+
+    let content = String::from_utf8(bytes)
+        .map_err(|e| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+
+    parse_markdown_with(&content, path, options)
+}
+
+/// Parse a note by extension: `.txt` with the plain-text chunker, everything else as Markdown
+pub fn parse_note_file(path: &Path) -> Result<ParsedDocument> {
+    parse_note_file_with(path, ChunkingOptions::default())
+}
+
+/// [`parse_note_file`] with non-default chunking (`options` only affects Markdown)
+pub fn parse_note_file_with(path: &Path, options: ChunkingOptions) -> Result<ParsedDocument> {
+    if is_text_note(path) {
+        crate::indexing::text::parse_text_file(path)
+    } else {
+        parse_markdown_file_with(path, options)
+    }
+}
+
+/// [`parse_note_file_with`] for a note already in memory (say an entry read from an archive);
+/// `path` picks the parser and names the note
+pub fn parse_note_bytes_with(bytes: Vec<u8>, path: &Path, options: ChunkingOptions) -> Result<ParsedDocument> {
+    if is_text_note(path) {
+        crate::indexing::text::parse_text_bytes(&bytes, path)
+    } else {
+        parse_markdown_bytes(bytes, path, options)
+    }
+}
+
+fn is_text_note(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("txt"))
+        .unwrap_or(false)
+}
+
+/// Quick binary sniff over the first few KB: any null byte, or too many
+/// bytes that don't decode as UTF-8, marks the content as binary.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    if sample.is_empty() {
+        return false;
+    }
+    if sample.contains(&0) {
+        return true;
+    }
+
+    let mut invalid = 0usize;
+    let mut rest = sample;
+    while !rest.is_empty() {
+        match std::str::from_utf8(rest) {
+            Ok(_) => break,
+            Err(e) => match e.error_len() {
+                Some(len) => {
+                    invalid += len;
+                    rest = &rest[e.valid_up_to() + len..];
+                }
+                // Sequence cut off by the sniff window, not an encoding error
+                None => break,
+            },
+        }
+    }
 
-    parse_markdown(&content, path)
+    invalid as f32 / sample.len() as f32 > MAX_INVALID_UTF8_RATIO
 }
 
 /// Parse Markdown content
 pub fn parse_markdown(content: &str, path: &Path) -> Result<ParsedDocument> {
+    parse_markdown_with(content, path, ChunkingOptions::default())
+}
+
+/// [`parse_markdown`] with non-default chunking
+pub fn parse_markdown_with(content: &str, path: &Path, options: ChunkingOptions) -> Result<ParsedDocument> {
     // Extract frontmatter
     let (frontmatter, markdown_content) = extract_frontmatter(content);
-    let metadata = parse_frontmatter(frontmatter);
+    let (metadata, frontmatter_error) = parse_frontmatter(frontmatter);
+
+    // Comments (and MDX's imports and JSX) never reach the chunks
+    let stripped = strip_markup(&markdown_content, is_mdx(path));
 
     // Parse Markdown structure
-    let (title, header_hierarchy, chunks) = parse_structure(&markdown_content)?;
+    let (title, header_hierarchy, mut chunks) = parse_structure(&stripped.text, options)?;
+    // The body is a suffix of `content`, so offsets into it shift by the frontmatter's length
+    let body_offset = content.len() - markdown_content.len();
+    for chunk in &mut chunks {
+        chunk.normalize_whitespace();
+        if chunk.end_offset > 0 {
+            chunk.start_offset = body_offset + stripped.source_offset(chunk.start_offset, false);
+            chunk.end_offset = body_offset + stripped.source_offset(chunk.end_offset, true);
+        }
+    }
+
+    // Frontmatter-only notes would otherwise be invisible to search
+    if chunks.is_empty() {
+        if let Some(chunk) = metadata_chunk(&metadata) {
+            chunks.push(chunk);
+        }
+    }
 
     Ok(ParsedDocument {
         metadata,
@@ -57,77 +296,148 @@ pub fn parse_markdown(content: &str, path: &Path) -> Result<ParsedDocument> {
         }),
         chunks,
         header_hierarchy,
+        frontmatter_error,
     })
 }
 
+/// Synthesize a single chunk from frontmatter fields (title, tags, aliases, custom fields).
+/// Returns None when there is no metadata worth embedding.
+fn metadata_chunk(metadata: &DocumentMetadata) -> Option<TextChunk> {
+    let mut lines = Vec::new();
+    if let Some(title) = metadata.title.as_deref().filter(|t| !t.trim().is_empty()) {
+        lines.push(format!("Title: {}", title.trim()));
+    }
+    if !metadata.tags.is_empty() {
+        lines.push(format!("Tags: {}", metadata.tags.join(", ")));
+    }
+    if !metadata.aliases.is_empty() {
+        lines.push(format!("Aliases: {}", metadata.aliases.join(", ")));
+    }
+    // Sorted so the synthesized text (and its embedding) is stable across runs
+    let mut custom: Vec<(&String, &String)> = metadata.custom.iter().collect();
+    custom.sort();
+    for (key, value) in custom {
+        if !value.trim().is_empty() {
+            lines.push(format!("{}: {}", key, value.trim()));
+        }
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(TextChunk {
+        text: lines.join("\n"),
+        context: String::new(),
+        chunk_index: 0,
+        start_line: 1,
+        end_line: 1,
+        start_offset: 0,
+        end_offset: 0,
+        headings: Vec::new(),
+        kind: ChunkKind::Metadata,
+    })
+}
+
+/// Parse a YAML list or comma-separated string into trimmed, non-empty values
+fn yaml_string_list(value: &serde_yaml::Value) -> Vec<String> {
+    if let Some(array) = value.as_sequence() {
+        array
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect()
+    } else if let Some(list) = value.as_str() {
+        list.split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
 /// Extract frontmatter from content
 fn extract_frontmatter(content: &str) -> (Option<String>, String) {
-    // Simple frontmatter extraction - look for YAML between --- markers
-    if content.starts_with("---\n") {
-        if let Some(end_pos) = content[4..].find("\n---\n") {
-            let frontmatter = content[4..end_pos + 4].to_string();
-            let markdown_content = content[end_pos + 9..].to_string();
+    // Look for YAML between `---` lines; tolerate a UTF-8 BOM and CRLF line endings
+    let body = content.strip_prefix('\u{feff}').unwrap_or(content);
+
+    let Some(rest) = body
+        .strip_prefix("---\n")
+        .or_else(|| body.strip_prefix("---\r\n"))
+    else {
+        return (None, content.to_string());
+    };
+
+    // Walk the remaining lines looking for the closing delimiter
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == "---" {
+            let frontmatter = rest[..offset].to_string();
+            let markdown_content = rest[offset + line.len()..].to_string();
             return (Some(frontmatter), markdown_content);
         }
+        offset += line.len();
     }
+
     (None, content.to_string())
 }
 
-/// Parse frontmatter YAML into metadata
-fn parse_frontmatter(frontmatter: Option<String>) -> DocumentMetadata {
+/// Parse frontmatter YAML into metadata.
+/// Invalid YAML yields empty metadata plus the parse error, so callers can warn about it.
+fn parse_frontmatter(frontmatter: Option<String>) -> (DocumentMetadata, Option<String>) {
     let mut metadata = DocumentMetadata::default();
 
     if let Some(fm) = frontmatter {
-        if let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(&fm) {
-            if let Some(map) = value.as_mapping() {
+        match serde_yaml::from_str::<serde_yaml::Value>(&fm) {
+            Err(e) => return (metadata, Some(e.to_string())),
+            Ok(value) => if let Some(map) = value.as_mapping() {
                 // Extract common fields
                 if let Some(title) = map.get("title").and_then(|v| v.as_str()) {
                     metadata.title = Some(title.to_string());
                 }
 
+                // Lists or comma-separated strings
                 if let Some(tags) = map.get("tags") {
-                    if let Some(tag_array) = tags.as_sequence() {
-                        metadata.tags = tag_array
-                            .iter()
-                            .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                            .collect();
-                    } else if let Some(tag_str) = tags.as_str() {
-                        // Handle comma-separated tags
-                        metadata.tags = tag_str
-                            .split(',')
-                            .map(|s| s.trim().to_string())
-                            .filter(|s| !s.is_empty())
-                            .collect();
-                    }
+                    metadata.tags = yaml_string_list(tags);
                 }
+                if let Some(aliases) = map.get("aliases") {
+                    metadata.aliases = yaml_string_list(aliases);
+                }
+                metadata.created = map.get("created").and_then(|v| v.as_str()).map(str::to_string);
+                metadata.modified = map.get("modified").and_then(|v| v.as_str()).map(str::to_string);
 
                 // Extract custom fields
                 for (key, value) in map.iter() {
                     if let (Some(k), Some(v)) = (key.as_str(), value.as_str()) {
-                        if !matches!(k, "title" | "tags" | "created" | "modified") {
+                        if !matches!(k, "title" | "tags" | "aliases" | "created" | "modified") {
                             metadata.custom.insert(k.to_string(), v.to_string());
                         }
                     }
                 }
-            }
+            },
         }
     }
 
-    metadata
+    (metadata, None)
 }
 
 /// Chunking configuration
-const MIN_CHUNK_SIZE: usize = 50;  // Minimum characters per chunk
-const MAX_CHUNK_SIZE: usize = 500; // Maximum characters per chunk
-const TARGET_CHUNK_SIZE: usize = 300; // Target size for optimal embeddings
+pub(crate) const MIN_CHUNK_SIZE: usize = 50;  // Minimum characters per chunk
+pub(crate) const MAX_CHUNK_SIZE: usize = 500; // Maximum characters per chunk
+pub(crate) const TARGET_CHUNK_SIZE: usize = 300; // Target size for optimal embeddings
 
 /// Parse Markdown structure and extract chunks
-fn parse_structure(content: &str) -> Result<(Option<String>, Vec<String>, Vec<TextChunk>)> {
+fn parse_structure(content: &str, options: ChunkingOptions) -> Result<(Option<String>, Vec<String>, Vec<TextChunk>)> {
     let parser = Parser::new(content);
-    let events: Vec<Event> = parser.collect();
+    let events: Vec<(Event, Range<usize>)> = parser.into_offset_iter().collect();
+    // Byte offset where each line starts, for the exact spans of list-item chunks
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset);
 
     let mut title: Option<String> = None;
-    let mut header_stack: Vec<String> = Vec::new();
+    let mut header_stack = HeadingStack::default();
     let mut current_text = String::new();
     let mut chunks = Vec::new();
     let mut chunk_index = 0;
@@ -136,21 +446,29 @@ fn parse_structure(content: &str) -> Result<(Option<String>, Vec<String>, Vec<Te
     let mut in_heading = false;
     let mut current_heading_level = 0;
     let mut heading_text = String::new();
+    let mut list_depth = 0usize;
+    let mut source = SourceMap::default();
 
-    for event in &events {
+    for (event, range) in &events {
         match event {
             Event::Start(Tag::Heading { level, id: _, classes: _, attrs: _ }) => {
                 // Save current chunk if we have text
                 if !current_text.trim().is_empty() {
+                    let span = source.span(&current_text);
                     chunks.push(TextChunk {
                         text: current_text.trim().to_string(),
-                        context: build_context(&header_stack),
+                        context: header_stack.context(options.context_depth),
+                        headings: header_stack.path(),
                         chunk_index,
                         start_line: chunk_start_line,
                         end_line: line_number.max(chunk_start_line),
+                        start_offset: span.start,
+                        end_offset: span.end,
+                        kind: ChunkKind::Content,
                     });
                     chunk_index += 1;
                     current_text.clear();
+                    source.clear();
                 }
 
                 in_heading = true;
@@ -160,12 +478,11 @@ fn parse_structure(content: &str) -> Result<(Option<String>, Vec<String>, Vec<Te
             }
             Event::End(TagEnd::Heading(_)) if in_heading => {
                 in_heading = false;
-                let heading = heading_text.trim().to_string();
+                let heading = normalize_whitespace(&heading_text);
 
                 // Update header stack using the level we captured
                 let level = current_heading_level;
-                header_stack.truncate(level.saturating_sub(1));
-                header_stack.push(heading.clone());
+                header_stack.enter(level, heading.clone());
 
                 // First H1 becomes the title
                 if level == 1 && title.is_none() {
@@ -182,6 +499,7 @@ fn parse_structure(content: &str) -> Result<(Option<String>, Vec<String>, Vec<Te
                 if in_heading {
                     heading_text.push_str(text);
                 } else {
+                    source.push(current_text.len(), text, range.clone());
                     current_text.push_str(text);
                     current_text.push(' ');
                 }
@@ -202,16 +520,117 @@ fn parse_structure(content: &str) -> Result<(Option<String>, Vec<String>, Vec<Te
                     let new_chunks = split_text_intelligently(
                         &current_text,
                         &header_stack,
+                        options.context_depth,
                         chunk_start_line,
                         line_number - 1, // End line of the paragraph
+                        &source,
                         &mut chunk_index,
                     );
                     chunks.extend(new_chunks);
                     current_text.clear();
+                    source.clear();
                     chunk_start_line = line_number;
                 }
             }
+            Event::Rule if options.split_on_rule && list_depth == 0 => {
+                // A thematic break separates topics like a heading would, under the same context
+                if current_text.len() > MAX_CHUNK_SIZE {
+                    chunks.extend(split_text_intelligently(
+                        &current_text,
+                        &header_stack,
+                        options.context_depth,
+                        chunk_start_line,
+                        line_number.max(chunk_start_line),
+                        &source,
+                        &mut chunk_index,
+                    ));
+                } else if !current_text.trim().is_empty() {
+                    let span = source.span(&current_text);
+                    chunks.push(TextChunk {
+                        text: current_text.trim().to_string(),
+                        context: header_stack.context(options.context_depth),
+                        headings: header_stack.path(),
+                        chunk_index,
+                        start_line: chunk_start_line,
+                        end_line: line_number.max(chunk_start_line),
+                        start_offset: span.start,
+                        end_offset: span.end,
+                        kind: ChunkKind::Content,
+                    });
+                    chunk_index += 1;
+                }
+                current_text.clear();
+                source.clear();
+                // The next chunk starts on the line after the rule
+                line_number = line_of(range.start) + 1;
+                chunk_start_line = line_number;
+            }
+            Event::Start(Tag::List(_)) => {
+                list_depth += 1;
+            }
+            Event::End(TagEnd::List(_)) => {
+                list_depth = list_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::Item) if options.list_items && list_depth == 1 => {
+                // Text before the list is a chunk of its own
+                if !current_text.trim().is_empty() {
+                    let span = source.span(&current_text);
+                    chunks.push(TextChunk {
+                        text: current_text.trim().to_string(),
+                        context: header_stack.context(options.context_depth),
+                        headings: header_stack.path(),
+                        chunk_index,
+                        start_line: chunk_start_line,
+                        end_line: line_number.max(chunk_start_line),
+                        start_offset: span.start,
+                        end_offset: span.end,
+                        kind: ChunkKind::Content,
+                    });
+                    chunk_index += 1;
+                    current_text.clear();
+                    source.clear();
+                }
+                chunk_start_line = line_of(range.start);
+            }
+            Event::End(TagEnd::Item) if options.list_items && list_depth == 1 => {
+                // The item's source, without the blank lines a loose list leaves after it
+                let end = range.start + content[range.clone()].trim_end().len();
+                let end_line = line_of(end.saturating_sub(1)).max(chunk_start_line);
+                if current_text.len() > MAX_CHUNK_SIZE {
+                    chunks.extend(split_text_intelligently(
+                        &current_text,
+                        &header_stack,
+                        options.context_depth,
+                        chunk_start_line,
+                        end_line,
+                        &source,
+                        &mut chunk_index,
+                    ));
+                } else if !current_text.trim().is_empty() {
+                    let span = source.span(&current_text);
+                    chunks.push(TextChunk {
+                        text: current_text.trim().to_string(),
+                        context: header_stack.context(options.context_depth),
+                        headings: header_stack.path(),
+                        chunk_index,
+                        start_line: chunk_start_line,
+                        end_line,
+                        start_offset: span.start,
+                        end_offset: span.end,
+                        kind: ChunkKind::Content,
+                    });
+                    chunk_index += 1;
+                }
+                current_text.clear();
+                source.clear();
+                line_number = end_line + 1;
+                chunk_start_line = line_number;
+            }
             Event::End(TagEnd::Item) => {
+                if options.list_items {
+                    // Keep sub-items on their own lines within the item's chunk
+                    current_text.push('\n');
+                }
                 line_number += 1;
             }
             Event::End(TagEnd::CodeBlock) => {
@@ -228,31 +647,92 @@ fn parse_structure(content: &str) -> Result<(Option<String>, Vec<String>, Vec<Te
             let new_chunks = split_text_intelligently(
                 &current_text,
                 &header_stack,
+                options.context_depth,
                 chunk_start_line,
                 line_number,
+                &source,
                 &mut chunk_index,
             );
             chunks.extend(new_chunks);
         } else {
+            let span = source.span(&current_text);
             chunks.push(TextChunk {
                 text: current_text.trim().to_string(),
-                context: build_context(&header_stack),
+                context: header_stack.context(options.context_depth),
+                headings: header_stack.path(),
                 chunk_index,
                 start_line: chunk_start_line,
                 end_line: line_number.max(chunk_start_line),
+                start_offset: span.start,
+                end_offset: span.end,
+                kind: ChunkKind::Content,
             });
         }
     }
 
-    Ok((title, header_stack, chunks))
+    Ok((title, header_stack.path(), chunks))
+}
+
+/// Byte position of `inner` in `outer`, which it must be a slice of
+pub(crate) fn offset_in(outer: &str, inner: &str) -> usize {
+    inner.as_ptr() as usize - outer.as_ptr() as usize
+}
+
+/// Where the text gathered for a chunk came from: each text event's place in the gathered text
+/// and its byte range in the source
+#[derive(Debug, Default)]
+struct SourceMap {
+    segments: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl SourceMap {
+    /// `text` was appended at `at` from `source`
+    fn push(&mut self, at: usize, text: &str, source: Range<usize>) {
+        self.segments.push((at..at + text.len(), source));
+    }
+
+    fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    /// Source range of `text` (the gathered text) without its surrounding whitespace
+    fn span(&self, text: &str) -> Range<usize> {
+        let trimmed = text.trim();
+        let start = offset_in(text, trimmed);
+        self.span_of(start..start + trimmed.len())
+    }
+
+    /// Source range of the gathered text `range`; `0..0` when no text event falls in it.
+    /// Positions inside an event are exact when it was taken from the source verbatim, and
+    /// widen to the whole event otherwise (escapes, entities).
+    fn span_of(&self, range: Range<usize>) -> Range<usize> {
+        let first = self.segments.iter().find(|(text, _)| text.end > range.start);
+        let last = self.segments.iter().rev().find(|(text, _)| text.start < range.end);
+        let (Some((first_text, first_source)), Some((last_text, last_source))) = (first, last) else {
+            return 0..0;
+        };
+        let start = if first_text.len() == first_source.len() {
+            first_source.start + range.start.saturating_sub(first_text.start)
+        } else {
+            first_source.start
+        };
+        let end = if last_text.len() == last_source.len() {
+            last_source.start + (range.end - last_text.start).min(last_text.len())
+        } else {
+            last_source.end
+        };
+        start..end.max(start)
+    }
 }
 
 /// Split text intelligently at sentence boundaries while respecting size constraints
 fn split_text_intelligently(
     text: &str,
-    header_stack: &[String],
+    header_stack: &HeadingStack,
+    context_depth: Option<usize>,
     start_line: usize,
     end_line: usize,
+    source: &SourceMap,
     chunk_index: &mut usize,
 ) -> Vec<TextChunk> {
     let mut chunks = Vec::new();
@@ -287,13 +767,17 @@ fn split_text_intelligently(
 
     let mut current_chunk = String::new();
     let mut current_start = start_line;
-    let context = build_context(header_stack);
+    // Where the sentences in `current_chunk` lie in `text`
+    let mut current_range = 0..0;
+    let context = header_stack.context(context_depth);
+    let headings = header_stack.path();
 
     for sentence in sentences {
         let sentence = sentence.trim();
         if sentence.is_empty() {
             continue;
         }
+        let sentence_start = offset_in(text, sentence);
 
         // If adding this sentence would exceed max size, save current chunk
         // Also try to target TARGET_CHUNK_SIZE for optimal embedding quality
@@ -304,12 +788,17 @@ fn split_text_intelligently(
             && current_chunk.len() + sentence.len() + 1 > MAX_CHUNK_SIZE;
         
         if (would_exceed_max || reached_target) && current_chunk.len() >= MIN_CHUNK_SIZE {
+            let span = source.span_of(current_range.clone());
             chunks.push(TextChunk {
                 text: current_chunk.trim().to_string(),
                 context: context.clone(),
+                headings: headings.clone(),
                 chunk_index: *chunk_index,
                 start_line: current_start,
                 end_line: end_line,
+                start_offset: span.start,
+                end_offset: span.end,
+                kind: ChunkKind::Content,
             });
             *chunk_index += 1;
             current_chunk.clear();
@@ -319,18 +808,26 @@ fn split_text_intelligently(
         // Add sentence to current chunk
         if !current_chunk.is_empty() {
             current_chunk.push(' ');
+        } else {
+            current_range.start = sentence_start;
         }
         current_chunk.push_str(sentence);
+        current_range.end = sentence_start + sentence.len();
     }
+    let span = source.span_of(current_range);
 
     // Add remaining chunk if it meets minimum size
     if !current_chunk.trim().is_empty() && current_chunk.len() >= MIN_CHUNK_SIZE {
         chunks.push(TextChunk {
             text: current_chunk.trim().to_string(),
             context,
+            headings,
             chunk_index: *chunk_index,
             start_line: current_start,
             end_line,
+            start_offset: span.start,
+            end_offset: span.end,
+            kind: ChunkKind::Content,
         });
         *chunk_index += 1;
     } else if !current_chunk.trim().is_empty() {
@@ -339,13 +836,18 @@ fn split_text_intelligently(
             last_chunk.text.push_str(" ");
             last_chunk.text.push_str(&current_chunk.trim());
             last_chunk.end_line = end_line;
+            last_chunk.end_offset = last_chunk.end_offset.max(span.end);
         } else {
             chunks.push(TextChunk {
                 text: current_chunk.trim().to_string(),
                 context,
+                headings,
                 chunk_index: *chunk_index,
                 start_line: current_start,
                 end_line,
+                start_offset: span.start,
+                end_offset: span.end,
+                kind: ChunkKind::Content,
             });
             *chunk_index += 1;
         }
@@ -354,12 +856,45 @@ fn split_text_intelligently(
     chunks
 }
 
-/// Build context string from header hierarchy
-fn build_context(headers: &[String]) -> String {
-    if headers.is_empty() {
-        return String::new();
+/// Headings enclosing the current position, outermost first, with their levels
+#[derive(Debug, Default)]
+struct HeadingStack {
+    entries: Vec<(usize, String)>,
+}
+
+impl HeadingStack {
+    /// A heading closes every open section at its level or deeper, so an H4 right under an H1
+    /// and a later H3 end up as siblings under the H1
+    fn enter(&mut self, level: usize, heading: String) {
+        while self.entries.last().is_some_and(|(open, _)| *open >= level) {
+            self.entries.pop();
+        }
+        self.entries.push((level, heading));
+    }
+
+    /// Every heading, outermost first
+    fn path(&self) -> Vec<String> {
+        self.entries.iter().map(|(_, heading)| heading.clone()).collect()
+    }
+
+    /// Breadcrumb stored with chunks: the document title (a leading H1) and at most `depth` of
+    /// the innermost headings under it; None keeps every level
+    fn context(&self, depth: Option<usize>) -> String {
+        let (title, rest) = match self.entries.split_first() {
+            Some((first, rest)) if first.0 == 1 => (Some(first), rest),
+            _ => (None, self.entries.as_slice()),
+        };
+        let rest = match depth {
+            Some(depth) if rest.len() > depth => &rest[rest.len() - depth..],
+            _ => rest,
+        };
+        title
+            .into_iter()
+            .chain(rest)
+            .map(|(_, heading)| heading.as_str())
+            .collect::<Vec<_>>()
+            .join(" > ")
     }
-    headers.join(" > ")
 }
 
 #[cfg(test)]
@@ -405,6 +940,7 @@ mod tests {
 title: Test Document
 tags: [rust, testing]
 custom_field: custom_value
+created: 2024-03-01
 ---
 
 # Main Title
@@ -419,6 +955,35 @@ Content here.
         assert!(doc.metadata.tags.contains(&"rust".to_string()));
         assert!(doc.metadata.tags.contains(&"testing".to_string()));
         assert_eq!(doc.metadata.custom.get("custom_field"), Some(&"custom_value".to_string()));
+        assert_eq!(doc.metadata.created.as_deref(), Some("2024-03-01"));
+        assert_eq!(doc.metadata.modified, None);
+    }
+
+    #[test]
+    fn test_invalid_frontmatter_keeps_body() {
+        let content = r#"---
+title: Broken
+tags: [rust, testing
+---
+
+# Notes
+
+The body should still be indexed.
+"#;
+        let doc = parse_markdown(content, Path::new("broken.md")).unwrap();
+        assert!(doc.frontmatter_error.is_some());
+        assert!(doc.metadata.title.is_none());
+        assert!(doc.metadata.tags.is_empty());
+        assert!(doc.metadata.custom.is_empty());
+        assert!(doc.chunks.iter().any(|c| c.text.contains("body should still be indexed")));
+        assert!(doc.chunks.iter().all(|c| c.kind == ChunkKind::Content));
+    }
+
+    #[test]
+    fn test_valid_frontmatter_has_no_error() {
+        let content = "---\ntitle: Fine\n---\n\nBody.\n";
+        let doc = parse_markdown(content, Path::new("fine.md")).unwrap();
+        assert!(doc.frontmatter_error.is_none());
     }
 
     #[test]
@@ -593,6 +1158,105 @@ This is test content.
         assert!(!doc.chunks.is_empty());
     }
 
+    #[test]
+    fn test_parse_frontmatter_crlf() {
+        let content = "---\r\ntitle: Windows Note\r\ntags: [win, crlf]\r\n---\r\n\r\n# Heading\r\n\r\nBody text here.\r\n";
+        let doc = parse_markdown(content, Path::new("win.md")).unwrap();
+        assert_eq!(doc.metadata.title, Some("Windows Note".to_string()));
+        assert_eq!(doc.metadata.tags, vec!["win".to_string(), "crlf".to_string()]);
+        assert!(!doc.chunks.is_empty());
+        for chunk in &doc.chunks {
+            assert!(!chunk.text.contains("title:"));
+            assert!(!chunk.text.contains("---"));
+        }
+    }
+
+    #[test]
+    fn test_parse_frontmatter_with_bom() {
+        let content = "\u{feff}---\ntitle: BOM Note\n---\n\nBody text here.\n";
+        let doc = parse_markdown(content, Path::new("bom.md")).unwrap();
+        assert_eq!(doc.metadata.title, Some("BOM Note".to_string()));
+        assert!(doc.chunks.iter().all(|c| !c.text.contains("title:")));
+
+        let content = "\u{feff}---\r\ntitle: BOM CRLF\r\n---\r\nBody text here.\r\n";
+        let doc = parse_markdown(content, Path::new("bom.md")).unwrap();
+        assert_eq!(doc.metadata.title, Some("BOM CRLF".to_string()));
+        assert!(doc.chunks.iter().all(|c| !c.text.contains("title:")));
+    }
+
+    #[test]
+    fn test_frontmatter_closing_delimiter_at_eof() {
+        let content = "---\ntitle: No Trailing Newline\n---";
+        let (frontmatter, body) = extract_frontmatter(content);
+        assert_eq!(frontmatter, Some("title: No Trailing Newline\n".to_string()));
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_frontmatter_is_body() {
+        let content = "---\ntitle: Broken\nno closing line\n";
+        let (frontmatter, body) = extract_frontmatter(content);
+        assert!(frontmatter.is_none());
+        assert_eq!(body, content);
+    }
+
+    #[test]
+    fn test_frontmatter_only_note_gets_metadata_chunk() {
+        let content = "---\ntitle: Kubernetes Cheatsheet\ntags: [k8s, ops]\naliases: [kubectl notes]\nlink: https://kubernetes.io\n---\n";
+        let doc = parse_markdown(content, Path::new("stub.md")).unwrap();
+        assert_eq!(doc.chunks.len(), 1);
+
+        let chunk = &doc.chunks[0];
+        assert_eq!(chunk.kind, ChunkKind::Metadata);
+        assert_eq!((chunk.start_line, chunk.end_line), (1, 1));
+        assert!(chunk.text.contains("Title: Kubernetes Cheatsheet"));
+        assert!(chunk.text.contains("Tags: k8s, ops"));
+        assert!(chunk.text.contains("Aliases: kubectl notes"));
+        assert!(chunk.text.contains("link: https://kubernetes.io"));
+    }
+
+    #[test]
+    fn test_note_with_body_has_no_metadata_chunk() {
+        let content = "---\ntitle: Notes\n---\n\nSome body text.\n";
+        let doc = parse_markdown(content, Path::new("notes.md")).unwrap();
+        assert!(doc.chunks.iter().all(|c| c.kind == ChunkKind::Content));
+    }
+
+    #[test]
+    fn test_extract_hashtags() {
+        let tags = extract_hashtags("Standup #Work notes, see #project/alpha and issue #42");
+        assert_eq!(tags, vec!["#Work".to_string(), "#project/alpha".to_string()]);
+        assert!(extract_hashtags("no tags here").is_empty());
+    }
+
+    #[test]
+    fn test_tag_normalization() {
+        let norm = TagNormalization::default();
+        assert_eq!(norm.normalize("#Work"), "work");
+        assert_eq!(norm.normalize("WORK"), "work");
+
+        let strict = TagNormalization { case_sensitive: true, strip_hash: false };
+        assert_eq!(strict.normalize("#Work"), "#Work");
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(!looks_binary(b""));
+        assert!(!looks_binary("# Notes\n\nPlain text with ünïcödé.".as_bytes()));
+        assert!(looks_binary(b"# Title\n\x00\x01\x02"));
+        assert!(looks_binary(&[0xFF, 0xD8, 0xFF, 0xE0, 0x10, 0x4A, 0x46, 0x49, 0x46]));
+    }
+
+    #[test]
+    fn test_parse_markdown_file_skips_binary() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("image.md");
+        fs::write(&test_file, b"# Looks like markdown\n\x00\x00\x89PNG").unwrap();
+
+        let result = parse_markdown_file(&test_file);
+        assert!(matches!(result, Err(Error::BinaryContent(_))));
+    }
+
     #[test]
     fn test_parse_markdown_file_nonexistent() {
         let result = parse_markdown_file(Path::new("/nonexistent/file.md"));
@@ -654,25 +1318,232 @@ More content.
         assert!(!doc.chunks.is_empty());
     }
 
+    fn assert_tidy(text: &str) {
+        assert!(!text.contains("  "), "doubled space in {:?}", text);
+        assert!(!text.contains('\r'), "carriage return in {:?}", text);
+        assert!(text.lines().all(|line| line == line.trim()), "untrimmed line in {:?}", text);
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(normalize_whitespace("  a  b\t c  \r\n d \n\n  e  "), "a b c\nd\n\ne");
+        assert_eq!(normalize_whitespace(" \r\n "), "");
+    }
+
+    #[test]
+    fn test_chunks_have_normalized_whitespace() {
+        let content = "# Plan for `deploy` *today* \r\n\r\n- **ship** the   fix  \r\n- ask *Sam*   about `logs`\r\n  - nested   item \r\n\r\nSome   text with `code` and *emphasis*.  \r\nNext line.\r\n";
+        for list_items in [false, true] {
+            let doc = parse_markdown_with(content, Path::new("plan.md"), ChunkingOptions { list_items, ..Default::default() }).unwrap();
+            assert!(!doc.chunks.is_empty());
+            for chunk in &doc.chunks {
+                assert_tidy(&chunk.text);
+                assert_tidy(&chunk.context);
+            }
+            assert_eq!(doc.title, "Plan for today");
+        }
+    }
+
+    #[test]
+    fn test_list_items_as_chunks() {
+        let content = r#"# Groceries
+
+- apples and pears
+- oat milk
+  - the barista one
+  - two cartons
+- coffee beans
+- rice
+- dish soap
+"#;
+        let list_items = ChunkingOptions { list_items: true, ..Default::default() };
+        let doc = parse_markdown_with(content, Path::new("groceries.md"), list_items).unwrap();
+
+        let chunks: Vec<(&str, usize, usize)> = doc
+            .chunks
+            .iter()
+            .map(|c| (c.text.as_str(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(chunks[0], ("apples and pears", 3, 3));
+        // Sub-items stay with their parent
+        assert_eq!(chunks[1].1..=chunks[1].2, 4..=6);
+        assert!(chunks[1].0.contains("oat milk") && chunks[1].0.contains("two cartons"));
+        assert_eq!(chunks[4], ("dish soap", 9, 9));
+        assert!(doc.chunks.iter().all(|c| c.context == "Groceries"));
+        assert!(doc.chunks.iter().enumerate().all(|(i, c)| c.chunk_index == i));
+
+        // Off by default: the list stays together
+        let doc = parse_markdown(content, Path::new("groceries.md")).unwrap();
+        assert_eq!(doc.chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_horizontal_rules_split_chunks() {
+        let content = r#"# Ideas
+
+Batch the weekly review into one evening.
+
+---
+
+Try a standing desk for the home office.
+
+***
+
+Read more about sourdough starters.
+"#;
+        let doc = parse_markdown(content, Path::new("ideas.md")).unwrap();
+        let chunks: Vec<(&str, &str)> = doc.chunks.iter().map(|c| (c.text.as_str(), c.context.as_str())).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                ("Batch the weekly review into one evening.", "Ideas"),
+                ("Try a standing desk for the home office.", "Ideas"),
+                ("Read more about sourdough starters.", "Ideas"),
+            ]
+        );
+        // Each section starts on the line after its rule
+        assert_eq!((doc.chunks[1].start_line, doc.chunks[2].start_line), (6, 10));
+        assert!(doc.chunks.iter().enumerate().all(|(i, c)| c.chunk_index == i));
+
+        // Turned off, the sections stay together
+        let together = ChunkingOptions { split_on_rule: false, ..Default::default() };
+        let doc = parse_markdown_with(content, Path::new("ideas.md"), together).unwrap();
+        assert_eq!(doc.chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_list_items_as_chunks_keeps_surrounding_text() {
+        let content = r#"Before the list.
+
+1. first
+
+2. second
+
+After the list.
+"#;
+        let doc = parse_markdown_with(content, Path::new("loose.md"), ChunkingOptions { list_items: true, ..Default::default() }).unwrap();
+        let chunks: Vec<(&str, usize, usize)> = doc
+            .chunks
+            .iter()
+            .map(|c| (c.text.as_str(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(chunks[0].0, "Before the list.");
+        assert_eq!(chunks[1], ("first", 3, 3));
+        assert_eq!(chunks[2], ("second", 5, 5));
+        assert_eq!(chunks[3].0, "After the list.");
+        assert_eq!(chunks.len(), 4);
+    }
+
+    /// The chunk's text up to and including its first full stop
+    fn first_sentence(chunk: &TextChunk) -> &str {
+        let end = chunk.text.find(". ").map_or(chunk.text.len(), |i| i + 1);
+        &chunk.text[..end]
+    }
+
+    #[test]
+    fn test_offsets_slice_back_to_the_chunk_source() {
+        let long = "Every sentence here is filler about the quarterly roadmap review. ".repeat(12);
+        let content = format!(
+            "---\r\ntags: [plans]\r\n---\r\n# Plans\r\n\r\nShip the sync engine by spring.\r\n\r\n## Later\r\n\r\n{}\r\n\r\n---\r\n\r\nRevisit the pricing page.\r\n",
+            long.trim()
+        );
+        let doc = parse_markdown(&content, Path::new("plans.md")).unwrap();
+        assert!(doc.chunks.len() > 3, "{:?}", doc.chunks);
+        for chunk in &doc.chunks {
+            let source = &content[chunk.start_offset..chunk.end_offset];
+            assert!(source.starts_with(first_sentence(chunk)), "{:?} vs {:?}", source, chunk.text);
+            assert!(source.ends_with('.'), "{:?}", source);
+        }
+        assert_eq!(&content[doc.chunks[0].start_offset..doc.chunks[0].end_offset], "Ship the sync engine by spring.");
+        // The long paragraph's pieces follow each other
+        let pieces = &doc.chunks[1..doc.chunks.len() - 1];
+        assert!(pieces.windows(2).all(|w| w[0].end_offset < w[1].start_offset));
+
+        // A soft break keeps the chunk's source contiguous across lines
+        let doc = parse_markdown("Line one of the note.\nLine two of it.\n", Path::new("note.md")).unwrap();
+        assert_eq!((doc.chunks[0].start_offset, doc.chunks[0].end_offset), (0, 37));
+
+        // A frontmatter summary has no source range
+        let doc = parse_markdown("---\ntitle: Empty\n---\n", Path::new("empty.md")).unwrap();
+        assert_eq!((doc.chunks[0].start_offset, doc.chunks[0].end_offset), (0, 0));
+    }
+
+    /// Stack after entering `headings` (level, text) in order
+    fn heading_stack(headings: &[(usize, &str)]) -> HeadingStack {
+        let mut stack = HeadingStack::default();
+        for (level, heading) in headings {
+            stack.enter(*level, heading.to_string());
+        }
+        stack
+    }
+
     #[test]
     fn test_build_context() {
-        let headers = vec!["Document".to_string(), "Section".to_string(), "Subsection".to_string()];
-        let context = build_context(&headers);
-        assert_eq!(context, "Document > Section > Subsection");
+        let stack = heading_stack(&[(1, "Document"), (2, "Section"), (3, "Subsection")]);
+        assert_eq!(stack.context(None), "Document > Section > Subsection");
     }
 
     #[test]
     fn test_build_context_empty() {
-        let headers = vec![];
-        let context = build_context(&headers);
-        assert_eq!(context, "");
+        assert_eq!(HeadingStack::default().context(None), "");
+        assert!(HeadingStack::default().path().is_empty());
     }
 
     #[test]
     fn test_build_context_single() {
-        let headers = vec!["Document".to_string()];
-        let context = build_context(&headers);
-        assert_eq!(context, "Document");
+        assert_eq!(heading_stack(&[(1, "Document")]).context(Some(3)), "Document");
+    }
+
+    #[test]
+    fn test_context_depth_keeps_title_and_innermost_levels() {
+        let stack = heading_stack(&[(1, "A"), (2, "B"), (3, "C"), (4, "D"), (5, "E"), (6, "F")]);
+        assert_eq!(stack.context(Some(3)), "A > D > E > F");
+        assert_eq!(stack.context(Some(1)), "A > F");
+        assert_eq!(stack.context(None), "A > B > C > D > E > F");
+        assert_eq!(stack.path(), vec!["A", "B", "C", "D", "E", "F"]);
+
+        // Without a leading H1 there is no title to keep
+        let stack = heading_stack(&[(2, "B"), (3, "C"), (4, "D"), (5, "E")]);
+        assert_eq!(stack.context(Some(2)), "D > E");
+    }
+
+    #[test]
+    fn test_skipped_heading_levels() {
+        // H4 straight under the H1, then an H3: siblings, not nested
+        let stack = heading_stack(&[(1, "Doc"), (4, "Deep"), (3, "Middle")]);
+        assert_eq!(stack.path(), vec!["Doc", "Middle"]);
+
+        let stack = heading_stack(&[(1, "Doc"), (4, "Deep"), (5, "Deeper"), (2, "Section")]);
+        assert_eq!(stack.path(), vec!["Doc", "Section"]);
+    }
+
+    #[test]
+    fn test_repeated_heading_levels() {
+        let stack = heading_stack(&[(1, "Doc"), (2, "One"), (2, "Two"), (3, "Inner"), (3, "Next")]);
+        assert_eq!(stack.path(), vec!["Doc", "Two", "Next"]);
+
+        // A second H1 starts over
+        let stack = heading_stack(&[(1, "Doc"), (2, "One"), (1, "Appendix")]);
+        assert_eq!(stack.path(), vec!["Appendix"]);
+    }
+
+    #[test]
+    fn test_chunks_carry_capped_context_and_full_headings() {
+        let content = "# A\n\n## B\n\n### C\n\n#### D\n\n##### E\n\nDeep text here.\n\n### G\n\nBack up.\n";
+        let doc = parse_markdown(content, Path::new("deep.md")).unwrap();
+        let deep = doc.chunks.iter().find(|c| c.text.contains("Deep")).unwrap();
+        assert_eq!(deep.context, "A > C > D > E");
+        assert_eq!(deep.headings, vec!["A", "B", "C", "D", "E"]);
+
+        let back = doc.chunks.iter().find(|c| c.text.contains("Back")).unwrap();
+        assert_eq!(back.context, "A > B > G");
+        assert_eq!(back.headings, vec!["A", "B", "G"]);
+
+        let uncapped = ChunkingOptions { context_depth: None, ..Default::default() };
+        let doc = parse_markdown_with(content, Path::new("deep.md"), uncapped).unwrap();
+        let deep = doc.chunks.iter().find(|c| c.text.contains("Deep")).unwrap();
+        assert_eq!(deep.context, "A > B > C > D > E");
     }
 }
 
@@ -0,0 +1,556 @@
+use crate::core::config::Config;
+use crate::core::duration::frontmatter_date;
+use crate::core::error::{Error, Result};
+use crate::indexing::discovery::SkipReason;
+use crate::indexing::parser::{extract_hashtags, parse_note_file_with, ChunkingOptions, ParsedDocument, TagNormalization, TextChunk};
+use crate::search::files::centroid;
+use crate::search::model::{EmbeddingModel, EMBED_BATCH_SIZE};
+use crate::storage::state::get_file_modified_time;
+use crate::storage::vectors::{FileMeta, VectorEntry, VectorStore};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Build the text that gets embedded for a chunk.
+/// Shared by every indexing entry point so CLI, TUI and watch vectors stay comparable.
+pub fn embedding_text(chunk: &TextChunk, config: &Config) -> String {
+    if config.embed_context && !chunk.context.trim().is_empty() {
+        format!("{}{}{}", chunk.context, config.context_separator, chunk.text)
+    } else {
+        chunk.text.clone()
+    }
+}
+
+/// Build embedding texts for all chunks of a document
+pub fn embedding_texts(chunks: &[TextChunk], config: &Config) -> Vec<String> {
+    chunks.iter().map(|c| embedding_text(c, config)).collect()
+}
+
+/// Embed a document's chunks. With `context_weight` above 0, each chunk's body and heading
+/// context are embedded separately and blended (see [`blend_embeddings`]); otherwise each
+/// chunk is embedded as its [`embedding_text`]. Also returns how many chunks were longer than
+/// the model's token limit.
+pub fn embed_chunks(
+    model: &EmbeddingModel,
+    chunks: &[TextChunk],
+    config: &Config,
+    progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<(Vec<Vec<f32>>, usize)> {
+    if config.context_weight <= 0.0 {
+        // Use embed_passages for BGE model compatibility (better search quality)
+        return model.embed_passages_batched(&embedding_texts(chunks, config), EMBED_BATCH_SIZE, progress);
+    }
+
+    let bodies: Vec<String> = chunks.iter().map(|chunk| chunk.text.clone()).collect();
+    let (mut embeddings, truncated) = model.embed_passages_batched(&bodies, EMBED_BATCH_SIZE, progress)?;
+
+    // Chunks of one section share their context, so each distinct context is embedded once
+    let mut contexts: Vec<String> = chunks
+        .iter()
+        .map(|chunk| chunk.context.trim())
+        .filter(|context| !context.is_empty())
+        .map(str::to_string)
+        .collect();
+    contexts.sort_unstable();
+    contexts.dedup();
+    if contexts.is_empty() {
+        return Ok((embeddings, truncated));
+    }
+    let (context_embeddings, _) = model.embed_passages_batched(&contexts, EMBED_BATCH_SIZE, None)?;
+    let by_context: HashMap<&str, &Vec<f32>> = contexts.iter().map(String::as_str).zip(&context_embeddings).collect();
+    for (chunk, embedding) in chunks.iter().zip(embeddings.iter_mut()) {
+        if let Some(context) = by_context.get(chunk.context.trim()) {
+            *embedding = blend_embeddings(embedding, context, config.context_weight);
+        }
+    }
+    Ok((embeddings, truncated))
+}
+
+/// `(1 - weight) * body + weight * context`, scaled back to unit length
+pub fn blend_embeddings(body: &[f32], context: &[f32], weight: f32) -> Vec<f32> {
+    let mut blended: Vec<f32> = body
+        .iter()
+        .zip(context)
+        .map(|(b, c)| (1.0 - weight) * b + weight * c)
+        .collect();
+    let norm = blended.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        blended.iter_mut().for_each(|x| *x /= norm);
+    }
+    blended
+}
+
+/// Normalized, de-duplicated tags for a chunk: document tags plus inline `#tags`
+pub fn chunk_tags(document_tags: &[String], chunk: &TextChunk, normalization: &TagNormalization) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let inline = extract_hashtags(&chunk.text);
+    for tag in document_tags.iter().chain(inline.iter()) {
+        let normalized = normalization.normalize(tag);
+        if !normalized.is_empty() && !tags.contains(&normalized) {
+            tags.push(normalized);
+        }
+    }
+    tags
+}
+
+/// Result of indexing a single file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedFile {
+    /// Chunks embedded and stored
+    pub chunks_stored: usize,
+    /// Chunks left out because of `Config::max_chunks_per_file`
+    pub chunks_dropped: usize,
+    /// Stored chunks longer than the model's token limit, embedded from part of their text
+    pub chunks_truncated: usize,
+    /// YAML error for frontmatter that was ignored (the body was still indexed)
+    pub frontmatter_error: Option<String>,
+}
+
+impl IndexedFile {
+    /// Whether the file hit the chunk cap
+    pub fn truncated(&self) -> bool {
+        self.chunks_dropped > 0
+    }
+}
+
+/// Whether a chunk has too little text for a meaningful embedding
+/// (fewer than `min_chars` letters and digits; 0 disables the check)
+pub fn is_low_content(text: &str, min_chars: usize) -> bool {
+    min_chars > 0 && text.chars().filter(|c| c.is_alphanumeric()).take(min_chars).count() < min_chars
+}
+
+/// Drop chunks beyond `max` (if set). Returns how many were dropped.
+pub fn cap_chunks(chunks: &mut Vec<TextChunk>, max: Option<usize>) -> usize {
+    match max {
+        Some(max) if chunks.len() > max => {
+            let dropped = chunks.len() - max;
+            chunks.truncate(max);
+            dropped
+        }
+        _ => 0,
+    }
+}
+
+/// Parse, embed and store a single file, replacing any vectors it had before.
+/// At most `config.max_chunks_per_file` chunks are stored.
+pub fn index_file(
+    path: &Path,
+    file_path_str: &str,
+    config: &Config,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+) -> Result<IndexedFile> {
+    index_file_with_progress(path, file_path_str, config, model, vector_store, None)
+}
+
+/// [`index_file`], reporting `(batches_done, total_batches)` while the chunks are embedded
+pub fn index_file_with_progress(
+    path: &Path,
+    file_path_str: &str,
+    config: &Config,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+    progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<IndexedFile> {
+    let _span = tracing::info_span!("index_file", file = file_path_str).entered();
+    let doc = parse_note_file_with(path, ChunkingOptions::from_config(config))?;
+    // Lets searches keep only recently changed notes (`--changed-since`)
+    let modified = get_file_modified_time(path).ok();
+    index_document(doc, file_path_str, modified, config, model, vector_store, progress)
+}
+
+/// Embed and store a note that's already parsed (a file, or an entry read from an archive)
+/// under `file_path_str`, replacing any vectors it had before. `modified` is the Unix time
+/// `--changed-since` goes by.
+pub fn index_document(
+    mut doc: ParsedDocument,
+    file_path_str: &str,
+    modified: Option<u64>,
+    config: &Config,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+    progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<IndexedFile> {
+    // Keep one pathological file from dominating the index (and skip embedding the excess)
+    let chunks_dropped = cap_chunks(&mut doc.chunks, config.max_chunks_per_file);
+    tracing::debug!(chunks = doc.chunks.len(), dropped = chunks_dropped, "Parsed note");
+
+    let (embeddings, chunks_truncated) = embed_chunks(model, &doc.chunks, config, progress)?;
+    let chunks_stored = store_document(&doc, file_path_str, embeddings, modified, config, vector_store)?;
+
+    Ok(IndexedFile {
+        chunks_stored,
+        chunks_dropped,
+        chunks_truncated,
+        frontmatter_error: doc.frontmatter_error,
+    })
+}
+
+/// Store a parsed note's chunks with their embeddings (one per chunk, in order) under
+/// `file_path_str`, replacing its old vectors; returns the number of chunks stored
+pub fn store_document(
+    doc: &ParsedDocument,
+    file_path_str: &str,
+    embeddings: Vec<Vec<f32>>,
+    modified: Option<u64>,
+    config: &Config,
+    vector_store: &VectorStore,
+) -> Result<usize> {
+    let normalization = TagNormalization::from_config(config);
+    // `--after`/`--before` go by the note's own date when its frontmatter has one
+    let note_date = doc.metadata.modified.as_deref()
+        .and_then(frontmatter_date)
+        .or_else(|| doc.metadata.created.as_deref().and_then(frontmatter_date));
+    let mut entries = Vec::with_capacity(doc.chunks.len());
+    for (chunk, embedding) in doc.chunks.iter().zip(embeddings) {
+        entries.push(VectorEntry::new(
+            file_path_str.to_string(),
+            chunk.chunk_index,
+            embedding,
+            chunk.text.clone(),
+            chunk.context.clone(),
+            chunk.start_line,
+            chunk.end_line,
+        )
+        .with_offsets(chunk.start_offset, chunk.end_offset)
+        .with_tags(chunk_tags(&doc.metadata.tags, chunk, &normalization))
+        .with_kind(chunk.kind)
+        .with_low_content(is_low_content(&chunk.text, config.min_chunk_chars))
+        .with_collection(config.collection.as_str())
+        .with_modified(modified)
+        .with_note_date(note_date)
+        .with_metadata(doc.metadata.custom.clone())
+        .with_headings(chunk.headings.clone()));
+    }
+    // Summary of the whole file, ranked first by `search --two-stage`
+    let document = centroid(&entries);
+    // Frontmatter names, so the TUI can match and boost notes by alias
+    let meta = FileMeta {
+        title: doc.metadata.title.clone(),
+        aliases: doc.metadata.aliases.clone(),
+    };
+    // Old vectors go only once the new embeddings are ready, in the same transaction as the
+    // new ones, so an interrupted run never leaves the file half indexed
+    vector_store.replace_file(file_path_str, &entries, document.as_deref(), &meta)?;
+    Ok(entries.len())
+}
+
+/// Category of a problem hit while indexing a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    PermissionDenied,
+    BrokenSymlink,
+    SymlinkLoop,
+    InvalidUtf8Path,
+    InvalidUtf8Content,
+    BinaryContent,
+    ReadFailed,
+    ModifiedTimeFailed,
+    HashFailed,
+    EmbeddingFailed,
+    StoreFailed,
+    StateUpdateFailed,
+    ChunkLimit,
+    InvalidFrontmatter,
+}
+
+impl WarningKind {
+    /// Classify an indexing error for the summary
+    pub fn from_error(error: &Error) -> Self {
+        match error {
+            Error::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied => WarningKind::PermissionDenied,
+            Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidData => WarningKind::InvalidUtf8Content,
+            Error::BinaryContent(_) => WarningKind::BinaryContent,
+            Error::Model(_) | Error::Candle(_) | Error::Tokenizer(_) => WarningKind::EmbeddingFailed,
+            Error::Database(_) => WarningKind::StoreFailed,
+            _ => WarningKind::ReadFailed,
+        }
+    }
+
+    /// Category for a path discovery had to skip
+    pub fn from_skip_reason(reason: SkipReason) -> Self {
+        match reason {
+            SkipReason::PermissionDenied => WarningKind::PermissionDenied,
+            SkipReason::BrokenSymlink => WarningKind::BrokenSymlink,
+            SkipReason::SymlinkLoop => WarningKind::SymlinkLoop,
+            SkipReason::Unreadable => WarningKind::ReadFailed,
+        }
+    }
+
+    /// Short human-readable label used in the grouped summary
+    pub fn label(&self) -> &'static str {
+        match self {
+            WarningKind::PermissionDenied => "permission denied",
+            WarningKind::BrokenSymlink => "broken symlink",
+            WarningKind::SymlinkLoop => "symlink loop",
+            WarningKind::InvalidUtf8Path => "invalid UTF-8 path",
+            WarningKind::InvalidUtf8Content => "invalid UTF-8 content",
+            WarningKind::BinaryContent => "binary content",
+            WarningKind::ReadFailed => "read failure",
+            WarningKind::ModifiedTimeFailed => "modification time unavailable",
+            WarningKind::HashFailed => "hash failure",
+            WarningKind::EmbeddingFailed => "embedding failure",
+            WarningKind::StoreFailed => "storage failure",
+            WarningKind::StateUpdateFailed => "state update failure",
+            WarningKind::ChunkLimit => "chunk limit reached",
+            WarningKind::InvalidFrontmatter => "invalid frontmatter",
+        }
+    }
+}
+
+/// A warning collected while indexing
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IndexWarning {
+    /// Relative path of the file the warning refers to
+    pub file: String,
+    pub kind: WarningKind,
+    pub message: String,
+    /// Whether the file was left out of the index because of this warning
+    pub skipped: bool,
+}
+
+impl std::fmt::Display for IndexWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} ({})", self.file, self.message, self.kind.label())
+    }
+}
+
+/// Outcome of an indexing run
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct IndexReport {
+    pub processed: usize,
+    pub skipped_unchanged: usize,
+    pub chunks_indexed: usize,
+    /// Chunks embedded from only their first (or last) `max_tokens` tokens
+    pub chunks_truncated: usize,
+    pub warnings: Vec<IndexWarning>,
+}
+
+impl IndexReport {
+    /// Record a warning
+    pub fn warn(&mut self, file: impl Into<String>, kind: WarningKind, message: impl Into<String>, skipped: bool) {
+        self.warnings.push(IndexWarning {
+            file: file.into(),
+            kind,
+            message: message.into(),
+            skipped,
+        });
+    }
+
+    /// Count `indexed`'s truncated chunks; the per-file note `--verbose` prints, if it had any
+    pub fn record_truncated(&mut self, indexed: &IndexedFile, max_tokens: usize) -> Option<String> {
+        self.chunks_truncated += indexed.chunks_truncated;
+        (indexed.chunks_truncated > 0).then(|| {
+            format!(
+                "{} of {} chunks truncated at {} tokens",
+                indexed.chunks_truncated, indexed.chunks_stored, max_tokens
+            )
+        })
+    }
+
+    /// e.g. "37 chunks truncated at 256 tokens; ...", or None when nothing was cut
+    pub fn truncation_summary(&self, max_tokens: usize) -> Option<String> {
+        (self.chunks_truncated > 0).then(|| {
+            format!(
+                "{} chunk{} truncated at {} tokens; text past the limit doesn't affect search (split long sections with headings, or raise max_tokens if it's below the model's limit)",
+                self.chunks_truncated,
+                if self.chunks_truncated == 1 { "" } else { "s" },
+                max_tokens
+            )
+        })
+    }
+
+    /// Number of files left out of the index because of warnings
+    pub fn skipped_files(&self) -> usize {
+        self.warnings.iter().filter(|w| w.skipped).count()
+    }
+
+    /// Grouped one-line summary, e.g.
+    /// "12 files skipped: 8 permission denied, 3 invalid UTF-8 path, 1 embedding failure"
+    pub fn summary(&self) -> Option<String> {
+        if self.warnings.is_empty() {
+            return None;
+        }
+
+        let mut counts: std::collections::BTreeMap<WarningKind, usize> = std::collections::BTreeMap::new();
+        for w in self.warnings.iter().filter(|w| w.skipped) {
+            *counts.entry(w.kind).or_insert(0) += 1;
+        }
+        let skipped = self.skipped_files();
+        let mut groups: Vec<(WarningKind, usize)> = counts.into_iter().collect();
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let breakdown: Vec<String> = groups
+            .iter()
+            .map(|(kind, n)| format!("{} {}", n, kind.label()))
+            .collect();
+
+        let other = self.warnings.len() - skipped;
+        let mut line = if skipped > 0 {
+            format!(
+                "{} file{} skipped: {}",
+                skipped,
+                if skipped == 1 { "" } else { "s" },
+                breakdown.join(", ")
+            )
+        } else {
+            String::new()
+        };
+        if other > 0 {
+            if !line.is_empty() {
+                line.push_str("; ");
+            }
+            line.push_str(&format!("{} other warning{}", other, if other == 1 { "" } else { "s" }));
+        }
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexing::parser::ChunkKind;
+    use std::path::PathBuf;
+
+    fn chunk(context: &str, text: &str) -> TextChunk {
+        TextChunk {
+            text: text.to_string(),
+            context: context.to_string(),
+            headings: Vec::new(),
+            chunk_index: 0,
+            start_line: 1,
+            end_line: 1,
+            start_offset: 0,
+            end_offset: text.len(),
+            kind: ChunkKind::Content,
+        }
+    }
+
+    #[test]
+    fn test_context_weight_changes_top_chunk() {
+        // "deployment checklist": one chunk's body matches, the other sits under that heading
+        let query = [1.0, 0.0, 0.0];
+        let chunks = [
+            ("Misc", [0.8, 0.6, 0.0], [0.0, 0.0, 1.0]),
+            ("Deployment checklist", [0.6, 0.0, 0.8], [1.0, 0.0, 0.0]),
+        ];
+        let top = |weight: f32| {
+            let scores: Vec<(&str, f32)> = chunks
+                .iter()
+                .map(|(context, body, context_embedding)| {
+                    let blended = blend_embeddings(body, context_embedding, weight);
+                    let norm: f32 = blended.iter().map(|x| x * x).sum::<f32>().sqrt();
+                    assert!((norm - 1.0).abs() < 1e-5);
+                    (*context, blended.iter().zip(&query).map(|(a, b)| a * b).sum())
+                })
+                .collect();
+            scores.into_iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap().0
+        };
+
+        assert_eq!(top(0.0), "Misc");
+        assert_eq!(top(0.2), "Misc");
+        assert_eq!(top(0.5), "Deployment checklist");
+        assert_eq!(blend_embeddings(&[0.6, 0.8], &[1.0, 0.0], 0.0), vec![0.6, 0.8]);
+    }
+
+    #[test]
+    fn test_report_summary_groups_skipped_files() {
+        let mut report = IndexReport::default();
+        assert!(report.summary().is_none());
+
+        for i in 0..3 {
+            report.warn(format!("locked{}.md", i), WarningKind::PermissionDenied, "denied", true);
+        }
+        report.warn("bad.md", WarningKind::InvalidUtf8Path, "invalid path", true);
+        report.warn("slow.md", WarningKind::HashFailed, "hash failed", false);
+
+        assert_eq!(report.skipped_files(), 4);
+        assert_eq!(
+            report.summary().unwrap(),
+            "4 files skipped: 3 permission denied, 1 invalid UTF-8 path; 1 other warning"
+        );
+    }
+
+    #[test]
+    fn test_truncated_chunks_are_counted_not_warned() {
+        let mut report = IndexReport::default();
+        let file = |chunks_truncated| IndexedFile { chunks_stored: 12, chunks_dropped: 0, chunks_truncated, frontmatter_error: None };
+        assert_eq!(report.record_truncated(&file(0), 256), None);
+        assert!(report.truncation_summary(256).is_none());
+
+        assert_eq!(report.record_truncated(&file(3), 256).unwrap(), "3 of 12 chunks truncated at 256 tokens");
+        report.record_truncated(&file(34), 256);
+        assert!(report.truncation_summary(256).unwrap().starts_with("37 chunks truncated at 256 tokens;"));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_is_low_content() {
+        assert!(is_low_content("- milk", 12));
+        assert!(is_low_content("", 12));
+        assert!(is_low_content("***  ---", 1));
+        assert!(!is_low_content("Replace the kitchen tap washer", 12));
+        assert!(!is_low_content("milk", 0));
+    }
+
+    #[test]
+    fn test_cap_chunks() {
+        let mut chunks: Vec<TextChunk> = (0..10).map(|i| chunk("", &format!("chunk {}", i))).collect();
+        assert_eq!(cap_chunks(&mut chunks, None), 0);
+        assert_eq!(chunks.len(), 10);
+
+        assert_eq!(cap_chunks(&mut chunks, Some(20)), 0);
+        assert_eq!(chunks.len(), 10);
+
+        assert_eq!(cap_chunks(&mut chunks, Some(4)), 6);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[3].text, "chunk 3");
+    }
+
+    #[test]
+    fn test_chunk_limit_warning_is_not_a_skip() {
+        let mut report = IndexReport::default();
+        report.warn("huge.md", WarningKind::ChunkLimit, "stored 4 of 10 chunks", false);
+        assert_eq!(report.skipped_files(), 0);
+        assert_eq!(report.summary().unwrap(), "1 other warning");
+    }
+
+    #[test]
+    fn test_warning_kind_from_error() {
+        let denied = Error::Io(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "no"));
+        assert_eq!(WarningKind::from_error(&denied), WarningKind::PermissionDenied);
+        let binary = Error::BinaryContent("image.md".to_string());
+        assert_eq!(WarningKind::from_error(&binary), WarningKind::BinaryContent);
+        let model = Error::Model("boom".to_string());
+        assert_eq!(WarningKind::from_error(&model), WarningKind::EmbeddingFailed);
+    }
+
+    #[test]
+    fn test_embedding_text_with_context() {
+        let config = Config::new(Some(PathBuf::from("/tmp/notes2vec-test"))).unwrap();
+        let text = embedding_text(&chunk("Doc > Agenda", "Standup at 10"), &config);
+        assert_eq!(text, "Doc > Agenda\nStandup at 10");
+    }
+
+    #[test]
+    fn test_embedding_text_custom_separator() {
+        let mut config = Config::new(Some(PathBuf::from("/tmp/notes2vec-test"))).unwrap();
+        config.context_separator = " | ".to_string();
+        let text = embedding_text(&chunk("Doc", "Body"), &config);
+        assert_eq!(text, "Doc | Body");
+    }
+
+    #[test]
+    fn test_embedding_text_context_disabled() {
+        let mut config = Config::new(Some(PathBuf::from("/tmp/notes2vec-test"))).unwrap();
+        config.embed_context = false;
+        let text = embedding_text(&chunk("Doc", "Body"), &config);
+        assert_eq!(text, "Body");
+    }
+
+    #[test]
+    fn test_embedding_text_empty_context() {
+        let config = Config::new(Some(PathBuf::from("/tmp/notes2vec-test"))).unwrap();
+        let text = embedding_text(&chunk("  ", "Body"), &config);
+        assert_eq!(text, "Body");
+    }
+}
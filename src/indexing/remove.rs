@@ -0,0 +1,178 @@
+//! Taking files out of the index by path (`notes2vec remove <path-or-glob>`), without touching
+//! the notes themselves.
+//!
+//! A plain path removes that file, or every file under it when it names a folder. A pattern
+//! with glob characters (`*`, `?`, `[`, `{`) is matched against whole stored paths, where `*`
+//! stays within one folder and `**` crosses folders.
+
+use crate::core::error::{Error, Result};
+use crate::storage::state::StateStore;
+use crate::storage::vectors::{chunk_file_path, VectorStore};
+use globset::{Glob, GlobBuilder, GlobMatcher};
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+
+/// Files removed per write transaction
+pub const REMOVE_BATCH_SIZE: usize = 256;
+
+/// Which stored paths a `remove` applies to
+#[derive(Debug, Clone)]
+pub enum RemovePattern {
+    /// A file, or the folder holding files, with `/` separators and no trailing `/`
+    Prefix(String),
+    Glob(GlobMatcher),
+}
+
+impl RemovePattern {
+    /// Parse a path or glob. An empty pattern, or one naming the whole index (`.`, `/`, or
+    /// wildcards alone like `*` and `**`), is refused so a slip can't wipe everything.
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let normalized = pattern.trim().replace('\\', "/");
+        let normalized = normalized.trim_start_matches("./").trim_end_matches('/');
+        if normalized.is_empty() || normalized == "." {
+            return Err(Error::Config(
+                "Refusing to remove with an empty pattern, which would match the whole index. Name a file, a folder or a glob.".to_string(),
+            ));
+        }
+        if normalized.chars().all(|c| c == '*' || c == '/') {
+            return Err(Error::Config(format!(
+                "Refusing to remove with '{}', which would match the whole index. Name a file, a folder or a narrower glob.",
+                pattern.trim()
+            )));
+        }
+        if !normalized.contains(['*', '?', '[', '{']) {
+            return Ok(RemovePattern::Prefix(normalized.to_string()));
+        }
+        let glob: Glob = GlobBuilder::new(normalized)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| Error::Config(format!("Invalid glob '{}': {}", pattern, e)))?;
+        Ok(RemovePattern::Glob(glob.compile_matcher()))
+    }
+
+    /// Whether the stored path `file_path` matches
+    pub fn matches(&self, file_path: &str) -> bool {
+        match self {
+            RemovePattern::Prefix(prefix) => file_path
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+            RemovePattern::Glob(matcher) => matcher.is_match(file_path),
+        }
+    }
+}
+
+/// Files a `remove` took out of the index (or would have, for a dry run)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RemovalReport {
+    /// Matching paths with their stored chunk counts, sorted; a path with state but no
+    /// vectors counts 0 chunks
+    pub files: Vec<(String, usize)>,
+    pub chunks: usize,
+    pub dry_run: bool,
+}
+
+/// Every indexed path matching `pattern`, with its chunk count
+pub fn matching_files(pattern: &RemovePattern, vector_store: &VectorStore, state_store: &StateStore) -> Result<BTreeMap<String, usize>> {
+    let mut files = BTreeMap::new();
+    vector_store.for_each_key(|chunk_id| {
+        let file_path = chunk_file_path(chunk_id);
+        if pattern.matches(file_path) {
+            *files.entry(file_path.to_string()).or_insert(0) += 1;
+        }
+        ControlFlow::Continue(())
+    })?;
+    // Files whose state was kept without vectors (all chunks filtered out, say) go too
+    for (file_path, _) in state_store.list_states()? {
+        if pattern.matches(&file_path) {
+            files.entry(file_path).or_insert(0);
+        }
+    }
+    Ok(files)
+}
+
+/// Remove the vectors and state of every indexed file matching `pattern`, in transactions of
+/// [`REMOVE_BATCH_SIZE`] files. With `dry_run`, only report what would go.
+pub fn remove_matching(
+    pattern: &RemovePattern,
+    vector_store: &VectorStore,
+    state_store: &StateStore,
+    dry_run: bool,
+) -> Result<RemovalReport> {
+    let files: Vec<(String, usize)> = matching_files(pattern, vector_store, state_store)?.into_iter().collect();
+    if !dry_run {
+        for batch in files.chunks(REMOVE_BATCH_SIZE) {
+            let paths: Vec<String> = batch.iter().map(|(file_path, _)| file_path.clone()).collect();
+            // Vectors first: a file whose state survives an interruption is indexed again,
+            // not left behind as a state with nothing to search
+            vector_store.remove_files(&paths)?;
+            state_store.remove_files(&paths)?;
+        }
+    }
+    Ok(RemovalReport {
+        chunks: files.iter().map(|(_, chunks)| chunks).sum(),
+        files,
+        dry_run,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::storage::state::FileState;
+    use crate::storage::vectors::VectorEntry;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_patterns() {
+        let folder = RemovePattern::parse("./archive/2023/").unwrap();
+        assert!(folder.matches("archive/2023/jan.md"));
+        assert!(folder.matches("archive/2023"));
+        assert!(!folder.matches("archive/2023-old/jan.md"));
+
+        let glob = RemovePattern::parse("journal/*.txt").unwrap();
+        assert!(glob.matches("journal/monday.txt"));
+        assert!(!glob.matches("journal/2024/monday.txt"));
+        assert!(RemovePattern::parse("journal/**/*.txt").unwrap().matches("journal/2024/monday.txt"));
+
+        for everything in ["", "  ", ".", "./", "/", "*", "**", "./**/", "**/*", "*/**"] {
+            assert!(RemovePattern::parse(everything).is_err(), "{:?}", everything);
+        }
+        assert!(RemovePattern::parse("**/*.md").is_ok());
+        assert!(RemovePattern::parse("notes/[").is_err());
+    }
+
+    #[test]
+    fn test_remove_takes_out_vectors_and_state_of_matches_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let vector_store = VectorStore::open(&config).unwrap();
+        let state_store = StateStore::open(&config).unwrap();
+
+        for (file, chunks) in [("archive/a.md", 2), ("archive/sub/b.md", 1), ("archived.md", 1), ("todo.md", 3)] {
+            for i in 0..chunks {
+                vector_store
+                    .insert(&VectorEntry::new(file.to_string(), i, vec![1.0, 0.0], "text".to_string(), String::new(), 1, 1))
+                    .unwrap();
+            }
+            state_store.store_file_state(file, &FileState::new(1, "hash".to_string())).unwrap();
+        }
+        let pattern = RemovePattern::parse("archive").unwrap();
+
+        let preview = remove_matching(&pattern, &vector_store, &state_store, true).unwrap();
+        assert_eq!(preview.files, vec![("archive/a.md".to_string(), 2), ("archive/sub/b.md".to_string(), 1)]);
+        assert_eq!(preview.chunks, 3);
+        assert_eq!(vector_store.get_file_count().unwrap(), 4);
+
+        let removed = remove_matching(&pattern, &vector_store, &state_store, false).unwrap();
+        assert_eq!(removed.files, preview.files);
+        assert!(vector_store.get_file_vectors("archive/a.md").unwrap().is_empty());
+        assert_eq!(vector_store.get_file_count().unwrap(), 2);
+        let mut left: Vec<String> = state_store.list_states().unwrap().into_iter().map(|(file, _)| file).collect();
+        left.sort();
+        assert_eq!(left, vec!["archived.md", "todo.md"]);
+
+        assert!(remove_matching(&pattern, &vector_store, &state_store, false).unwrap().files.is_empty());
+    }
+}
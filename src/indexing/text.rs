@@ -0,0 +1,426 @@
+//! Chunker for plain-text notes.
+//!
+//! `.txt` files have no headings, so chunks get a synthesized context instead: the
+//! file's first non-blank line (as a pseudo-title) and the first line of the paragraph
+//! the chunk starts in. Files are read line by line, so only the chunk being built is
+//! held in memory, and every chunk's line range matches the file exactly.
+
+use crate::core::error::{Error, Result};
+use crate::indexing::parser::{
+    looks_binary, offset_in, ChunkKind, DocumentMetadata, ParsedDocument, TextChunk, MAX_CHUNK_SIZE,
+    MIN_CHUNK_SIZE, TARGET_CHUNK_SIZE,
+};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Characters kept from the pseudo-title and from the paragraph line in a context
+pub const MAX_CONTEXT_PART_CHARS: usize = 80;
+
+/// Bytes read before chunking to rule out binary content
+const SNIFF_LEN: u64 = 8192;
+
+/// Parse a plain-text file without loading it whole
+pub fn parse_text_file(path: &Path) -> Result<ParsedDocument> {
+    let mut file = File::open(path)?;
+    let mut head = Vec::new();
+    (&mut file).take(SNIFF_LEN).read_to_end(&mut head)?;
+    if looks_binary(&head) {
+        return Err(Error::BinaryContent(path.display().to_string()));
+    }
+
+    let reader = BufReader::new(head.as_slice().chain(file));
+    let mut chunks = Vec::new();
+    let title = chunk_text(reader, |chunk| chunks.push(chunk))?;
+    Ok(text_document(title, chunks, path))
+}
+
+/// [`parse_text_file`] for a note already in memory (say an entry read from an archive);
+/// `path` only names it
+pub fn parse_text_bytes(bytes: &[u8], path: &Path) -> Result<ParsedDocument> {
+    if looks_binary(bytes) {
+        return Err(Error::BinaryContent(path.display().to_string()));
+    }
+
+    let mut chunks = Vec::new();
+    let title = chunk_text(bytes, |chunk| chunks.push(chunk))?;
+    Ok(text_document(title, chunks, path))
+}
+
+fn text_document(title: Option<String>, chunks: Vec<TextChunk>, path: &Path) -> ParsedDocument {
+    ParsedDocument {
+        metadata: DocumentMetadata::default(),
+        title: title.unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Untitled")
+                .to_string()
+        }),
+        chunks,
+        header_hierarchy: Vec::new(),
+        frontmatter_error: None,
+    }
+}
+
+/// Chunk text from `reader`, handing each chunk to `emit` as soon as it is complete.
+/// Returns the pseudo-title (the first non-blank line), if the text has one.
+pub fn chunk_text<R: BufRead>(mut reader: R, mut emit: impl FnMut(TextChunk)) -> Result<Option<String>> {
+    let mut chunker = TextChunker::default();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let content = line.trim_end_matches(['\n', '\r']);
+        for chunk in chunker.push_line_ending(content, line.len() - content.len()) {
+            emit(chunk);
+        }
+    }
+    if let Some(chunk) = chunker.finish() {
+        emit(chunk);
+    }
+    Ok(chunker.title)
+}
+
+/// Streaming state: paragraphs (separated by blank lines) are packed into chunks of up to
+/// `MAX_CHUNK_SIZE` bytes, and a new paragraph starts a new chunk once `TARGET_CHUNK_SIZE`
+/// is reached. A paragraph that doesn't fit is split between lines, and a single overlong
+/// line between words.
+#[derive(Debug, Default)]
+pub struct TextChunker {
+    title: Option<String>,
+    /// 1-based number of the last line pushed
+    line_number: usize,
+    /// Byte offset where the next line starts
+    next_offset: usize,
+    /// First line of the paragraph being read
+    paragraph_head: Option<String>,
+    /// Text of the chunk being built
+    text: String,
+    start_line: usize,
+    end_line: usize,
+    /// Byte range of the chunk being built in the source
+    start_offset: usize,
+    end_offset: usize,
+    /// First line of the paragraph the chunk being built starts in
+    chunk_head: String,
+    /// A blank line came after the last text added to the chunk
+    paragraph_break: bool,
+    chunk_index: usize,
+}
+
+impl TextChunker {
+    /// Add the next line (without its line ending, taken to be `\n`); returns any chunks
+    /// completed by it
+    pub fn push_line(&mut self, line: &str) -> Vec<TextChunk> {
+        self.push_line_ending(line, 1)
+    }
+
+    /// [`push_line`](Self::push_line) for a line followed by `ending` bytes of line ending
+    /// (2 for `\r\n`), so chunks' byte offsets match the source
+    pub fn push_line_ending(&mut self, line: &str, ending: usize) -> Vec<TextChunk> {
+        self.line_number += 1;
+        let line_start = self.next_offset;
+        self.next_offset += line.len() + ending;
+        let mut done = Vec::new();
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            self.paragraph_head = None;
+            self.paragraph_break = !self.text.is_empty();
+            return done;
+        }
+        if self.title.is_none() {
+            self.title = Some(truncate_chars(trimmed, MAX_CONTEXT_PART_CHARS));
+        }
+        if self.paragraph_head.is_none() {
+            self.paragraph_head = Some(truncate_chars(trimmed, MAX_CONTEXT_PART_CHARS));
+        }
+
+        let separator = if self.text.is_empty() {
+            0
+        } else if self.paragraph_break {
+            2
+        } else {
+            1
+        };
+        if self.paragraph_break && self.text.len() >= TARGET_CHUNK_SIZE {
+            done.extend(self.flush());
+        } else if !self.text.is_empty() && self.text.len() + separator + trimmed.len() > MAX_CHUNK_SIZE {
+            // Prefer ending chunks between paragraphs; mid-paragraph only once the chunk is big enough
+            if self.paragraph_break || self.text.len() >= MIN_CHUNK_SIZE {
+                done.extend(self.flush());
+            }
+        }
+
+        if trimmed.len() <= MAX_CHUNK_SIZE {
+            self.append(trimmed, line_start + offset_in(line, trimmed));
+        } else {
+            for piece in split_long_line(trimmed, MAX_CHUNK_SIZE) {
+                if !self.text.is_empty() && self.text.len() + 1 + piece.len() > MAX_CHUNK_SIZE {
+                    done.extend(self.flush());
+                }
+                self.append(piece, line_start + offset_in(line, piece));
+            }
+        }
+        done
+    }
+
+    /// The chunk still being built once the input has ended
+    pub fn finish(&mut self) -> Option<TextChunk> {
+        self.flush()
+    }
+
+    /// Bytes held for the chunk being built (stays near `MAX_CHUNK_SIZE` whatever the file size)
+    pub fn buffered_len(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Add `text`, found at byte `offset` of the source, to the chunk being built
+    fn append(&mut self, text: &str, offset: usize) {
+        if self.text.is_empty() {
+            self.start_line = self.line_number;
+            self.start_offset = offset;
+            self.chunk_head = self.paragraph_head.clone().unwrap_or_default();
+        } else if self.paragraph_break {
+            self.text.push_str("\n\n");
+        } else if self.end_line == self.line_number {
+            // Another piece of the same overlong line
+            self.text.push(' ');
+        } else {
+            self.text.push('\n');
+        }
+        self.text.push_str(text);
+        self.end_line = self.line_number;
+        self.end_offset = offset + text.len();
+        self.paragraph_break = false;
+    }
+
+    fn flush(&mut self) -> Option<TextChunk> {
+        if self.text.is_empty() {
+            return None;
+        }
+        let mut chunk = TextChunk {
+            text: std::mem::take(&mut self.text),
+            context: self.context(),
+            headings: Vec::new(),
+            chunk_index: self.chunk_index,
+            start_line: self.start_line,
+            end_line: self.end_line,
+            start_offset: self.start_offset,
+            end_offset: self.end_offset,
+            kind: ChunkKind::Content,
+        };
+        chunk.normalize_whitespace();
+        self.chunk_index += 1;
+        self.paragraph_break = false;
+        Some(chunk)
+    }
+
+    /// `Pseudo-title > First line of the paragraph`, or just the title for the opening paragraph
+    fn context(&self) -> String {
+        let title = self.title.as_deref().unwrap_or_default();
+        if self.chunk_head.is_empty() || self.chunk_head == title {
+            title.to_string()
+        } else {
+            format!("{} > {}", title, self.chunk_head)
+        }
+    }
+}
+
+/// At most `max` characters, with `…` marking a cut
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// Pieces of at most `max` bytes, cut at whitespace where possible and always on a char boundary
+fn split_long_line(line: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.len() > max {
+        let mut cut = max;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        if let Some(space) = rest[..cut].rfind(char::is_whitespace) {
+            if space > 0 {
+                cut = space;
+            }
+        }
+        pieces.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn chunks_of(text: &str) -> (Option<String>, Vec<TextChunk>) {
+        let mut chunks = Vec::new();
+        let title = chunk_text(text.as_bytes(), |chunk| chunks.push(chunk)).unwrap();
+        (title, chunks)
+    }
+
+    #[test]
+    fn test_context_is_title_and_paragraph_head() {
+        let paragraph = "Filler sentence that pads the paragraph out. ".repeat(8);
+        let text = format!(
+            "\n  Trip planning\n\n{}\n\nPacking list\nboots and a rain jacket\n{}\n",
+            paragraph.trim(),
+            paragraph.trim()
+        );
+        let (title, chunks) = chunks_of(&text);
+
+        assert_eq!(title.as_deref(), Some("Trip planning"));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].context, "Trip planning");
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (2, 4));
+        assert!(chunks[0].text.starts_with("Trip planning\n\nFiller"));
+        assert_eq!(chunks[1].context, "Trip planning > Packing list");
+        assert_eq!((chunks[1].start_line, chunks[1].end_line), (6, 8));
+    }
+
+    #[test]
+    fn test_chunk_whitespace_is_normalized() {
+        let (_, chunks) = chunks_of("Title  line \r\n\r\nfirst   line  \r\n\tsecond line\r\n");
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text, "Title line\n\nfirst line\nsecond line");
+        assert_eq!(chunks[0].context, "Title line");
+    }
+
+    #[test]
+    fn test_offsets_slice_back_to_the_chunk_source() {
+        let paragraph = "Filler sentence that pads the paragraph out. ".repeat(8);
+        let long_line = "alpha beta gamma delta ".repeat(40);
+        let text = format!(
+            "  Trip planning\r\n\r\n{}\r\n\r\nPack boots. And a rain jacket\n{}\n",
+            paragraph.trim(),
+            long_line.trim()
+        );
+        let (_, chunks) = chunks_of(&text);
+        assert!(chunks.len() > 3);
+        for chunk in &chunks {
+            let source = &text[chunk.start_offset..chunk.end_offset];
+            let first_sentence = chunk.text.lines().next().unwrap().split(". ").next().unwrap();
+            assert!(source.starts_with(first_sentence), "{:?} vs {:?}", source, chunk.text);
+            assert_eq!(source.trim(), source);
+        }
+        assert_eq!(chunks[0].start_offset, 2);
+        assert_eq!(&text[chunks[1].start_offset..chunks[1].start_offset + 11], "Pack boots.");
+    }
+
+    #[test]
+    fn test_long_context_parts_are_truncated() {
+        let long = "word ".repeat(40);
+        let (title, chunks) = chunks_of(&format!("{}\n\n{}x\n", long, long));
+        let title = title.unwrap();
+        assert!(title.ends_with('…'));
+        assert!(title.chars().count() <= MAX_CONTEXT_PART_CHARS + 1);
+        let context = &chunks.last().unwrap().context;
+        assert!(context.chars().count() <= 2 * (MAX_CONTEXT_PART_CHARS + 1) + 3);
+    }
+
+    #[test]
+    fn test_overlong_line_is_split_on_words() {
+        let line = "alpha beta gamma delta ".repeat(100);
+        let (_, chunks) = chunks_of(&format!("Title\n\n{}\nnext line\n", line.trim()));
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|c| c.text.len() <= MAX_CHUNK_SIZE));
+        assert!(chunks.iter().skip(1).all(|c| !c.text.starts_with(' ')));
+        // Pieces of line 3 all point at line 3
+        assert!(chunks[1..chunks.len() - 1].iter().all(|c| (c.start_line, c.end_line) == (3, 3)));
+        assert_eq!(chunks.last().unwrap().end_line, 4);
+    }
+
+    #[test]
+    fn test_split_long_line_respects_char_boundaries() {
+        let line = "é".repeat(300);
+        let pieces = split_long_line(&line, 101);
+        assert!(pieces.iter().all(|p| p.len() <= 101));
+        assert_eq!(pieces.concat(), line);
+    }
+
+    #[test]
+    fn test_large_file_streams_with_exact_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("big.txt");
+
+        // ~2 MB: numbered lines in paragraphs of 1-7 lines, with CRLF endings in places
+        let mut writer = std::io::BufWriter::new(fs::File::create(&path).unwrap());
+        let mut line_number = 0;
+        let mut size = 0;
+        let mut paragraph = 0;
+        while size < 2 * 1024 * 1024 {
+            paragraph += 1;
+            for _ in 0..(paragraph % 7 + 1) {
+                line_number += 1;
+                let ending = if line_number % 5 == 0 { "\r\n" } else { "\n" };
+                let line = format!("line {} of paragraph {} with some filler words{}", line_number, paragraph, ending);
+                size += line.len();
+                writer.write_all(line.as_bytes()).unwrap();
+            }
+            line_number += 1;
+            size += 1;
+            writer.write_all(b"\n").unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+
+        let mut chunker = TextChunker::default();
+        let mut reader = BufReader::new(fs::File::open(&path).unwrap());
+        let mut line = String::new();
+        let mut chunks = Vec::new();
+        let mut max_buffered = 0;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+            chunks.extend(chunker.push_line(line.trim_end_matches(['\n', '\r'])));
+            max_buffered = max_buffered.max(chunker.buffered_len());
+        }
+        chunks.extend(chunker.finish());
+
+        assert!(max_buffered <= MAX_CHUNK_SIZE, "buffered {} bytes", max_buffered);
+        assert!(chunks.len() > 1000);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.chunk_index, i);
+            let first = chunk.text.lines().next().unwrap();
+            let last = chunk.text.lines().last().unwrap();
+            assert!(first.starts_with(&format!("line {} ", chunk.start_line)), "{} vs {}", first, chunk.start_line);
+            assert!(last.starts_with(&format!("line {} ", chunk.end_line)), "{} vs {}", last, chunk.end_line);
+        }
+
+        // The file parser agrees with the streaming scan
+        let doc = parse_text_file(&path).unwrap();
+        assert_eq!(doc.title, "line 1 of paragraph 1 with some filler words");
+        assert_eq!(doc.chunks.len(), chunks.len());
+        assert_eq!(doc.chunks.last().unwrap().end_line, chunks.last().unwrap().end_line);
+    }
+
+    #[test]
+    fn test_binary_and_empty_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = temp_dir.path().join("image.txt");
+        fs::write(&binary, [0x89, b'P', b'N', b'G', 0, 0, 0, 13]).unwrap();
+        assert!(matches!(parse_text_file(&binary), Err(Error::BinaryContent(_))));
+
+        let empty = temp_dir.path().join("empty.txt");
+        fs::write(&empty, "\n\n   \n").unwrap();
+        let doc = parse_text_file(&empty).unwrap();
+        assert_eq!(doc.title, "empty");
+        assert!(doc.chunks.is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::core::error::Result;
+use crate::storage::state::{calculate_file_hash, StateStore};
+use std::path::Path;
+
+/// Tracked files that no longer match their source, found by `notes2vec verify`
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct VerifyReport {
+    /// Tracked files checked
+    pub checked: usize,
+    /// Files whose content changed since they were indexed
+    pub stale: Vec<String>,
+    /// Files that were indexed but no longer exist
+    pub orphans: Vec<String>,
+    /// Files that exist but couldn't be read, with the reason
+    pub unreadable: Vec<(String, String)>,
+}
+
+impl VerifyReport {
+    /// Whether every tracked file still matches its source
+    pub fn is_clean(&self) -> bool {
+        self.stale.is_empty() && self.orphans.is_empty() && self.unreadable.is_empty()
+    }
+}
+
+/// Compare every tracked file under `root` with its stored hash. Nothing is modified.
+/// Hashes are always recomputed, so a change that kept the old modification time is caught too.
+pub fn verify_index(state_store: &StateStore, root: &Path) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    let mut states = state_store.list_states()?;
+    states.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (file_path, state) in states {
+        report.checked += 1;
+        let path = root.join(&file_path);
+        if !path.is_file() {
+            report.orphans.push(file_path);
+            continue;
+        }
+        match calculate_file_hash(&path) {
+            Ok(hash) if hash == state.content_hash => {}
+            Ok(_) => report.stale.push(file_path),
+            Err(e) => report.unreadable.push((file_path, e.to_string())),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::storage::state::get_file_modified_time;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn track(store: &StateStore, root: &Path, file_path: &str) {
+        let path = root.join(file_path);
+        let modified = get_file_modified_time(&path).unwrap();
+        let hash = calculate_file_hash(&path).unwrap();
+        store.update_file_state(file_path, modified, hash).unwrap();
+    }
+
+    #[test]
+    fn test_edited_file_is_stale_and_deleted_file_is_orphan() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("base"))).unwrap();
+        config.init().unwrap();
+        let root = temp_dir.path().join("notes");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("kept.md"), "# Kept").unwrap();
+        fs::write(root.join("edited.md"), "# Draft").unwrap();
+        fs::write(root.join("deleted.md"), "# Gone soon").unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        for file in ["kept.md", "edited.md", "deleted.md"] {
+            track(&store, &root, file);
+        }
+        assert!(verify_index(&store, &root).unwrap().is_clean());
+
+        fs::write(root.join("edited.md"), "# Final").unwrap();
+        fs::remove_file(root.join("deleted.md")).unwrap();
+
+        let report = verify_index(&store, &root).unwrap();
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.stale, vec!["edited.md"]);
+        assert_eq!(report.orphans, vec!["deleted.md"]);
+        assert!(report.unreadable.is_empty());
+
+        // Verifying never touches the state store
+        assert_eq!(store.list_states().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_notes_root_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("base"))).unwrap();
+        config.init().unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        assert_eq!(store.get_notes_root().unwrap(), None);
+        store.set_notes_root(Path::new("/home/me/notes")).unwrap();
+        assert_eq!(store.get_notes_root().unwrap(), Some(Path::new("/home/me/notes").to_path_buf()));
+        // Metadata isn't listed as a tracked file
+        assert!(store.list_states().unwrap().is_empty());
+    }
+}
@@ -1,32 +1,18 @@
+// Each module's submodules are declared once, in its mod.rs
 // Core functionality
-pub mod core {
-    pub mod config;
-    pub mod error;
-}
+pub mod core;
 
 // Data storage
-pub mod storage {
-    pub mod state;
-    pub mod vectors;
-}
+pub mod storage;
 
 // Indexing pipeline
-pub mod indexing {
-    pub mod discovery;
-    pub mod parser;
-}
+pub mod indexing;
 
 // Search & ML
-pub mod search {
-    pub mod model;
-}
+pub mod search;
 
 // User interfaces
-pub mod ui {
-    pub mod cli;
-    pub mod tui;
-    pub mod watch;
-}
+pub mod ui;
 
 // Re-export commonly used types
 pub use core::error::{Error, Result};
@@ -34,9 +20,10 @@ pub use core::config::Config;
 pub use storage::state::{StateStore, calculate_file_hash, get_file_modified_time};
 pub use storage::vectors::{VectorStore, VectorEntry};
 pub use indexing::discovery::discover_files;
+/// Same module as [`indexing::parser`], kept at the root for code written against it
 pub use indexing::parser;
 pub use search::model::EmbeddingModel;
 pub use ui::cli::Cli;
-pub use ui::tui::SearchTui;
+pub use ui::tui::{Screen, SearchTui};
 pub use ui::watch::FileWatcher;
 
@@ -1,58 +1,216 @@
 use clap::Parser;
-use notes2vec::{Cli, Config, discover_files, Error, Result};
-use notes2vec::{EmbeddingModel, StateStore, calculate_file_hash, get_file_modified_time};
-use notes2vec::{VectorStore, VectorEntry, SearchTui, FileWatcher};
-use std::path::PathBuf;
+use notes2vec::{Cli, Config, Error, Result};
+use notes2vec::indexing::discovery::{discover_files_with_report, DiscoveredFile, DiscoveryOptions};
+use notes2vec::{EmbeddingModel, StateStore};
+use notes2vec::storage::state::{recorded_model_checksums, recorded_similarity_metric, FileCheck, FileSnapshot, FileState};
+use notes2vec::{VectorStore, SearchTui, FileWatcher};
+use notes2vec::core::doctor::run_checks;
+use notes2vec::core::duration::unix_cutoff;
+use notes2vec::core::sample::SampleRng;
+use notes2vec::core::config::{SettingSource, DEFAULT_COLLECTION, ENV_PREFIX, HOME_ENV};
+use notes2vec::search::collections::{search_collections, CollectionStore};
+use notes2vec::search::checksums::model_mismatch;
+use notes2vec::search::metric::metric_mismatch;
+use notes2vec::search::options::{SearchOptions, DEFAULT_EXPAND_CHUNKS, DEFAULT_TWO_STAGE_FILES};
+use notes2vec::storage::crypto::PASSPHRASE_ENV;
+use notes2vec::indexing::archive::{index_archive, is_archive};
+use notes2vec::indexing::import::import_notes;
+use notes2vec::indexing::remove::{remove_matching, RemovePattern};
+use notes2vec::indexing::pipeline::{embed_chunks, index_file, index_file_with_progress, IndexReport, WarningKind};
+use notes2vec::indexing::verify::verify_index;
+use notes2vec::search::calibration::{ConfidenceThresholds, ScoreCalibration};
+use notes2vec::search::explain::ScoreBreakdown;
+use notes2vec::search::files::{best_chunks_for_query, search_files, similar_files, DEFAULT_TOP_K};
+use notes2vec::storage::vectors::VectorEntry;
+use notes2vec::ui::cli::{ConfigAction, ContextLines, read_source_lines, SourceLine, write_collection_results, write_discovered_files, write_doctor_report, write_file_paths, write_file_results, write_search_results_in_context, write_search_results_with, write_search_report, write_similar_files, write_streamed_results, streamed_results, OutputFormat, SearchReport};
+use notes2vec::ui::progress::ProgressLine;
+use notes2vec::ui::socket::search_remote;
+use notes2vec::ui::watch_status::{status_path, WatchStatus, STATUS_INTERVAL};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Number of warnings printed inline during `index` before deferring to the summary
+const MAX_INLINE_WARNINGS: usize = 5;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    // Diagnostics go to stderr (or the TUI's console) and --log-file; results stay on stdout
+    notes2vec::core::logging::init(cli.log_level.as_deref(), cli.log_file.as_deref().map(Path::new), cli.quiet)?;
+
+    // Every command loads its settings through Config::load, which applies NOTES2VEC_* overrides.
+    // Several collections are searched together instead (see SearchMode::Collections).
+    match cli.collection.as_slice() {
+        [] => {}
+        [collection] => std::env::set_var(format!("{}COLLECTION", ENV_PREFIX), collection),
+        _ if matches!(cli.command, Some(notes2vec::ui::cli::Commands::Search { .. })) => {}
+        _ => {
+            return Err(Error::Config(
+                "--collection can only be given more than once with 'search'".to_string(),
+            ))
+        }
+    }
+    if let Some(revision) = &cli.model_revision {
+        std::env::set_var(format!("{}MODEL_REVISION", ENV_PREFIX), revision);
+    }
 
     match &cli.command {
-        Some(notes2vec::ui::cli::Commands::Init { base_dir }) => {
-            handle_init(base_dir.as_deref())
+        Some(notes2vec::ui::cli::Commands::Init { base_dir, encrypt, .. }) => {
+            handle_init(base_dir.as_deref(), *encrypt, cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Index { path, force, paranoid, force_overwrite, accept_new_model, base_dir, verbose, format, include_hidden, exclude_hidden }) => {
+            let hidden = hidden_flag(*include_hidden, *exclude_hidden);
+            let policy = IndexPolicy {
+                rescan: Rescan::from_flags(*force, *paranoid),
+                force_overwrite: *force_overwrite,
+                accept_new_model: *accept_new_model,
+            };
+            handle_index(path.as_deref(), policy, base_dir.as_deref(), *verbose, *format, hidden, cli.quiet)
         }
-        Some(notes2vec::ui::cli::Commands::Index { path, force, base_dir }) => {
-            handle_index(path.as_str(), *force, base_dir.as_deref())
+        Some(notes2vec::ui::cli::Commands::Watch { base_dir, status: true, .. }) => {
+            handle_watch_status(base_dir.as_deref(), cli.quiet)
         }
-        Some(notes2vec::ui::cli::Commands::Watch { path, base_dir }) => {
-            handle_watch(path.as_str(), base_dir.as_deref())
+        Some(notes2vec::ui::cli::Commands::Watch { path, base_dir, include_hidden, exclude_hidden, serve_socket, .. }) => {
+            let hidden = hidden_flag(*include_hidden, *exclude_hidden);
+            handle_watch(path.as_deref(), base_dir.as_deref(), hidden, *serve_socket, cli.quiet)
         }
         Some(notes2vec::ui::cli::Commands::Search {
             query,
             limit,
             base_dir,
             interactive,
+            candidates,
+            by_file,
+            explain,
+            all_collections,
+            files_only,
+            null,
+            stream,
+            changed_since,
+            after,
+            before,
+            two_stage,
+            expand,
+            context_lines_before,
+            context_lines_after,
+            format,
+        }) => {
+            // The shortlist and expansion sizes come from config (`two_stage_files`,
+            // `expand_chunks`) once it's loaded
+            let options = SearchOptions::new(*limit)
+                .with_candidates(*candidates)
+                .with_explain(*explain)
+                .with_changed_since(changed_since.map(unix_cutoff))
+                .with_date_range(*after, *before)
+                .with_two_stage(two_stage.then_some(DEFAULT_TWO_STAGE_FILES))
+                .with_expand(expand.then_some(DEFAULT_EXPAND_CHUNKS));
+            let context = ContextLines::from_flags(*context_lines_before, *context_lines_after);
+            let mode = match format {
+                OutputFormat::Json if cli.collection.len() > 1 => {
+                    return Err(Error::Config(
+                        "--format json searches one collection; give --collection once".to_string(),
+                    ))
+                }
+                OutputFormat::Json => SearchMode::Report,
+                OutputFormat::Text => SearchMode::from_flags(*by_file, *all_collections, *files_only, *null, *stream, context, &cli.collection)?,
+            };
+            handle_search(query.as_deref(), options, base_dir.as_deref(), *interactive, mode, cli.quiet, TuiOptions::from_cli(&cli))
+        }
+        Some(notes2vec::ui::cli::Commands::Recent { limit, base_dir }) => {
+            handle_recent(*limit, base_dir.as_deref(), cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::ImportNotes { source, dest, base_dir, no_index }) => {
+            handle_import_notes(source.as_str(), dest.as_str(), base_dir.as_deref(), *no_index, cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Similar { file, limit, base_dir }) => {
+            handle_similar(file.as_str(), *limit, base_dir.as_deref(), cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Remove { pattern, dry_run, base_dir }) => {
+            handle_remove(pattern, *dry_run, base_dir.as_deref(), cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Vacuum { base_dir }) => {
+            handle_vacuum(base_dir.as_deref(), cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Files { path, base_dir }) => {
+            handle_files(path.as_deref(), base_dir.as_deref(), cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Doctor { base_dir }) => {
+            handle_doctor(base_dir.as_deref(), cli.seed, cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Verify { path, base_dir, fix }) => {
+            handle_verify(path.as_deref(), base_dir.as_deref(), *fix, cli.quiet)
+        }
+        Some(notes2vec::ui::cli::Commands::Config { action }) => {
+            handle_config(action, TuiOptions::from_cli(&cli))
+        }
+        Some(notes2vec::ui::cli::Commands::Embed {
+            text,
+            stdin,
+            vectors,
+            limit,
+            base_dir,
         }) => {
-            handle_search(query.as_deref(), *limit, base_dir.as_deref(), *interactive)
+            handle_embed(text.as_deref(), *stdin, *vectors, *limit, base_dir.as_deref(), cli.quiet)
         }
         None => {
             // No subcommand provided - always open TUI for interactive search
             // If query is provided, it will be used as initial search, otherwise TUI starts empty
-            handle_search(cli.query.as_deref(), cli.limit, cli.base_dir.as_deref(), true)
+            handle_search(cli.query.as_deref(), SearchOptions::new(cli.limit), cli.base_dir.as_deref(), true, SearchMode::Chunks { stream: false }, cli.quiet, TuiOptions::from_cli(&cli))
         }
     }
 }
 
-fn handle_init(base_dir: Option<&str>) -> Result<()> {
-    println!("Initializing notes2vec...");
+fn handle_init(base_dir: Option<&str>, encrypt: bool, quiet: bool) -> Result<()> {
+    if !quiet {
+        println!("Initializing notes2vec...");
+    }
     
     let base_path = base_dir
         .map(PathBuf::from)
         .or_else(|| Config::default_base_dir().ok());
     
-    let config = Config::new(base_path)?;
+    let mut config = Config::load(base_path)?;
+    config.encrypt = encrypt;
+
+    if encrypt && std::env::var(PASSPHRASE_ENV).map(|p| p.is_empty()).unwrap_or(true) {
+        return Err(Error::Config(format!(
+            "--encrypt needs a passphrase; set {} and run init again",
+            PASSPHRASE_ENV
+        )));
+    }
     
     if config.is_initialized() {
-        println!("notes2vec is already initialized at: {:?}", config.base_dir);
-        println!("To reinitialize, delete the directory and run 'init' again.");
+        if encrypt {
+            // Sets up encryption on a still-empty index, and refuses one that already holds
+            // plaintext data instead of quietly leaving it unencrypted
+            VectorStore::open(&config)?;
+            StateStore::open(&config)?;
+        }
+        if !quiet {
+            println!("notes2vec is already initialized at: {:?}", config.base_dir);
+            if encrypt {
+                println!("✓ Encryption is enabled (keep {} set when using notes2vec)", PASSPHRASE_ENV);
+            }
+            println!("To reinitialize, delete the directory and run 'init' again.");
+        }
         return Ok(());
     }
     
     config.init()?;
+    if encrypt {
+        // Opening the stores writes their salt and verification records
+        VectorStore::open(&config)?;
+        StateStore::open(&config)?;
+    }
+    if quiet {
+        return Ok(());
+    }
     println!("✓ Created configuration directory: {:?}", config.base_dir);
     println!("✓ Created database directory: {:?}", config.database_dir);
     println!("✓ Created models directory: {:?}", config.models_dir);
     println!("✓ Created state directory: {:?}", config.state_path.parent().unwrap_or(&config.base_dir));
+    if encrypt {
+        println!("✓ Enabled encryption (keep {} set when using notes2vec)", PASSPHRASE_ENV);
+    }
     
     println!("\nInitialization complete!");
     println!("Next steps:");
@@ -62,293 +220,1262 @@ fn handle_init(base_dir: Option<&str>) -> Result<()> {
     Ok(())
 }
 
-fn handle_index(path: &str, force: bool, base_dir: Option<&str>) -> Result<()> {
-    println!("Indexing notes from: {}", path);
-    
-    // Validate path exists and is a directory
-    let root_path = PathBuf::from(path);
-    if !root_path.exists() {
+/// `--include-hidden` / `--exclude-hidden`; `None` leaves the configured `include_hidden`
+fn hidden_flag(include_hidden: bool, exclude_hidden: bool) -> Option<bool> {
+    match (include_hidden, exclude_hidden) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// The notes directory given on the command line, else the selected collection's configured `path`
+fn notes_dir(path: Option<&str>, config: &Config) -> Result<PathBuf> {
+    let root = match path {
+        Some(path) => PathBuf::from(path),
+        None => config.collection_path().map(Path::to_path_buf).ok_or_else(|| {
+            Error::Config(format!(
+                "No notes directory given, and collection '{}' has no path in config.toml ([collections.{}] path = \"...\")",
+                config.collection, config.collection
+            ))
+        })?,
+    };
+    if !root.exists() {
         return Err(Error::Config(format!(
             "Path does not exist: {}",
-            path
+            root.display()
         )));
     }
-    if !root_path.is_dir() {
+    if !root.is_dir() {
         return Err(Error::Config(format!(
             "Path is not a directory: {}",
-            path
+            root.display()
         )));
     }
-    
-    // Check if initialized
+    Ok(root)
+}
+
+/// Create a named collection on first use, once the base directory has been initialized
+fn create_collection(config: &Config) -> Result<()> {
+    let base_initialized = config.base_dir.join("database").exists();
+    if config.collection != DEFAULT_COLLECTION && base_initialized && !config.is_initialized() {
+        config.init()?;
+    }
+    Ok(())
+}
+
+/// Which files `index` processes
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Rescan {
+    /// Files whose size, timestamp or content changed (see `StateStore::check_file`)
+    Changed,
+    /// Hash every file, even when its timestamp and size match the index
+    Paranoid,
+    /// Every file, changed or not
+    Force,
+}
+
+impl Rescan {
+    fn from_flags(force: bool, paranoid: bool) -> Self {
+        if force {
+            Rescan::Force
+        } else if paranoid {
+            Rescan::Paranoid
+        } else {
+            Rescan::Changed
+        }
+    }
+}
+
+/// What `index` is allowed to redo or replace
+#[derive(Clone, Copy)]
+struct IndexPolicy {
+    rescan: Rescan,
+    /// Replace files indexed from another folder under the same relative path
+    force_overwrite: bool,
+    /// Re-embed everything when the model's files no longer match the index, instead of stopping
+    accept_new_model: bool,
+}
+
+fn handle_index(
+    path: Option<&str>,
+    policy: IndexPolicy,
+    base_dir: Option<&str>,
+    verbose: bool,
+    format: OutputFormat,
+    include_hidden: Option<bool>,
+    quiet: bool,
+) -> Result<()> {
+    let IndexPolicy { rescan, force_overwrite, accept_new_model } = policy;
+    // JSON output must stay machine-readable, so status lines are only printed in text mode
+    let text = format == OutputFormat::Text;
+    let status = text && !quiet;
+
+    // Validate path exists and is a directory
     let base_path = base_dir.map(PathBuf::from);
-    let config = Config::new(base_path)?;
-    if !config.is_initialized() {
-        return Err(Error::Config(
-            "notes2vec is not initialized. Run 'notes2vec init' first.".to_string(),
-        ));
+    let mut config = Config::load(base_path)?;
+    if let Some(archive) = path.map(Path::new).filter(|path| path.is_file() && is_archive(path)) {
+        if let Some(include_hidden) = include_hidden {
+            config.include_hidden = include_hidden;
+        }
+        return handle_index_archive(archive, config, verbose, format, quiet);
+    }
+    let root_path = notes_dir(path, &config)?;
+    if status {
+        println!("Indexing notes from: {}", root_path.display());
+    }
+
+    // Check if initialized
+    create_collection(&config)?;
+    config.ensure_initialized(true)?;
+    if let Some(include_hidden) = include_hidden {
+        config.include_hidden = include_hidden;
     }
     
     // Open state store and vector store
     let state_store = StateStore::open(&config)?;
     let vector_store = VectorStore::open(&config)?;
+    let notes_root = std::fs::canonicalize(&root_path)?;
+
+    // Stored vectors made with another model or prefixes can't be compared with new queries
+    let rescan = if rescan != Rescan::Force && state_store.needs_reindex(&config)? {
+        if status {
+            println!("Model or embedding prefixes changed since the last index; re-embedding every file");
+        }
+        Rescan::Force
+    } else {
+        rescan
+    };
     
     // Discover all Markdown files
-    println!("Discovering Markdown files...");
-    let files = discover_files(&root_path)?;
-    println!("Found {} Markdown files", files.len());
+    if status {
+        println!("Discovering Markdown files...");
+    }
+    let discovery = discover_files_with_report(&root_path, DiscoveryOptions::from_config(&config))?;
+    if status {
+        println!("Found {} Markdown files", discovery.files.len());
+    }
     
+    let mut report = IndexReport::default();
+    // Unreadable folders and broken links are listed with the other warnings, grouped in the summary
+    for (path, reason) in &discovery.skipped {
+        let shown = path.strip_prefix(&root_path).unwrap_or(path).display().to_string();
+        report.warn(shown, WarningKind::from_skip_reason(*reason), "Skipped during discovery", true);
+        print_inline_warning(&report, verbose, text);
+    }
+    let files = discovery.into_files();
+
+    // Entries are keyed by relative path, so another folder's `inbox.md` would be replaced
+    let overwritten = state_store.files_from_other_roots(
+        files.iter().filter_map(|file| file.relative_path.to_str()),
+        &notes_root,
+    )?;
+    if let Some((example, other_root)) = overwritten.first() {
+        let summary = format!(
+            "{} file(s) here were indexed from another folder under the same path (e.g. {} from {})",
+            overwritten.len(),
+            example,
+            other_root.display()
+        );
+        if !force_overwrite {
+            return Err(Error::Config(format!(
+                "{}; indexing {} would overwrite their vectors. Pass --force-overwrite to replace them, or index this folder into its own collection with --collection.",
+                summary,
+                root_path.display()
+            )));
+        }
+        eprintln!("⚠ {}; overwriting their vectors (--force-overwrite)", summary);
+    }
+    // Lets `notes2vec verify` find the sources without being told
+    state_store.set_notes_root(&notes_root)?;
+
     if files.is_empty() {
-        println!("No Markdown files found in {}", path);
+        if status {
+            println!("No Markdown files found in {}", root_path.display());
+        } else if !text {
+            print_json_report(&report)?;
+        }
         return Ok(());
     }
     
     // Initialize embedding model once for all files
-    println!("Initializing embedding model...");
-    let model = match EmbeddingModel::init_verbose(&config) {
-        Ok(m) => m,
-        Err(e) => {
-            eprintln!("⚠ Warning: Failed to initialize embedding model: {}. Using hash-based embeddings.", e);
-            return Err(Error::Model(format!("Failed to initialize model: {}", e)));
+    if status {
+        println!("Initializing embedding model...");
+    }
+    let model_init = if status {
+        EmbeddingModel::init_verbose(&config)
+    } else {
+        EmbeddingModel::init_quiet(&config)
+    };
+    let model = model_init
+        .map_err(|e| Error::Model(format!("Failed to initialize model: {}", e)))?;
+    // Same model id, new files: the stored vectors came from other weights
+    let recorded = state_store.get_model_checksums()?;
+    let rescan = match model_mismatch(recorded.as_ref(), model.checksums(recorded.as_ref())) {
+        Some(message) if rescan != Rescan::Force => {
+            if !accept_new_model {
+                return Err(Error::Model(message));
+            }
+            if status {
+                println!("Model files changed since the last index; re-embedding every file (--accept-new-model)");
+            }
+            Rescan::Force
         }
+        _ => rescan,
     };
+    // --force re-embeds everything, so only an incremental index has to match the stored length
+    if rescan != Rescan::Force {
+        state_store.check_embedding_dim(model.embedding_dim())?;
+    }
     
     // Process files
-    println!("Processing files...");
-    let mut processed = 0;
-    let mut skipped = 0;
-    let mut errors = 0;
-    let mut chunks_indexed = 0;
+    if status {
+        println!("Processing files...");
+    }
     
-    for file in &files {
+    let mut index_one = |file: &DiscoveredFile| {
         // Convert path to string, skip if invalid UTF-8
         let file_path_str = match file.relative_path.to_str() {
             Some(s) => s,
             None => {
-                eprintln!("  ⚠ Warning: Skipping file with invalid UTF-8 path: {}", file.relative_path.display());
-                errors += 1;
-                continue;
+                let display = file.relative_path.display().to_string();
+                report.warn(display, WarningKind::InvalidUtf8Path, "Skipping file with invalid UTF-8 path", true);
+                print_inline_warning(&report, verbose, text);
+                return;
             }
         };
         
-        // Check if file has changed (unless force is true)
-        if !force {
-            match (get_file_modified_time(&file.path), calculate_file_hash(&file.path)) {
-                (Ok(modified_time), Ok(hash)) => {
-                    if let Ok(false) = state_store.has_file_changed(
-                        file_path_str,
-                        modified_time,
-                        &hash,
-                    ) {
-                        skipped += 1;
-                        continue;
+        // Check if file has changed (unless forced)
+        if rescan != Rescan::Force {
+            let paranoid = rescan == Rescan::Paranoid;
+            let check = FileSnapshot::read(&file.path).map(|snapshot| {
+                state_store.check_file(file_path_str, &file.path, snapshot, paranoid)
+            });
+            match check {
+                Ok(Ok(FileCheck::Unchanged)) => {
+                    report.skipped_unchanged += 1;
+                    return;
+                }
+                Ok(Ok(FileCheck::Touched(state))) => {
+                    // Same content under a new timestamp: remember it, nothing to reindex
+                    if let Err(e) = state_store.store_file_state(file_path_str, &state.with_root(&notes_root)) {
+                        report.warn(
+                            file_path_str,
+                            WarningKind::StateUpdateFailed,
+                            format!("Failed to update state: {}", e),
+                            false,
+                        );
+                        print_inline_warning(&report, verbose, text);
                     }
+                    report.skipped_unchanged += 1;
+                    return;
                 }
-                (Err(e), _) => {
-                    eprintln!("  ⚠ Warning: Could not get modification time for {}: {}. Processing anyway.", 
-                             file.relative_path.display(), e);
+                Ok(Ok(FileCheck::Changed)) => {}
+                Err(e) => {
+                    report.warn(
+                        file_path_str,
+                        WarningKind::ModifiedTimeFailed,
+                        format!("Could not get modification time: {}. Processing anyway.", e),
+                        false,
+                    );
+                    print_inline_warning(&report, verbose, text);
                 }
-                (_, Err(e)) => {
-                    eprintln!("  ⚠ Warning: Could not calculate hash for {}: {}. Processing anyway.", 
-                             file.relative_path.display(), e);
+                Ok(Err(e)) => {
+                    report.warn(
+                        file_path_str,
+                        WarningKind::HashFailed,
+                        format!("Could not check for changes: {}. Processing anyway.", e),
+                        false,
+                    );
+                    print_inline_warning(&report, verbose, text);
                 }
             }
         }
         
-        match notes2vec::indexing::parser::parse_markdown_file(&file.path) {
-            Ok(doc) => {
-                // Remove old vectors for this file if re-indexing
-                if force {
-                    if let Err(e) = vector_store.remove_file(file_path_str) {
-                        eprintln!("  ⚠ Warning: Failed to remove old vectors for {}: {}", 
-                                 file.relative_path.display(), e);
-                    }
+        // Long files take several forward passes; show batch progress so they don't look stuck
+        let mut show_progress = |done: usize, total: usize| {
+            if total > 1 {
+                let line = format!("  … {} (embedding batch {}/{})", file.relative_path.display(), done, total);
+                print!("\r{}", line);
+                if done == total {
+                    print!("\r{}\r", " ".repeat(line.chars().count()));
                 }
-                
-                // Generate embeddings for all chunks
-                // Reuse chunk texts to avoid extra allocations
-                // Use embed_passages for BGE model compatibility (better search quality)
-                let chunk_texts: Vec<String> = doc.chunks.iter().map(|c| c.text.clone()).collect();
-                let embeddings = match model.embed_passages(&chunk_texts) {
-                    Ok(emb) => emb,
-                    Err(e) => {
-                        eprintln!("  ⚠ Warning: Failed to generate embeddings: {}. Skipping file.", e);
-                        continue;
+                let _ = std::io::stdout().flush();
+            }
+        };
+        let progress: Option<&mut dyn FnMut(usize, usize)> = if status { Some(&mut show_progress) } else { None };
+
+        // Parse, embed and store through the shared pipeline (same path as the TUI and watcher)
+        match index_file_with_progress(&file.path, file_path_str, &config, &model, &vector_store, progress) {
+            Ok(indexed) => {
+                let chunk_count = indexed.chunks_stored;
+                report.chunks_indexed += chunk_count;
+                if let Some(note) = report.record_truncated(&indexed, model.max_tokens()) {
+                    if verbose && text {
+                        eprintln!("  ⚠ {}: {}", file.relative_path.display(), note);
                     }
-                };
-                
-                // Store vectors with embeddings - batch insert for better performance
-                // Pre-allocate vector entries to reduce allocations
-                let mut entries_to_insert = Vec::with_capacity(doc.chunks.len());
-                for (chunk, embedding) in doc.chunks.iter().zip(embeddings.iter()) {
-                    entries_to_insert.push(notes2vec::VectorEntry::new(
-                        file_path_str.to_string(),
-                        chunk.chunk_index,
-                        embedding.clone(),
-                        chunk.text.clone(),
-                        chunk.context.clone(),
-                        chunk.start_line,
-                        chunk.end_line,
-                    ));
                 }
-                
-                // Insert all entries (redb handles transactions efficiently)
-                for entry in &entries_to_insert {
-                    if let Err(e) = vector_store.insert(entry) {
-                        eprintln!("  ⚠ Warning: Failed to store vector for chunk {}: {}", 
-                                 entry.chunk_index, e);
-                    } else {
-                        chunks_indexed += 1;
-                    }
+                if indexed.truncated() {
+                    report.warn(
+                        file_path_str,
+                        WarningKind::ChunkLimit,
+                        format!(
+                            "Stored the first {} of {} chunks (max_chunks_per_file)",
+                            chunk_count,
+                            chunk_count + indexed.chunks_dropped
+                        ),
+                        false,
+                    );
+                    print_inline_warning(&report, verbose, text);
                 }
-                
-                // Update state store
-                if let (Ok(modified_time), Ok(hash)) =
-                    (get_file_modified_time(&file.path), calculate_file_hash(&file.path))
-                {
-                    if let Err(e) = state_store.update_file_state(
+                if let Some(error) = &indexed.frontmatter_error {
+                    report.warn(
                         file_path_str,
-                        modified_time,
-                        hash,
-                    ) {
-                        eprintln!("  ⚠ Warning: Failed to update state for {}: {}", 
-                                 file.relative_path.display(), e);
+                        WarningKind::InvalidFrontmatter,
+                        format!("Ignored frontmatter that isn't valid YAML: {}", error),
+                        false,
+                    );
+                    print_inline_warning(&report, verbose, text);
+                }
+
+                // Update state store
+                if let Ok(state) = FileState::from_file(&file.path).map(|state| state.with_root(&notes_root)) {
+                    if let Err(e) = state_store.store_file_state(file_path_str, &state) {
+                        report.warn(
+                            file_path_str,
+                            WarningKind::StateUpdateFailed,
+                            format!("Failed to update state: {}", e),
+                            false,
+                        );
+                        print_inline_warning(&report, verbose, text);
                     }
                 }
                 
-                println!("  ✓ {} ({} chunks)", file.relative_path.display(), doc.chunks.len());
-                processed += 1;
+                if status {
+                    println!("  ✓ {} ({} chunks)", file.relative_path.display(), chunk_count);
+                }
+                report.processed += 1;
             }
             Err(e) => {
-                eprintln!("  ✗ {}: {}", file.relative_path.display(), e);
-                errors += 1;
+                report.warn(file_path_str, WarningKind::from_error(&e), e.to_string(), true);
+                print_inline_warning(&report, verbose, text);
             }
         }
+    };
+
+    // Overall throughput and ETA; skipped and failed files count as done
+    let total_bytes = files.iter().map(|f| f.size).sum();
+    let mut progress = status.then(|| ProgressLine::new(files.len(), total_bytes));
+    for file in &files {
+        if let Some(progress) = progress.as_mut() {
+            progress.clear();
+        }
+        index_one(file);
+        if let Some(progress) = progress.as_mut() {
+            progress.file_done(file.size);
+        }
+    }
+    if let Some(progress) = progress.as_mut() {
+        progress.clear();
+    }
+    state_store.record_model(&config, &model)?;
+
+    if !text {
+        return print_json_report(&report);
     }
     
+    if quiet {
+        // Only problems are worth reporting when quiet
+        if let Some(summary) = report.summary() {
+            eprintln!("⚠ {}", summary);
+        }
+        return Ok(());
+    }
+
     println!("\nIndexing complete!");
-    println!("  Processed: {} files", processed);
-    println!("  Chunks indexed: {}", chunks_indexed);
-    if skipped > 0 {
-        println!("  Skipped (unchanged): {} files", skipped);
+    println!("  Processed: {} files", report.processed);
+    println!("  Chunks indexed: {}", report.chunks_indexed);
+    if report.skipped_unchanged > 0 {
+        println!("  Skipped (unchanged): {} files", report.skipped_unchanged);
+    }
+    if let Some(truncated) = report.truncation_summary(model.max_tokens()) {
+        println!("  ⚠ {}", truncated);
     }
-    if errors > 0 {
-        println!("  Errors: {} files", errors);
+    if let Some(summary) = report.summary() {
+        if !verbose && report.warnings.len() > MAX_INLINE_WARNINGS {
+            println!("  ⚠ {} — rerun with --verbose for details", summary);
+        } else {
+            println!("  ⚠ {}", summary);
+        }
     }
     
     Ok(())
 }
 
-fn handle_watch(path: &str, base_dir: Option<&str>) -> Result<()> {
+/// `index vault.zip`: index the archive's notes under their paths inside it. Every entry is
+/// embedded again on each run; there are no file states to compare against.
+fn handle_index_archive(archive: &Path, config: Config, verbose: bool, format: OutputFormat, quiet: bool) -> Result<()> {
+    let text = format == OutputFormat::Text;
+    let status = text && !quiet;
+    if status {
+        println!("Indexing notes from archive: {}", archive.display());
+    }
+
+    create_collection(&config)?;
+    config.ensure_initialized(true)?;
+    let state_store = StateStore::open(&config)?;
+    let vector_store = VectorStore::open(&config)?;
+    let model = if status {
+        EmbeddingModel::init_verbose(&config)
+    } else {
+        EmbeddingModel::init_quiet(&config)
+    }
+    .map_err(|e| Error::Model(format!("Failed to initialize model: {}", e)))?;
+    state_store.check_embedding_dim(model.embedding_dim())?;
+
+    let mut warnings_seen = 0;
+    let mut truncated_seen = 0;
+    let report = index_archive(archive, &config, &model, &vector_store, |path, report| {
+        if verbose && text && report.chunks_truncated > truncated_seen {
+            eprintln!("  ⚠ {}: {} chunks truncated at {} tokens", path, report.chunks_truncated - truncated_seen, model.max_tokens());
+        }
+        truncated_seen = report.chunks_truncated;
+        if report.warnings.len() > warnings_seen {
+            warnings_seen = report.warnings.len();
+            print_inline_warning(report, verbose, text);
+        } else if status {
+            println!("  ✓ {}", path);
+        }
+    })?;
+    state_store.record_model(&config, &model)?;
+
+    if !text {
+        return print_json_report(&report);
+    }
+    if quiet {
+        if let Some(summary) = report.summary() {
+            eprintln!("⚠ {}", summary);
+        }
+        return Ok(());
+    }
+    println!("\nIndexing complete!");
+    println!("  Processed: {} notes", report.processed);
+    println!("  Chunks indexed: {}", report.chunks_indexed);
+    if let Some(truncated) = report.truncation_summary(model.max_tokens()) {
+        println!("  ⚠ {}", truncated);
+    }
+    if let Some(summary) = report.summary() {
+        println!("  ⚠ {}", summary);
+    }
+    Ok(())
+}
+
+/// Print the most recent warning inline, unless the inline budget is used up
+fn print_inline_warning(report: &IndexReport, verbose: bool, text: bool) {
+    if !text {
+        return;
+    }
+    if verbose || report.warnings.len() <= MAX_INLINE_WARNINGS {
+        if let Some(w) = report.warnings.last() {
+            let marker = if w.skipped { "✗" } else { "⚠" };
+            eprintln!("  {} {}", marker, w);
+        }
+    }
+}
+
+fn print_json_report(report: &IndexReport) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| Error::Unknown(format!("Failed to serialize report: {}", e)))?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn handle_watch(
+    path: Option<&str>,
+    base_dir: Option<&str>,
+    include_hidden: Option<bool>,
+    serve_socket: bool,
+    quiet: bool,
+) -> Result<()> {
     // Check if initialized
     let base_path = base_dir.map(PathBuf::from);
-    let config = Config::new(base_path)?;
-    if !config.is_initialized() {
-        return Err(Error::Config(
-            "notes2vec is not initialized. Run 'notes2vec init' first.".to_string(),
-        ));
-    }
-    
-    let watch_path = PathBuf::from(path);
-    if !watch_path.exists() {
-        return Err(Error::Config(format!(
-            "Path does not exist: {}",
-            path
-        )));
+    let mut config = Config::load(base_path)?;
+    create_collection(&config)?;
+    config.ensure_initialized(true)?;
+    if let Some(include_hidden) = include_hidden {
+        config.include_hidden = include_hidden;
     }
     
-    if !watch_path.is_dir() {
-        return Err(Error::Config(format!(
-            "Path is not a directory: {}",
-            path
-        )));
-    }
+    let watch_path = notes_dir(path, &config)?;
     
     // Create watcher
-    let mut watcher = FileWatcher::new(&watch_path, config)?;
+    let mut watcher = FileWatcher::new(&watch_path, config)?
+        .quiet(quiet)
+        .serve_socket(serve_socket);
     
     // Start watching (blocks until interrupted)
     watcher.watch()
 }
 
+/// `watch --status`: the status file the watcher keeps, with ages. `--quiet` prints the raw JSON.
+fn handle_watch_status(base_dir: Option<&str>, quiet: bool) -> Result<()> {
+    let config = Config::load(base_dir.map(PathBuf::from))?;
+    let path = status_path(&config);
+    let Some(status) = WatchStatus::read(&path)? else {
+        return Err(Error::Config(format!(
+            "No watch status at {}. Is 'notes2vec watch' running?",
+            path.display()
+        )));
+    };
+    if quiet {
+        println!("{}", std::fs::read_to_string(&path)?.trim_end());
+        return Ok(());
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let age = |at: u64| format_age(now.saturating_sub(at));
+    println!("Watcher (pid {}) on {}", status.pid, status.root.display());
+    println!("  started:     {}", age(status.started_at));
+    println!("  updated:     {}", age(status.updated_at));
+    println!("  last change: {}", status.last_event_at.map_or_else(|| "none yet".to_string(), age));
+    println!("  indexed:     {}", status.indexed);
+    println!("  removed:     {}", status.removed);
+    println!("  failed:      {}", status.failed);
+    if let Some(error) = &status.last_error {
+        println!("  last error:  {}", error);
+    }
+    // An idle watcher still rewrites the file every STATUS_INTERVAL
+    if now.saturating_sub(status.updated_at) > 2 * STATUS_INTERVAL.as_secs() {
+        println!("\n⚠ Not updated for {}; the watcher may have stopped.", format_age(now.saturating_sub(status.updated_at)).trim_end_matches(" ago"));
+    }
+    Ok(())
+}
+
+/// What a non-interactive `search` ranks
+#[derive(Clone, PartialEq, Eq)]
+enum SearchMode {
+    /// Best chunk per file in the selected collection, as JSON lines with `--stream`
+    Chunks { stream: bool },
+    /// Whole files by their top chunks (`--by-file`)
+    ByFile,
+    /// Best chunk per file across the named collections, or all of them when empty
+    /// (`--collection` given more than once, or `--all-collections`)
+    Collections { names: Vec<String>, stream: bool },
+    /// Absolute paths of the best files only (`--files-only`), NUL-terminated with `--null`
+    FilesOnly { null: bool },
+    /// Best chunk per file, printed with lines read from the note around it
+    /// (`--context-lines-before`/`--context-lines-after`)
+    InContext(ContextLines),
+    /// Best chunk per file as one JSON document with the candidate count (`--format json`)
+    Report,
+}
+
+impl SearchMode {
+    fn from_flags(
+        by_file: bool,
+        all_collections: bool,
+        files_only: bool,
+        null: bool,
+        stream: bool,
+        context: Option<ContextLines>,
+        collections: &[String],
+    ) -> Result<Self> {
+        let several = collections.len() > 1;
+        if several && (files_only || by_file || context.is_some()) {
+            return Err(Error::Config(
+                "--files-only, --by-file and --context-lines-* search one collection; give --collection once".to_string(),
+            ));
+        }
+        Ok(if files_only {
+            SearchMode::FilesOnly { null }
+        } else if all_collections {
+            SearchMode::Collections { names: Vec::new(), stream }
+        } else if several {
+            SearchMode::Collections { names: collections.to_vec(), stream }
+        } else if by_file {
+            SearchMode::ByFile
+        } else if let Some(context) = context {
+            SearchMode::InContext(context)
+        } else {
+            SearchMode::Chunks { stream }
+        })
+    }
+}
+
+/// TUI appearance flags taken from the command line
+struct TuiOptions {
+    raw_scores: bool,
+    no_welcome: bool,
+    no_background: bool,
+    no_ascii_art: bool,
+    scratch_file: Option<PathBuf>,
+}
+
+impl TuiOptions {
+    fn from_cli(cli: &Cli) -> Self {
+        Self {
+            raw_scores: cli.raw_scores,
+            no_welcome: cli.no_welcome,
+            no_background: cli.no_background,
+            no_ascii_art: cli.no_ascii_art,
+            scratch_file: cli.scratch_file.as_ref().map(PathBuf::from),
+        }
+    }
+
+    /// Command-line flags win over config.toml and the environment
+    fn apply(&self, config: &mut Config) {
+        let flags = [
+            ("raw_scores", self.raw_scores),
+            ("show_welcome", self.no_welcome),
+            ("paint_background", self.no_background),
+            ("ascii_art", self.no_ascii_art),
+            ("scratch_file", self.scratch_file.is_some()),
+        ];
+        config.raw_scores |= self.raw_scores;
+        config.show_welcome &= !self.no_welcome;
+        config.paint_background &= !self.no_background;
+        config.ascii_art &= !self.no_ascii_art;
+        if let Some(scratch_file) = &self.scratch_file {
+            config.scratch_file = scratch_file.clone();
+        }
+        for (key, given) in flags {
+            if given {
+                config.set_source(key, SettingSource::Flag);
+            }
+        }
+    }
+}
+
 fn handle_search(
     query: Option<&str>,
-    limit: usize,
+    options: SearchOptions,
     base_dir: Option<&str>,
     interactive: bool,
+    mode: SearchMode,
+    quiet: bool,
+    tui_options: TuiOptions,
 ) -> Result<()> {
     // Check if initialized
     let base_path = base_dir.map(PathBuf::from);
-    let config = Config::new(base_path)?;
-    if !config.is_initialized() {
-        return Err(Error::Config(
-            "notes2vec is not initialized. Run 'notes2vec init' first.".to_string(),
-        ));
-    }
+    let mut config = Config::load(base_path)?;
+    tui_options.apply(&mut config);
+    let options = options
+        .with_two_stage(options.two_stage.map(|_| config.two_stage_files))
+        .with_expand(options.expand.map(|_| config.expand_chunks));
 
-    // Use interactive TUI mode if requested or no query provided
+    // Use interactive TUI mode if requested or no query provided.
+    // The TUI runs its own setup flow when notes2vec isn't initialized yet.
     if interactive || query.map(|q| q.is_empty()).unwrap_or(true) {
-        let mut tui = SearchTui::new(config)?;
+        let mut tui = SearchTui::from_config(config)?.with_query(query.unwrap_or_default());
         return tui.run();
     }
 
     // Non-interactive mode
     let query = query.unwrap();
-    println!("Searching for: \"{}\"", query);
+    if let SearchMode::Collections { names, stream } = &mode {
+        return handle_search_collections(&config, names, query, &options, *stream, quiet);
+    }
+    config.ensure_initialized(false)?;
+    // Scores only mean what they did when the index was built for this metric
+    let recorded_metric = recorded_similarity_metric(&config).ok().flatten();
+    if let Some(warning) = metric_mismatch(recorded_metric.as_deref(), config.similarity_metric) {
+        eprintln!("⚠ {}", warning);
+    }
 
-    // Open vector store
-    let vector_store = VectorStore::open(&config)?;
+    // A watcher started with --serve-socket has the model loaded already; ask it first
+    // (it doesn't say how many candidates it ranked, so reports are always searched here)
+    let by_file = mode == SearchMode::ByFile;
+    let remote = if by_file || mode == SearchMode::Report { None } else { search_remote(&config, query, &options)? };
+    let (deduped, candidates) = match remote {
+        Some(results) => (results, None),
+        None => {
+            // Searching never writes, so it also works on a read-only base_dir
+            let vector_store = VectorStore::open_read_only(&config)?;
+
+            // Initialize embedding model and generate query embedding
+            // (--files-only, --stream and --format json keep stdout for their output, so model status isn't printed there)
+            let model = if quiet || matches!(mode, SearchMode::FilesOnly { .. } | SearchMode::Chunks { stream: true } | SearchMode::Report) {
+                EmbeddingModel::init_quiet(&config)?
+            } else {
+                EmbeddingModel::init_verbose(&config)?
+            };
+            let recorded_model = recorded_model_checksums(&config).ok().flatten();
+            if let Some(warning) = model_mismatch(recorded_model.as_ref(), model.checksums(recorded_model.as_ref())) {
+                eprintln!("⚠ {}", warning);
+            }
+
+            if by_file {
+                let files = search_files(query, &options, &model, &vector_store)?;
+                let mut stdout = std::io::stdout().lock();
+                write_file_results(&mut stdout, query, &files, quiet)?;
+                return Ok(());
+            }
+
+            let best = best_chunks_for_query(query, &options, &model, &vector_store)?;
+            (best.results, Some(best.candidates))
+        }
+    };
+
+    if let SearchMode::FilesOnly { null } = mode {
+        return write_files_only(&config, &deduped, null);
+    }
+    let sources = match mode {
+        SearchMode::InContext(context) => read_result_sources(&config, &deduped, context)?,
+        _ => Vec::new(),
+    };
+
+    // This path ranks on cosine alone; the breakdown says so explicitly
+    let breakdowns: Vec<ScoreBreakdown> = if options.explain {
+        deduped.iter().map(|(_, sim)| ScoreBreakdown::new(*sim)).collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    let confidence = ConfidenceThresholds::from_config(&config);
+    if mode == (SearchMode::Chunks { stream: true }) {
+        write_streamed_results(&mut stdout, &deduped, &[], &breakdowns, confidence)?;
+    } else if mode == SearchMode::Report {
+        let report = SearchReport {
+            query,
+            model_id: &config.model_id,
+            threshold: ScoreCalibration::from_config(&config).min,
+            total_candidates_considered: candidates.unwrap_or(deduped.len()),
+            returned: deduped.len(),
+            results: streamed_results(&deduped, &[], &breakdowns, confidence),
+        };
+        write_search_report(&mut stdout, &report)?;
+    } else if !sources.is_empty() {
+        write_search_results_in_context(&mut stdout, query, &deduped, &breakdowns, &sources, confidence, quiet)?;
+    } else {
+        write_search_results_with(&mut stdout, query, &deduped, &breakdowns, confidence, quiet)?;
+    }
+
+    Ok(())
+}
+
+/// `search --files-only`: absolute paths and nothing else on stdout, so it can feed `xargs`.
+/// Exits with status 1 when nothing matched, like `grep`.
+fn write_files_only(config: &Config, results: &[(VectorEntry, f32)], null: bool) -> Result<()> {
+    // Results are already one per file, best first
+    let paths = note_paths(config, results)?;
+
+    let mut stdout = std::io::stdout().lock();
+    write_file_paths(&mut stdout, &paths, null)?;
+    stdout.flush()?;
+    if paths.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Each result's lines from its note, with `context` lines around them. A note that can't be
+/// read any more gets no lines, so its result falls back to the indexed preview.
+fn read_result_sources(config: &Config, results: &[(VectorEntry, f32)], context: ContextLines) -> Result<Vec<Vec<SourceLine>>> {
+    Ok(note_paths(config, results)?
+        .iter()
+        .zip(results)
+        .map(|(path, (entry, _))| read_source_lines(path, entry.start_line, entry.end_line, context).unwrap_or_default())
+        .collect())
+}
 
-    // Initialize embedding model and generate query embedding
-    let model = EmbeddingModel::init_verbose(&config)?;
-    let query_texts = vec![query.to_string()];
-    let query_embeddings = model.embed_queries(&query_texts)?;
+/// Where each result's note is on disk: under the folder its file was indexed from, or the
+/// notes root for files indexed before per-file roots were recorded. The state store is only
+/// read, so this works on a read-only base_dir.
+fn note_paths(config: &Config, results: &[(VectorEntry, f32)]) -> Result<Vec<PathBuf>> {
+    let state_store = StateStore::open_read_only(config)?;
+    let notes_root = state_store.get_notes_root()?;
+    results
+        .iter()
+        .map(|(entry, _)| {
+            let root = match state_store.get_file_state(&entry.file_path)?.and_then(|state| state.root) {
+                Some(root) => root,
+                None => notes_root.clone().ok_or_else(|| {
+                    Error::Config("The index doesn't record its notes directory yet. Run 'notes2vec index <path>' again.".to_string())
+                })?,
+            };
+            Ok(root.join(&entry.file_path))
+        })
+        .collect()
+}
 
-    if query_embeddings.is_empty() {
-        return Err(Error::Model("Failed to generate query embedding".to_string()));
+/// `search --all-collections` or repeated `--collection`: embed the query once and rank the
+/// collections' results together, each scaled by its configured weight.
+/// No names means every collection.
+fn handle_search_collections(
+    config: &Config,
+    names: &[String],
+    query: &str,
+    options: &SearchOptions,
+    stream: bool,
+    quiet: bool,
+) -> Result<()> {
+    let names = if names.is_empty() { config.collections()? } else { names.to_vec() };
+    if names.is_empty() {
+        return Err(Error::Config(
+            "notes2vec is not initialized. Run 'notes2vec init' first.".to_string(),
+        ));
     }
 
-    let query_embedding = &query_embeddings[0];
+    let stores = names
+        .iter()
+        .map(|name| CollectionStore::open(config, name))
+        .collect::<Result<Vec<_>>>()?;
+
+    let model = if quiet || stream {
+        EmbeddingModel::init_quiet(config)?
+    } else {
+        EmbeddingModel::init_verbose(config)?
+    };
+    let hits = search_collections(query, options, &model, &stores)?;
 
-    // Search for similar vectors (get more candidates for deduplication)
-    let results = vector_store.search(&query_embedding, limit * 3)?;
+    // Ranked on (weighted) cosine alone, as in the single-collection path
+    let breakdowns: Vec<ScoreBreakdown> = if options.explain {
+        hits.iter().map(|hit| ScoreBreakdown::new(hit.similarity)).collect()
+    } else {
+        Vec::new()
+    };
 
-    // Deduplicate: keep best match per file (like TUI does)
-    // Optimized: Pre-allocate HashMap and avoid unnecessary clones
-    use std::collections::HashMap;
-    let mut best_by_file: HashMap<String, (VectorEntry, f32)> = HashMap::with_capacity(results.len());
-    for (entry, sim) in results {
-        match best_by_file.get_mut(&entry.file_path) {
-            Some(current) => {
-                if sim > current.1 {
-                    *current = (entry, sim);
+    let mut stdout = std::io::stdout().lock();
+    let confidence = ConfidenceThresholds::from_config(config);
+    if stream {
+        let results: Vec<(VectorEntry, f32)> = hits.iter().map(|hit| (hit.entry.clone(), hit.similarity)).collect();
+        let labels: Vec<&str> = hits.iter().map(|hit| hit.collection.as_str()).collect();
+        write_streamed_results(&mut stdout, &results, &labels, &breakdowns, confidence)?;
+    } else {
+        write_collection_results(&mut stdout, query, &hits, &breakdowns, confidence, quiet)?;
+    }
+    Ok(())
+}
+
+fn handle_import_notes(
+    source: &str,
+    dest: &str,
+    base_dir: Option<&str>,
+    no_index: bool,
+    quiet: bool,
+) -> Result<()> {
+    let report = import_notes(std::path::Path::new(source), std::path::Path::new(dest))?;
+
+    if !quiet {
+        println!("Imported {} files into {}:", report.mappings.len(), dest);
+        for mapping in &report.mappings {
+            if mapping.links_rewritten > 0 {
+                println!("  {} -> {} ({} links rewritten)", mapping.source, mapping.dest, mapping.links_rewritten);
+            } else {
+                println!("  {} -> {}", mapping.source, mapping.dest);
+            }
+        }
+        println!("Mapping saved to {}", report.mapping_file.display());
+    }
+
+    if no_index {
+        return Ok(());
+    }
+
+    if !quiet {
+        println!();
+    }
+    let policy = IndexPolicy {
+        rescan: Rescan::Changed,
+        force_overwrite: false,
+        accept_new_model: false,
+    };
+    handle_index(Some(dest), policy, base_dir, false, OutputFormat::Text, None, quiet)
+}
+
+fn handle_similar(file: &str, limit: usize, base_dir: Option<&str>, quiet: bool) -> Result<()> {
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    config.ensure_initialized(false)?;
+
+    // Stored paths are relative to the indexed directory
+    let file = file.strip_prefix("./").unwrap_or(file);
+    // A lookup only, so like search it needs no write access and no index lock
+    let vector_store = VectorStore::open_read_only(&config)?;
+    let results = similar_files(&vector_store, file, limit, DEFAULT_TOP_K)?;
+
+    let mut stdout = std::io::stdout().lock();
+    write_similar_files(&mut stdout, file, &results, quiet)?;
+    Ok(())
+}
+
+fn handle_config(action: &ConfigAction, tui_options: TuiOptions) -> Result<()> {
+    match action {
+        ConfigAction::Show { base_dir } => {
+            let mut config = Config::load(base_dir.as_ref().map(PathBuf::from))?;
+            tui_options.apply(&mut config);
+
+            let base_dir_source = if base_dir.is_some() {
+                SettingSource::Flag
+            } else if std::env::var_os(HOME_ENV).is_some_and(|v| !v.is_empty()) {
+                SettingSource::Env
+            } else {
+                SettingSource::Default
+            };
+            let quoted = |path: &Path| format!("{:?}", path.display().to_string());
+
+            println!("# {}", config.config_file().display());
+            print_annotated("base_dir", &quoted(&config.base_dir), base_dir_source);
+            // Derived from base_dir and the collection
+            println!("database_dir = {}", quoted(&config.database_dir));
+            println!("models_dir = {}", quoted(&config.models_dir));
+            println!("state_path = {}", quoted(&config.state_path));
+            for (key, value) in config.settings() {
+                print_annotated(key, &value, config.source(key));
+            }
+            for (name, settings) in &config.collection_settings {
+                if let Some(path) = &settings.path {
+                    print_annotated(&format!("collections.{}.path", name), &quoted(path), SettingSource::File);
                 }
+                print_annotated(&format!("collections.{}.weight", name), &settings.weight.to_string(), SettingSource::File);
             }
-            None => {
-                best_by_file.insert(entry.file_path.clone(), (entry, sim));
+            for problem in config.initialization_problems(true) {
+                println!("# warning: {}", problem);
             }
         }
+        ConfigAction::Path { base_dir } => {
+            let config = Config::load(base_dir.as_ref().map(PathBuf::from))?;
+            println!("{}", config.config_file().display());
+        }
+        ConfigAction::Set { key, value, base_dir } => {
+            let config = Config::load(base_dir.as_ref().map(PathBuf::from))?;
+            config.persist_setting(key, value)?;
+            println!("✓ Set {} in {}", key, config.config_file().display());
+        }
     }
-    let mut deduped: Vec<(VectorEntry, f32)> = best_by_file.into_values().collect();
-    deduped.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-    deduped.truncate(limit);
+    Ok(())
+}
+
+/// `key = value` with a trailing comment naming where the value came from
+fn print_annotated(key: &str, value: &str, source: SettingSource) {
+    println!("{:<40} # {}", format!("{} = {}", key, value), source.as_str());
+}
+
+fn handle_recent(limit: usize, base_dir: Option<&str>, quiet: bool) -> Result<()> {
+    // Check if initialized
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    config.ensure_initialized(false)?;
+
+    let state_store = StateStore::open_read_only(&config)?;
+    let recent = state_store.recently_indexed(limit)?;
+
+    if recent.is_empty() {
+        if !quiet {
+            println!("No files have been indexed yet.");
+        }
+        return Ok(());
+    }
+
+    if !quiet {
+        println!("Recently indexed files:");
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    for (path, state) in &recent {
+        println!("  {:>10}  {}", format_age(now.saturating_sub(state.indexed_at)), path);
+    }
+
+    Ok(())
+}
 
-    if deduped.is_empty() {
-        println!("\nNo results found.");
+fn handle_remove(pattern: &str, dry_run: bool, base_dir: Option<&str>, quiet: bool) -> Result<()> {
+    // Refuse an empty pattern before opening anything
+    let pattern = RemovePattern::parse(pattern)?;
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    config.ensure_initialized(true)?;
+
+    // A dry run only reads, so it doesn't take the index lock and works on a read-only base_dir
+    let (vector_store, state_store) = if dry_run {
+        (VectorStore::open_read_only(&config)?, StateStore::open_read_only(&config)?)
     } else {
-        println!("\nFound {} results:", deduped.len());
-        for (i, (entry, similarity)) in deduped.iter().enumerate() {
-            println!("\n{}. {} (similarity: {:.3})", i + 1, entry.file_path, similarity);
-            if !entry.context.is_empty() {
-                println!("   Context: {}", entry.context);
+        (VectorStore::open(&config)?, StateStore::open(&config)?)
+    };
+    let report = remove_matching(&pattern, &vector_store, &state_store, dry_run)?;
+
+    if report.files.is_empty() {
+        println!("No indexed files match.");
+        return Ok(());
+    }
+    if !quiet {
+        for (file_path, chunks) in &report.files {
+            println!("  - {} ({} chunks)", file_path, chunks);
+        }
+    }
+    let verb = if report.dry_run { "Would remove" } else { "✓ Removed" };
+    println!("{} {} files ({} chunks)", verb, report.files.len(), report.chunks);
+    if report.dry_run && !quiet {
+        println!("Run again without --dry-run to remove them.");
+    }
+    Ok(())
+}
+
+fn handle_vacuum(base_dir: Option<&str>, quiet: bool) -> Result<()> {
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    config.ensure_initialized(true)?;
+
+    if !quiet {
+        println!("Rebuilding vector database...");
+    }
+    let stats = VectorStore::vacuum(&config)?;
+
+    println!(
+        "✓ Vacuumed {} entries: {} -> {} (reclaimed {})",
+        stats.entries,
+        format_size(stats.size_before),
+        format_size(stats.size_after),
+        format_size(stats.size_before.saturating_sub(stats.size_after))
+    );
+
+    // Once every embedding is known to be unit length, search skips computing norms
+    let repaired = VectorStore::open(&config)?.normalize_all()?;
+    if repaired > 0 {
+        println!("✓ Re-normalized {} embeddings", repaired);
+    }
+    Ok(())
+}
+
+fn handle_files(path: Option<&str>, base_dir: Option<&str>, quiet: bool) -> Result<()> {
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    let root_path = notes_dir(path, &config)?;
+
+    // Same discovery as `index`, so a missing file here is missing from the index too
+    let discovery = discover_files_with_report(&root_path, DiscoveryOptions::from_config(&config))?;
+    for (path, reason) in &discovery.skipped {
+        let shown = path.strip_prefix(&root_path).unwrap_or(path);
+        eprintln!("  ⚠ Skipped {} ({})", shown.display(), reason.label());
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    write_discovered_files(&mut stdout, &discovery.files, quiet)?;
+    Ok(())
+}
+
+/// Exits with the worst check's status (1 for warnings, 2 for failures) so scripts can tell
+fn handle_doctor(base_dir: Option<&str>, seed: Option<u64>, quiet: bool) -> Result<()> {
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    let report = run_checks(&config, &mut SampleRng::from_seed_or_clock(seed));
+
+    let mut stdout = std::io::stdout().lock();
+    write_doctor_report(&mut stdout, &report, quiet)?;
+    stdout.flush()?;
+    match report.exit_code() {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
+
+fn handle_verify(path: Option<&str>, base_dir: Option<&str>, fix: bool, quiet: bool) -> Result<()> {
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    config.ensure_initialized(fix)?;
+
+    // Without --fix nothing is written, so it works on a read-only base_dir
+    let state_store = if fix { StateStore::open(&config)? } else { StateStore::open_read_only(&config)? };
+    let root = match path {
+        Some(path) => PathBuf::from(path),
+        None => state_store.get_notes_root()?.ok_or_else(|| {
+            Error::Config(
+                "No indexed directory is recorded. Pass it explicitly: notes2vec verify /path/to/notes".to_string(),
+            )
+        })?,
+    };
+    // Every file would look deleted (and --fix would empty the index) if the directory is gone
+    if !root.is_dir() {
+        return Err(Error::Config(format!(
+            "Notes directory does not exist: {}",
+            root.display()
+        )));
+    }
+
+    let report = verify_index(&state_store, &root)?;
+    for file_path in &report.stale {
+        println!("  stale   {}", file_path);
+    }
+    for file_path in &report.orphans {
+        println!("  orphan  {}", file_path);
+    }
+    for (file_path, error) in &report.unreadable {
+        eprintln!("  ⚠ Could not read {}: {}", file_path, error);
+    }
+
+    if report.is_clean() {
+        if !quiet {
+            println!("✓ All {} indexed files match {}", report.checked, root.display());
+        }
+        return Ok(());
+    }
+    if !fix {
+        if !quiet {
+            println!(
+                "{} stale, {} orphaned of {} indexed files. Run 'notes2vec verify --fix' to update the index.",
+                report.stale.len(),
+                report.orphans.len(),
+                report.checked
+            );
+        }
+        return Ok(());
+    }
+
+    let vector_store = VectorStore::open(&config)?;
+    for file_path in &report.orphans {
+        vector_store.remove_file(file_path)?;
+        state_store.remove_file(file_path)?;
+    }
+
+    let mut reindexed = 0;
+    if !report.stale.is_empty() {
+        let model = if quiet {
+            EmbeddingModel::init_quiet(&config)?
+        } else {
+            EmbeddingModel::init_verbose(&config)?
+        };
+        for file_path in &report.stale {
+            let path = root.join(file_path);
+            let result = index_file(&path, file_path, &config, &model, &vector_store)
+                .and_then(|_| state_store.store_file_state(file_path, &FileState::from_file(&path)?));
+            match result {
+                Ok(()) => reindexed += 1,
+                Err(e) => eprintln!("  ✗ Failed to reindex {}: {}", file_path, e),
             }
-            // Show preview of text (first 150 chars)
-            let preview: String = entry.text.chars().take(150).collect();
-            println!("   Preview: {}...", preview);
-            println!("   Lines: {}-{}", entry.start_line, entry.end_line);
         }
     }
 
+    if !quiet {
+        println!(
+            "✓ Reindexed {} stale files, removed {} orphaned files",
+            reindexed,
+            report.orphans.len()
+        );
+    }
     Ok(())
 }
 
+fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f >= KB * KB * KB {
+        format!("{:.1} GB", bytes_f / (KB * KB * KB))
+    } else if bytes_f >= KB * KB {
+        format!("{:.1} MB", bytes_f / (KB * KB))
+    } else if bytes_f >= KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Human-friendly age like "5m ago" or "3d ago"
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => format!("{}s ago", seconds),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+fn handle_embed(
+    text: Option<&str>,
+    stdin: bool,
+    vectors: bool,
+    limit: usize,
+    base_dir: Option<&str>,
+    quiet: bool,
+) -> Result<()> {
+    use std::io::Read;
+
+    let input = match (text, stdin) {
+        (Some(t), _) => t.to_string(),
+        (None, true) => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)?;
+            buf
+        }
+        (None, false) => {
+            return Err(Error::Config(
+                "Nothing to embed. Pass some text or use --stdin.".to_string(),
+            ));
+        }
+    };
+
+    // Check if initialized
+    let base_path = base_dir.map(PathBuf::from);
+    let config = Config::load(base_path)?;
+    config.ensure_initialized(false)?;
+
+    // Chunk with the normal parser so results match what indexing would produce
+    let chunking = notes2vec::indexing::parser::ChunkingOptions::from_config(&config);
+    let doc = notes2vec::indexing::parser::parse_markdown_with(&input, std::path::Path::new("stdin"), chunking)?;
+    if doc.chunks.is_empty() {
+        println!("No chunks produced from input.");
+        return Ok(());
+    }
+
+    let model = if quiet {
+        EmbeddingModel::init_quiet(&config)?
+    } else {
+        EmbeddingModel::init_verbose(&config)?
+    };
+    let (embeddings, _) = embed_chunks(&model, &doc.chunks, &config, None)?;
+
+    // Neighbors are only looked up, so this needs no write access and no index lock
+    let vector_store = if vectors { None } else { Some(VectorStore::open_read_only(&config)?) };
+
+    for (chunk, embedding) in doc.chunks.iter().zip(embeddings.iter()) {
+        println!(
+            "\nChunk {} (lines {}-{}){}",
+            chunk.chunk_index,
+            chunk.start_line,
+            chunk.end_line,
+            if chunk.context.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", chunk.context)
+            }
+        );
+        for line in chunk.text.lines() {
+            println!("  | {}", line);
+        }
+
+        match &vector_store {
+            None => {
+                let values: Vec<String> = embedding.iter().map(|v| format!("{:.6}", v)).collect();
+                println!("  vector ({} dims): [{}]", embedding.len(), values.join(", "));
+            }
+            Some(store) => {
+                let neighbors = store.search(embedding, limit)?;
+                if neighbors.is_empty() {
+                    println!("  (index is empty)");
+                }
+                for (i, (entry, similarity)) in neighbors.iter().enumerate() {
+                    println!(
+                        "  {}. {}:{}-{} (similarity: {:.3}){}",
+                        i + 1,
+                        entry.file_path,
+                        entry.start_line,
+                        entry.end_line,
+                        similarity,
+                        if entry.context.is_empty() {
+                            String::new()
+                        } else {
+                            format!("  {}", entry.context)
+                        }
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
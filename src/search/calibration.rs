@@ -0,0 +1,179 @@
+use crate::core::config::Config;
+use std::fmt;
+
+/// Maps raw cosine similarity into a 0-100 display score.
+/// Embedding models rarely use the full [-1, 1] range: unrelated passages still score
+/// well above zero and near-duplicates rarely reach 1.0, so a raw "55%" can be the best
+/// match a model will ever produce. Calibration stretches the model's useful range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreCalibration {
+    /// Cosine at (or below) which the display score is 0
+    pub min: f32,
+    /// Cosine at (or above) which the display score is 100
+    pub max: f32,
+}
+
+impl ScoreCalibration {
+    /// No calibration: display score is cosine * 100
+    pub const RAW: ScoreCalibration = ScoreCalibration { min: 0.0, max: 1.0 };
+
+    /// Reference range for a known embedding model
+    pub fn for_model(model_id: &str) -> Self {
+        let id = model_id.to_lowercase();
+        if id.contains("bge-small") || id.contains("bge-base") {
+            // BGE scores are compressed towards the top: unrelated text sits around 0.4
+            ScoreCalibration { min: 0.40, max: 0.85 }
+        } else if id.contains("minilm") {
+            ScoreCalibration { min: 0.15, max: 0.75 }
+        } else {
+            Self::RAW
+        }
+    }
+
+    /// Calibration for the current configuration (config overrides win over model defaults)
+    pub fn from_config(config: &Config) -> Self {
+        if config.raw_scores {
+            return Self::RAW;
+        }
+        let defaults = Self::for_model(&config.model_id);
+        ScoreCalibration {
+            min: config.score_min.unwrap_or(defaults.min),
+            max: config.score_max.unwrap_or(defaults.max),
+        }
+    }
+
+    /// Display score in 0..=100, clamped and rounded to the nearest integer
+    pub fn display_percent(&self, cosine: f32) -> u8 {
+        if !cosine.is_finite() {
+            return 0;
+        }
+        let span = self.max - self.min;
+        let normalized = if span <= f32::EPSILON {
+            if cosine >= self.max { 1.0 } else { 0.0 }
+        } else {
+            (cosine - self.min) / span
+        };
+        (normalized.clamp(0.0, 1.0) * 100.0).round() as u8
+    }
+}
+
+/// Qualitative reading of a similarity score, shown next to the number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+    Strong,
+    Likely,
+    Weak,
+}
+
+impl Confidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Confidence::Strong => "strong",
+            Confidence::Likely => "likely",
+            Confidence::Weak => "weak",
+        }
+    }
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Cosine thresholds behind the confidence label (`confidence_strong` / `confidence_likely`).
+/// Labels use raw similarity, so they don't shift when the display calibration changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceThresholds {
+    /// Similarity at or above which a result is `Strong`
+    pub strong: f32,
+    /// Similarity at or above which a result is `Likely`; anything lower is `Weak`
+    pub likely: f32,
+}
+
+impl ConfidenceThresholds {
+    /// Thresholds for the current configuration, or `None` when labels are turned off
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.confidence_labels.then_some(ConfidenceThresholds {
+            strong: config.confidence_strong,
+            likely: config.confidence_likely,
+        })
+    }
+
+    pub fn label(&self, similarity: f32) -> Confidence {
+        if similarity >= self.strong {
+            Confidence::Strong
+        } else if similarity >= self.likely {
+            Confidence::Likely
+        } else {
+            Confidence::Weak
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_calibration_is_identity_percent() {
+        let raw = ScoreCalibration::RAW;
+        assert_eq!(raw.display_percent(0.0), 0);
+        assert_eq!(raw.display_percent(0.554), 55);
+        assert_eq!(raw.display_percent(0.556), 56);
+        assert_eq!(raw.display_percent(1.0), 100);
+    }
+
+    #[test]
+    fn test_calibration_maps_reference_range() {
+        let cal = ScoreCalibration { min: 0.2, max: 0.7 };
+        assert_eq!(cal.display_percent(0.2), 0);
+        assert_eq!(cal.display_percent(0.45), 50);
+        assert_eq!(cal.display_percent(0.7), 100);
+    }
+
+    #[test]
+    fn test_calibration_clamps() {
+        let cal = ScoreCalibration { min: 0.2, max: 0.7 };
+        assert_eq!(cal.display_percent(-0.5), 0);
+        assert_eq!(cal.display_percent(0.95), 100);
+        assert_eq!(cal.display_percent(f32::NAN), 0);
+    }
+
+    #[test]
+    fn test_calibration_degenerate_range() {
+        let cal = ScoreCalibration { min: 0.5, max: 0.5 };
+        assert_eq!(cal.display_percent(0.4), 0);
+        assert_eq!(cal.display_percent(0.5), 100);
+    }
+
+    #[test]
+    fn test_model_defaults() {
+        assert_eq!(
+            ScoreCalibration::for_model("sentence-transformers/all-MiniLM-L6-v2"),
+            ScoreCalibration { min: 0.15, max: 0.75 }
+        );
+        assert_eq!(ScoreCalibration::for_model("unknown/model"), ScoreCalibration::RAW);
+    }
+
+    #[test]
+    fn test_confidence_labels_at_default_thresholds() {
+        let config = Config::new(Some(std::path::PathBuf::from("unused"))).unwrap();
+        let thresholds = ConfidenceThresholds::from_config(&config).expect("labels are on by default");
+
+        assert_eq!(thresholds.label(0.92), Confidence::Strong);
+        assert_eq!(thresholds.label(0.75), Confidence::Strong);
+        assert_eq!(thresholds.label(0.74), Confidence::Likely);
+        assert_eq!(thresholds.label(0.60), Confidence::Likely);
+        assert_eq!(thresholds.label(0.59), Confidence::Weak);
+        assert_eq!(thresholds.label(0.10), Confidence::Weak);
+        assert_eq!(thresholds.label(f32::NAN), Confidence::Weak);
+    }
+
+    #[test]
+    fn test_confidence_labels_can_be_disabled() {
+        let mut config = Config::new(Some(std::path::PathBuf::from("unused"))).unwrap();
+        config.confidence_labels = false;
+        assert_eq!(ConfidenceThresholds::from_config(&config), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! SHA-256 checksums of the downloaded model files.
+//!
+//! A model repository can publish new weights under the same id, so the id alone doesn't say
+//! which embeddings an index holds. The checksums are recorded in the index's metadata with
+//! every index built, so a model that changed upstream (or on disk) no longer matches it. Each
+//! is kept with the file's size and modification time, and a file that still has both isn't
+//! hashed again.
+
+use crate::core::error::Result;
+use crate::search::model::MODEL_FILES;
+use crate::storage::state::{calculate_file_hash, get_file_modified_time};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Hash of one model file, with the size and modification time it had when hashed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChecksum {
+    pub sha256: String,
+    pub size: u64,
+    pub modified: u64,
+}
+
+/// Checksums of [`MODEL_FILES`], by file name
+pub type ModelChecksums = BTreeMap<String, FileChecksum>;
+
+fn checksum(path: &Path) -> Result<FileChecksum> {
+    Ok(FileChecksum {
+        sha256: calculate_file_hash(path)?,
+        size: std::fs::metadata(path)?.len(),
+        modified: get_file_modified_time(path)?,
+    })
+}
+
+/// Hash every model file in `model_dir`
+pub fn compute_checksums(model_dir: &Path) -> Result<ModelChecksums> {
+    MODEL_FILES
+        .iter()
+        .map(|file| Ok((file.to_string(), checksum(&model_dir.join(file))?)))
+        .collect()
+}
+
+/// Current checksums of the files in `model_dir`. Files whose size and modification time still
+/// match `recorded` keep their recorded hash, so checking a model doesn't re-read its weights.
+pub fn verify_checksums(model_dir: &Path, recorded: &ModelChecksums) -> Result<ModelChecksums> {
+    MODEL_FILES
+        .iter()
+        .map(|file| {
+            let path = model_dir.join(file);
+            let size = std::fs::metadata(&path)?.len();
+            let modified = get_file_modified_time(&path)?;
+            let current = match recorded.get(*file) {
+                Some(known) if known.size == size && known.modified == modified => known.clone(),
+                _ => checksum(&path)?,
+            };
+            Ok((file.to_string(), current))
+        })
+        .collect()
+}
+
+/// Names of the files whose hash differs between two sets of checksums
+pub fn changed_files(recorded: &ModelChecksums, current: &ModelChecksums) -> Vec<String> {
+    current
+        .iter()
+        .filter(|(file, now)| recorded.get(*file).is_none_or(|then| then.sha256 != now.sha256))
+        .map(|(file, _)| file.clone())
+        .collect()
+}
+
+/// What to tell someone using a model whose files hash to `current` with an index built from
+/// files that hashed to `recorded`; None when they agree or either is unknown (indexes from
+/// before checksums were recorded pass)
+pub fn model_mismatch(recorded: Option<&ModelChecksums>, current: Option<&ModelChecksums>) -> Option<String> {
+    let changed = changed_files(recorded?, current?);
+    if changed.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "The embedding model's files changed since this index was built ({}: same model id, different checksums), so new embeddings won't match the stored ones. Run 'notes2vec index --accept-new-model' to re-embed your notes with it, or pin the old files with --model-revision.",
+        changed.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_model(dir: &Path, weights: &str) {
+        for file in MODEL_FILES {
+            let content = if file == "model.safetensors" { weights } else { file };
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_changed_weights_are_named() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        write_model(dir, "weights v1");
+
+        let recorded = compute_checksums(dir).unwrap();
+        assert_eq!(recorded.len(), MODEL_FILES.len());
+        assert_eq!(verify_checksums(dir, &recorded).unwrap(), recorded);
+
+        // New weights under the same name, as an upstream update would bring
+        std::fs::write(dir.join("model.safetensors"), "weights v2, longer").unwrap();
+        let current = verify_checksums(dir, &recorded).unwrap();
+        assert_eq!(changed_files(&recorded, &current), vec!["model.safetensors"]);
+        assert_eq!(current, compute_checksums(dir).unwrap());
+    }
+
+    #[test]
+    fn test_unchanged_files_keep_their_recorded_hash() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        write_model(dir, "weights");
+        let mut recorded = compute_checksums(dir).unwrap();
+        // Same size and time: trusted without re-reading
+        recorded.get_mut("model.safetensors").unwrap().sha256 = "kept".to_string();
+        let current = verify_checksums(dir, &recorded).unwrap();
+        assert_eq!(current["model.safetensors"].sha256, "kept");
+        assert!(changed_files(&recorded, &current).is_empty());
+        assert!(compute_checksums(&dir.join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_model_mismatch_needs_both_sides() {
+        let temp_dir = TempDir::new().unwrap();
+        write_model(temp_dir.path(), "weights v1");
+        let old = compute_checksums(temp_dir.path()).unwrap();
+        write_model(temp_dir.path(), "weights v2, longer");
+        let new = compute_checksums(temp_dir.path()).unwrap();
+
+        let message = model_mismatch(Some(&old), Some(&new)).unwrap();
+        assert!(message.contains("model.safetensors"), "{}", message);
+        assert!(message.contains("--accept-new-model"));
+        assert_eq!(model_mismatch(Some(&old), Some(&old)), None);
+        assert_eq!(model_mismatch(None, Some(&new)), None);
+        assert_eq!(model_mismatch(Some(&old), None), None);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use crate::search::files::best_chunk_per_file_by_embedding;
+use crate::search::model::EmbeddingModel;
+use crate::search::options::SearchOptions;
+use crate::storage::vectors::{score_order, VectorEntry, VectorStore};
+
+/// A search result and the collection it was found in
+#[derive(Debug, Clone)]
+pub struct CollectionHit {
+    pub collection: String,
+    pub entry: VectorEntry,
+    /// Cosine similarity scaled by the collection's weight
+    pub similarity: f32,
+}
+
+/// One collection's store, opened for searching alongside others
+pub struct CollectionStore {
+    pub name: String,
+    /// Multiplier on this collection's similarities (`collections.<name>.weight`)
+    pub weight: f32,
+    pub store: VectorStore,
+}
+
+impl CollectionStore {
+    /// Open `name`'s index read-only, with its configured weight
+    pub fn open(config: &Config, name: &str) -> Result<Self> {
+        let collection = config.clone().with_collection(name)?;
+        if !collection.database_dir.exists() {
+            return Err(Error::Config(format!(
+                "Collection '{}' has no index yet. Run 'notes2vec index --collection {}' first.",
+                name, name
+            )));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            weight: config.collection_weight(name),
+            store: VectorStore::open_read_only(&collection)?,
+        })
+    }
+}
+
+/// Search every collection with one query embedding and rank the hits together
+/// (`notes2vec search --all-collections`, or `--collection` given more than once)
+pub fn search_collections(
+    query: &str,
+    options: &SearchOptions,
+    model: &EmbeddingModel,
+    stores: &[CollectionStore],
+) -> Result<Vec<CollectionHit>> {
+    let query_embeddings = model.embed_queries(&[query.to_string()])?;
+    let query_embedding = query_embeddings
+        .first()
+        .ok_or_else(|| Error::Model("Failed to generate query embedding".to_string()))?;
+    search_collections_by_embedding(query_embedding, options, stores)
+}
+
+/// Best chunk per file from each store, weighted and merged into one ranking of at most
+/// `options.limit` hits. The same path in two collections is two different notes, so both are kept.
+pub fn search_collections_by_embedding(
+    query_embedding: &[f32],
+    options: &SearchOptions,
+    stores: &[CollectionStore],
+) -> Result<Vec<CollectionHit>> {
+    let mut hits = Vec::new();
+    for collection in stores {
+        for (entry, similarity) in best_chunk_per_file_by_embedding(query_embedding, options, &collection.store)? {
+            hits.push(CollectionHit {
+                collection: collection.name.clone(),
+                entry,
+                similarity: similarity * collection.weight,
+            });
+        }
+    }
+
+    // Ties broken by collection and path so output is stable
+    hits.sort_by(|a, b| {
+        score_order(a.similarity, b.similarity)
+            .then_with(|| a.collection.cmp(&b.collection))
+            .then_with(|| a.entry.file_path.cmp(&b.entry.file_path))
+    });
+    hits.truncate(options.limit);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::CollectionSettings;
+    use tempfile::TempDir;
+
+    fn entry(file: &str, embedding: Vec<f32>) -> VectorEntry {
+        VectorEntry::new(file.to_string(), 0, embedding, file.to_string(), String::new(), 1, 1)
+    }
+
+    fn open_collection(base: &Config, name: &str) -> VectorStore {
+        let config = base.clone().with_collection(name).unwrap();
+        config.init().unwrap();
+        VectorStore::open(&config).unwrap()
+    }
+
+    fn searchable(base: &Config, names: &[&str]) -> Vec<CollectionStore> {
+        names.iter().map(|name| CollectionStore::open(base, name).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_results_from_both_collections_are_labelled_and_ranked_together() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+
+        {
+            let work = open_collection(&base, "work");
+            work.insert(&entry("roadmap.md", vec![0.9, 0.436])).unwrap();
+            work.insert(&entry("standup.md", vec![0.2, 0.98])).unwrap();
+            let personal = open_collection(&base, "personal");
+            personal.insert(&entry("journal.md", vec![0.6, 0.8])).unwrap();
+            // Same relative path as a work note; still a different note
+            personal.insert(&entry("roadmap.md", vec![1.0, 0.0])).unwrap();
+        }
+
+        assert_eq!(base.collections().unwrap(), vec!["personal", "work"]);
+
+        let stores = searchable(&base, &["personal", "work"]);
+        let hits = search_collections_by_embedding(&[1.0, 0.0], &SearchOptions::new(3), &stores).unwrap();
+
+        let labelled: Vec<(&str, &str)> = hits
+            .iter()
+            .map(|hit| (hit.collection.as_str(), hit.entry.file_path.as_str()))
+            .collect();
+        assert_eq!(
+            labelled,
+            vec![("personal", "roadmap.md"), ("work", "roadmap.md"), ("personal", "journal.md")]
+        );
+        assert!(hits.windows(2).all(|pair| pair[0].similarity >= pair[1].similarity));
+    }
+
+    #[test]
+    fn test_collection_weight_deprioritizes_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut base = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        {
+            let notes = open_collection(&base, "notes");
+            notes.insert(&entry("plan.md", vec![0.8, 0.6])).unwrap();
+            let archive = open_collection(&base, "archive");
+            archive.insert(&entry("old-plan.md", vec![1.0, 0.0])).unwrap();
+        }
+
+        let query = [1.0, 0.0];
+        let options = SearchOptions::new(2);
+        let hits = search_collections_by_embedding(&query, &options, &searchable(&base, &["archive", "notes"])).unwrap();
+        assert_eq!(hits[0].collection, "archive");
+
+        base.collection_settings.insert(
+            "archive".to_string(),
+            CollectionSettings { path: None, weight: 0.5 },
+        );
+        let hits = search_collections_by_embedding(&query, &options, &searchable(&base, &["archive", "notes"])).unwrap();
+        assert_eq!(hits[0].collection, "notes");
+        assert!((hits[1].similarity - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_missing_collection_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        assert!(CollectionStore::open(&base, "nowhere").is_err());
+    }
+
+    #[test]
+    fn test_collection_names_are_validated() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+
+        let default = base.clone().with_collection("default").unwrap();
+        assert_eq!(default.database_dir, base.database_dir);
+        let work = base.clone().with_collection("work-2024").unwrap();
+        assert_eq!(work.database_dir, base.base_dir.join("collections").join("work-2024").join("database"));
+
+        for bad in ["", "../escape", "a/b", "with space"] {
+            assert!(base.clone().with_collection(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Query expansion by pseudo-relevance feedback (`search --expand`).
+//!
+//! A two-word query embeds to a vague point. The chunks it matches best are assumed to be on
+//! topic, and the terms they share with each other but not with the query pull the embedding
+//! toward related passages that never use the query's words.
+
+use crate::core::error::Result;
+use crate::search::files::centroid;
+use crate::search::options::SearchOptions;
+use crate::storage::vectors::{VectorEntry, VectorStore};
+
+/// Average of the (normalized) query embedding and the mean of the feedback chunks, normalized.
+/// Without usable feedback the query is returned as it is.
+pub fn expand_embedding(query_embedding: &[f32], feedback: &[VectorEntry]) -> Vec<f32> {
+    let Some(feedback) = centroid(feedback).filter(|c| c.len() == query_embedding.len()) else {
+        return query_embedding.to_vec();
+    };
+    let query_norm = query_embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if query_norm <= f32::EPSILON {
+        return query_embedding.to_vec();
+    }
+
+    let mixed: Vec<f32> = query_embedding
+        .iter()
+        .zip(&feedback)
+        .map(|(q, f)| q / query_norm + f)
+        .collect();
+    let norm = mixed.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return query_embedding.to_vec();
+    }
+    mixed.into_iter().map(|v| v / norm).collect()
+}
+
+/// The embedding to search with: the query itself, or with `options.expand` set, the query
+/// expanded with that many of its best matches (after filters) from a first search
+pub fn expanded_query(vector_store: &VectorStore, query_embedding: &[f32], options: &SearchOptions) -> Result<Vec<f32>> {
+    let Some(chunks) = options.expand.filter(|chunks| *chunks > 0) else {
+        return Ok(query_embedding.to_vec());
+    };
+    let mut feedback = vector_store.search(query_embedding, options.candidate_limit(vector_store.chunk_count()))?;
+    feedback.retain(|(entry, _)| options.admits(entry));
+    feedback.truncate(chunks);
+    let feedback: Vec<VectorEntry> = feedback.into_iter().map(|(entry, _)| entry).collect();
+    Ok(expand_embedding(query_embedding, &feedback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::search::files::best_chunk_per_file_by_embedding;
+    use tempfile::TempDir;
+
+    fn entry(file: &str, embedding: Vec<f32>) -> VectorEntry {
+        VectorEntry::new(file.to_string(), 0, embedding, file.to_string(), String::new(), 1, 1)
+    }
+
+    #[test]
+    fn test_expansion_lifts_a_related_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        // The short query only names the x topic. The best match also covers y, and so does
+        // related.md, which the query alone ranks below a note that shares nothing but x.
+        store.insert(&entry("best.md", vec![1.0, 0.5, 0.0])).unwrap();
+        store.insert(&entry("related.md", vec![0.5, 1.0, 0.0])).unwrap();
+        store.insert(&entry("tangent.md", vec![0.6, 0.0, 0.8])).unwrap();
+        let query = [1.0, 0.0, 0.0];
+
+        let paths = |options: SearchOptions| -> Vec<String> {
+            best_chunk_per_file_by_embedding(&query, &options, &store)
+                .unwrap()
+                .into_iter()
+                .map(|(entry, _)| entry.file_path)
+                .collect()
+        };
+        assert_eq!(paths(SearchOptions::new(3)), vec!["best.md", "tangent.md", "related.md"]);
+        assert_eq!(paths(SearchOptions::new(3).with_expand(Some(1))), vec!["best.md", "related.md", "tangent.md"]);
+    }
+
+    #[test]
+    fn test_no_feedback_keeps_the_query() {
+        assert_eq!(expand_embedding(&[2.0, 0.0], &[]), vec![2.0, 0.0]);
+        // Feedback from a model with another dimension is ignored
+        assert_eq!(expand_embedding(&[2.0, 0.0], &[entry("a.md", vec![1.0, 0.0, 0.0])]), vec![2.0, 0.0]);
+
+        let expanded = expand_embedding(&[2.0, 0.0], &[entry("a.md", vec![0.0, 1.0])]);
+        let expected = 1.0 / 2.0f32.sqrt();
+        assert!((expanded[0] - expected).abs() < 1e-6);
+        assert!((expanded[1] - expected).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+/// How a result's final score was assembled.
+/// Each ranking stage records its contribution here rather than adjusting the score anonymously.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct ScoreBreakdown {
+    /// Cosine similarity between the query and the chunk
+    pub cosine: f32,
+    /// Bonus for the query text appearing in the path, heading context or chunk text
+    pub lexical: f32,
+    /// Bonus for the result's file path matching the query (only with `path_signal` on)
+    pub path: f32,
+    /// Bonus for recently modified notes (no recency stage yet, so always 0)
+    pub recency: f32,
+    /// Adjustment from a reranker (no reranker yet, so always 0)
+    pub rerank: f32,
+    /// Bonus for sitting in a folder results were picked from earlier in the TUI session
+    #[serde(skip_serializing_if = "is_zero")]
+    pub session: f32,
+    /// Score used for ranking: the sum of the parts, capped at 1.0
+    pub final_score: f32,
+}
+
+impl ScoreBreakdown {
+    pub fn new(cosine: f32) -> Self {
+        Self {
+            cosine,
+            final_score: cosine,
+            ..Self::default()
+        }
+    }
+
+    /// Record the lexical stage's bonus
+    pub fn with_lexical(mut self, lexical: f32) -> Self {
+        self.lexical = lexical;
+        self.final_score = self.total();
+        self
+    }
+
+    /// Record the path signal's bonus
+    pub fn with_path(mut self, path: f32) -> Self {
+        self.path = path;
+        self.final_score = self.total();
+        self
+    }
+
+    /// Record the session folder bonus
+    pub fn with_session(mut self, session: f32) -> Self {
+        self.session = session;
+        self.final_score = self.total();
+        self
+    }
+
+    fn total(&self) -> f32 {
+        (self.cosine + self.lexical + self.path + self.recency + self.rerank + self.session).min(1.0)
+    }
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cosine {:.3} + lexical {:.3} + path {:.3} + recency {:.3} + rerank {:.3}",
+            self.cosine, self.lexical, self.path, self.recency, self.rerank
+        )?;
+        // Only the TUI has a session to boost from
+        if self.session != 0.0 {
+            write!(f, " + session {:.3}", self.session)?;
+        }
+        write!(f, " = {:.3}", self.final_score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakdown_sums_and_caps() {
+        let breakdown = ScoreBreakdown::new(0.6).with_lexical(0.15);
+        assert!((breakdown.final_score - 0.75).abs() < 1e-6);
+
+        let capped = ScoreBreakdown::new(0.95).with_lexical(0.30);
+        assert_eq!(capped.final_score, 1.0);
+        assert!((capped.lexical - 0.30).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_breakdown_display() {
+        let breakdown = ScoreBreakdown::new(0.5).with_lexical(0.1);
+        assert_eq!(
+            breakdown.to_string(),
+            "cosine 0.500 + lexical 0.100 + path 0.000 + recency 0.000 + rerank 0.000 = 0.600"
+        );
+        let breakdown = breakdown.with_path(0.05);
+        assert!((breakdown.final_score - 0.65).abs() < 1e-6);
+        assert!(breakdown.with_session(0.03).to_string().ends_with("+ session 0.030 = 0.680"));
+    }
+}
@@ -0,0 +1,415 @@
+use crate::core::error::{Error, Result};
+use crate::search::expand::expanded_query;
+use crate::search::model::EmbeddingModel;
+use crate::search::options::SearchOptions;
+use crate::storage::vectors::{rank_order, score_order, VectorEntry, VectorStore};
+use std::collections::HashMap;
+
+/// Number of top chunks averaged into a file's score
+pub const DEFAULT_TOP_K: usize = 3;
+
+/// A file ranked by the aggregate relevance of its chunks
+#[derive(Debug, Clone)]
+pub struct FileResult {
+    pub file_path: String,
+    /// Mean similarity of the file's top-k matching chunks
+    pub score: f32,
+    /// The file's best matching chunk
+    pub best_chunk: VectorEntry,
+    pub best_similarity: f32,
+    /// Chunks that contributed to `score`
+    pub chunks_used: usize,
+}
+
+/// Best chunk of each file, with the size of the pool it was picked from
+#[derive(Debug, Clone, Default)]
+pub struct BestChunks {
+    pub results: Vec<(VectorEntry, f32)>,
+    /// Candidate chunks that passed the filters, before grouping by file and the limit
+    pub candidates: usize,
+}
+
+/// Rank files by the mean of their top `top_k` chunk similarities.
+/// A file with several moderately relevant chunks can beat one with a single strong hit.
+pub fn aggregate_by_file(results: Vec<(VectorEntry, f32)>, top_k: usize) -> Vec<FileResult> {
+    let top_k = top_k.max(1);
+    let mut by_file: HashMap<String, Vec<(VectorEntry, f32)>> = HashMap::new();
+    for (entry, sim) in results {
+        by_file.entry(entry.file_path.clone()).or_default().push((entry, sim));
+    }
+
+    let mut files: Vec<FileResult> = by_file
+        .into_iter()
+        .filter_map(|(file_path, mut chunks)| {
+            chunks.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
+            chunks.truncate(top_k);
+            let chunks_used = chunks.len();
+            let score = chunks.iter().map(|(_, s)| s).sum::<f32>() / chunks_used as f32;
+            let (best_chunk, best_similarity) = chunks.into_iter().next()?;
+            Some(FileResult {
+                file_path,
+                score,
+                best_chunk,
+                best_similarity,
+                chunks_used,
+            })
+        })
+        .collect();
+
+    // Ties broken by path so output is stable
+    files.sort_by(|a, b| score_order(a.score, b.score).then_with(|| a.file_path.cmp(&b.file_path)));
+    files
+}
+
+/// Rank at most `options.limit` files against an already-embedded query
+pub fn search_files_by_embedding(
+    vector_store: &VectorStore,
+    query_embedding: &[f32],
+    options: &SearchOptions,
+    top_k: usize,
+) -> Result<Vec<FileResult>> {
+    let query_embedding = expanded_query(vector_store, query_embedding, options)?;
+    // Each file needs up to top_k chunks in the pool to be scored fairly
+    let pool = SearchOptions::new(options.limit.saturating_mul(top_k.max(1)));
+    let mut candidates = vector_store.search(&query_embedding, pool.candidate_limit(vector_store.chunk_count()))?;
+    candidates.retain(|(entry, _)| options.admits(entry));
+
+    let mut files = aggregate_by_file(candidates, top_k);
+    files.truncate(options.limit);
+    Ok(files)
+}
+
+/// Rank files for a text query using mean-of-top-k aggregation
+pub fn search_files(
+    query: &str,
+    options: &SearchOptions,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+) -> Result<Vec<FileResult>> {
+    let query_embeddings = model.embed_queries(&[query.to_string()])?;
+    let query_embedding = query_embeddings
+        .first()
+        .ok_or_else(|| Error::Model("Failed to generate query embedding".to_string()))?;
+    search_files_by_embedding(vector_store, query_embedding, options, DEFAULT_TOP_K)
+}
+
+/// Best chunk of each file for a text query, by cosine alone (what `notes2vec search` prints)
+pub fn best_chunk_per_file(
+    query: &str,
+    options: &SearchOptions,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+) -> Result<Vec<(VectorEntry, f32)>> {
+    best_chunks_for_query(query, options, model, vector_store).map(|best| best.results)
+}
+
+/// [`best_chunk_per_file`] with the candidate count (`search --format json`)
+pub fn best_chunks_for_query(
+    query: &str,
+    options: &SearchOptions,
+    model: &EmbeddingModel,
+    vector_store: &VectorStore,
+) -> Result<BestChunks> {
+    let query_embeddings = model.embed_queries(&[query.to_string()])?;
+    let query_embedding = query_embeddings
+        .first()
+        .ok_or_else(|| Error::Model("Failed to generate query embedding".to_string()))?;
+    best_chunks_by_embedding(query_embedding, options, vector_store)
+}
+
+/// Best chunk of each file against an already-embedded query
+pub fn best_chunk_per_file_by_embedding(
+    query_embedding: &[f32],
+    options: &SearchOptions,
+    vector_store: &VectorStore,
+) -> Result<Vec<(VectorEntry, f32)>> {
+    best_chunks_by_embedding(query_embedding, options, vector_store).map(|best| best.results)
+}
+
+/// [`best_chunk_per_file_by_embedding`] with the candidate count
+pub fn best_chunks_by_embedding(
+    query_embedding: &[f32],
+    options: &SearchOptions,
+    vector_store: &VectorStore,
+) -> Result<BestChunks> {
+    let query_embedding = expanded_query(vector_store, query_embedding, options)?;
+    // Fetch more candidates than results so deduplication still leaves enough files
+    let candidate_limit = options.candidate_limit(vector_store.chunk_count());
+    let mut results = match options.two_stage {
+        Some(files) => vector_store.search_two_stage(&query_embedding, candidate_limit, files)?,
+        None => vector_store.search(&query_embedding, candidate_limit)?,
+    };
+    results.retain(|(entry, _)| options.admits(entry));
+    let candidates = results.len();
+
+    let mut best_by_file: HashMap<String, (VectorEntry, f32)> = HashMap::with_capacity(results.len());
+    for (entry, sim) in results {
+        match best_by_file.get_mut(&entry.file_path) {
+            Some(current) => {
+                if rank_order(&entry, sim, &current.0, current.1).is_lt() {
+                    *current = (entry, sim);
+                }
+            }
+            None => {
+                best_by_file.insert(entry.file_path.clone(), (entry, sim));
+            }
+        }
+    }
+    let mut deduped: Vec<(VectorEntry, f32)> = best_by_file.into_values().collect();
+    // Map order changes from run to run, so ties are broken by path
+    deduped.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
+    deduped.truncate(options.limit);
+    Ok(BestChunks {
+        results: deduped,
+        candidates,
+    })
+}
+
+/// Files most similar to an indexed file, ranked against the mean of its chunk embeddings.
+/// The source file itself is never returned.
+pub fn similar_files(
+    vector_store: &VectorStore,
+    file_path: &str,
+    limit: usize,
+    top_k: usize,
+) -> Result<Vec<FileResult>> {
+    let vectors = vector_store.get_file_vectors(file_path)?;
+    let centroid = centroid(&vectors).ok_or_else(|| {
+        Error::Config(format!(
+            "'{}' is not in the index. Use the path relative to the indexed directory.",
+            file_path
+        ))
+    })?;
+
+    // Room for the source file's own chunks, which are dropped below
+    let options = SearchOptions::new(limit.saturating_add(1).saturating_mul(top_k.max(1)));
+    let mut candidates = vector_store.search(&centroid, options.candidate_limit(vector_store.chunk_count()))?;
+    candidates.retain(|(entry, _)| entry.file_path != file_path);
+
+    let mut files = aggregate_by_file(candidates, top_k);
+    files.truncate(limit);
+    Ok(files)
+}
+
+/// L2-normalized mean of the entries' embeddings, so dot products stay cosines.
+/// Low-content chunks are left out unless they're all the file has.
+/// Also a file's stored document vector (see [`VectorStore::set_document_vector`]).
+pub fn centroid(entries: &[VectorEntry]) -> Option<Vec<f32>> {
+    let substantive: Vec<&VectorEntry> = entries.iter().filter(|e| !e.low_content).collect();
+    let used: Vec<&VectorEntry> = if substantive.is_empty() {
+        entries.iter().collect()
+    } else {
+        substantive
+    };
+
+    let dims = used.first()?.embedding.len();
+    let mut sum = vec![0.0f32; dims];
+    for entry in used.iter().filter(|e| e.embedding.len() == dims) {
+        for (total, value) in sum.iter_mut().zip(&entry.embedding) {
+            *total += value;
+        }
+    }
+
+    let norm = sum.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return None;
+    }
+    Some(sum.into_iter().map(|v| v / norm).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::core::sample::SampleRng;
+    use std::collections::HashSet;
+    use tempfile::TempDir;
+
+    fn entry(file: &str, index: usize, embedding: Vec<f32>) -> VectorEntry {
+        VectorEntry::new(file.to_string(), index, embedding, format!("{} #{}", file, index), String::new(), 1, 1)
+    }
+
+    #[test]
+    fn test_aggregate_mean_of_top_k() {
+        let results = vec![
+            (entry("a.md", 0, vec![]), 0.9),
+            (entry("a.md", 1, vec![]), 0.5),
+            (entry("a.md", 2, vec![]), 0.4),
+            (entry("a.md", 3, vec![]), 0.1),
+            (entry("b.md", 0, vec![]), 0.55),
+        ];
+        let files = aggregate_by_file(results, 3);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file_path, "a.md");
+        assert!((files[0].score - 0.6).abs() < 1e-6);
+        assert_eq!(files[0].chunks_used, 3);
+        assert_eq!(files[0].best_chunk.chunk_index, 0);
+        assert!((files[0].best_similarity - 0.9).abs() < 1e-6);
+        assert!((files[1].score - 0.55).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_best_chunk_ties_are_ordered_by_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+        for file in ["d.md", "b.md", "e.md", "a.md", "c.md"] {
+            store.insert(&entry(file, 0, vec![0.6, 0.8])).unwrap();
+        }
+
+        let paths: Vec<String> = best_chunk_per_file_by_embedding(&[1.0, 0.0], &SearchOptions::new(4), &store)
+            .unwrap()
+            .into_iter()
+            .map(|(entry, _)| entry.file_path)
+            .collect();
+        assert_eq!(paths, vec!["a.md", "b.md", "c.md", "d.md"]);
+    }
+
+    #[test]
+    fn test_several_moderate_chunks_outrank_single_strong_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        // Query points along the x axis
+        let query = vec![1.0, 0.0];
+        // steady.md: three chunks with cosine 0.8 each
+        for i in 0..3 {
+            store.insert(&entry("steady.md", i, vec![0.8, 0.6])).unwrap();
+        }
+        // spike.md: one near-perfect chunk and two unrelated ones
+        store.insert(&entry("spike.md", 0, vec![1.0, 0.05])).unwrap();
+        store.insert(&entry("spike.md", 1, vec![0.0, 1.0])).unwrap();
+        store.insert(&entry("spike.md", 2, vec![0.1, 1.0])).unwrap();
+
+        // Chunk-level search puts spike.md first
+        let chunks = store.search(&query, 1).unwrap();
+        assert_eq!(chunks[0].0.file_path, "spike.md");
+
+        let files = search_files_by_embedding(&store, &query, &SearchOptions::new(10), 3).unwrap();
+        assert_eq!(files[0].file_path, "steady.md");
+        assert_eq!(files[1].file_path, "spike.md");
+        assert_eq!(files[1].best_chunk.chunk_index, 0);
+    }
+
+    #[test]
+    fn test_similar_files_stay_on_topic() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        // Topic A lives near the x axis, topic B near the y axis
+        store.insert(&entry("a_source.md", 0, vec![1.0, 0.1])).unwrap();
+        store.insert(&entry("a_source.md", 1, vec![0.9, 0.2])).unwrap();
+        store.insert(&entry("a_other.md", 0, vec![0.95, 0.15])).unwrap();
+        store.insert(&entry("a_third.md", 0, vec![0.8, 0.3])).unwrap();
+        store.insert(&entry("b_unrelated.md", 0, vec![0.1, 1.0])).unwrap();
+
+        let similar = similar_files(&store, "a_source.md", 2, DEFAULT_TOP_K).unwrap();
+        let paths: Vec<&str> = similar.iter().map(|f| f.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["a_other.md", "a_third.md"]);
+
+        // The unrelated file only shows up last, and the source never does
+        let all = similar_files(&store, "a_source.md", 10, DEFAULT_TOP_K).unwrap();
+        assert_eq!(all.last().unwrap().file_path, "b_unrelated.md");
+        assert!(all.iter().all(|f| f.file_path != "a_source.md"));
+
+        assert!(matches!(similar_files(&store, "missing.md", 5, DEFAULT_TOP_K), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_centroid_is_normalized_and_skips_low_content() {
+        let entries = vec![
+            entry("a.md", 0, vec![1.0, 0.0]),
+            entry("a.md", 1, vec![0.0, 1.0]),
+            entry("a.md", 2, vec![-1.0, 0.0]).with_low_content(true),
+        ];
+        let c = centroid(&entries).unwrap();
+        let expected = 1.0 / 2.0f32.sqrt();
+        assert!((c[0] - expected).abs() < 1e-6);
+        assert!((c[1] - expected).abs() < 1e-6);
+        assert!(centroid(&[]).is_none());
+    }
+
+    /// `dims` values uniform in -1..1
+    fn noise(rng: &mut SampleRng, dims: usize) -> Vec<f32> {
+        (0..dims).map(|_| rng.below(20_001) as f32 / 10_000.0 - 1.0).collect()
+    }
+
+    fn mix(base: &[f32], offset: &[f32], scale: f32) -> Vec<f32> {
+        base.iter().zip(offset).map(|(b, o)| b + o * scale).collect()
+    }
+
+    #[test]
+    fn test_two_stage_matches_exact_top_ten() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        // Files cluster around a few topics, and each file's chunks around the file
+        let mut rng = SampleRng::new(2187);
+        let dims = 24;
+        let topics: Vec<Vec<f32>> = (0..8).map(|_| noise(&mut rng, dims)).collect();
+        for file in 0..120 {
+            let center = mix(&topics[file % topics.len()], &noise(&mut rng, dims), 0.4);
+            let path = format!("note-{:03}.md", file);
+            let entries: Vec<VectorEntry> = (0..4)
+                .map(|chunk| entry(&path, chunk, mix(&center, &noise(&mut rng, dims), 0.3)))
+                .collect();
+            for chunk in &entries {
+                store.insert(chunk).unwrap();
+            }
+            store.set_document_vector(&path, &centroid(&entries).unwrap()).unwrap();
+        }
+        assert_eq!(store.document_count().unwrap(), 120);
+
+        let exact = SearchOptions::new(10);
+        let two_stage = exact.with_two_stage(Some(30));
+        for _ in 0..10 {
+            let topic = &topics[rng.below(topics.len() as u64) as usize];
+            let query = mix(topic, &noise(&mut rng, dims), 0.5);
+            let paths = |options| -> HashSet<String> {
+                best_chunk_per_file_by_embedding(&query, &options, &store)
+                    .unwrap()
+                    .into_iter()
+                    .map(|(entry, _)| entry.file_path)
+                    .collect()
+            };
+            let overlap = paths(exact).intersection(&paths(two_stage)).count();
+            assert!(overlap >= 8, "two-stage kept {} of the exact top 10", overlap);
+        }
+    }
+
+    #[test]
+    fn test_two_stage_still_scores_files_without_document_vectors() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        let on_topic = entry("documented.md", 0, vec![1.0, 0.0]);
+        let off_topic = entry("elsewhere.md", 0, vec![0.0, 1.0]);
+        for chunk in [&on_topic, &off_topic] {
+            store.insert(chunk).unwrap();
+            store.set_document_vector(&chunk.file_path, &chunk.embedding).unwrap();
+        }
+        // Indexed before document vectors were kept
+        store.insert(&entry("legacy.md", 0, vec![0.9, 0.1])).unwrap();
+
+        let ranked = store.search_documents(&[1.0, 0.0], 5).unwrap();
+        let ranked: Vec<&str> = ranked.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(ranked, vec!["documented.md", "elsewhere.md"]);
+
+        let options = SearchOptions::new(5).with_two_stage(Some(1));
+        let paths: Vec<String> = best_chunk_per_file_by_embedding(&[1.0, 0.0], &options, &store)
+            .unwrap()
+            .into_iter()
+            .map(|(entry, _)| entry.file_path)
+            .collect();
+        assert_eq!(paths, vec!["documented.md", "legacy.md"]);
+    }
+}
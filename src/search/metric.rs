@@ -0,0 +1,69 @@
+/// How query embeddings are compared with stored ones (the `similarity_metric` setting).
+/// Scores stay "higher is closer" for all of them, so ranking and filtering don't change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimilarityMetric {
+    /// Angle only; the bundled model's vectors are unit length, so this is the usual choice
+    #[default]
+    Cosine,
+    /// Raw dot product, so vector length counts (same as cosine for unit vectors)
+    Dot,
+    /// `1 / (1 + distance)`, in (0, 1]
+    Euclidean,
+}
+
+impl SimilarityMetric {
+    pub const NAMES: [&'static str; 3] = ["cosine", "dot", "euclidean"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cosine" => Some(Self::Cosine),
+            "dot" => Some(Self::Dot),
+            "euclidean" => Some(Self::Euclidean),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Dot => "dot",
+            Self::Euclidean => "euclidean",
+        }
+    }
+}
+
+/// Warning for searching with `current` when the index was built for `recorded` (indexes from
+/// before the metric was recorded were built for cosine); None when they agree
+pub fn metric_mismatch(recorded: Option<&str>, current: SimilarityMetric) -> Option<String> {
+    let recorded = recorded.unwrap_or(SimilarityMetric::Cosine.as_str());
+    (recorded != current.as_str()).then(|| {
+        format!(
+            "The index was built for {} similarity but similarity_metric is {}, so scores and confidence thresholds may be off. Set it back, or run 'notes2vec index' to keep the new metric.",
+            recorded,
+            current.as_str()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metric_names() {
+        for name in SimilarityMetric::NAMES {
+            assert_eq!(SimilarityMetric::parse(name).unwrap().as_str(), name);
+        }
+        assert_eq!(SimilarityMetric::parse(" Dot "), Some(SimilarityMetric::Dot));
+        assert_eq!(SimilarityMetric::parse("manhattan"), None);
+    }
+
+    #[test]
+    fn test_metric_mismatch() {
+        assert!(metric_mismatch(None, SimilarityMetric::Cosine).is_none());
+        assert!(metric_mismatch(Some("dot"), SimilarityMetric::Dot).is_none());
+        let warning = metric_mismatch(Some("cosine"), SimilarityMetric::Euclidean).unwrap();
+        assert!(warning.contains("cosine") && warning.contains("euclidean"), "{}", warning);
+        assert!(metric_mismatch(None, SimilarityMetric::Dot).is_some());
+    }
+}
@@ -1,2 +1,10 @@
+pub mod calibration;
+pub mod checksums;
+pub mod collections;
+pub mod expand;
+pub mod explain;
+pub mod files;
+pub mod metric;
 pub mod model;
+pub mod options;
 
@@ -1,28 +1,251 @@
 use crate::core::config::Config;
 use crate::core::error::{Error, Result};
+use crate::search::checksums::{compute_checksums, verify_checksums, ModelChecksums};
 use candle_core::{Device, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
 use hf_hub::api::sync::Api;
+use hf_hub::api::Progress;
+use hf_hub::{Cache, Repo, RepoType};
 // sha2 dependency is used elsewhere; no hashing fallback is used for embeddings.
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use tokenizers::{PaddingParams, Tokenizer};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokenizers::{PaddingParams, Tokenizer, TruncationDirection, TruncationParams, TruncationStrategy};
 
 /// Default embedding model (small, strong, good for semantic search)
 /// https://huggingface.co/BAAI/bge-small-en-v1.5
 const DEFAULT_MODEL: &str = "BAAI/bge-small-en-v1.5";
 
-/// Identifier for the embedding model used to build/query the index.
-/// If this changes, you should re-index.
+/// Default identifier for the embedding model used to build/query the index
+/// (overridable with the `model_id` setting). If this changes, you should re-index.
 pub const EMBEDDING_MODEL_ID: &str = DEFAULT_MODEL;
 
+/// Prepended to queries before embedding, unless the `query_prefix` setting says otherwise
+pub const DEFAULT_QUERY_PREFIX: &str = "query: ";
+
+/// Prepended to passages before embedding, unless the `passage_prefix` setting says otherwise
+pub const DEFAULT_PASSAGE_PREFIX: &str = "passage: ";
+
+/// How the model's token embeddings are pooled into one vector per text
+pub const POOLING: &str = "cls";
+
+/// Smallest `max_tokens` setting accepted; the limit has to leave room for the special tokens
+pub const MIN_MAX_TOKENS: usize = 16;
+
+/// Sequence length assumed when a model's config.json doesn't give `max_position_embeddings`
+const DEFAULT_MAX_POSITIONS: usize = 512;
+
+/// Loading and download progress: shown at info for CLI commands, kept at debug otherwise
+macro_rules! status {
+    ($verbose:expr, $($arg:tt)+) => {
+        if $verbose {
+            tracing::info!($($arg)+)
+        } else {
+            tracing::debug!($($arg)+)
+        }
+    };
+}
+
+/// Which end of a text longer than the token limit is cut off (the `truncation` setting)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Truncation {
+    /// Keep the start of the text, where a note's chunk usually says what it's about
+    #[default]
+    End,
+    /// Keep the end of the text
+    Start,
+}
+
+impl Truncation {
+    pub const NAMES: [&'static str; 2] = ["end", "start"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "end" => Some(Self::End),
+            "start" => Some(Self::Start),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::End => "end",
+            Self::Start => "start",
+        }
+    }
+}
+
+/// Tokens per text for a `max_tokens` setting and a model that accepts `model_limit`:
+/// the setting, but never more than the model can take
+pub fn token_limit(max_tokens: Option<usize>, model_limit: usize) -> usize {
+    max_tokens.map_or(model_limit, |max| max.min(model_limit))
+}
+
+/// Set `tokenizer` up for batched embedding, once at load: pad each batch to its longest text,
+/// and cut every text down to `max_length` tokens, special tokens included, so a long chunk is
+/// embedded from its first (or last) tokens instead of failing the batch.
+/// Encoding only reads the tokenizer afterwards, so it can be shared across threads unlocked.
+pub fn configure_tokenizer(tokenizer: &mut Tokenizer, max_length: usize, truncation: Truncation) -> Result<()> {
+    let padding = PaddingParams {
+        strategy: tokenizers::PaddingStrategy::BatchLongest,
+        // Keep the pad token the tokenizer file sets, if any
+        ..tokenizer.get_padding().cloned().unwrap_or_default()
+    };
+    tokenizer.with_padding(Some(padding));
+
+    let direction = match truncation {
+        Truncation::End => TruncationDirection::Right,
+        Truncation::Start => TruncationDirection::Left,
+    };
+    tokenizer
+        .with_truncation(Some(TruncationParams {
+            direction,
+            max_length,
+            strategy: TruncationStrategy::LongestFirst,
+            stride: 0,
+        }))
+        .map_err(|e| Error::Tokenizer(format!("Failed to set truncation: {}", e)))?;
+    Ok(())
+}
+
+/// Fingerprint of how text becomes a vector apart from the model weights: the query and
+/// passage prefixes and the pooling. Passages embedded under one scheme don't compare with
+/// queries embedded under another, so a change means re-embedding everything.
+pub fn embedding_scheme(query_prefix: &str, passage_prefix: &str) -> String {
+    format!("query={:?} passage={:?} pooling={}", query_prefix, passage_prefix, POOLING)
+}
+
+/// Chunks embedded per forward pass when indexing a file.
+/// Bounds the padded batch tensor and lets long files report progress between passes.
+pub const EMBED_BATCH_SIZE: usize = 32;
+
+/// Files fetched from the model repository into `Config::model_dir`
+pub const MODEL_FILES: [&str; 3] = ["config.json", "tokenizer.json", "model.safetensors"];
+
+/// Byte progress of one model file download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// File being fetched (one of `MODEL_FILES`)
+    pub file: String,
+    /// 1-based position of `file` in `MODEL_FILES`
+    pub file_number: usize,
+    pub file_count: usize,
+    pub downloaded: usize,
+    /// Total bytes (0 until the server reports a size)
+    pub total: usize,
+}
+
+impl DownloadProgress {
+    /// Fraction of the current file downloaded, in 0.0..=1.0
+    pub fn ratio(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.downloaded as f64 / self.total as f64).min(1.0)
+        }
+    }
+}
+
+/// Forwards hf-hub download callbacks as `DownloadProgress` updates
+struct ProgressAdapter<'a> {
+    progress: DownloadProgress,
+    on_progress: &'a mut dyn FnMut(&DownloadProgress),
+}
+
+impl Progress for ProgressAdapter<'_> {
+    fn init(&mut self, size: usize, _filename: &str) {
+        self.progress.total = size;
+        self.progress.downloaded = 0;
+        (self.on_progress)(&self.progress);
+    }
+
+    fn update(&mut self, size: usize) {
+        self.progress.downloaded += size;
+        (self.on_progress)(&self.progress);
+    }
+
+    fn finish(&mut self) {
+        self.progress.downloaded = self.progress.total;
+        (self.on_progress)(&self.progress);
+    }
+}
+
+/// Model files not yet present in `config.model_dir()`
+pub fn missing_model_files(config: &Config) -> Vec<&'static str> {
+    let model_dir = config.model_dir();
+    MODEL_FILES
+        .iter()
+        .copied()
+        .filter(|file| !model_dir.join(file).exists())
+        .collect()
+}
+
+/// Download the model files into `config.model_dir()`, reporting byte progress per file.
+/// Files already in the Hugging Face cache are copied without downloading again. `model_revision` pins the branch, tag or commit they come from.
+pub fn download_model_files(config: &Config, on_progress: &mut dyn FnMut(&DownloadProgress)) -> Result<()> {
+    let model_dir = config.model_dir();
+    std::fs::create_dir_all(&model_dir)?;
+
+    let source = match &config.model_revision {
+        Some(revision) => Repo::with_revision(config.model_id.clone(), RepoType::Model, revision.clone()),
+        None => Repo::model(config.model_id.clone()),
+    };
+    let api = Api::new().map_err(Error::HuggingFace)?;
+    let repo = api.repo(source.clone());
+    let cache = Cache::default().repo(source);
+
+    for (i, file) in MODEL_FILES.iter().enumerate() {
+        let progress = DownloadProgress {
+            file: file.to_string(),
+            file_number: i + 1,
+            file_count: MODEL_FILES.len(),
+            downloaded: 0,
+            total: 0,
+        };
+        let path = match cache.get(file) {
+            Some(path) => {
+                on_progress(&progress);
+                path
+            }
+            None => {
+                let adapter = ProgressAdapter { progress, on_progress: &mut *on_progress };
+                repo.download_with_progress(file, adapter).map_err(Error::HuggingFace)?
+            }
+        };
+        std::fs::copy(&path, model_dir.join(file))?;
+    }
+
+    Ok(())
+}
+
+/// The parts of a model's config.json that set its embedding length and input length
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+struct ModelDims {
+    hidden_size: usize,
+    #[serde(default = "default_max_positions")]
+    max_position_embeddings: usize,
+}
+
+fn default_max_positions() -> usize {
+    DEFAULT_MAX_POSITIONS
+}
+
+/// Model, tokenizer and dimensions read from the model files
+type LoadedModel = (Option<Arc<Mutex<BertModel>>>, Option<Arc<Tokenizer>>, ModelDims);
+
 /// Embedding model manager
 pub struct EmbeddingModel {
     model: Option<Arc<Mutex<BertModel>>>,
-    tokenizer: Option<Arc<Mutex<Tokenizer>>>,
+    tokenizer: Option<Arc<Tokenizer>>,
     device: Device,
-    #[allow(dead_code)]
+    query_prefix: String,
+    passage_prefix: String,
+    /// Length of the vectors it produces (`hidden_size` in the model's config.json)
+    embedding_dim: usize,
+    /// Tokens per text after truncation, special tokens included
+    max_tokens: usize,
+    /// Checksums of the model files, worked out on first use (see [`checksums`](Self::checksums))
+    checksums: OnceLock<Option<ModelChecksums>>,
     model_path: PathBuf,
     #[allow(dead_code)]
     tokenizer_path: PathBuf,
@@ -49,16 +272,20 @@ impl EmbeddingModel {
 
     fn init_with_verbosity(config: &Config, verbose: bool) -> Result<Self> {
         // Ensure models directory exists
-        std::fs::create_dir_all(&config.models_dir)?;
+        // Leave existing directories alone so read-only installs can load the model
+        let model_dir = config.model_dir();
+        if !model_dir.is_dir() {
+            std::fs::create_dir_all(&model_dir)?;
+        }
 
-        let model_path = config.models_dir.join("model.safetensors");
-        let config_path = config.models_dir.join("config.json");
-        let tokenizer_path = config.models_dir.join("tokenizer.json");
+        let model_path = model_dir.join("model.safetensors");
+        let config_path = model_dir.join("config.json");
+        let tokenizer_path = model_dir.join("tokenizer.json");
 
         // Try to download and load model if files don't exist.
         // No fallback: if the model can't be loaded, return an error.
-        let (model, tokenizer) = if model_path.exists() && config_path.exists() && tokenizer_path.exists() {
-            Self::load_model_files(&model_path, &config_path, &tokenizer_path, verbose)?
+        let (model, tokenizer, dims) = if model_path.exists() && config_path.exists() && tokenizer_path.exists() {
+            Self::load_model_files(config, &model_path, &config_path, &tokenizer_path, verbose)?
         } else {
             Self::download_model(config, &model_path, &config_path, &tokenizer_path, verbose)?
         };
@@ -75,6 +302,11 @@ impl EmbeddingModel {
             model,
             tokenizer,
             device,
+            query_prefix: config.query_prefix.clone(),
+            passage_prefix: config.passage_prefix.clone(),
+            embedding_dim: dims.hidden_size,
+            max_tokens: token_limit(config.max_tokens, dims.max_position_embeddings),
+            checksums: OnceLock::new(),
             model_path,
             tokenizer_path,
         })
@@ -82,76 +314,51 @@ impl EmbeddingModel {
 
     /// Download model from HuggingFace Hub
     fn download_model(
-        _config: &Config,
+        config: &Config,
         model_path: &PathBuf,
         config_path: &PathBuf,
         tokenizer_path: &PathBuf,
         verbose: bool,
-    ) -> Result<(Option<Arc<Mutex<BertModel>>>, Option<Arc<Mutex<Tokenizer>>>)> {
-        if verbose {
-            println!("Downloading embedding model from HuggingFace Hub...");
-            println!("Model: {}", DEFAULT_MODEL);
-        }
+    ) -> Result<LoadedModel> {
+        status!(verbose, model = %config.model_id, "Downloading embedding model from HuggingFace Hub");
         
-        // Initialize API
-        let api = Api::new().map_err(|e| {
-            Error::HuggingFace(e)
+        let mut announced = 0;
+        download_model_files(config, &mut |progress| {
+            if progress.file_number > announced {
+                announced = progress.file_number;
+                status!(verbose, "Downloading {}", progress.file);
+            }
         })?;
-        
-        // Get model repository
-        let repo = api.model(DEFAULT_MODEL.to_string());
-        
-        // Download required files
-        if verbose {
-            println!("  Downloading config.json...");
-        }
-        let config_file = repo.get("config.json")
-            .map_err(|e| Error::HuggingFace(e))?;
-        
-        if verbose {
-            println!("  Downloading tokenizer.json...");
-        }
-        let tokenizer_file = repo.get("tokenizer.json")
-            .map_err(|e| Error::HuggingFace(e))?;
-        
-        if verbose {
-            println!("  Downloading model.safetensors (this may take a while)...");
-        }
-        let weights_file = repo.get("model.safetensors")
-            .map_err(|e| Error::HuggingFace(e))?;
 
-        // Copy files to our models directory
-        std::fs::copy(&config_file, config_path)?;
-        std::fs::copy(&tokenizer_file, tokenizer_path)?;
-        std::fs::copy(&weights_file, model_path)?;
-
-        if verbose {
-            println!("✓ Model downloaded successfully");
-        }
+        status!(verbose, "✓ Model downloaded successfully");
 
         // Load the downloaded model
-        Self::load_model_files(model_path, config_path, tokenizer_path, verbose)
+        Self::load_model_files(config, model_path, config_path, tokenizer_path, verbose)
     }
 
     /// Load model files from disk
     fn load_model_files(
+        config: &Config,
         model_path: &PathBuf,
         config_path: &PathBuf,
         tokenizer_path: &PathBuf,
         verbose: bool,
-    ) -> Result<(Option<Arc<Mutex<BertModel>>>, Option<Arc<Mutex<Tokenizer>>>)> {
-        if verbose {
-            println!("Loading model from disk...");
-        }
+    ) -> Result<LoadedModel> {
+        status!(verbose, "Loading model from disk");
         
         // Load and parse config
         let config_content = std::fs::read_to_string(config_path)?;
         let bert_config: BertConfig = serde_json::from_str(&config_content)
             .map_err(|e| Error::Model(format!("Failed to parse config: {}", e)))?;
+        // candle keeps the config's fields private
+        let dims: ModelDims = serde_json::from_str(&config_content)
+            .map_err(|e| Error::Model(format!("Failed to parse config: {}", e)))?;
 
         // Load tokenizer
-        let tokenizer = Tokenizer::from_file(tokenizer_path)
+        let mut tokenizer = Tokenizer::from_file(tokenizer_path)
             .map_err(|e| Error::Tokenizer(format!("Failed to load tokenizer: {}", e)))?;
+        let max_tokens = token_limit(config.max_tokens, dims.max_position_embeddings);
+        configure_tokenizer(&mut tokenizer, max_tokens, config.truncation)?;
 
         // Determine device (CUDA if available, else CPU)
         let device = Device::cuda_if_available(0).unwrap_or(Device::Cpu);
@@ -166,23 +373,62 @@ impl EmbeddingModel {
         let model = BertModel::load(vb, &bert_config)
             .map_err(|e| Error::Model(format!("Failed to load model: {}", e)))?;
 
-        if verbose {
-            println!("✓ Model loaded successfully");
-        }
+        status!(verbose, path = %model_path.display(), dim = dims.hidden_size, "✓ Model loaded successfully");
 
         Ok((
             Some(Arc::new(Mutex::new(model))),
-            Some(Arc::new(Mutex::new(tokenizer))),
+            Some(Arc::new(tokenizer)),
+            dims,
         ))
     }
 
+    /// Length of the embeddings this model produces
+    pub fn embedding_dim(&self) -> usize {
+        self.embedding_dim
+    }
+
+    /// Checksums of the model files loaded, recorded with the indexes they build. Files whose
+    /// size and modification time match `recorded` (what the index holds) keep that hash instead
+    /// of being read again; the result is kept for the rest of the process. None if the files
+    /// couldn't be read.
+    pub fn checksums(&self, recorded: Option<&ModelChecksums>) -> Option<&ModelChecksums> {
+        self.checksums
+            .get_or_init(|| {
+                let model_dir = self.model_path.parent()?;
+                let current = match recorded {
+                    Some(recorded) => verify_checksums(model_dir, recorded),
+                    None => compute_checksums(model_dir),
+                };
+                current
+                    .map_err(|e| tracing::warn!("Could not checksum the model files: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Tokens of each text the model sees; the rest is truncated
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
     /// Check if full model is available
     pub fn is_model_loaded(&self) -> bool {
         self.model.is_some() && self.tokenizer.is_some()
     }
 
+    /// Run one tiny embedding so the first real query doesn't pay for tensor allocation and setup.
+    /// Fails like [`embed`](Self::embed) when the model isn't loaded.
+    pub fn warmup(&self) -> Result<()> {
+        self.embed_queries(&["warmup".to_string()]).map(|_| ())
+    }
+
     /// Generate embeddings for texts
     pub fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embed_counting_truncated(texts).map(|(embeddings, _)| embeddings)
+    }
+
+    /// [`embed`](Self::embed), also returning how many texts were cut to [`max_tokens`](Self::max_tokens)
+    fn embed_counting_truncated(&self, texts: &[String]) -> Result<(Vec<Vec<f32>>, usize)> {
         if let (Some(model), Some(tokenizer)) = (&self.model, &self.tokenizer) {
             self.embed_with_model(model, tokenizer, texts)
         } else {
@@ -192,46 +438,87 @@ impl EmbeddingModel {
         }
     }
 
-    /// Embed query texts (recommended for BGE models).
+    /// Embed query texts, each behind the query prefix (recommended for BGE models).
     pub fn embed_queries(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        let prefixed: Vec<String> = texts.iter().map(|t| format!("query: {}", t)).collect();
+        let prefixed: Vec<String> = texts.iter().map(|t| format!("{}{}", self.query_prefix, t)).collect();
         self.embed(&prefixed)
     }
 
-    /// Embed passage texts (recommended for BGE models).
+    /// Embed passage texts, each behind the passage prefix (recommended for BGE models).
+    /// Blank texts never reach the tokenizer; they get a zero vector, which matches nothing.
     pub fn embed_passages(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
-        let prefixed: Vec<String> = texts.iter().map(|t| format!("passage: {}", t)).collect();
-        self.embed(&prefixed)
+        self.embed_passages_counting_truncated(texts).map(|(embeddings, _)| embeddings)
     }
 
-    /// Generate embeddings using the loaded BERT model
+    /// [`embed_passages`](Self::embed_passages), also returning how many passages were longer
+    /// than [`max_tokens`](Self::max_tokens) and embedded from part of their text
+    pub fn embed_passages_counting_truncated(&self, texts: &[String]) -> Result<(Vec<Vec<f32>>, usize)> {
+        let prefixed: Vec<String> = texts
+            .iter()
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| format!("{}{}", self.passage_prefix, t))
+            .collect();
+        if prefixed.len() == texts.len() {
+            return self.embed_counting_truncated(&prefixed);
+        }
+
+        let (embedded, truncated) = if prefixed.is_empty() {
+            (Vec::new(), 0)
+        } else {
+            self.embed_counting_truncated(&prefixed)?
+        };
+        let dims = embedded.first().map(Vec::len).unwrap_or(0);
+        let mut embedded = embedded.into_iter();
+        let embeddings = texts
+            .iter()
+            .map(|t| {
+                if t.trim().is_empty() {
+                    vec![0.0; dims]
+                } else {
+                    embedded.next().unwrap_or_default()
+                }
+            })
+            .collect();
+        Ok((embeddings, truncated))
+    }
+
+    /// Embed passage texts in batches of `batch_size`, calling `progress(batches_done, total_batches)`
+    /// after each forward pass. Also returns how many passages were truncated.
+    pub fn embed_passages_batched(
+        &self,
+        texts: &[String],
+        batch_size: usize,
+        progress: Option<&mut dyn FnMut(usize, usize)>,
+    ) -> Result<(Vec<Vec<f32>>, usize)> {
+        let mut truncated = 0;
+        let embeddings = embed_in_batches(
+            texts,
+            batch_size,
+            |batch| {
+                let (embeddings, cut) = self.embed_passages_counting_truncated(batch)?;
+                truncated += cut;
+                Ok(embeddings)
+            },
+            progress,
+        )?;
+        Ok((embeddings, truncated))
+    }
+
+    /// Generate embeddings using the loaded BERT model, with the number of texts truncated
     fn embed_with_model(
         &self,
         model: &Arc<Mutex<BertModel>>,
-        tokenizer: &Arc<Mutex<Tokenizer>>,
+        tokenizer: &Tokenizer,
         texts: &[String],
-    ) -> Result<Vec<Vec<f32>>> {
-        // Lock model and tokenizer
-        let model_guard = model.lock()
-            .map_err(|e| Error::Model(format!("Failed to lock model: {}", e)))?;
-        let mut tokenizer_guard = tokenizer.lock()
-            .map_err(|e| Error::Model(format!("Failed to lock tokenizer: {}", e)))?;
-
-        // Configure tokenizer padding for batch processing
-        if let Some(pp) = tokenizer_guard.get_padding_mut() {
-            pp.strategy = tokenizers::PaddingStrategy::BatchLongest;
-        } else {
-            let pp = PaddingParams {
-                strategy: tokenizers::PaddingStrategy::BatchLongest,
-                ..Default::default()
-            };
-            tokenizer_guard.with_padding(Some(pp));
-        }
-
-        // Tokenize texts
-        let tokens = tokenizer_guard
+    ) -> Result<(Vec<Vec<f32>>, usize)> {
+        // Tokenize texts (padding and truncation were set at load, so no lock is needed)
+        let tokens = tokenizer
             .encode_batch(texts.to_vec(), true)
             .map_err(|e| Error::Tokenizer(format!("Tokenization failed: {}", e)))?;
+        let truncated = count_truncated(&tokens);
+        if truncated > 0 {
+            tracing::debug!(truncated, max_tokens = self.max_tokens, "Truncated texts longer than the token limit");
+        }
 
         // Convert token IDs to tensors
         let token_ids: Result<Vec<Tensor>> = tokens
@@ -246,6 +533,8 @@ impl EmbeddingModel {
         let token_type_ids = token_ids.zeros_like()?;
 
         // Forward pass through BERT model
+        let model_guard = model.lock()
+            .map_err(|e| Error::Model(format!("Failed to lock model: {}", e)))?;
         let embeddings = model_guard.forward(&token_ids, &token_type_ids)?;
 
         // CLS pooling (recommended for BGE-style retrieval models)
@@ -264,7 +553,7 @@ impl EmbeddingModel {
             result.push(embedding.to_vec1()?);
         }
 
-        Ok(result)
+        Ok((result, truncated))
     }
 
     /// L2 normalization
@@ -274,3 +563,172 @@ impl EmbeddingModel {
 
     // (Hash-based fallback removed intentionally)
 }
+
+/// Encodings the tokenizer cut to its truncation length (the cut tokens are kept as overflow)
+pub fn count_truncated(encodings: &[tokenizers::Encoding]) -> usize {
+    encodings.iter().filter(|encoding| !encoding.get_overflowing().is_empty()).count()
+}
+
+/// Run `embed_batch` over `texts` in chunks of `batch_size`, reporting `(batches_done, total_batches)`
+/// after each batch. Nothing is reported for empty input.
+pub fn embed_in_batches<F>(
+    texts: &[String],
+    batch_size: usize,
+    mut embed_batch: F,
+    mut progress: Option<&mut dyn FnMut(usize, usize)>,
+) -> Result<Vec<Vec<f32>>>
+where
+    F: FnMut(&[String]) -> Result<Vec<Vec<f32>>>,
+{
+    let batch_size = batch_size.max(1);
+    let total = texts.len().div_ceil(batch_size);
+    let mut embeddings = Vec::with_capacity(texts.len());
+
+    for (i, batch) in texts.chunks(batch_size).enumerate() {
+        embeddings.extend(embed_batch(batch)?);
+        if let Some(progress) = progress.as_mut() {
+            progress(i + 1, total);
+        }
+    }
+
+    Ok(embeddings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_embed(batch: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(batch.iter().map(|t| vec![t.len() as f32]).collect())
+    }
+
+    #[test]
+    fn test_batch_progress_fires_once_per_batch() {
+        let texts: Vec<String> = (0..500).map(|i| format!("chunk {}", i)).collect();
+        let mut calls = Vec::new();
+        let mut record = |done: usize, total: usize| calls.push((done, total));
+
+        let embeddings = embed_in_batches(&texts, 32, fake_embed, Some(&mut record)).unwrap();
+
+        assert_eq!(embeddings.len(), 500);
+        // 500 / 32 rounds up to 16 batches
+        assert_eq!(calls.len(), 16);
+        assert_eq!(calls.first(), Some(&(1, 16)));
+        assert_eq!(calls.last(), Some(&(16, 16)));
+    }
+
+    #[test]
+    fn test_batch_progress_exact_and_empty() {
+        let texts: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        let mut count = 0;
+        let mut record = |_: usize, total: usize| {
+            assert_eq!(total, 2);
+            count += 1;
+        };
+        embed_in_batches(&texts, 4, fake_embed, Some(&mut record)).unwrap();
+        assert_eq!(count, 2);
+
+        let mut count = 0;
+        let mut record = |_: usize, _: usize| count += 1;
+        let embeddings = embed_in_batches(&[], 4, fake_embed, Some(&mut record)).unwrap();
+        assert!(embeddings.is_empty());
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_warmup_without_model_is_a_clean_error() {
+        let unloaded = EmbeddingModel {
+            model: None,
+            tokenizer: None,
+            device: Device::Cpu,
+            query_prefix: DEFAULT_QUERY_PREFIX.to_string(),
+            passage_prefix: DEFAULT_PASSAGE_PREFIX.to_string(),
+            embedding_dim: 384,
+            max_tokens: 512,
+            checksums: OnceLock::new(),
+            model_path: PathBuf::new(),
+            tokenizer_path: PathBuf::new(),
+        };
+        assert!(!unloaded.is_model_loaded());
+        assert!(matches!(unloaded.warmup(), Err(Error::Model(_))));
+    }
+
+    /// Word-level tokenizer over `w0`..`w99` (anything else is `[UNK]`), split on whitespace
+    fn word_tokenizer() -> Tokenizer {
+        let mut vocab: std::collections::HashMap<String, u32> = (0..100).map(|i| (format!("w{}", i), i + 1)).collect();
+        vocab.insert("[UNK]".to_string(), 0);
+        let model = tokenizers::models::wordlevel::WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(tokenizers::pre_tokenizers::whitespace::Whitespace {});
+        tokenizer
+    }
+
+    #[test]
+    fn test_long_text_is_truncated_not_an_error() {
+        let long_chunk: String = (0..20_000).map(|i| format!("w{} ", i % 100)).collect();
+        let texts = vec![long_chunk.clone(), "w1 w2 w3".to_string()];
+
+        let mut tokenizer = word_tokenizer();
+        configure_tokenizer(&mut tokenizer, 64, Truncation::End).unwrap();
+        let encodings = tokenizer.encode_batch(texts, true).unwrap();
+        assert_eq!(encodings[0].get_ids().len(), 64);
+        assert_eq!(encodings[0].get_ids()[..3], [1, 2, 3]);
+        // Shorter texts are padded to the batch's longest
+        assert_eq!(encodings[1].get_ids()[..3], [2, 3, 4]);
+        assert_eq!(encodings[1].get_attention_mask().iter().sum::<u32>(), 3);
+        // Only the long text counts as truncated, padding notwithstanding
+        assert_eq!(count_truncated(&encodings), 1);
+
+        // Keeping the end: the text's last word survives
+        configure_tokenizer(&mut tokenizer, 64, Truncation::Start).unwrap();
+        let encoding = tokenizer.encode(long_chunk, true).unwrap();
+        assert_eq!(encoding.get_ids().len(), 64);
+        assert_eq!(encoding.get_ids().last(), Some(&100));
+    }
+
+    #[test]
+    fn test_configured_tokenizer_encodes_on_two_threads_at_once() {
+        let mut tokenizer = word_tokenizer();
+        configure_tokenizer(&mut tokenizer, 32, Truncation::End).unwrap();
+        let tokenizer = Arc::new(tokenizer);
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+
+        let handles: Vec<_> = (0..2)
+            .map(|thread| {
+                let tokenizer = Arc::clone(&tokenizer);
+                let barrier = Arc::clone(&barrier);
+                std::thread::spawn(move || {
+                    // Both threads are inside the shared tokenizer at the same time
+                    barrier.wait();
+                    let texts: Vec<String> = (0..50).map(|i| format!("w{} w{} w{}", thread, i, i % 7)).collect();
+                    tokenizer.encode_batch(texts, true).unwrap()
+                })
+            })
+            .collect();
+        for (thread, handle) in handles.into_iter().enumerate() {
+            let encodings = handle.join().unwrap();
+            assert_eq!(encodings.len(), 50);
+            assert_eq!(encodings[0].get_ids()[0], thread as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_token_limit_never_exceeds_the_model() {
+        assert_eq!(token_limit(None, 512), 512);
+        assert_eq!(token_limit(Some(256), 512), 256);
+        assert_eq!(token_limit(Some(4096), 512), 512);
+        assert_eq!(Truncation::parse(" Start "), Some(Truncation::Start));
+        assert_eq!(Truncation::parse("middle"), None);
+    }
+
+    #[test]
+    fn test_batches_preserve_order() {
+        let texts: Vec<String> = vec!["a".into(), "bb".into(), "ccc".into()];
+        let embeddings = embed_in_batches(&texts, 2, fake_embed, None).unwrap();
+        assert_eq!(embeddings, vec![vec![1.0], vec![2.0], vec![3.0]]);
+    }
+}
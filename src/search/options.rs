@@ -0,0 +1,214 @@
+/// Smallest candidate pool fetched before filtering, boosting and per-file grouping
+pub const MIN_CANDIDATES: usize = 200;
+/// Candidates fetched per requested result
+pub const CANDIDATES_PER_RESULT: usize = 20;
+/// Files shortlisted by two-stage search unless `two_stage_files` says otherwise
+pub const DEFAULT_TWO_STAGE_FILES: usize = 100;
+/// Best chunks blended into the query by `--expand` unless `expand_chunks` says otherwise
+pub const DEFAULT_EXPAND_CHUNKS: usize = 3;
+/// Results the TUI shows unless `tui_results` says otherwise
+pub const DEFAULT_TUI_RESULTS: usize = 20;
+/// Chunks of one file the TUI shows unless `tui_results_per_file` says otherwise
+pub const DEFAULT_TUI_RESULTS_PER_FILE: usize = 5;
+
+use crate::storage::vectors::VectorEntry;
+
+/// Options for a single search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Number of results wanted after post-processing
+    pub limit: usize,
+    /// Fixed number of nearest chunks to fetch before post-processing.
+    /// None sizes the pool from the store (see [`adaptive_candidate_limit`]).
+    pub candidates: Option<usize>,
+    /// Keep a per-result [`ScoreBreakdown`](crate::search::explain::ScoreBreakdown)
+    pub explain: bool,
+    /// Only chunks of files modified at or after this Unix time (`--changed-since`)
+    pub changed_since: Option<u64>,
+    /// Only chunks of notes dated at or after this Unix time (`--after`)
+    pub after: Option<u64>,
+    /// Only chunks of notes dated before this Unix time (`--before`)
+    pub before: Option<u64>,
+    /// Rank files by their document vectors first and score only the chunks of this many
+    /// (`--two-stage`); None scores every chunk
+    pub two_stage: Option<usize>,
+    /// Expand the query with this many of its best chunks before searching (`--expand`,
+    /// see [`expanded_query`](crate::search::expand::expanded_query)); None searches as typed
+    pub expand: Option<usize>,
+    /// Keep at most this many chunks of each file (TUI search); None keeps them all
+    pub per_file: Option<usize>,
+    /// Coalesce results on consecutive chunks of one file after ranking (TUI search)
+    pub merge_adjacent: bool,
+}
+
+impl SearchOptions {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            candidates: None,
+            explain: false,
+            changed_since: None,
+            after: None,
+            before: None,
+            two_stage: None,
+            expand: None,
+            per_file: None,
+            merge_adjacent: false,
+        }
+    }
+
+    /// Override the adaptive candidate pool (e.g. from `--candidates`)
+    pub fn with_candidates(mut self, candidates: Option<usize>) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Return score breakdowns alongside results (e.g. from `--explain`)
+    pub fn with_explain(mut self, explain: bool) -> Self {
+        self.explain = explain;
+        self
+    }
+
+    /// Drop results from files last modified before `changed_since` (a Unix time)
+    pub fn with_changed_since(mut self, changed_since: Option<u64>) -> Self {
+        self.changed_since = changed_since;
+        self
+    }
+
+    /// Keep notes dated within `[after, before)` (Unix times); see [`VectorEntry::dated`]
+    pub fn with_date_range(mut self, after: Option<u64>, before: Option<u64>) -> Self {
+        self.after = after;
+        self.before = before;
+        self
+    }
+
+    /// Narrow the search to the best-matching files first (see
+    /// [`VectorStore::search_two_stage`](crate::storage::vectors::VectorStore::search_two_stage))
+    pub fn with_two_stage(mut self, files: Option<usize>) -> Self {
+        self.two_stage = files;
+        self
+    }
+
+    /// Blend the best `chunks` matches into the query embedding before the real search
+    pub fn with_expand(mut self, chunks: Option<usize>) -> Self {
+        self.expand = chunks;
+        self
+    }
+
+    /// Cap the chunks kept from any one file
+    pub fn with_per_file(mut self, chunks: Option<usize>) -> Self {
+        self.per_file = chunks;
+        self
+    }
+
+    /// Merge results on adjacent chunks of a file into one
+    pub fn with_merge_adjacent(mut self, merge: bool) -> Self {
+        self.merge_adjacent = merge;
+        self
+    }
+
+    /// Whether `entry` passes the `changed_since` window and the date range. Entries indexed
+    /// before modification times were recorded have no known age, so they only pass without one.
+    pub fn admits(&self, entry: &VectorEntry) -> bool {
+        let changed = match self.changed_since {
+            Some(cutoff) => entry.modified.is_some_and(|modified| modified >= cutoff),
+            None => true,
+        };
+        let dated = match (self.after, self.before) {
+            (None, None) => true,
+            (after, before) => entry.dated().is_some_and(|date| {
+                after.is_none_or(|after| date >= after) && before.is_none_or(|before| date < before)
+            }),
+        };
+        changed && dated
+    }
+
+    /// Candidate pool size for a store holding `store_size` chunks; an explicit override wins
+    pub fn candidate_limit(&self, store_size: usize) -> usize {
+        self.candidates
+            .unwrap_or_else(|| adaptive_candidate_limit(store_size, self.limit))
+    }
+}
+
+/// Candidate pool size: `min(store_size, max(requested_limit * 20, 200))`.
+///
+/// Lexical boosting, filters and per-file grouping all run on the candidates, so the pool
+/// has to be well above the requested limit for them to change the ranking. It grows with
+/// the requested limit and never exceeds the store (fetching more than every chunk is pointless).
+pub fn adaptive_candidate_limit(store_size: usize, requested_limit: usize) -> usize {
+    store_size.min(
+        requested_limit
+            .saturating_mul(CANDIDATES_PER_RESULT)
+            .max(MIN_CANDIDATES),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_since_window() {
+        let entry = |modified| {
+            VectorEntry::new("a.md".to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1).with_modified(modified)
+        };
+        let all = SearchOptions::new(10);
+        assert!(all.admits(&entry(None)));
+
+        let recent = all.with_changed_since(Some(1_000));
+        assert!(recent.admits(&entry(Some(1_000))));
+        assert!(recent.admits(&entry(Some(5_000))));
+        assert!(!recent.admits(&entry(Some(999))));
+        assert!(!recent.admits(&entry(None)));
+    }
+
+    #[test]
+    fn test_date_range() {
+        let entry = |modified, note_date| {
+            VectorEntry::new("a.md".to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1)
+                .with_modified(modified)
+                .with_note_date(note_date)
+        };
+        let range = SearchOptions::new(10).with_date_range(Some(1_000), Some(2_000));
+        assert!(range.admits(&entry(Some(1_000), None)));
+        assert!(range.admits(&entry(Some(1_999), None)));
+        assert!(!range.admits(&entry(Some(2_000), None)));
+        assert!(!range.admits(&entry(Some(999), None)));
+        assert!(!range.admits(&entry(None, None)));
+
+        // The frontmatter date wins over the file's modification time
+        assert!(range.admits(&entry(Some(5_000), Some(1_500))));
+        assert!(!range.admits(&entry(Some(1_500), Some(5_000))));
+
+        let after = SearchOptions::new(10).with_date_range(Some(1_000), None);
+        assert!(after.admits(&entry(Some(u64::MAX), None)));
+        let before = SearchOptions::new(10).with_date_range(None, Some(1_000));
+        assert!(before.admits(&entry(Some(0), None)));
+        assert!(!before.admits(&entry(Some(1_000), None)));
+    }
+
+    #[test]
+    fn test_small_store_caps_candidates() {
+        assert_eq!(adaptive_candidate_limit(150, 10), 150);
+        assert_eq!(adaptive_candidate_limit(0, 10), 0);
+    }
+
+    #[test]
+    fn test_floor_applies_to_small_limits() {
+        assert_eq!(adaptive_candidate_limit(10_000, 1), MIN_CANDIDATES);
+        assert_eq!(adaptive_candidate_limit(10_000, 10), MIN_CANDIDATES);
+    }
+
+    #[test]
+    fn test_large_store_scales_with_limit() {
+        assert_eq!(adaptive_candidate_limit(500_000, 50), 1000);
+        assert_eq!(adaptive_candidate_limit(500_000, usize::MAX), 500_000);
+    }
+
+    #[test]
+    fn test_override_wins() {
+        let options = SearchOptions::new(10).with_candidates(Some(5000));
+        assert_eq!(options.candidate_limit(100), 5000);
+        assert_eq!(SearchOptions::new(10).candidate_limit(100), 100);
+    }
+}
@@ -0,0 +1,273 @@
+//! Opt-in at-rest encryption for database values.
+//!
+//! Keys (chunk ids, file paths) stay in plaintext so lookups and prefix scans keep
+//! working; values (chunk text, embeddings, file state) are sealed with
+//! XChaCha20-Poly1305 under a key derived from a passphrase with Argon2id.
+//! The passphrase is read from `NOTES2VEC_PASSPHRASE`.
+//!
+//! Each database keeps its salt and a verification record in a `crypto_meta` table,
+//! so a wrong passphrase fails when the store is opened rather than on the first read.
+//!
+//! Cost: search scans every row, so every row is decrypted per query. A sealed value is
+//! the prefix plus the hex-encoded nonce, ciphertext and tag, `2 * len + 85` bytes, so
+//! stored values roughly double (see `test_sealed_size`). In a release build, opening a
+//! ~5 KB row (a 384-dimension embedding plus its chunk text) measured about 55 µs, most of
+//! it hex decoding, which puts searches over 100k chunks roughly 5 s behind an unencrypted
+//! index. Key derivation measured a one-off ~70 ms per opened store.
+
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use redb::{Database, ReadableTable, TableDefinition, TableError};
+use std::borrow::Cow;
+
+/// Environment variable holding the passphrase
+pub const PASSPHRASE_ENV: &str = "NOTES2VEC_PASSPHRASE";
+
+/// Salt and verification record for an encrypted database
+const CRYPTO_META_TABLE: TableDefinition<&str, &str> = TableDefinition::new("crypto_meta");
+const SALT_KEY: &str = "salt";
+const VERIFIER_KEY: &str = "verifier";
+const VERIFIER_PLAINTEXT: &str = "notes2vec-verify";
+
+/// Marks a sealed value (and its format version)
+const SEALED_PREFIX: &str = "enc1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Value cipher for one database
+pub struct Cipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// Derive the key from a passphrase and salt
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| Error::Encryption(format!("Key derivation failed: {}", e)))?;
+        Ok(Self {
+            cipher: XChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    /// Encrypt a value for storage
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| Error::Encryption("Failed to encrypt value".to_string()))?;
+
+        let mut sealed = String::with_capacity(SEALED_PREFIX.len() + (NONCE_LEN + ciphertext.len()) * 2);
+        sealed.push_str(SEALED_PREFIX);
+        sealed.push_str(&to_hex(&nonce));
+        sealed.push_str(&to_hex(&ciphertext));
+        Ok(sealed)
+    }
+
+    /// Decrypt a stored value
+    pub fn open(&self, stored: &str) -> Result<String> {
+        let hex = stored.strip_prefix(SEALED_PREFIX).ok_or_else(|| {
+            Error::Encryption("Found an unencrypted value in an encrypted database".to_string())
+        })?;
+        let bytes = from_hex(hex)
+            .filter(|b| b.len() > NONCE_LEN)
+            .ok_or_else(|| Error::Encryption("Malformed encrypted value".to_string()))?;
+
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| Error::Encryption("Failed to decrypt value (wrong passphrase?)".to_string()))?;
+        String::from_utf8(plaintext)
+            .map_err(|_| Error::Encryption("Decrypted value is not valid UTF-8".to_string()))
+    }
+}
+
+/// Seal a value if the store is encrypted
+pub fn encode_value<'a>(cipher: Option<&Cipher>, plaintext: &'a str) -> Result<Cow<'a, str>> {
+    match cipher {
+        Some(cipher) => cipher.seal(plaintext).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(plaintext)),
+    }
+}
+
+/// Open a stored value if the store is encrypted
+pub fn decode_value<'a>(cipher: Option<&Cipher>, stored: &'a str) -> Result<Cow<'a, str>> {
+    match cipher {
+        Some(cipher) => cipher.open(stored).map(Cow::Owned),
+        None if stored.starts_with(SEALED_PREFIX) => Err(Error::Encryption(format!(
+            "Database is encrypted; set {} to open it",
+            PASSPHRASE_ENV
+        ))),
+        None => Ok(Cow::Borrowed(stored)),
+    }
+}
+
+/// Set up or unlock encryption for a database.
+///
+/// - An encrypted database always needs the passphrase, whatever the config says.
+/// - A new, empty database is encrypted when `config.encrypt` is set.
+/// - Existing plaintext data is never encrypted in place; rebuild the index instead.
+pub fn open_cipher(db: &Database, config: &Config, has_data: bool) -> Result<Option<Cipher>> {
+    let (salt, verifier) = read_meta(db)?;
+
+    if let Some(salt) = salt {
+        let salt = from_hex(&salt)
+            .ok_or_else(|| Error::Encryption("Malformed salt in encryption metadata".to_string()))?;
+        let cipher = Cipher::derive(&passphrase()?, &salt)?;
+        let verified = verifier
+            .and_then(|v| cipher.open(&v).ok())
+            .map(|v| v == VERIFIER_PLAINTEXT)
+            .unwrap_or(false);
+        if !verified {
+            return Err(Error::Encryption(format!(
+                "Wrong passphrase in {} for {}",
+                PASSPHRASE_ENV,
+                config.base_dir.display()
+            )));
+        }
+        return Ok(Some(cipher));
+    }
+
+    if !config.encrypt {
+        return Ok(None);
+    }
+
+    if has_data {
+        return Err(Error::Encryption(
+            "Cannot encrypt an existing unencrypted index. Delete the database directory and re-run 'notes2vec init --encrypt'.".to_string(),
+        ));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = Cipher::derive(&passphrase()?, &salt)?;
+    write_meta(db, &to_hex(&salt), &cipher.seal(VERIFIER_PLAINTEXT)?)?;
+    Ok(Some(cipher))
+}
+
+/// Carry the salt and verifier over to a rebuilt copy of the database (see `VectorStore::vacuum`)
+pub fn copy_meta(src: &Database, dst: &Database) -> Result<()> {
+    if let (Some(salt), Some(verifier)) = read_meta(src)? {
+        write_meta(dst, &salt, &verifier)?;
+    }
+    Ok(())
+}
+
+fn passphrase() -> Result<String> {
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(p) if !p.is_empty() => Ok(p),
+        _ => Err(Error::Encryption(format!(
+            "Encrypted index needs a passphrase; set {}",
+            PASSPHRASE_ENV
+        ))),
+    }
+}
+
+/// Read the salt and verifier in a read transaction, so read-only stores can check them.
+/// A database that never had the table (unencrypted) has neither.
+fn read_meta(db: &Database) -> Result<(Option<String>, Option<String>)> {
+    let read_txn = db.begin_read().map_err(|e| {
+        Error::Database(format!("Failed to begin read transaction: {}", e))
+    })?;
+    let table = match read_txn.open_table(CRYPTO_META_TABLE) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok((None, None)),
+        Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+    };
+    let salt = table.get(SALT_KEY).map_err(|e| {
+        Error::Database(format!("Failed to read encryption metadata: {}", e))
+    })?.map(|g| g.value().to_string());
+    let verifier = table.get(VERIFIER_KEY).map_err(|e| {
+        Error::Database(format!("Failed to read encryption metadata: {}", e))
+    })?.map(|g| g.value().to_string());
+    Ok((salt, verifier))
+}
+
+fn write_meta(db: &Database, salt: &str, verifier: &str) -> Result<()> {
+    let write_txn = db.begin_write().map_err(|e| {
+        Error::Database(format!("Failed to begin write transaction: {}", e))
+    })?;
+    {
+        let mut table = write_txn.open_table(CRYPTO_META_TABLE).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+        table.insert(SALT_KEY, salt).map_err(|e| {
+            Error::Database(format!("Failed to store encryption metadata: {}", e))
+        })?;
+        table.insert(VERIFIER_KEY, verifier).map_err(|e| {
+            Error::Database(format!("Failed to store encryption metadata: {}", e))
+        })?;
+    }
+    write_txn.commit().map_err(|e| {
+        Error::Database(format!("Failed to commit transaction: {}", e))
+    })?;
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open_roundtrip() {
+        let cipher = Cipher::derive("correct horse", b"0123456789abcdef").unwrap();
+        let sealed = cipher.seal("secret note text").unwrap();
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(cipher.open(&sealed).unwrap(), "secret note text");
+    }
+
+    #[test]
+    fn test_sealed_size() {
+        let cipher = Cipher::derive("correct horse", b"0123456789abcdef").unwrap();
+        for len in [0, 1, 5000] {
+            let sealed = cipher.seal(&"x".repeat(len)).unwrap();
+            // 24-byte nonce and 16-byte tag around the ciphertext, all hex-encoded
+            assert_eq!(sealed.len(), SEALED_PREFIX.len() + 2 * (NONCE_LEN + len + 16));
+            assert_eq!(sealed.len(), 2 * len + 85);
+        }
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let cipher = Cipher::derive("correct horse", b"0123456789abcdef").unwrap();
+        let other = Cipher::derive("battery staple", b"0123456789abcdef").unwrap();
+        let sealed = cipher.seal("secret").unwrap();
+        assert!(other.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_decode_without_cipher() {
+        assert_eq!(decode_value(None, "{\"a\":1}").unwrap(), "{\"a\":1}");
+        assert!(decode_value(None, "enc1:00").is_err());
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let bytes = vec![0u8, 1, 127, 255];
+        assert_eq!(from_hex(&to_hex(&bytes)), Some(bytes));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+}
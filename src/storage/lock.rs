@@ -0,0 +1,140 @@
+//! Advisory lock held while a process has an index open for writing.
+//!
+//! redb refuses a second writer with a bare "database already open" error. The lock file next
+//! to the database records who holds it, so the second process can say which one to wait for.
+//! The OS lock on the file is what counts: it goes away with the process, so a file left
+//! behind by a crash is simply taken over.
+
+use crate::core::error::{Error, Result};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the lock file in the database directory
+pub const LOCK_FILE_NAME: &str = "index.lock";
+
+/// Who, if anyone, holds the lock on an index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockState {
+    /// No lock file
+    Free,
+    /// A lock file nobody holds, left behind by a process that didn't exit cleanly
+    Stale,
+    /// Held by a running process (its PID, if it could be read)
+    Held(Option<u32>),
+}
+
+/// Exclusive hold on an index; released (and the file removed) when dropped
+#[derive(Debug)]
+pub struct IndexLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl IndexLock {
+    /// Lock the index in `database_dir`, or explain which process holds it
+    pub fn acquire(database_dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(database_dir)?;
+        let path = database_dir.join(LOCK_FILE_NAME);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(held_by(holder_pid(&mut file))),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(Self { file, path })
+    }
+
+    /// Lock file location
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check the lock on the index in `database_dir` without taking it or writing anything
+    pub fn state(database_dir: &Path) -> Result<LockState> {
+        let mut file = match File::open(database_dir.join(LOCK_FILE_NAME)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(LockState::Free),
+            Err(e) => return Err(e.into()),
+        };
+        match file.try_lock_shared() {
+            Ok(()) => Ok(LockState::Stale),
+            Err(TryLockError::WouldBlock) => Ok(LockState::Held(holder_pid(&mut file))),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+}
+
+impl Drop for IndexLock {
+    fn drop(&mut self) {
+        // Removed while still locked, so nobody sees it half gone
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::debug!(path = %self.path.display(), error = %e, "Could not remove index lock");
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+/// PID written by the process holding the lock (None if it can't be read)
+fn holder_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+fn held_by(pid: Option<u32>) -> Error {
+    let holder = match pid {
+        Some(pid) => format!("Another notes2vec is indexing (pid {})", pid),
+        None => "Another notes2vec is indexing".to_string(),
+    };
+    Error::Database(format!("{}. Wait for it to finish, or stop it, and try again.", holder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_lock_names_the_holder() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock = IndexLock::acquire(temp_dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(lock.path()).unwrap(), std::process::id().to_string());
+
+        let message = IndexLock::acquire(temp_dir.path()).unwrap_err().to_string();
+        assert!(
+            message.contains(&format!("Another notes2vec is indexing (pid {})", std::process::id())),
+            "{}",
+            message
+        );
+
+        assert_eq!(IndexLock::state(temp_dir.path()).unwrap(), LockState::Held(Some(std::process::id())));
+
+        // Released and cleaned up on drop
+        let path = lock.path().to_path_buf();
+        drop(lock);
+        assert!(!path.exists());
+        assert_eq!(IndexLock::state(temp_dir.path()).unwrap(), LockState::Free);
+        assert!(IndexLock::acquire(temp_dir.path()).is_ok());
+    }
+
+    #[test]
+    fn test_left_over_lock_file_is_taken_over() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(LOCK_FILE_NAME), "999999").unwrap();
+        assert_eq!(IndexLock::state(temp_dir.path()).unwrap(), LockState::Stale);
+        let lock = IndexLock::acquire(temp_dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(lock.path()).unwrap(), std::process::id().to_string());
+    }
+}
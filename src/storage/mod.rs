@@ -1,3 +1,6 @@
+pub mod crypto;
+pub mod lock;
+pub mod readonly;
 pub mod state;
 pub mod vectors;
 
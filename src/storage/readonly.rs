@@ -0,0 +1,209 @@
+//! Read-only access to redb files.
+//!
+//! redb 1.x has no read-only open mode: opening a database always rewrites its header to
+//! mark it in use, which fails on read-only mounts or files owned by another user.
+//! `ReadOnlyBackend` opens the file read-only and keeps anything redb writes in memory,
+//! so read transactions see a consistent view while the file itself is never modified.
+
+use crate::core::error::{Error, Result};
+use redb::{Builder, Database, StorageBackend};
+use std::fs::{File, TryLockError};
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+
+/// Storage backend over a file opened read-only; writes are kept in memory and dropped on close
+#[derive(Debug)]
+pub struct ReadOnlyBackend {
+    file: File,
+    overlay: Mutex<Overlay>,
+}
+
+/// In-memory writes layered over the file, applied in order on every read
+#[derive(Debug, Default)]
+struct Overlay {
+    /// Length after `set_len`, if redb resized the storage
+    len: Option<u64>,
+    writes: Vec<(u64, Vec<u8>)>,
+}
+
+impl ReadOnlyBackend {
+    /// Open `path` without write access.
+    /// Takes a shared lock, so it waits for no one but fails while an indexer holds the database.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        match file.try_lock_shared() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(Error::Database(format!(
+                    "Database {} is locked. Another notes2vec process may be running. Close other instances and try again.",
+                    path.display()
+                )))
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        Ok(Self {
+            file,
+            overlay: Mutex::new(Overlay::default()),
+        })
+    }
+
+    fn file_len(&self) -> io::Result<u64> {
+        Ok(self.file.metadata()?.len())
+    }
+
+    fn read_file(&self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            self.file.read_exact_at(buffer, offset)
+        }
+        #[cfg(windows)]
+        {
+            let mut done = 0;
+            while done < buffer.len() {
+                let n = self.file.seek_read(&mut buffer[done..], offset + done as u64)?;
+                if n == 0 {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                }
+                done += n;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl StorageBackend for ReadOnlyBackend {
+    fn len(&self) -> io::Result<u64> {
+        let overlay = self.overlay.lock().unwrap();
+        match overlay.len {
+            Some(len) => Ok(len),
+            None => self.file_len(),
+        }
+    }
+
+    fn read(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let overlay = self.overlay.lock().unwrap();
+        let file_len = self.file_len()?;
+        let storage_len = overlay.len.unwrap_or(file_len);
+        let end = offset + len as u64;
+        if end > storage_len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+
+        // Bytes past the end of the file exist only because of an in-memory resize: zeroes
+        let mut buffer = vec![0; len];
+        let from_file = file_len.saturating_sub(offset).min(len as u64) as usize;
+        if from_file > 0 {
+            self.read_file(offset, &mut buffer[..from_file])?;
+        }
+
+        for (write_offset, data) in &overlay.writes {
+            let write_end = write_offset + data.len() as u64;
+            if write_end <= offset || *write_offset >= end {
+                continue;
+            }
+            let start = offset.max(*write_offset);
+            let stop = end.min(write_end);
+            buffer[(start - offset) as usize..(stop - offset) as usize]
+                .copy_from_slice(&data[(start - write_offset) as usize..(stop - write_offset) as usize]);
+        }
+        Ok(buffer)
+    }
+
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        let mut overlay = self.overlay.lock().unwrap();
+        overlay.len = Some(len);
+        // Forget writes past the new end, as truncating a file would
+        for (offset, data) in overlay.writes.iter_mut() {
+            if *offset >= len {
+                data.clear();
+            } else if *offset + data.len() as u64 > len {
+                data.truncate((len - *offset) as usize);
+            }
+        }
+        overlay.writes.retain(|(_, data)| !data.is_empty());
+        Ok(())
+    }
+
+    fn sync_data(&self, _eventual: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> io::Result<()> {
+        let mut overlay = self.overlay.lock().unwrap();
+        overlay.writes.push((offset, data.to_vec()));
+        Ok(())
+    }
+}
+
+/// Open an existing redb file for reading only; nothing is ever written to `path`
+pub fn open_read_only(path: &Path) -> Result<Database> {
+    Builder::new()
+        .create_with_backend(ReadOnlyBackend::open(path)?)
+        .map_err(|e| Error::Database(format!("Failed to open {} read-only: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redb::{ReadableTable, TableDefinition};
+    use tempfile::TempDir;
+
+    const TABLE: TableDefinition<&str, &str> = TableDefinition::new("test");
+
+    #[test]
+    fn test_overlay_reads_back_writes_without_touching_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("data.bin");
+        std::fs::write(&path, [1u8; 8]).unwrap();
+
+        let backend = ReadOnlyBackend::open(&path).unwrap();
+        backend.write(2, &[9, 9]).unwrap();
+        backend.set_len(10).unwrap();
+        assert_eq!(backend.len().unwrap(), 10);
+        assert_eq!(backend.read(0, 10).unwrap(), vec![1, 1, 9, 9, 1, 1, 1, 1, 0, 0]);
+        assert!(backend.read(6, 8).is_err());
+
+        drop(backend);
+        assert_eq!(std::fs::read(&path).unwrap(), vec![1u8; 8]);
+    }
+
+    #[test]
+    fn test_open_read_only_leaves_database_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.redb");
+        {
+            let db = Database::create(&path).unwrap();
+            let txn = db.begin_write().unwrap();
+            txn.open_table(TABLE).unwrap().insert("key", "value").unwrap();
+            txn.commit().unwrap();
+        }
+        let before = std::fs::read(&path).unwrap();
+
+        {
+            let db = open_read_only(&path).unwrap();
+            let txn = db.begin_read().unwrap();
+            let table = txn.open_table(TABLE).unwrap();
+            assert_eq!(table.get("key").unwrap().unwrap().value(), "value");
+        }
+
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn test_locked_database_is_named() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.redb");
+        // Held open for writing, as an indexer would
+        let _db = Database::create(&path).unwrap();
+
+        let message = open_read_only(&path).unwrap_err().to_string();
+        assert!(message.contains("is locked"), "{}", message);
+        assert!(message.contains(&path.display().to_string()), "{}", message);
+    }
+}
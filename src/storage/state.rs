@@ -1,8 +1,12 @@
 use crate::core::config::Config;
 use crate::core::error::{Error, Result};
-use redb::{Database, ReadableTable, TableDefinition};
+use crate::search::checksums::ModelChecksums;
+use crate::search::model::{embedding_scheme, EmbeddingModel, DEFAULT_PASSAGE_PREFIX, DEFAULT_QUERY_PREFIX};
+use crate::storage::crypto::{decode_value, encode_value, open_cipher, Cipher};
+use crate::storage::readonly;
+use redb::{Database, ReadableTable, TableDefinition, TableError};
 use sha2::{Digest, Sha256};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 /// Table definition for file state tracking
@@ -12,6 +16,24 @@ const FILE_STATE_TABLE: TableDefinition<&str, &str> = TableDefinition::new("file
 // Stored in FILE_STATE_TABLE as a JSON string; used to detect model changes and force re-index.
 const META_MODEL_ID_KEY: &str = "__notes2vec_meta_model_id__";
 
+// Query/passage prefixes and pooling the stored vectors were made with (see `embedding_scheme`).
+const META_EMBEDDING_SCHEME_KEY: &str = "__notes2vec_meta_embedding_scheme__";
+
+// Length of the stored embeddings, so a model with another output size is caught on open.
+const META_EMBEDDING_DIM_KEY: &str = "__notes2vec_meta_embedding_dim__";
+
+// Similarity metric searches were meant to use on the stored vectors (`similarity_metric`).
+const META_SIMILARITY_METRIC_KEY: &str = "__notes2vec_meta_similarity_metric__";
+
+// Checksums of the model files the stored vectors were made with, as JSON (see `search::checksums`).
+const META_MODEL_CHECKSUMS_KEY: &str = "__notes2vec_meta_model_checksums__";
+
+// Directory the tracked paths are relative to, recorded by the last full index.
+const META_NOTES_ROOT_KEY: &str = "__notes2vec_meta_notes_root__";
+
+// Prefix shared by all metadata keys, which are not file states.
+const META_KEY_PREFIX: &str = "__notes2vec_meta_";
+
 /// State information for a file
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct FileState {
@@ -21,6 +43,42 @@ pub struct FileState {
     pub content_hash: String,
     /// Timestamp when file was last indexed
     pub indexed_at: u64,
+    /// File size in bytes (missing in states written before sizes were tracked)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Absolute notes directory the file was indexed from (missing in states written before
+    /// roots were tracked). Two folders can hold the same relative path; see
+    /// [`StateStore::files_from_other_roots`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
+}
+
+/// Modification time and size of a file on disk: what the fast path compares before hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSnapshot {
+    pub modified: u64,
+    pub size: u64,
+}
+
+impl FileSnapshot {
+    pub fn read(path: &Path) -> Result<Self> {
+        Ok(Self {
+            modified: get_file_modified_time(path)?,
+            size: std::fs::metadata(path)?.len(),
+        })
+    }
+}
+
+/// Outcome of [`StateStore::check_file`]
+#[derive(Debug, Clone)]
+pub enum FileCheck {
+    /// Matches what's indexed
+    Unchanged,
+    /// Same content, but the modification time or size on record is out of date (touched,
+    /// checked out again, restored). Store this state; there's nothing to reindex.
+    Touched(FileState),
+    /// Content differs from what's indexed, or the file was never indexed
+    Changed,
 }
 
 impl FileState {
@@ -33,9 +91,29 @@ impl FileState {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            size: None,
+            root: None,
         }
     }
 
+    /// Record the file size, so a resized file is never skipped on its timestamp alone
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Record the notes directory the file was indexed from
+    pub fn with_root(mut self, root: &Path) -> Self {
+        self.root = Some(root.to_path_buf());
+        self
+    }
+
+    /// Current state of a file on disk, stamped as indexed now
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let snapshot = FileSnapshot::read(path)?;
+        Ok(Self::new(snapshot.modified, calculate_file_hash(path)?).with_size(snapshot.size))
+    }
+
     /// Serialize to JSON string
     fn to_json(&self) -> Result<String> {
         serde_json::to_string(self)
@@ -52,6 +130,8 @@ impl FileState {
 /// State store for tracking file changes
 pub struct StateStore {
     db: Database,
+    /// Set when values are encrypted at rest
+    cipher: Option<Cipher>,
 }
 
 impl StateStore {
@@ -83,16 +163,46 @@ impl StateStore {
         let write_txn = db.begin_write().map_err(|e| {
             Error::Database(format!("Failed to begin write transaction: {}", e))
         })?;
-        {
-            let _table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+        let has_data = {
+            let table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
                 Error::Database(format!("Failed to open table: {}", e))
             })?;
-        }
+            // Metadata keys hold plaintext and don't count as indexed data
+            let mut has_data = false;
+            for item in table.iter().map_err(|e| {
+                Error::Database(format!("Failed to iterate table: {}", e))
+            })? {
+                let (key, _value) = item.map_err(|e| {
+                    Error::Database(format!("Failed to read table item: {}", e))
+                })?;
+                if !key.value().starts_with(META_KEY_PREFIX) {
+                    has_data = true;
+                    break;
+                }
+            }
+            has_data
+        };
         write_txn.commit().map_err(|e| {
             Error::Database(format!("Failed to commit transaction: {}", e))
         })?;
 
-        Ok(Self { db })
+        let cipher = open_cipher(&db, config, has_data)?;
+
+        Ok(Self { db, cipher })
+    }
+
+    /// Open an existing state store without writing anything, like
+    /// [`VectorStore::open_read_only`](crate::storage::vectors::VectorStore::open_read_only)
+    pub fn open_read_only(config: &Config) -> Result<Self> {
+        if !config.state_path.exists() {
+            return Err(Error::Database(
+                "No state database found. Run 'notes2vec index' first.".to_string(),
+            ));
+        }
+        let db = readonly::open_read_only(&config.state_path)?;
+        // Never set up encryption here: that would mean writing the salt
+        let cipher = open_cipher(&db, config, true)?;
+        Ok(Self { db, cipher })
     }
 
     /// Get the state of a file
@@ -110,7 +220,7 @@ impl StateStore {
         })? {
             Some(guard) => {
                 // Extract the value string before dropping the guard
-                let json_str = guard.value().to_string();
+                let json_str = decode_value(self.cipher.as_ref(), guard.value())?.into_owned();
                 FileState::from_json(&json_str).map(Some)
             }
             None => Ok(None),
@@ -126,6 +236,11 @@ impl StateStore {
         last_modified: u64,
         content_hash: String,
     ) -> Result<()> {
+        self.store_file_state(file_path, &FileState::new(last_modified, content_hash))
+    }
+
+    /// Store a complete file state (see [`FileState::from_file`])
+    pub fn store_file_state(&self, file_path: &str, state: &FileState) -> Result<()> {
         let write_txn = self.db.begin_write().map_err(|e| {
             Error::Database(format!("Failed to begin write transaction: {}", e))
         })?;
@@ -135,9 +250,9 @@ impl StateStore {
                 Error::Database(format!("Failed to open table: {}", e))
             })?;
 
-            let state = FileState::new(last_modified, content_hash);
             let json_str = state.to_json()?;
-            table.insert(file_path, json_str.as_str()).map_err(|e| {
+            let stored = encode_value(self.cipher.as_ref(), &json_str)?;
+            table.insert(file_path, stored.as_ref()).map_err(|e| {
                 Error::Database(format!("Failed to insert file state: {}", e))
             })?;
         }
@@ -172,6 +287,31 @@ impl StateStore {
         Ok(())
     }
 
+    /// Remove several files from the state store in one transaction
+    pub fn remove_files(&self, file_paths: &[String]) -> Result<()> {
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+
+        {
+            let mut table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+
+            for file_path in file_paths {
+                table.remove(file_path.as_str()).map_err(|e| {
+                    Error::Database(format!("Failed to remove file state: {}", e))
+                })?;
+            }
+        }
+
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+
     /// Check if a file has changed since last indexing
     pub fn has_file_changed(
         &self,
@@ -191,6 +331,109 @@ impl StateStore {
         }
     }
 
+    /// Decide whether a file needs reindexing, hashing it only when the snapshot can't settle it:
+    ///
+    /// - never indexed, or a different size than on record: changed, without hashing
+    /// - same modification time and size as on record: unchanged, without hashing,
+    ///   unless `paranoid` (a timestamp-preserving restore of same-size content slips past this)
+    /// - otherwise the hash decides; equal content with a new timestamp is `Touched`
+    ///
+    /// States recorded without a size are always hashed.
+    pub fn check_file(
+        &self,
+        file_path: &str,
+        path: &Path,
+        snapshot: FileSnapshot,
+        paranoid: bool,
+    ) -> Result<FileCheck> {
+        let stored = match self.get_file_state(file_path)? {
+            Some(state) => state,
+            None => return Ok(FileCheck::Changed),
+        };
+
+        match stored.size {
+            Some(size) if size != snapshot.size => return Ok(FileCheck::Changed),
+            Some(_) if !paranoid && stored.last_modified == snapshot.modified => {
+                return Ok(FileCheck::Unchanged)
+            }
+            _ => {}
+        }
+
+        let hash = calculate_file_hash(path)?;
+        if hash != stored.content_hash {
+            return Ok(FileCheck::Changed);
+        }
+        if stored.last_modified == snapshot.modified && stored.size == Some(snapshot.size) {
+            return Ok(FileCheck::Unchanged);
+        }
+        Ok(FileCheck::Touched(FileState {
+            last_modified: snapshot.modified,
+            content_hash: hash,
+            indexed_at: stored.indexed_at,
+            size: Some(snapshot.size),
+            root: stored.root,
+        }))
+    }
+
+    /// List the state of every tracked file (metadata keys are skipped)
+    pub fn list_states(&self) -> Result<Vec<(String, FileState)>> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+
+        let table = read_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+
+        let mut states = Vec::new();
+        for item in table.iter().map_err(|e| {
+            Error::Database(format!("Failed to iterate table: {}", e))
+        })? {
+            let (key, value) = item.map_err(|e| {
+                Error::Database(format!("Failed to read table item: {}", e))
+            })?;
+            let key_str = key.value();
+            if key_str.starts_with(META_KEY_PREFIX) {
+                continue;
+            }
+            // Skip unreadable states rather than failing the whole listing
+            let json_str = decode_value(self.cipher.as_ref(), value.value())?;
+            if let Ok(state) = FileState::from_json(&json_str) {
+                states.push((key_str.to_string(), state));
+            }
+        }
+
+        Ok(states)
+    }
+
+    /// Which of `files` are tracked as indexed from another notes directory than `root`, with
+    /// that directory. Indexing them from `root` would replace the other folder's vectors,
+    /// since entries are keyed by relative path. States without a recorded root aren't reported.
+    pub fn files_from_other_roots<'a>(
+        &self,
+        files: impl IntoIterator<Item = &'a str>,
+        root: &Path,
+    ) -> Result<Vec<(String, PathBuf)>> {
+        let files: std::collections::HashSet<&str> = files.into_iter().collect();
+        Ok(self
+            .list_states()?
+            .into_iter()
+            .filter(|(path, _)| files.contains(path.as_str()))
+            .filter_map(|(path, state)| match state.root {
+                Some(other) if other != root => Some((path, other)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Most recently indexed files, newest first (ties broken by path)
+    pub fn recently_indexed(&self, limit: usize) -> Result<Vec<(String, FileState)>> {
+        let mut states = self.list_states()?;
+        states.sort_by(|a, b| b.1.indexed_at.cmp(&a.1.indexed_at).then_with(|| a.0.cmp(&b.0)));
+        states.truncate(limit);
+        Ok(states)
+    }
+
     pub fn get_model_id(&self) -> Result<Option<String>> {
         let read_txn = self.db.begin_read().map_err(|e| {
             Error::Database(format!("Failed to begin read transaction: {}", e))
@@ -230,6 +473,289 @@ impl StateStore {
 
         Ok(())
     }
+
+    /// Embedding scheme fingerprint recorded by the last index, if any
+    pub fn get_embedding_scheme(&self) -> Result<Option<String>> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+
+        let table = read_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+
+        let v = table.get(META_EMBEDDING_SCHEME_KEY).map_err(|e| {
+            Error::Database(format!("Failed to get embedding scheme: {}", e))
+        })?;
+
+        Ok(v.map(|guard| guard.value().to_string()))
+    }
+
+    pub fn set_embedding_scheme(&self, scheme: &str) -> Result<()> {
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+
+        {
+            let mut table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            table.insert(META_EMBEDDING_SCHEME_KEY, scheme).map_err(|e| {
+                Error::Database(format!("Failed to store embedding scheme: {}", e))
+            })?;
+        }
+
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+
+    /// Embedding length recorded by the last index, if any
+    pub fn get_embedding_dim(&self) -> Result<Option<usize>> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+
+        let table = read_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+
+        let v = table.get(META_EMBEDDING_DIM_KEY).map_err(|e| {
+            Error::Database(format!("Failed to get embedding dimension: {}", e))
+        })?;
+
+        match v {
+            Some(guard) => guard.value().parse().map(Some).map_err(|_| {
+                Error::Database(format!("Invalid stored embedding dimension: {}", guard.value()))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_embedding_dim(&self, embedding_dim: usize) -> Result<()> {
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+
+        {
+            let mut table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            table.insert(META_EMBEDDING_DIM_KEY, embedding_dim.to_string().as_str()).map_err(|e| {
+                Error::Database(format!("Failed to store embedding dimension: {}", e))
+            })?;
+        }
+
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+
+    /// Similarity metric recorded by the last index, if any
+    pub fn get_similarity_metric(&self) -> Result<Option<String>> {
+        read_similarity_metric(&self.db)
+    }
+
+    pub fn set_similarity_metric(&self, metric: &str) -> Result<()> {
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+
+        {
+            let mut table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            table.insert(META_SIMILARITY_METRIC_KEY, metric).map_err(|e| {
+                Error::Database(format!("Failed to store similarity metric: {}", e))
+            })?;
+        }
+
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+
+    /// Model file checksums recorded by the last index, if any
+    pub fn get_model_checksums(&self) -> Result<Option<ModelChecksums>> {
+        read_model_checksums(&self.db)
+    }
+
+    pub fn set_model_checksums(&self, checksums: &ModelChecksums) -> Result<()> {
+        let json = serde_json::to_string(checksums)
+            .map_err(|e| Error::Database(format!("Failed to serialize model checksums: {}", e)))?;
+
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+
+        {
+            let mut table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            table.insert(META_MODEL_CHECKSUMS_KEY, json.as_str()).map_err(|e| {
+                Error::Database(format!("Failed to store model checksums: {}", e))
+            })?;
+        }
+
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+
+    /// [`record_embedding_setup`](Self::record_embedding_setup) for the loaded `model`, along
+    /// with the checksums of its files when they could be read
+    pub fn record_model(&self, config: &Config, model: &EmbeddingModel) -> Result<()> {
+        self.record_embedding_setup(config, model.embedding_dim())?;
+        let recorded = self.get_model_checksums()?;
+        match model.checksums(recorded.as_ref()) {
+            Some(checksums) if recorded.as_ref() != Some(checksums) => self.set_model_checksums(checksums),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record the model, embedding scheme, embedding length (`embedding_dim`, from the
+    /// loaded model) and similarity metric as what the index was built with
+    pub fn record_embedding_setup(&self, config: &Config, embedding_dim: usize) -> Result<()> {
+        self.set_model_id(&config.model_id)?;
+        self.set_embedding_scheme(&config.embedding_scheme())?;
+        self.set_embedding_dim(embedding_dim)?;
+        self.set_similarity_metric(config.similarity_metric.as_str())
+    }
+
+    /// Fail if the index was built with embeddings of another length than `model_dim` (the
+    /// loaded model's). A model with the same id can still change size between revisions,
+    /// and mixed lengths can't be compared. Indexes recorded before lengths were tracked pass.
+    pub fn check_embedding_dim(&self, model_dim: usize) -> Result<()> {
+        match self.get_embedding_dim()? {
+            Some(stored) if stored != model_dim => Err(Error::Model(format!(
+                "The index holds {}-dimensional embeddings but the loaded model produces {}-dimensional ones. Run 'notes2vec index --force' to re-embed your notes with it.",
+                stored, model_dim
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the stored vectors were made with another model or embedding scheme than
+    /// `config` would use now, so every file has to be embedded again. Indexes recorded
+    /// before schemes were tracked were made with the default prefixes; ones with no model
+    /// recorded are taken to match the configured model.
+    pub fn needs_reindex(&self, config: &Config) -> Result<bool> {
+        if self.get_model_id()?.is_some_and(|id| id != config.model_id) {
+            return Ok(true);
+        }
+        let stored_scheme = self
+            .get_embedding_scheme()?
+            .unwrap_or_else(|| embedding_scheme(DEFAULT_QUERY_PREFIX, DEFAULT_PASSAGE_PREFIX));
+        Ok(stored_scheme != config.embedding_scheme())
+    }
+
+    /// Directory the last full index ran on, which tracked paths are relative to
+    pub fn get_notes_root(&self) -> Result<Option<PathBuf>> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+
+        let table = read_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+
+        let v = table.get(META_NOTES_ROOT_KEY).map_err(|e| {
+            Error::Database(format!("Failed to get notes root: {}", e))
+        })?;
+
+        match v {
+            Some(guard) => {
+                let root = decode_value(self.cipher.as_ref(), guard.value())?;
+                Ok(Some(PathBuf::from(root.as_ref())))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Record the indexed directory; the path is encrypted like file states when enabled
+    pub fn set_notes_root(&self, root: &Path) -> Result<()> {
+        let root = root.to_str().ok_or_else(|| {
+            Error::Database(format!("Notes directory is not valid UTF-8: {}", root.display()))
+        })?;
+
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+
+        {
+            let mut table = write_txn.open_table(FILE_STATE_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            let stored = encode_value(self.cipher.as_ref(), root)?;
+            table.insert(META_NOTES_ROOT_KEY, stored.as_ref()).map_err(|e| {
+                Error::Database(format!("Failed to store notes root: {}", e))
+            })?;
+        }
+
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Similarity metric the index at `config` was built for, read without opening the state
+/// store for writing, so search works on read-only mounts. None if nothing was recorded.
+pub fn recorded_similarity_metric(config: &Config) -> Result<Option<String>> {
+    if !config.state_path.exists() {
+        return Ok(None);
+    }
+    read_similarity_metric(&readonly::open_read_only(&config.state_path)?)
+}
+
+fn read_similarity_metric(db: &Database) -> Result<Option<String>> {
+    read_meta(db, META_SIMILARITY_METRIC_KEY, "similarity metric")
+}
+
+/// Checksums of the model files the index at `config` was built with, read like
+/// [`recorded_similarity_metric`]. None if nothing was recorded.
+pub fn recorded_model_checksums(config: &Config) -> Result<Option<ModelChecksums>> {
+    if !config.state_path.exists() {
+        return Ok(None);
+    }
+    read_model_checksums(&readonly::open_read_only(&config.state_path)?)
+}
+
+fn read_model_checksums(db: &Database) -> Result<Option<ModelChecksums>> {
+    match read_meta(db, META_MODEL_CHECKSUMS_KEY, "model checksums")? {
+        Some(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| Error::Database(format!("Unreadable model checksums: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// Metadata value stored under `key` (`what` names it in errors)
+fn read_meta(db: &Database, key: &str, what: &str) -> Result<Option<String>> {
+    let read_txn = db.begin_read().map_err(|e| {
+        Error::Database(format!("Failed to begin read transaction: {}", e))
+    })?;
+
+    let table = match read_txn.open_table(FILE_STATE_TABLE) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(None),
+        Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+    };
+
+    let v = table.get(key).map_err(|e| {
+        Error::Database(format!("Failed to get {}: {}", what, e))
+    })?;
+
+    Ok(v.map(|guard| guard.value().to_string()))
 }
 
 /// Calculate SHA256 hash of file contents
@@ -268,6 +794,8 @@ pub fn get_file_modified_time(path: &Path) -> Result<u64> {
 mod tests {
     use super::*;
     use crate::core::config::Config;
+    use crate::search::checksums::FileChecksum;
+    use crate::search::metric::{metric_mismatch, SimilarityMetric};
     use std::fs;
     use tempfile::TempDir;
 
@@ -384,6 +912,169 @@ mod tests {
         store.remove_file("nonexistent.md").unwrap();
     }
 
+    #[test]
+    fn test_state_store_list_states_skips_meta() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path().join("test_notes2vec");
+        let config = Config::new(Some(base_dir)).unwrap();
+        config.init().unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        store.set_model_id("some-model").unwrap();
+        store.update_file_state("a.md", 1, "h1".to_string()).unwrap();
+        store.update_file_state("b.md", 2, "h2".to_string()).unwrap();
+
+        let states = store.list_states().unwrap();
+        assert_eq!(states.len(), 2);
+        assert!(states.iter().all(|(path, _)| !path.starts_with(META_KEY_PREFIX)));
+    }
+
+    #[test]
+    fn test_changed_passage_prefix_needs_reindex() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        // Nothing recorded yet: taken to be the defaults
+        assert!(!store.needs_reindex(&config).unwrap());
+
+        store.record_embedding_setup(&config, 384).unwrap();
+        assert!(!store.needs_reindex(&config).unwrap());
+
+        config.passage_prefix = "Represent this note: ".to_string();
+        assert!(store.needs_reindex(&config).unwrap());
+        store.record_embedding_setup(&config, 384).unwrap();
+        assert!(!store.needs_reindex(&config).unwrap());
+
+        config.query_prefix = String::new();
+        assert!(store.needs_reindex(&config).unwrap());
+        store.record_embedding_setup(&config, 384).unwrap();
+
+        config.context_weight = 0.3;
+        assert!(store.needs_reindex(&config).unwrap());
+        store.record_embedding_setup(&config, 384).unwrap();
+        assert!(!store.needs_reindex(&config).unwrap());
+
+        config.model_id = "sentence-transformers/all-MiniLM-L6-v2".to_string();
+        assert!(store.needs_reindex(&config).unwrap());
+    }
+
+    #[test]
+    fn test_same_path_from_another_root_is_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let work = temp_dir.path().join("work");
+        let home = temp_dir.path().join("home");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::write(work.join("inbox.md"), "work inbox").unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        let indexed = FileState::from_file(&work.join("inbox.md")).unwrap().with_root(&work);
+        store.store_file_state("inbox.md", &indexed).unwrap();
+        store.store_file_state("work-only.md", &indexed).unwrap();
+        // Indexed before roots were recorded
+        store.update_file_state("legacy.md", 1, "h1".to_string()).unwrap();
+
+        let files = ["inbox.md", "legacy.md", "new.md"];
+        assert!(store.files_from_other_roots(files, &work).unwrap().is_empty());
+        assert_eq!(
+            store.files_from_other_roots(files, &home).unwrap(),
+            vec![("inbox.md".to_string(), work.clone())]
+        );
+
+        // A touched file keeps the root it was indexed from
+        std::fs::write(work.join("inbox.md"), "work inbox").unwrap();
+        let snapshot = FileSnapshot { modified: indexed.last_modified + 10, size: 10 };
+        match store.check_file("inbox.md", &work.join("inbox.md"), snapshot, false).unwrap() {
+            FileCheck::Touched(state) => assert_eq!(state.root, Some(work)),
+            other => panic!("expected a touched file, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_search_with_another_metric_is_warned_about() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        assert_eq!(recorded_similarity_metric(&config).unwrap(), None);
+
+        {
+            let store = StateStore::open(&config).unwrap();
+            assert_eq!(store.get_similarity_metric().unwrap(), None);
+            store.record_embedding_setup(&config, 384).unwrap();
+        }
+        let recorded = recorded_similarity_metric(&config).unwrap();
+        assert_eq!(recorded.as_deref(), Some("cosine"));
+        assert!(metric_mismatch(recorded.as_deref(), config.similarity_metric).is_none());
+
+        config.similarity_metric = SimilarityMetric::Euclidean;
+        let warning = metric_mismatch(recorded.as_deref(), config.similarity_metric).unwrap();
+        assert!(warning.contains("built for cosine"), "{}", warning);
+    }
+
+    #[test]
+    fn test_embedding_dim_mismatch_is_an_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        // Nothing recorded yet: any model is accepted
+        store.check_embedding_dim(768).unwrap();
+
+        store.record_embedding_setup(&config, 384).unwrap();
+        assert_eq!(store.get_embedding_dim().unwrap(), Some(384));
+        store.check_embedding_dim(384).unwrap();
+        let err = store.check_embedding_dim(768).unwrap_err().to_string();
+        assert!(err.contains("384") && err.contains("768"), "{}", err);
+        assert!(err.contains("index --force"), "{}", err);
+
+        // Not a file state
+        assert!(store.list_states().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_model_checksums_are_recorded_apart_from_file_states() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        assert_eq!(recorded_model_checksums(&config).unwrap(), None);
+
+        let checksum = FileChecksum { sha256: "abc123".to_string(), size: 10, modified: 20 };
+        let checksums = ModelChecksums::from([("model.safetensors".to_string(), checksum)]);
+        {
+            let store = StateStore::open(&config).unwrap();
+            assert_eq!(store.get_model_checksums().unwrap(), None);
+            store.set_model_checksums(&checksums).unwrap();
+            assert!(store.list_states().unwrap().is_empty());
+        }
+        assert_eq!(recorded_model_checksums(&config).unwrap(), Some(checksums));
+    }
+
+    #[test]
+    fn test_state_store_recently_indexed_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path().join("test_notes2vec");
+        let config = Config::new(Some(base_dir)).unwrap();
+        config.init().unwrap();
+
+        let store = StateStore::open(&config).unwrap();
+        store.update_file_state("first.md", 1, "h1".to_string()).unwrap();
+        // indexed_at has one-second resolution
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        store.update_file_state("second.md", 2, "h2".to_string()).unwrap();
+
+        let recent = store.recently_indexed(10).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].0, "second.md");
+        assert_eq!(recent[1].0, "first.md");
+
+        let recent = store.recently_indexed(1).unwrap();
+        assert_eq!(recent.len(), 1);
+    }
+
     #[test]
     fn test_calculate_file_hash() {
         let temp_dir = TempDir::new().unwrap();
@@ -455,5 +1146,90 @@ mod tests {
         let result = get_file_modified_time(std::path::Path::new("/nonexistent/file.txt"));
         assert!(result.is_err());
     }
-}
 
+    /// Set a file's modification time, as a timestamp-preserving restore would
+    fn set_modified(path: &Path, secs: u64) {
+        let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs)).unwrap();
+    }
+
+    fn check(store: &StateStore, path: &Path, paranoid: bool) -> FileCheck {
+        let snapshot = FileSnapshot::read(path).unwrap();
+        store.check_file("note.md", path, snapshot, paranoid).unwrap()
+    }
+
+    #[test]
+    fn test_check_file_restores_with_preserved_timestamps() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = StateStore::open(&config).unwrap();
+        let note = temp_dir.path().join("note.md");
+
+        fs::write(&note, "meeting at noon").unwrap();
+        set_modified(&note, 1_700_000_000);
+        assert!(matches!(check(&store, &note, false), FileCheck::Changed));
+        store.store_file_state("note.md", &FileState::from_file(&note).unwrap()).unwrap();
+        assert!(matches!(check(&store, &note, false), FileCheck::Unchanged));
+
+        // Backup restored over it with the indexed timestamp but a different size
+        fs::write(&note, "meeting at noon, bring slides").unwrap();
+        set_modified(&note, 1_700_000_000);
+        assert!(matches!(check(&store, &note, false), FileCheck::Changed));
+
+        // Same size and timestamp: only paranoid mode hashes it
+        fs::write(&note, "meeting at nine").unwrap();
+        set_modified(&note, 1_700_000_000);
+        assert!(matches!(check(&store, &note, false), FileCheck::Unchanged));
+        assert!(matches!(check(&store, &note, true), FileCheck::Changed));
+
+        // Older backup with its own (earlier) timestamp: the hash catches it
+        fs::write(&note, "meeting at 10am").unwrap();
+        set_modified(&note, 1_600_000_000);
+        assert!(matches!(check(&store, &note, false), FileCheck::Changed));
+    }
+
+    #[test]
+    fn test_check_file_touched_content_is_not_reindexed() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = StateStore::open(&config).unwrap();
+        let note = temp_dir.path().join("note.md");
+
+        fs::write(&note, "meeting at noon").unwrap();
+        set_modified(&note, 1_700_000_000);
+        let indexed = FileState::from_file(&note).unwrap();
+        store.store_file_state("note.md", &indexed).unwrap();
+
+        // git checkout of the same content, with the timestamp going backwards
+        set_modified(&note, 1_600_000_000);
+        match check(&store, &note, false) {
+            FileCheck::Touched(state) => {
+                assert_eq!(state.last_modified, 1_600_000_000);
+                assert_eq!(state.indexed_at, indexed.indexed_at);
+                store.store_file_state("note.md", &state).unwrap();
+            }
+            other => panic!("expected Touched, got {:?}", other),
+        }
+        assert!(matches!(check(&store, &note, false), FileCheck::Unchanged));
+    }
+
+    #[test]
+    fn test_check_file_hashes_states_without_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = StateStore::open(&config).unwrap();
+        let note = temp_dir.path().join("note.md");
+
+        fs::write(&note, "meeting at noon").unwrap();
+        set_modified(&note, 1_700_000_000);
+        store.update_file_state("note.md", 1_700_000_000, "stale-hash".to_string()).unwrap();
+        assert!(matches!(check(&store, &note, false), FileCheck::Changed));
+
+        // A state with the right hash but no size is recorded again, now with its size
+        store.update_file_state("note.md", 1_700_000_000, calculate_file_hash(&note).unwrap()).unwrap();
+        assert!(matches!(check(&store, &note, false), FileCheck::Touched(_)));
+    }
+}
@@ -1,13 +1,99 @@
-use crate::core::config::Config;
+use crate::core::config::{Config, DEFAULT_COLLECTION};
 use crate::core::error::{Error, Result};
-use redb::{Database, ReadableTable, TableDefinition};
+use crate::indexing::parser::ChunkKind;
+use crate::search::metric::SimilarityMetric;
+use crate::storage::crypto::{copy_meta, decode_value, encode_value, open_cipher, Cipher};
+use crate::storage::lock::IndexLock;
+use crate::storage::readonly;
+use redb::{Database, ReadableTable, TableDefinition, TableError, TableHandle, WriteTransaction};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
 
 /// Table definition for vector storage
 /// Key: chunk_id (format: "file_path:chunk_index")
 /// Value: JSON serialized VectorEntry
 const VECTORS_TABLE: TableDefinition<&str, &str> = TableDefinition::new("vectors");
 
+/// One summary embedding per file: the L2-normalized centroid of its chunks, written at index
+/// time for two-stage search (see [`VectorStore::search_two_stage`]).
+/// Key: file path. Value: JSON array of floats.
+const DOC_VECTORS_TABLE: TableDefinition<&str, &str> = TableDefinition::new("doc_vectors");
+
+/// Frontmatter names of each file (see [`FileMeta`]), for lexical matching by alias.
+/// Key: file path. Value: JSON object. Files without a title or aliases have no row.
+const FILE_META_TABLE: TableDefinition<&str, &str> = TableDefinition::new("file_meta");
+
+/// Store-wide flags, kept out of the vectors table so entry counts stay exact
+const STORE_META_TABLE: TableDefinition<&str, &str> = TableDefinition::new("vectors_meta");
+
+/// Present once [`VectorStore::normalize_all`] has checked every embedding
+const NORMALIZED_KEY: &str = "normalized";
+
+/// How far an embedding's length may be from 1.0 before it counts as unnormalized
+pub const NORM_TOLERANCE: f32 = 1e-3;
+
+/// Entries written per transaction by vacuum and normalize_all
+const VACUUM_BATCH_SIZE: usize = 1000;
+
+/// Outcome of [`VectorStore::vacuum`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VacuumStats {
+    /// Live entries copied into the rebuilt database
+    pub entries: usize,
+    /// File size before, in bytes
+    pub size_before: u64,
+    /// File size after, in bytes
+    pub size_after: u64,
+}
+
+/// What produced an embedding. Vectors from different sources live in
+/// unrelated spaces, so their similarities are meaningless when mixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingSource {
+    /// The real embedding model
+    #[default]
+    Model,
+    /// Hash-based fallback embeddings (written when the model was unavailable)
+    Hash,
+}
+
+impl EmbeddingSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EmbeddingSource::Model => "model",
+            EmbeddingSource::Hash => "hash",
+        }
+    }
+}
+
+/// Source of the embeddings that queries are compared against.
+/// Only the real model produces embeddings now, so anything else in the store is stale.
+pub const ACTIVE_EMBEDDING_SOURCE: EmbeddingSource = EmbeddingSource::Model;
+
+/// Names a note goes by besides its path, from its frontmatter
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl FileMeta {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.aliases.is_empty()
+    }
+
+    /// Title first, then aliases
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.title.iter().chain(&self.aliases).map(String::as_str)
+    }
+}
+
 /// Metadata for a vector entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorEntry {
@@ -21,10 +107,47 @@ pub struct VectorEntry {
     pub text: String,
     /// Context (e.g., "Document > Section") for the chunk
     pub context: String,
+    /// Every heading enclosing the chunk, outermost first; `context` may leave middle levels out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headings: Vec<String>,
     /// Start line in source file
     pub start_line: usize,
     /// End line in source file
     pub end_line: usize,
+    /// Byte range of the chunk in the source file (`start_offset..end_offset`); both 0 when
+    /// unknown (frontmatter summaries, entries written before offsets were kept)
+    #[serde(default)]
+    pub start_offset: usize,
+    #[serde(default)]
+    pub end_offset: usize,
+    /// Normalized tags (frontmatter and inline `#tags`)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether the text is note content or synthesized from frontmatter
+    #[serde(default)]
+    pub kind: ChunkKind,
+    /// What produced the embedding (entries written before this field existed came from the model)
+    #[serde(default)]
+    pub embedding_source: EmbeddingSource,
+    /// Too little text for a meaningful embedding; kept for previews but down-weighted in search
+    #[serde(default)]
+    pub low_content: bool,
+    /// Collection the entry was indexed into (entries written before collections were `default`)
+    #[serde(default = "default_collection")]
+    pub collection: String,
+    /// Source file's modification time (Unix seconds) when it was indexed; None for older entries
+    #[serde(default)]
+    pub modified: Option<u64>,
+    /// Start of the day the note's frontmatter dates it to (`modified`, else `created`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_date: Option<u64>,
+    /// The note's custom frontmatter fields (string values only), for `meta:key=value` filters
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+}
+
+fn default_collection() -> String {
+    DEFAULT_COLLECTION.to_string()
 }
 
 impl VectorEntry {
@@ -44,9 +167,103 @@ impl VectorEntry {
             embedding,
             text,
             context,
+            headings: Vec::new(),
             start_line,
             end_line,
+            start_offset: 0,
+            end_offset: 0,
+            tags: Vec::new(),
+            kind: ChunkKind::Content,
+            embedding_source: EmbeddingSource::Model,
+            low_content: false,
+            collection: default_collection(),
+            modified: None,
+            note_date: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Record the chunk's byte range in the source file
+    pub fn with_offsets(mut self, start_offset: usize, end_offset: usize) -> Self {
+        self.start_offset = start_offset;
+        self.end_offset = end_offset;
+        self
+    }
+
+    /// The chunk's byte range in the source file, if it was recorded
+    pub fn source_span(&self) -> Option<std::ops::Range<usize>> {
+        (self.end_offset > self.start_offset).then_some(self.start_offset..self.end_offset)
+    }
+
+    /// Attach tags to the entry
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Record the full heading path of the chunk
+    pub fn with_headings(mut self, headings: Vec<String>) -> Self {
+        self.headings = headings;
+        self
+    }
+
+    /// Set the chunk kind
+    pub fn with_kind(mut self, kind: ChunkKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the embedding source
+    pub fn with_embedding_source(mut self, source: EmbeddingSource) -> Self {
+        self.embedding_source = source;
+        self
+    }
+
+    /// Flag the entry as having too little text to embed meaningfully
+    pub fn with_low_content(mut self, low_content: bool) -> Self {
+        self.low_content = low_content;
+        self
+    }
+
+    /// Record the collection the entry belongs to
+    pub fn with_collection(mut self, collection: impl Into<String>) -> Self {
+        self.collection = collection.into();
+        self
+    }
+
+    /// Record the source file's modification time (Unix seconds)
+    pub fn with_modified(mut self, modified: Option<u64>) -> Self {
+        self.modified = modified;
+        self
+    }
+
+    /// Record the day the note's frontmatter dates it to (Unix seconds)
+    pub fn with_note_date(mut self, note_date: Option<u64>) -> Self {
+        self.note_date = note_date;
+        self
+    }
+
+    /// Attach the note's custom frontmatter fields
+    pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// When the note was written for `--after`/`--before`: its frontmatter date, else the file's
+    /// modification time
+    pub fn dated(&self) -> Option<u64> {
+        self.note_date.or(self.modified)
+    }
+
+    /// Error out if the entry can't be compared with query embeddings
+    fn check_embedding_source(&self) -> Result<()> {
+        if self.embedding_source != ACTIVE_EMBEDDING_SOURCE {
+            return Err(Error::EmbeddingSourceMismatch {
+                found: self.embedding_source.label().to_string(),
+                expected: ACTIVE_EMBEDDING_SOURCE.label().to_string(),
+            });
         }
+        Ok(())
     }
 
     /// Get a unique ID for this chunk
@@ -70,6 +287,20 @@ impl VectorEntry {
 /// Vector store for managing embeddings
 pub struct VectorStore {
     db: Database,
+    /// Set when values are encrypted at rest
+    cipher: Option<Cipher>,
+    /// Number of stored chunks, kept in step with insert/remove so it's free to read
+    chunk_count: AtomicUsize,
+    /// Multiplier for low-content similarities (0 leaves them out of search)
+    low_content_weight: f32,
+    /// How queries are compared with stored embeddings (`similarity_metric`)
+    metric: SimilarityMetric,
+    /// Every stored embedding is known to be unit length (see [`normalize_all`](Self::normalize_all)),
+    /// so ranking can skip the norms and use the dot product
+    normalized: AtomicBool,
+    /// Held while the store is open for writing (None when opened read-only); declared last so
+    /// the database is closed before it's released
+    _lock: Option<IndexLock>,
 }
 
 impl VectorStore {
@@ -83,63 +314,350 @@ impl VectorStore {
             std::fs::create_dir_all(parent)?;
         }
 
+        // Name the other process instead of failing on redb's own lock
+        let lock = IndexLock::acquire(&config.database_dir)?;
+
         // Create or open the database
         let db = if db_path.exists() {
-            Database::open(&db_path)
-                .map_err(|e| {
-                    let msg = e.to_string();
-                    if msg.to_lowercase().contains("lock") {
-                        Error::Database("Vector database is locked. Another notes2vec process may be running. Close other instances and try again.".to_string())
-                    } else {
-                        Error::Database(format!("Failed to open vector database: {}", e))
-                    }
-                })?
+            open_existing(&db_path)?
         } else {
             Database::create(&db_path)
                 .map_err(|e| Error::Database(format!("Failed to create vector database: {}", e)))?
         };
 
-        // Initialize table
-        let write_txn = db.begin_write().map_err(|e| {
-            Error::Database(format!("Failed to begin write transaction: {}", e))
+        // Initialize the table only when it's missing, so opening an index doesn't write
+        let stored_chunks = match count_entries(&db)? {
+            Some(count) => count,
+            None => {
+                let write_txn = db.begin_write().map_err(|e| {
+                    Error::Database(format!("Failed to begin write transaction: {}", e))
+                })?;
+                write_txn.open_table(VECTORS_TABLE).map_err(|e| {
+                    Error::Database(format!("Failed to open table: {}", e))
+                })?;
+                write_txn.commit().map_err(|e| {
+                    Error::Database(format!("Failed to commit transaction: {}", e))
+                })?;
+                0
+            }
+        };
+
+        let cipher = open_cipher(&db, config, stored_chunks > 0)?;
+        let normalized = read_normalized_flag(&db)?;
+        tracing::debug!(path = %db_path.display(), chunks = stored_chunks, normalized, "Opened vector store");
+
+        Ok(Self {
+            db,
+            cipher,
+            chunk_count: AtomicUsize::new(stored_chunks),
+            low_content_weight: config.low_content_weight,
+            metric: config.similarity_metric,
+            normalized: AtomicBool::new(normalized),
+            _lock: Some(lock),
+        })
+    }
+
+    /// Open an existing store for searching without writing anything under `base_dir`,
+    /// so search works on read-only mounts and for users who can't write the index.
+    /// Inserts and removals on a read-only store never reach the file.
+    pub fn open_read_only(config: &Config) -> Result<Self> {
+        let db_path = config.database_dir.join("vectors.redb");
+        if !db_path.exists() {
+            return Err(Error::Database(
+                "No vector database found. Run 'notes2vec index' first.".to_string(),
+            ));
+        }
+
+        let db = readonly::open_read_only(&db_path)?;
+        let stored_chunks = count_entries(&db)?.ok_or_else(|| {
+            Error::Database("Vector database has no vectors table. Run 'notes2vec index' first.".to_string())
         })?;
-        {
-            let _table = write_txn.open_table(VECTORS_TABLE).map_err(|e| {
-                Error::Database(format!("Failed to open table: {}", e))
+        let cipher = open_cipher(&db, config, stored_chunks > 0)?;
+        let normalized = read_normalized_flag(&db)?;
+
+        Ok(Self {
+            db,
+            cipher,
+            chunk_count: AtomicUsize::new(stored_chunks),
+            low_content_weight: config.low_content_weight,
+            metric: config.similarity_metric,
+            normalized: AtomicBool::new(normalized),
+            _lock: None,
+        })
+    }
+
+    /// Similarity used for ranking, or None when the entry is excluded from search
+    fn ranked_similarity(&self, query_embedding: &[f32], entry: &VectorEntry) -> Option<f32> {
+        let similarity = self.similarity(query_embedding, &entry.embedding);
+        if !entry.low_content {
+            return Some(similarity);
+        }
+        if self.low_content_weight <= 0.0 {
+            return None;
+        }
+        // Only shrink positive matches; scaling a negative one would raise it
+        Some(if similarity > 0.0 { similarity * self.low_content_weight } else { similarity })
+    }
+
+    /// `embedding` compared with the query under the configured metric, higher being closer.
+    /// Query embeddings come from the model already normalized, so once the stored ones
+    /// are known to be too the cosine is just the dot product.
+    fn similarity(&self, query_embedding: &[f32], embedding: &[f32]) -> f32 {
+        match self.metric {
+            SimilarityMetric::Cosine if self.is_normalized() => dot_product(query_embedding, embedding),
+            SimilarityMetric::Cosine => cosine_similarity(query_embedding, embedding),
+            SimilarityMetric::Dot => dot_product(query_embedding, embedding),
+            SimilarityMetric::Euclidean => 1.0 / (1.0 + euclidean_distance(query_embedding, embedding)),
+        }
+    }
+
+    /// Rebuild vectors.redb to reclaim space left behind by deletions.
+    ///
+    /// redb never shrinks a file in place, so live entries are streamed into a fresh
+    /// database next to the original, which is synced and then renamed over it. The
+    /// original is only replaced once the copy is complete, so a crash leaves it untouched.
+    /// Fails, naming the other process, if the index is locked for writing.
+    pub fn vacuum(config: &Config) -> Result<VacuumStats> {
+        let db_path = config.database_dir.join("vectors.redb");
+        if !db_path.exists() {
+            return Err(Error::Database(format!(
+                "No vector database at {}",
+                db_path.display()
+            )));
+        }
+        let tmp_path = db_path.with_extension("redb.vacuum");
+        let size_before = std::fs::metadata(&db_path)?.len();
+
+        // Held until the copy has replaced the original, so no writer can open the old file
+        // between the copy and the rename and have its changes lost
+        let _lock = IndexLock::acquire(&config.database_dir)?;
+
+        // Opened read-only: the copy never writes to the original
+        let source = readonly::open_read_only(&db_path)?;
+
+        // Leftover from an interrupted vacuum
+        if tmp_path.exists() {
+            std::fs::remove_file(&tmp_path)?;
+        }
+
+        let copied = match copy_live_entries(&source, &tmp_path) {
+            Ok(copied) => copied,
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        };
+
+        // Closed before the rename, which Windows refuses on an open file; the lock still
+        // keeps writers out
+        drop(source);
+        std::fs::rename(&tmp_path, &db_path)?;
+        let size_after = std::fs::metadata(&db_path)?.len();
+        tracing::info!(entries = copied, size_before, size_after, "Vacuumed vector database");
+
+        Ok(VacuumStats {
+            entries: copied,
+            size_before,
+            size_after,
+        })
+    }
+
+    /// Number of chunks in the store (cached; no table scan)
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_count.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Whether every embedding has been checked to be unit length since the last
+    /// insert that wasn't
+    pub fn is_normalized(&self) -> bool {
+        self.normalized.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Rescale every stored embedding whose length is off 1.0 by more than [`NORM_TOLERANCE`],
+    /// then mark the store normalized so search can use the dot product. Zero vectors (blank
+    /// chunks) have no direction and are left alone. Returns the number of embeddings repaired.
+    pub fn normalize_all(&self) -> Result<usize> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+        let table = read_txn.open_table(VECTORS_TABLE).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+
+        let mut batch: Vec<(String, String)> = Vec::with_capacity(VACUUM_BATCH_SIZE);
+        let mut repaired = 0;
+        for item in table.iter().map_err(|e| {
+            Error::Database(format!("Failed to iterate table: {}", e))
+        })? {
+            let (key, value) = item.map_err(|e| {
+                Error::Database(format!("Failed to read table item: {}", e))
             })?;
+            let json_str = decode_value(self.cipher.as_ref(), value.value())?;
+            let Ok(mut entry) = VectorEntry::from_json(&json_str) else {
+                continue;
+            };
+            if !normalize_l2(&mut entry.embedding) {
+                continue;
+            }
+            let stored = encode_value(self.cipher.as_ref(), &entry.to_json()?)?.into_owned();
+            batch.push((key.value().to_string(), stored));
+            if batch.len() >= VACUUM_BATCH_SIZE {
+                repaired += write_batch(&self.db, VECTORS_TABLE, &mut batch)?;
+            }
         }
+        repaired += write_batch(&self.db, VECTORS_TABLE, &mut batch)?;
+
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+        write_normalized_flag(&write_txn, true)?;
         write_txn.commit().map_err(|e| {
             Error::Database(format!("Failed to commit transaction: {}", e))
         })?;
+        self.normalized.store(true, AtomicOrdering::Relaxed);
 
-        Ok(Self { db })
+        Ok(repaired)
     }
 
     /// Insert or update a vector entry
     pub fn insert(&self, entry: &VectorEntry) -> Result<()> {
+        self.insert_batch(std::slice::from_ref(entry))
+    }
+
+    /// Insert or update several entries in one transaction: all of them are stored, or none
+    pub fn insert_batch(&self, entries: &[VectorEntry]) -> Result<()> {
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+        let inserted = self.insert_in(&write_txn, entries, None)?;
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+        self.record_inserted(inserted);
+        Ok(())
+    }
+
+    /// Replace everything stored for `file_path` (chunks, document vector, frontmatter names)
+    /// in a single transaction, so an interrupted or failed write leaves the old version whole.
+    /// Every entry must belong to `file_path`.
+    pub fn replace_file(
+        &self,
+        file_path: &str,
+        entries: &[VectorEntry],
+        document: Option<&[f32]>,
+        meta: &FileMeta,
+    ) -> Result<()> {
+        let document = document
+            .map(|embedding| {
+                serde_json::to_string(embedding)
+                    .map_err(|e| Error::Database(format!("Failed to serialize document vector: {}", e)))
+            })
+            .transpose()?;
+        let meta = if meta.is_empty() {
+            None
+        } else {
+            let json_str = serde_json::to_string(meta)
+                .map_err(|e| Error::Database(format!("Failed to serialize file metadata: {}", e)))?;
+            Some(json_str)
+        };
+
         let write_txn = self.db.begin_write().map_err(|e| {
             Error::Database(format!("Failed to begin write transaction: {}", e))
         })?;
+        let removed = remove_file_in(&write_txn, file_path)?;
+        // An early return drops the transaction uncommitted, which aborts it
+        let inserted = self.insert_in(&write_txn, entries, Some(file_path))?;
+        if let Some(json_str) = &document {
+            self.put_in(&write_txn, DOC_VECTORS_TABLE, file_path, json_str)?;
+        }
+        if let Some(json_str) = &meta {
+            self.put_in(&write_txn, FILE_META_TABLE, file_path, json_str)?;
+        }
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+
+        self.record_removed(removed);
+        self.record_inserted(inserted);
+        tracing::debug!(file = file_path, removed, inserted = entries.len(), "Replaced file vectors");
+        Ok(())
+    }
 
+    /// Write `entries` within `write_txn` (refusing any that don't belong to `only_file`, if
+    /// given), clearing the normalized flag if one is off unit length
+    fn insert_in(
+        &self,
+        write_txn: &WriteTransaction,
+        entries: &[VectorEntry],
+        only_file: Option<&str>,
+    ) -> Result<Inserted> {
+        let mut inserted = Inserted::default();
         {
             let mut table = write_txn.open_table(VECTORS_TABLE).map_err(|e| {
                 Error::Database(format!("Failed to open table: {}", e))
             })?;
 
-            let chunk_id = entry.chunk_id();
-            let json_str = entry.to_json()?;
-            table.insert(chunk_id.as_str(), json_str.as_str()).map_err(|e| {
-                Error::Database(format!("Failed to insert vector entry: {}", e))
-            })?;
+            for entry in entries {
+                if let Some(file_path) = only_file.filter(|file_path| *file_path != entry.file_path) {
+                    return Err(Error::Database(format!(
+                        "Chunk {} doesn't belong to {}",
+                        entry.chunk_id(),
+                        file_path
+                    )));
+                }
+                let chunk_id = entry.chunk_id();
+                let json_str = entry.to_json()?;
+                let stored = encode_value(self.cipher.as_ref(), &json_str)?;
+                let previous = table.insert(chunk_id.as_str(), stored.as_ref()).map_err(|e| {
+                    Error::Database(format!("Failed to insert vector entry: {}", e))
+                })?;
+                if previous.is_none() {
+                    inserted.new_chunks += 1;
+                }
+                // An embedding off unit length means search can no longer take the shortcut
+                inserted.breaks_normalized |= self.is_normalized() && !is_unit_length(&entry.embedding);
+            }
         }
 
-        write_txn.commit().map_err(|e| {
-            Error::Database(format!("Failed to commit transaction: {}", e))
-        })?;
+        if inserted.breaks_normalized {
+            write_normalized_flag(write_txn, false)?;
+        }
+        Ok(inserted)
+    }
 
+    /// Store `json_str` under `key` in one of the per-file tables, within `write_txn`
+    fn put_in(
+        &self,
+        write_txn: &WriteTransaction,
+        definition: TableDefinition<&str, &str>,
+        key: &str,
+        json_str: &str,
+    ) -> Result<()> {
+        let stored = encode_value(self.cipher.as_ref(), json_str)?;
+        let mut table = write_txn.open_table(definition).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+        table.insert(key, stored.as_ref()).map_err(|e| {
+            Error::Database(format!("Failed to insert {}: {}", definition.name(), e))
+        })?;
         Ok(())
     }
 
+    /// Update the cached counters once an insert has committed
+    fn record_inserted(&self, inserted: Inserted) {
+        self.chunk_count.fetch_add(inserted.new_chunks, AtomicOrdering::Relaxed);
+        if inserted.breaks_normalized {
+            self.normalized.store(false, AtomicOrdering::Relaxed);
+        }
+    }
+
+    /// Update the cached chunk count once a removal has committed
+    fn record_removed(&self, removed: usize) {
+        let _ = self.chunk_count.fetch_update(AtomicOrdering::Relaxed, AtomicOrdering::Relaxed, |n| {
+            Some(n.saturating_sub(removed))
+        });
+    }
+
     /// Get a vector entry by chunk ID
     pub fn get(&self, chunk_id: &str) -> Result<Option<VectorEntry>> {
         let read_txn = self.db.begin_read().map_err(|e| {
@@ -157,7 +675,7 @@ impl VectorStore {
         let result = match guard_option {
             Some(guard) => {
                 // Extract the value string before dropping the guard
-                let json_str = guard.value().to_string();
+                let json_str = decode_value(self.cipher.as_ref(), guard.value())?.into_owned();
                 drop(guard); // Explicitly drop guard
                 VectorEntry::from_json(&json_str).map(Some)
             }
@@ -167,76 +685,42 @@ impl VectorStore {
         result
     }
 
-    /// Remove all vectors for a specific file
-    /// Optimized: Uses chunk_id prefix matching to avoid deserializing all entries
+    /// Remove all vectors for a specific file, with its document vector and names
     pub fn remove_file(&self, file_path: &str) -> Result<usize> {
-        // First, collect all chunk IDs to remove in a read transaction
-        let read_txn = self.db.begin_read().map_err(|e| {
-            Error::Database(format!("Failed to begin read transaction: {}", e))
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
         })?;
-        
-        let read_table = read_txn.open_table(VECTORS_TABLE).map_err(|e| {
-            Error::Database(format!("Failed to open table: {}", e))
+        let removed = remove_file_in(&write_txn, file_path)?;
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
         })?;
+        self.record_removed(removed);
+        tracing::debug!(file = file_path, removed, "Removed file vectors");
+        Ok(removed)
+    }
 
-        // Collect all chunk IDs to remove
-        // Since chunk_id format is "file_path:chunk_index", we can optimize by checking prefix
-        let prefix = format!("{}:", file_path);
-        // Pre-allocate with reasonable capacity to reduce reallocations
-        let mut to_remove = Vec::with_capacity(100);
-        
-        for item in read_table.iter().map_err(|e| {
-            Error::Database(format!("Failed to iterate table: {}", e))
-        })? {
-            let (key, _value) = item.map_err(|e| {
-                Error::Database(format!("Failed to read table item: {}", e))
-            })?;
-            let key_str = key.value();
-            
-            // Check if key starts with our file_path prefix (optimization: avoid deserialization)
-            if key_str.starts_with(&prefix) {
-                to_remove.push(key_str.to_string());
-            }
-        }
-        
-        // Drop read transaction before starting write transaction
-        drop(read_table);
-        drop(read_txn);
-
-        // Now remove entries in a write transaction
-        if to_remove.is_empty() {
-            return Ok(0);
-        }
-
+    /// [`remove_file`](Self::remove_file) for several files in one transaction; returns the
+    /// number of chunks removed
+    pub fn remove_files(&self, file_paths: &[String]) -> Result<usize> {
         let write_txn = self.db.begin_write().map_err(|e| {
             Error::Database(format!("Failed to begin write transaction: {}", e))
         })?;
-
-        {
-            let mut table = write_txn.open_table(VECTORS_TABLE).map_err(|e| {
-                Error::Database(format!("Failed to open table: {}", e))
-            })?;
-
-            // Remove entries
-            for chunk_id in &to_remove {
-                table.remove(chunk_id.as_str()).map_err(|e| {
-                    Error::Database(format!("Failed to remove vector entry: {}", e))
-                })?;
-            }
+        let mut removed = 0;
+        for file_path in file_paths {
+            removed += remove_file_in(&write_txn, file_path)?;
         }
-
         write_txn.commit().map_err(|e| {
             Error::Database(format!("Failed to commit transaction: {}", e))
         })?;
-
-        Ok(to_remove.len())
+        self.record_removed(removed);
+        tracing::debug!(files = file_paths.len(), removed, "Removed vectors of several files");
+        Ok(removed)
     }
 
     /// Search for similar vectors using cosine similarity
     /// Uses a min-heap to efficiently maintain top K results without storing all vectors
     pub fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<(VectorEntry, f32)>> {
         use std::collections::BinaryHeap;
-        use std::cmp::Ordering;
 
         let read_txn = self.db.begin_read().map_err(|e| {
             Error::Database(format!("Failed to begin read transaction: {}", e))
@@ -256,9 +740,13 @@ impl VectorStore {
             let (_key, value) = item.map_err(|e| {
                 Error::Database(format!("Failed to read table item: {}", e))
             })?;
-            let json_str = value.value().to_string();
+            let json_str = decode_value(self.cipher.as_ref(), value.value())?;
             if let Ok(entry) = VectorEntry::from_json(&json_str) {
-                let similarity = cosine_similarity(query_embedding, &entry.embedding);
+                // Refuse to rank across embedding spaces
+                entry.check_embedding_source()?;
+                let Some(similarity) = self.ranked_similarity(query_embedding, &entry) else {
+                    continue;
+                };
                 
                 // Add to heap
                 heap.push(SimilarityEntry(entry, similarity));
@@ -276,8 +764,7 @@ impl VectorStore {
             .map(|se| (se.0, se.1))
             .collect();
         
-        // Sort descending by similarity
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        results.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
 
         Ok(results)
     }
@@ -290,7 +777,161 @@ impl VectorStore {
         limit: usize,
         allowed_files: &std::collections::HashSet<String>,
     ) -> Result<Vec<(VectorEntry, f32)>> {
-        use std::cmp::Ordering;
+        self.search_files_where(query_embedding, limit, |file_path| allowed_files.contains(file_path))
+    }
+
+    /// Store (or replace) the summary embedding of `file_path` used by two-stage search
+    pub fn set_document_vector(&self, file_path: &str, embedding: &[f32]) -> Result<()> {
+        let json_str = serde_json::to_string(embedding)
+            .map_err(|e| Error::Database(format!("Failed to serialize document vector: {}", e)))?;
+        let stored = encode_value(self.cipher.as_ref(), &json_str)?;
+
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+        {
+            let mut table = write_txn.open_table(DOC_VECTORS_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            table.insert(file_path, stored.as_ref()).map_err(|e| {
+                Error::Database(format!("Failed to insert document vector: {}", e))
+            })?;
+        }
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+        Ok(())
+    }
+
+    /// Store the frontmatter names of `file_path`; empty metadata stores nothing
+    pub fn set_file_meta(&self, file_path: &str, meta: &FileMeta) -> Result<()> {
+        if meta.is_empty() {
+            return Ok(());
+        }
+        let json_str = serde_json::to_string(meta)
+            .map_err(|e| Error::Database(format!("Failed to serialize file metadata: {}", e)))?;
+        let stored = encode_value(self.cipher.as_ref(), &json_str)?;
+
+        let write_txn = self.db.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+        {
+            let mut table = write_txn.open_table(FILE_META_TABLE).map_err(|e| {
+                Error::Database(format!("Failed to open table: {}", e))
+            })?;
+            table.insert(file_path, stored.as_ref()).map_err(|e| {
+                Error::Database(format!("Failed to insert file metadata: {}", e))
+            })?;
+        }
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+        Ok(())
+    }
+
+    /// Frontmatter names of every file that has any (empty for stores indexed before they
+    /// were kept)
+    pub fn file_meta(&self) -> Result<HashMap<String, FileMeta>> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+        let table = match read_txn.open_table(FILE_META_TABLE) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(HashMap::new()),
+            Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+        };
+
+        let mut names = HashMap::new();
+        for item in table.iter().map_err(|e| {
+            Error::Database(format!("Failed to iterate table: {}", e))
+        })? {
+            let (key, value) = item.map_err(|e| {
+                Error::Database(format!("Failed to read table item: {}", e))
+            })?;
+            let json_str = decode_value(self.cipher.as_ref(), value.value())?;
+            if let Ok(meta) = serde_json::from_str::<FileMeta>(&json_str) {
+                names.insert(key.value().to_string(), meta);
+            }
+        }
+        Ok(names)
+    }
+
+    /// Number of files with a document vector (0 for stores indexed before they were kept)
+    pub fn document_count(&self) -> Result<usize> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+        let table = match read_txn.open_table(DOC_VECTORS_TABLE) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(0),
+            Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+        };
+        let count = table.len().map_err(|e| {
+            Error::Database(format!("Failed to count document vectors: {}", e))
+        })?;
+        Ok(count as usize)
+    }
+
+    /// The `limit` files whose document vectors are most similar to the query, best first
+    /// (ties by path)
+    pub fn search_documents(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<(String, f32)>> {
+        let mut documents = self.document_similarities(query_embedding)?;
+        documents.truncate(limit);
+        Ok(documents)
+    }
+
+    /// Coarse-to-fine search: rank files by their document vectors, then score only the chunks
+    /// of the best `files` of them exactly. Decoding chunks is most of a search's cost, so this
+    /// is much faster on large stores, at the price of missing a strong chunk in a file whose
+    /// overall topic doesn't match. Files without a document vector (indexed before they were
+    /// kept) are always scored, so an older index is never silently narrowed.
+    pub fn search_two_stage(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        files: usize,
+    ) -> Result<Vec<(VectorEntry, f32)>> {
+        let documents = self.document_similarities(query_embedding)?;
+        let documented: std::collections::HashSet<&str> = documents.iter().map(|(path, _)| path.as_str()).collect();
+        let shortlist: std::collections::HashSet<&str> =
+            documents.iter().take(files).map(|(path, _)| path.as_str()).collect();
+        self.search_files_where(query_embedding, limit, |file_path| {
+            shortlist.contains(file_path) || !documented.contains(file_path)
+        })
+    }
+
+    /// Every document vector's similarity to the query, best first (ties by path)
+    fn document_similarities(&self, query_embedding: &[f32]) -> Result<Vec<(String, f32)>> {
+        let read_txn = self.db.begin_read().map_err(|e| {
+            Error::Database(format!("Failed to begin read transaction: {}", e))
+        })?;
+        let table = match read_txn.open_table(DOC_VECTORS_TABLE) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+        };
+
+        let mut documents = Vec::new();
+        for item in table.iter().map_err(|e| {
+            Error::Database(format!("Failed to iterate table: {}", e))
+        })? {
+            let (key, value) = item.map_err(|e| {
+                Error::Database(format!("Failed to read table item: {}", e))
+            })?;
+            let json_str = decode_value(self.cipher.as_ref(), value.value())?;
+            if let Ok(embedding) = serde_json::from_str::<Vec<f32>>(&json_str) {
+                documents.push((key.value().to_string(), self.similarity(query_embedding, &embedding)));
+            }
+        }
+        documents.sort_by(|a, b| score_order(a.1, b.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(documents)
+    }
+
+    /// Top `limit` chunks of the files `keep` accepts; other files' chunks aren't decoded
+    fn search_files_where<F>(&self, query_embedding: &[f32], limit: usize, keep: F) -> Result<Vec<(VectorEntry, f32)>>
+    where
+        F: Fn(&str) -> bool,
+    {
         use std::collections::BinaryHeap;
 
         let read_txn = self.db.begin_read().map_err(|e| {
@@ -311,15 +952,16 @@ impl VectorStore {
             })?;
 
             // Key format: "file_path:chunk_index" — check scope before deserializing.
-            let key_str = key.value();
-            let file_part = key_str.split(':').next().unwrap_or("");
-            if !allowed_files.contains(file_part) {
+            if !keep(chunk_file_path(key.value())) {
                 continue;
             }
 
-            let json_str = value.value().to_string();
+            let json_str = decode_value(self.cipher.as_ref(), value.value())?;
             if let Ok(entry) = VectorEntry::from_json(&json_str) {
-                let similarity = cosine_similarity(query_embedding, &entry.embedding);
+                entry.check_embedding_source()?;
+                let Some(similarity) = self.ranked_similarity(query_embedding, &entry) else {
+                    continue;
+                };
                 heap.push(SimilarityEntry(entry, similarity));
                 if heap.len() > limit {
                     heap.pop();
@@ -328,12 +970,63 @@ impl VectorStore {
         }
 
         let mut results: Vec<(VectorEntry, f32)> = heap.into_iter().map(|se| (se.0, se.1)).collect();
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        results.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
         Ok(results)
     }
 
+    /// Visit every entry in chunk ID order, one at a time; return `ControlFlow::Break(())`
+    /// from `visit` to stop early. Entries are decoded as they're reached, so memory stays
+    /// flat however large the store is. Entries that fail to deserialize are skipped;
+    /// decryption and database errors end the walk.
+    ///
+    /// The whole walk runs in one read transaction, which pins the snapshot it started with:
+    /// inserts and removals made from `visit` (or by another handle) commit normally but
+    /// aren't seen by this walk, and the pages they replace can't be reclaimed until it
+    /// returns. Keep `visit` quick on large stores, and collect changes to make afterwards
+    /// rather than holding the walk open around slow work.
+    pub fn for_each_entry<F>(&self, mut visit: F) -> Result<()>
+    where
+        F: FnMut(VectorEntry) -> ControlFlow<()>,
+    {
+        self.walk(|_, value| {
+            let json_str = decode_value(self.cipher.as_ref(), value)?;
+            Ok(match VectorEntry::from_json(&json_str) {
+                Ok(entry) => visit(entry),
+                Err(_) => ControlFlow::Continue(()),
+            })
+        })
+    }
+
+    /// Visit every chunk ID (`file_path:chunk_index`) in order without decoding any values,
+    /// for cheap path and scope scans. Same transaction caveats as
+    /// [`for_each_entry`](Self::for_each_entry).
+    pub fn for_each_key<F>(&self, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&str) -> ControlFlow<()>,
+    {
+        self.walk(|chunk_id, _| Ok(visit(chunk_id)))
+    }
+
+    /// Visit every entry belonging to `allowed_files` (every entry when the set is empty).
+    /// For one-off passes over the scope, such as building the TUI's completion dictionary.
+    pub fn for_each_scoped<F>(&self, allowed_files: &std::collections::HashSet<String>, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&VectorEntry),
+    {
+        self.walk(|chunk_id, value| {
+            if !allowed_files.is_empty() && !allowed_files.contains(chunk_file_path(chunk_id)) {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let json_str = decode_value(self.cipher.as_ref(), value)?;
+            if let Ok(entry) = VectorEntry::from_json(&json_str) {
+                visit(&entry);
+            }
+            Ok(ControlFlow::Continue(()))
+        })
+    }
+
     /// Get all vectors for a specific file
-    /// Optimized: Uses chunk_id prefix matching to avoid deserializing non-matching entries
+    /// Optimized: only reads the key range holding the file's chunks (see [`file_key_range`])
     pub fn get_file_vectors(&self, file_path: &str) -> Result<Vec<VectorEntry>> {
         let read_txn = self.db.begin_read().map_err(|e| {
             Error::Database(format!("Failed to begin read transaction: {}", e))
@@ -343,19 +1036,16 @@ impl VectorStore {
             Error::Database(format!("Failed to open table: {}", e))
         })?;
 
+        let (start, end) = file_key_range(file_path);
         let mut results = Vec::new();
-        let prefix = format!("{}:", file_path);
-
-        for item in table.iter().map_err(|e| {
+        for item in table.range::<&str>(start.as_str()..end.as_str()).map_err(|e| {
             Error::Database(format!("Failed to iterate table: {}", e))
         })? {
             let (key, value) = item.map_err(|e| {
                 Error::Database(format!("Failed to read table item: {}", e))
             })?;
-            
-            // Optimize: Check prefix before deserializing
-            if key.value().starts_with(&prefix) {
-                let json_str = value.value().to_string();
+            if chunk_file_path(key.value()) == file_path {
+                let json_str = decode_value(self.cipher.as_ref(), value.value())?;
                 if let Ok(entry) = VectorEntry::from_json(&json_str) {
                     results.push(entry);
                 }
@@ -370,6 +1060,19 @@ impl VectorStore {
 
     /// Get count of unique indexed files
     pub fn get_file_count(&self) -> Result<usize> {
+        let mut unique_files = std::collections::HashSet::new();
+        self.for_each_key(|chunk_id| {
+            unique_files.insert(chunk_file_path(chunk_id).to_string());
+            ControlFlow::Continue(())
+        })?;
+        Ok(unique_files.len())
+    }
+
+    /// Raw (chunk ID, stored value) pairs in key order, inside one read transaction
+    fn walk<F>(&self, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&str, &str) -> Result<ControlFlow<()>>,
+    {
         let read_txn = self.db.begin_read().map_err(|e| {
             Error::Database(format!("Failed to begin read transaction: {}", e))
         })?;
@@ -378,58 +1081,306 @@ impl VectorStore {
             Error::Database(format!("Failed to open table: {}", e))
         })?;
 
-        let mut unique_files = std::collections::HashSet::new();
-
         for item in table.iter().map_err(|e| {
             Error::Database(format!("Failed to iterate table: {}", e))
         })? {
-            let (key, _value) = item.map_err(|e| {
+            let (key, value) = item.map_err(|e| {
                 Error::Database(format!("Failed to read table item: {}", e))
             })?;
-            
-            // Extract file path from chunk_id (format: "file_path:chunk_index")
-            if let Some(file_path) = key.value().split(':').next() {
-                unique_files.insert(file_path.to_string());
+            if visit(key.value(), value.value())?.is_break() {
+                break;
             }
         }
 
-        Ok(unique_files.len())
+        Ok(())
+    }
+}
+
+/// File path part of a chunk ID (`file_path:chunk_index`); paths may contain `:` themselves
+pub(crate) fn chunk_file_path(chunk_id: &str) -> &str {
+    chunk_id.rsplit_once(':').map_or(chunk_id, |(file_path, _)| file_path)
+}
+
+/// Bounds of the chunk IDs that can belong to `file_path`: from `file_path:` up to, but not
+/// including, `file_path;` (`;` is the byte after `:`). Keys are ordered strings, so a range
+/// scan touches the file's own chunks and nothing else, apart from files whose names continue
+/// with `:`; callers still check [`chunk_file_path`] for those.
+fn file_key_range(file_path: &str) -> (String, String) {
+    (format!("{}:", file_path), format!("{};", file_path))
+}
+
+/// Higher scores first. Unlike `partial_cmp`, this is a total order: NaN sorts last
+/// instead of comparing equal to everything.
+pub fn score_order(a: f32, b: f32) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => b.total_cmp(&a),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
     }
 }
 
-/// Helper struct for maintaining top-K search results using a min-heap
+/// Order of ranked results: score descending, then file path, chunk index and collection
+/// ascending, so chunks with equal scores come out the same way on every run
+pub fn rank_order(a: &VectorEntry, a_score: f32, b: &VectorEntry, b_score: f32) -> Ordering {
+    score_order(a_score, b_score)
+        .then_with(|| a.file_path.cmp(&b.file_path))
+        .then_with(|| a.chunk_index.cmp(&b.chunk_index))
+        .then_with(|| a.collection.cmp(&b.collection))
+}
+
+/// Helper struct for maintaining top-K search results using a heap
 struct SimilarityEntry(VectorEntry, f32);
 
 impl PartialEq for SimilarityEntry {
     fn eq(&self, other: &Self) -> bool {
-        self.1 == other.1
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl Eq for SimilarityEntry {}
 
 impl PartialOrd for SimilarityEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        // Reverse ordering so BinaryHeap (a max-heap) behaves like a min-heap by similarity.
-        other.1.partial_cmp(&self.1)
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl std::cmp::Ord for SimilarityEntry {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Equal)
+impl Ord for SimilarityEntry {
+    /// Greater means ranked later, so the max-heap's top is the entry to drop
+    fn cmp(&self, other: &Self) -> Ordering {
+        rank_order(&self.0, self.1, &other.0, other.1)
     }
 }
 
-/// Calculate cosine similarity between two vectors
-/// Optimized: Since embeddings are L2-normalized, cosine similarity = dot product
-/// This avoids expensive sqrt operations and is ~2-3x faster
+/// Entries in the vectors table, read without a write transaction (None if the table is missing)
+fn count_entries(db: &Database) -> Result<Option<usize>> {
+    let read_txn = db.begin_read().map_err(|e| {
+        Error::Database(format!("Failed to begin read transaction: {}", e))
+    })?;
+    let table = match read_txn.open_table(VECTORS_TABLE) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(None),
+        Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+    };
+    let count = table.len().map_err(|e| {
+        Error::Database(format!("Failed to count vector entries: {}", e))
+    })?;
+    Ok(Some(count as usize))
+}
+
+/// Whether the store has been marked normalized (false if the meta table is missing)
+fn read_normalized_flag(db: &Database) -> Result<bool> {
+    let read_txn = db.begin_read().map_err(|e| {
+        Error::Database(format!("Failed to begin read transaction: {}", e))
+    })?;
+    let table = match read_txn.open_table(STORE_META_TABLE) {
+        Ok(table) => table,
+        Err(TableError::TableDoesNotExist(_)) => return Ok(false),
+        Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+    };
+    let flag = table.get(NORMALIZED_KEY).map_err(|e| {
+        Error::Database(format!("Failed to read store metadata: {}", e))
+    })?;
+    Ok(flag.is_some())
+}
+
+fn write_normalized_flag(write_txn: &WriteTransaction, normalized: bool) -> Result<()> {
+    let mut table = write_txn.open_table(STORE_META_TABLE).map_err(|e| {
+        Error::Database(format!("Failed to open table: {}", e))
+    })?;
+    let written = if normalized {
+        table.insert(NORMALIZED_KEY, "1").map(|_| ())
+    } else {
+        table.remove(NORMALIZED_KEY).map(|_| ())
+    };
+    written.map_err(|e| Error::Database(format!("Failed to write store metadata: {}", e)))
+}
+
+/// Open an existing vector database, explaining lock conflicts
+fn open_existing(db_path: &std::path::Path) -> Result<Database> {
+    Database::open(db_path).map_err(|e| {
+        let msg = e.to_string();
+        if msg.to_lowercase().contains("lock") {
+            Error::Database("Vector database is locked. Another notes2vec process may be running. Close other instances and try again.".to_string())
+        } else {
+            Error::Database(format!("Failed to open vector database: {}", e))
+        }
+    })
+}
+
+/// Copy every entry (values as stored, so encrypted stores stay encrypted) into a new
+/// database at `dest`, in batched transactions. Returns the number of entries copied.
+fn copy_live_entries(source: &Database, dest: &std::path::Path) -> Result<usize> {
+    let target = Database::create(dest)
+        .map_err(|e| Error::Database(format!("Failed to create vacuum database: {}", e)))?;
+    copy_meta(source, &target)?;
+    if read_normalized_flag(source)? {
+        let write_txn = target.begin_write().map_err(|e| {
+            Error::Database(format!("Failed to begin write transaction: {}", e))
+        })?;
+        write_normalized_flag(&write_txn, true)?;
+        write_txn.commit().map_err(|e| {
+            Error::Database(format!("Failed to commit transaction: {}", e))
+        })?;
+    }
+
+    let read_txn = source.begin_read().map_err(|e| {
+        Error::Database(format!("Failed to begin read transaction: {}", e))
+    })?;
+    let table = read_txn.open_table(VECTORS_TABLE).map_err(|e| {
+        Error::Database(format!("Failed to open table: {}", e))
+    })?;
+    let expected = table.len().map_err(|e| {
+        Error::Database(format!("Failed to count vector entries: {}", e))
+    })? as usize;
+
+    let mut batch: Vec<(String, String)> = Vec::with_capacity(VACUUM_BATCH_SIZE);
+    let mut copied = 0;
+    for item in table.iter().map_err(|e| {
+        Error::Database(format!("Failed to iterate table: {}", e))
+    })? {
+        let (key, value) = item.map_err(|e| {
+            Error::Database(format!("Failed to read table item: {}", e))
+        })?;
+        batch.push((key.value().to_string(), value.value().to_string()));
+        if batch.len() >= VACUUM_BATCH_SIZE {
+            copied += write_batch(&target, VECTORS_TABLE, &mut batch)?;
+        }
+    }
+    // Always commit once so the table exists even when the store is empty
+    copied += write_batch(&target, VECTORS_TABLE, &mut batch)?;
+
+    if copied != expected {
+        return Err(Error::Database(format!(
+            "Vacuum copied {} of {} entries; the original database was left in place",
+            copied, expected
+        )));
+    }
+
+    // Per-file tables are small, one row per file; stores indexed before they existed have none
+    for definition in [DOC_VECTORS_TABLE, FILE_META_TABLE] {
+        match read_txn.open_table(definition) {
+            Ok(table) => {
+                for item in table.iter().map_err(|e| {
+                    Error::Database(format!("Failed to iterate table: {}", e))
+                })? {
+                    let (key, value) = item.map_err(|e| {
+                        Error::Database(format!("Failed to read table item: {}", e))
+                    })?;
+                    batch.push((key.value().to_string(), value.value().to_string()));
+                    if batch.len() >= VACUUM_BATCH_SIZE {
+                        write_batch(&target, definition, &mut batch)?;
+                    }
+                }
+                write_batch(&target, definition, &mut batch)?;
+            }
+            Err(TableError::TableDoesNotExist(_)) => {}
+            Err(e) => return Err(Error::Database(format!("Failed to open table: {}", e))),
+        }
+    }
+
+    // Commits are durable already; sync the file once more before it replaces the original
+    drop(target);
+    std::fs::File::open(dest)?.sync_all()?;
+    Ok(copied)
+}
+
+/// What [`VectorStore::insert_in`] wrote, applied to the cached counters after commit
+#[derive(Debug, Clone, Copy, Default)]
+struct Inserted {
+    /// Chunks that didn't exist before (the rest replaced one with the same ID)
+    new_chunks: usize,
+    breaks_normalized: bool,
+}
+
+/// Remove `file_path`'s chunks, document vector and names within `write_txn`; returns the
+/// number of chunks removed
+fn remove_file_in(write_txn: &WriteTransaction, file_path: &str) -> Result<usize> {
+    let mut table = write_txn.open_table(VECTORS_TABLE).map_err(|e| {
+        Error::Database(format!("Failed to open table: {}", e))
+    })?;
+
+    // Chunk IDs are `file_path:chunk_index`, so the file's chunks sit together in one key
+    // range (alongside files whose own names continue with `:`, which are left alone)
+    let (start, end) = file_key_range(file_path);
+    let mut to_remove = Vec::new();
+    for item in table.range::<&str>(start.as_str()..end.as_str()).map_err(|e| {
+        Error::Database(format!("Failed to iterate table: {}", e))
+    })? {
+        let (key, _) = item.map_err(|e| {
+            Error::Database(format!("Failed to read table item: {}", e))
+        })?;
+        let chunk_id = key.value();
+        if chunk_file_path(chunk_id) == file_path {
+            to_remove.push(chunk_id.to_string());
+        }
+    }
+    for chunk_id in &to_remove {
+        table.remove(chunk_id.as_str()).map_err(|e| {
+            Error::Database(format!("Failed to remove vector entry: {}", e))
+        })?;
+    }
+
+    for (definition, what) in [(DOC_VECTORS_TABLE, "document vector"), (FILE_META_TABLE, "file metadata")] {
+        let mut table = write_txn.open_table(definition).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+        table.remove(file_path).map_err(|e| {
+            Error::Database(format!("Failed to remove {}: {}", what, e))
+        })?;
+    }
+
+    Ok(to_remove.len())
+}
+
+fn write_batch(
+    db: &Database,
+    definition: TableDefinition<&str, &str>,
+    batch: &mut Vec<(String, String)>,
+) -> Result<usize> {
+    let write_txn = db.begin_write().map_err(|e| {
+        Error::Database(format!("Failed to begin write transaction: {}", e))
+    })?;
+    {
+        let mut table = write_txn.open_table(definition).map_err(|e| {
+            Error::Database(format!("Failed to open table: {}", e))
+        })?;
+        for (key, value) in batch.iter() {
+            table.insert(key.as_str(), value.as_str()).map_err(|e| {
+                Error::Database(format!("Failed to insert vector entry: {}", e))
+            })?;
+        }
+    }
+    write_txn.commit().map_err(|e| {
+        Error::Database(format!("Failed to commit transaction: {}", e))
+    })?;
+    let written = batch.len();
+    batch.clear();
+    Ok(written)
+}
+
+/// Cosine similarity of two vectors of any length (0 if either is zero or they differ in size)
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let norms = l2_norm(a) * l2_norm(b);
+    if norms == 0.0 {
+        return 0.0;
+    }
+    dot_product(a, b) / norms
+}
+
+/// Straight-line distance between two vectors (infinite if they differ in size)
+fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return f32::INFINITY;
+    }
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum::<f32>().sqrt()
+}
+
+/// Cosine similarity of unit vectors, without the sqrt of [`cosine_similarity`]
+fn dot_product(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
         return 0.0;
     }
 
-    // For normalized vectors, cosine similarity = dot product
     // Use iterator with explicit SIMD-friendly pattern for better optimization
     a.iter()
         .zip(b.iter())
@@ -437,12 +1388,372 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         .sum()
 }
 
+fn l2_norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Unit length within [`NORM_TOLERANCE`], or all zeros
+fn is_unit_length(v: &[f32]) -> bool {
+    let norm = l2_norm(v);
+    norm == 0.0 || (norm - 1.0).abs() <= NORM_TOLERANCE
+}
+
+/// Scale `v` to unit length unless it already is (or can't be); true if it changed
+fn normalize_l2(v: &mut [f32]) -> bool {
+    let norm = l2_norm(v);
+    if !norm.is_finite() || is_unit_length(v) {
+        return false;
+    }
+    for x in v.iter_mut() {
+        *x /= norm;
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::config::Config;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_low_content_entries_are_down_weighted() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        let stub = VectorEntry::new("stubs.md".to_string(), 0, vec![1.0, 0.0], "milk".to_string(), String::new(), 1, 1)
+            .with_low_content(true);
+        let note = VectorEntry::new("note.md".to_string(), 0, vec![0.8, 0.6], "A real paragraph".to_string(), String::new(), 1, 3);
+
+        {
+            let store = VectorStore::open(&config).unwrap();
+            store.insert(&stub).unwrap();
+            store.insert(&note).unwrap();
+
+            // The stub matches the query exactly but still ranks below the real note
+            let results = store.search(&[1.0, 0.0], 10).unwrap();
+            assert_eq!(results[0].0.file_path, "note.md");
+            assert_eq!(results[1].0.file_path, "stubs.md");
+            assert!((results[1].1 - config.low_content_weight).abs() < 1e-6);
+            assert!(results[1].0.low_content);
+        }
+
+        // A weight of 0 excludes low-content chunks entirely
+        config.low_content_weight = 0.0;
+        let store = VectorStore::open(&config).unwrap();
+        let results = store.search(&[1.0, 0.0], 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.file_path, "note.md");
+        // ...but they're still stored for previews
+        assert_eq!(store.get_file_vectors("stubs.md").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_for_each_scoped_visits_only_scoped_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        let store = VectorStore::open(&config).unwrap();
+        for (file, chunk) in [("a.md", 0), ("a.md", 1), ("b.md", 0)] {
+            store
+                .insert(&VectorEntry::new(file.to_string(), chunk, vec![1.0], "text".to_string(), String::new(), 1, 1))
+                .unwrap();
+        }
+
+        let scope: std::collections::HashSet<String> = ["a.md".to_string()].into_iter().collect();
+        let mut seen = Vec::new();
+        store.for_each_scoped(&scope, |entry| seen.push(entry.chunk_id())).unwrap();
+        seen.sort();
+        assert_eq!(seen, vec!["a.md:0", "a.md:1"]);
+
+        let mut count = 0;
+        store.for_each_scoped(&Default::default(), |_| count += 1).unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_vacuum_reclaims_space_and_keeps_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        {
+            let store = VectorStore::open(&config).unwrap();
+            let text = "padding ".repeat(200);
+            for i in 0..300 {
+                let file = if i < 10 { "keep.md" } else { "gone.md" };
+                let entry = VectorEntry::new(file.to_string(), i, vec![0.5; 384], text.clone(), String::new(), 1, 1);
+                store.insert(&entry).unwrap();
+            }
+            store.set_document_vector("keep.md", &[0.6, 0.8]).unwrap();
+            store.set_document_vector("gone.md", &[1.0, 0.0]).unwrap();
+            let names = FileMeta { title: Some("Keeper".to_string()), aliases: vec!["Kept".to_string()] };
+            store.set_file_meta("keep.md", &names).unwrap();
+            store.set_file_meta("gone.md", &names).unwrap();
+            store.remove_file("gone.md").unwrap();
+            // Removing a file drops its document vector and names too
+            assert_eq!(store.document_count().unwrap(), 1);
+            assert_eq!(store.file_meta().unwrap().len(), 1);
+
+            // Refuses to run while the store is open for writing
+            let message = VectorStore::vacuum(&config).unwrap_err().to_string();
+            assert!(message.contains("Another notes2vec is indexing"), "{}", message);
+        }
+
+        let stats = VectorStore::vacuum(&config).unwrap();
+        assert_eq!(stats.entries, 10);
+        assert!(stats.size_after < stats.size_before);
+        assert!(!config.database_dir.join("vectors.redb.vacuum").exists());
+
+        let store = VectorStore::open(&config).unwrap();
+        assert_eq!(store.chunk_count(), 10);
+        assert_eq!(store.get_file_vectors("keep.md").unwrap().len(), 10);
+        let documents = store.search_documents(&[1.0, 0.0], 5).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].0, "keep.md");
+        assert_eq!(store.file_meta().unwrap().get("keep.md").unwrap().names().collect::<Vec<_>>(), ["Keeper", "Kept"]);
+    }
+
+    #[test]
+    fn test_for_each_entry_streams_a_large_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        // Bulk-load 10k entries across 100 files in a few transactions
+        let mut batch = Vec::new();
+        for i in 0..10_000 {
+            let entry = VectorEntry::new(format!("notes/{:03}.md", i % 100), i / 100, vec![0.6, 0.8], format!("chunk {}", i), String::new(), 1, 1);
+            batch.push((entry.chunk_id(), entry.to_json().unwrap()));
+            if batch.len() == VACUUM_BATCH_SIZE {
+                write_batch(&store.db, VECTORS_TABLE, &mut batch).unwrap();
+            }
+        }
+        write_batch(&store.db, VECTORS_TABLE, &mut batch).unwrap();
+
+        let mut visited = 0;
+        let mut last_id = String::new();
+        store
+            .for_each_entry(|entry| {
+                // Key order, one entry at a time
+                assert!(entry.chunk_id() > last_id);
+                last_id = entry.chunk_id();
+                visited += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(visited, 10_000);
+
+        let mut keys = 0;
+        store
+            .for_each_key(|_| {
+                keys += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(keys, 10_000);
+        assert_eq!(store.get_file_count().unwrap(), 100);
+
+        // Breaking stops the walk
+        let mut seen = 0;
+        store
+            .for_each_entry(|_| {
+                seen += 1;
+                if seen == 5 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert_eq!(seen, 5);
+
+        // A write made mid-walk commits but isn't part of the walk's snapshot
+        let mut first = true;
+        let mut during = 0;
+        store
+            .for_each_entry(|_| {
+                if first {
+                    first = false;
+                    let late = VectorEntry::new("zz-late.md".to_string(), 0, vec![1.0, 0.0], "late".to_string(), String::new(), 1, 1);
+                    store.insert(&late).unwrap();
+                }
+                during += 1;
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(during, 10_000);
+        assert_eq!(store.remove_file("zz-late.md").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_file_range_leaves_neighbouring_keys_alone() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+        // Keys sorting just before, inside and just after the range of `a.md`
+        for (file, chunks) in [("a.md", 12), ("a.md0", 1), ("a.md:b", 2), ("a.md;", 1), ("a.mdz", 1)] {
+            for index in 0..chunks {
+                store.insert(&VectorEntry::new(file.to_string(), index, vec![1.0], String::new(), String::new(), 1, 1)).unwrap();
+            }
+        }
+
+        let indices: Vec<usize> = store.get_file_vectors("a.md").unwrap().iter().map(|e| e.chunk_index).collect();
+        assert_eq!(indices, (0..12).collect::<Vec<_>>());
+        assert_eq!(store.remove_file("a.md").unwrap(), 12);
+        assert!(store.get_file_vectors("a.md").unwrap().is_empty());
+        for (file, chunks) in [("a.md0", 1), ("a.md:b", 2), ("a.md;", 1), ("a.mdz", 1)] {
+            assert_eq!(store.get_file_vectors(file).unwrap().len(), chunks, "{}", file);
+        }
+    }
+
+    #[test]
+    fn test_chunk_ids_with_colons_in_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+        for file in ["meeting", "meeting:notes"] {
+            store.insert(&VectorEntry::new(file.to_string(), 0, vec![1.0], "text".to_string(), String::new(), 1, 1)).unwrap();
+        }
+
+        assert_eq!(store.get_file_count().unwrap(), 2);
+        assert_eq!(store.get_file_vectors("meeting").unwrap().len(), 1);
+        assert_eq!(store.remove_file("meeting").unwrap(), 1);
+        assert_eq!(store.get_file_vectors("meeting:notes").unwrap().len(), 1);
+    }
+
+    fn version_of(file: &str, chunks: usize, version: &str) -> Vec<VectorEntry> {
+        (0..chunks)
+            .map(|i| VectorEntry::new(file.to_string(), i, vec![0.6, 0.8], format!("{} {}", version, i), String::new(), 1, 1))
+            .collect()
+    }
+
+    /// Every chunk of `file` has the same version, and there are as many as that version wrote
+    fn assert_whole(store: &VectorStore, file: &str, versions: &[(&str, usize)]) -> String {
+        let chunks = store.get_file_vectors(file).unwrap();
+        let version = chunks[0].text.split(' ').next().unwrap().to_string();
+        assert!(chunks.iter().all(|c| c.text.starts_with(&version)), "{} is mixed: {:?}", file, chunks);
+        let expected = versions.iter().find(|(v, _)| *v == version).unwrap().1;
+        assert_eq!(chunks.len(), expected, "{} is partly indexed", file);
+        version
+    }
+
+    #[test]
+    fn test_replace_file_is_all_or_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let old_names = FileMeta { title: Some("Old".to_string()), aliases: Vec::new() };
+        let versions = [("old", 3), ("new", 2)];
+
+        {
+            let store = VectorStore::open(&config).unwrap();
+            for file in ["a.md", "b.md", "c.md"] {
+                store.replace_file(file, &version_of(file, 3, "old"), Some(&[0.6, 0.8]), &old_names).unwrap();
+            }
+            assert_eq!(store.chunk_count(), 9);
+
+            // A write failing partway through (a chunk of another file) stores nothing
+            let mut entries = version_of("a.md", 2, "new");
+            entries.extend(version_of("b.md", 1, "new"));
+            assert!(store.replace_file("a.md", &entries, None, &FileMeta::default()).is_err());
+            assert_eq!(store.chunk_count(), 9);
+            assert_eq!(assert_whole(&store, "a.md", &versions), "old");
+            assert_eq!(store.document_count().unwrap(), 3);
+            assert_eq!(store.file_meta().unwrap()["a.md"], old_names);
+
+            // Interrupted between files: the first two are reindexed, then the run stops
+            for file in ["a.md", "b.md"] {
+                store.replace_file(file, &version_of(file, 2, "new"), None, &FileMeta::default()).unwrap();
+            }
+        }
+
+        let store = VectorStore::open(&config).unwrap();
+        assert_eq!(store.chunk_count(), 7);
+        assert_eq!(assert_whole(&store, "a.md", &versions), "new");
+        assert_eq!(assert_whole(&store, "b.md", &versions), "new");
+        assert_eq!(assert_whole(&store, "c.md", &versions), "old");
+        // The new versions replaced the document vectors and names too
+        assert_eq!(store.document_count().unwrap(), 1);
+        assert_eq!(store.file_meta().unwrap().keys().collect::<Vec<_>>(), ["c.md"]);
+    }
+
+    #[test]
+    fn test_normalize_all_repairs_unnormalized_embeddings() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        let entry = |file: &str, embedding: Vec<f32>| {
+            VectorEntry::new(file.to_string(), 0, embedding, "text".to_string(), String::new(), 1, 1)
+        };
+
+        {
+            let store = VectorStore::open(&config).unwrap();
+            store.insert(&entry("long.md", vec![3.0, 4.0])).unwrap();
+            store.insert(&entry("unit.md", vec![0.6, 0.8])).unwrap();
+            store.insert(&entry("blank.md", vec![0.0, 0.0])).unwrap();
+            assert!(!store.is_normalized());
+
+            // Not yet verified, so ranking divides by the norms
+            let results = store.search(&[0.6, 0.8], 1).unwrap();
+            assert!((results[0].1 - 1.0).abs() < 1e-6);
+
+            assert_eq!(store.normalize_all().unwrap(), 1);
+            assert!(store.is_normalized());
+            let repaired = store.get("long.md:0").unwrap().unwrap();
+            assert!((repaired.embedding[0] - 0.6).abs() < 1e-6);
+            assert!((repaired.embedding[1] - 0.8).abs() < 1e-6);
+            assert_eq!(store.get("blank.md:0").unwrap().unwrap().embedding, vec![0.0, 0.0]);
+            assert_eq!(store.normalize_all().unwrap(), 0);
+        }
+
+        // The flag is stored with the index, and an unnormalized insert clears it
+        let store = VectorStore::open(&config).unwrap();
+        assert!(store.is_normalized());
+        store.insert(&entry("unit2.md", vec![1.0, 0.0])).unwrap();
+        assert!(store.is_normalized());
+        store.insert(&entry("long2.md", vec![2.0, 0.0])).unwrap();
+        assert!(!store.is_normalized());
+        drop(store);
+        assert!(!VectorStore::open(&config).unwrap().is_normalized());
+    }
+
+    #[test]
+    fn test_similarity_metric_changes_ranking() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+
+        let entry = |file: &str, embedding: Vec<f32>| {
+            VectorEntry::new(file.to_string(), 0, embedding, "text".to_string(), String::new(), 1, 1)
+        };
+        {
+            let store = VectorStore::open(&config).unwrap();
+            // Same direction as the query but twice as long, vs. slightly off but close by
+            store.insert(&entry("long.md", vec![2.0, 0.0])).unwrap();
+            store.insert(&entry("near.md", vec![0.9, 0.1])).unwrap();
+        }
+        let mut top = |metric| {
+            config.similarity_metric = metric;
+            let store = VectorStore::open(&config).unwrap();
+            store.search(&[1.0, 0.0], 1).unwrap().remove(0)
+        };
+
+        assert_eq!(top(SimilarityMetric::Cosine).0.file_path, "long.md");
+        let (best, similarity) = top(SimilarityMetric::Dot);
+        assert_eq!(best.file_path, "long.md");
+        assert!((similarity - 2.0).abs() < 1e-6);
+        let (best, similarity) = top(SimilarityMetric::Euclidean);
+        assert_eq!(best.file_path, "near.md");
+        assert!(similarity > 0.0 && similarity <= 1.0);
+    }
+
     #[test]
     fn test_cosine_similarity() {
         let a = vec![1.0, 0.0];
@@ -547,6 +1858,27 @@ mod tests {
         assert_eq!(deserialized.end_line, entry.end_line);
     }
 
+    #[test]
+    fn test_vector_entry_metadata_is_stored() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        let metadata: HashMap<String, String> = [("status".to_string(), "draft".to_string())].into_iter().collect();
+        let entry = VectorEntry::new("a.md".to_string(), 0, vec![1.0], "text".to_string(), String::new(), 1, 1)
+            .with_metadata(metadata.clone());
+        store.insert(&entry).unwrap();
+        assert_eq!(store.get("a.md:0").unwrap().unwrap().metadata, metadata);
+
+        // Entries written before metadata was kept have none, and entries without any don't store it
+        let old = r#"{"file_path":"b.md","chunk_index":0,"embedding":[1.0],"text":"t","context":"","start_line":1,"end_line":1}"#;
+        assert!(VectorEntry::from_json(old).unwrap().metadata.is_empty());
+        assert_eq!(VectorEntry::from_json(old).unwrap().source_span(), None);
+        let bare = VectorEntry::new("b.md".to_string(), 0, vec![1.0], "t".to_string(), String::new(), 1, 1);
+        assert!(!bare.to_json().unwrap().contains("metadata"));
+    }
+
     #[test]
     fn test_vector_store_insert_and_get() {
         let temp_dir = TempDir::new().unwrap();
@@ -695,6 +2027,37 @@ mod tests {
         assert_eq!(retrieved_indices, chunk_indices);
     }
 
+    #[test]
+    fn test_vector_store_chunk_count() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_dir = temp_dir.path().join("test_notes2vec");
+        let config = Config::new(Some(base_dir)).unwrap();
+        config.init().unwrap();
+
+        {
+            let store = VectorStore::open(&config).unwrap();
+            assert_eq!(store.chunk_count(), 0);
+
+            for i in 0..4 {
+                let entry = VectorEntry::new("a.md".to_string(), i, vec![1.0], String::new(), String::new(), 1, 1);
+                store.insert(&entry).unwrap();
+            }
+            // Overwriting an existing chunk doesn't change the count
+            let entry = VectorEntry::new("a.md".to_string(), 0, vec![0.5], String::new(), String::new(), 1, 1);
+            store.insert(&entry).unwrap();
+            let entry = VectorEntry::new("b.md".to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1);
+            store.insert(&entry).unwrap();
+            assert_eq!(store.chunk_count(), 5);
+
+            store.remove_file("a.md").unwrap();
+            assert_eq!(store.chunk_count(), 1);
+        }
+
+        // Loaded from the table on open
+        let store = VectorStore::open(&config).unwrap();
+        assert_eq!(store.chunk_count(), 1);
+    }
+
     #[test]
     fn test_vector_store_get_file_count() {
         let temp_dir = TempDir::new().unwrap();
@@ -815,5 +2178,42 @@ mod tests {
             assert!(results[i].1 >= results[i + 1].1);
         }
     }
+
+    #[test]
+    fn test_equal_scores_rank_by_path_then_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+
+        for file in ["m.md", "c.md", "x.md", "a.md", "k.md"] {
+            for chunk in (0..8).rev() {
+                let entry = VectorEntry::new(file.to_string(), chunk, vec![0.6, 0.8], "same".to_string(), String::new(), 1, 1);
+                store.insert(&entry).unwrap();
+            }
+        }
+
+        let ids = |results: Vec<(VectorEntry, f32)>| -> Vec<String> {
+            results.into_iter().map(|(entry, _)| entry.chunk_id()).collect()
+        };
+        let first = ids(store.search(&[1.0, 0.0], 12).unwrap());
+        assert_eq!(first, ids(store.search(&[1.0, 0.0], 12).unwrap()));
+        let mut expected: Vec<String> = (0..8).map(|chunk| format!("a.md:{}", chunk)).collect();
+        expected.extend((0..4).map(|chunk| format!("c.md:{}", chunk)));
+        assert_eq!(first, expected);
+
+        let scope: std::collections::HashSet<String> = ["x.md".to_string(), "k.md".to_string()].into_iter().collect();
+        let scoped = ids(store.search_scoped(&[1.0, 0.0], 3, &scope).unwrap());
+        assert_eq!(scoped, vec!["k.md:0", "k.md:1", "k.md:2"]);
+        assert_eq!(scoped, ids(store.search_scoped(&[1.0, 0.0], 3, &scope).unwrap()));
+    }
+
+    #[test]
+    fn test_score_order_puts_nan_last() {
+        let mut scores = [0.5, f32::NAN, 0.9, -0.2];
+        scores.sort_by(|a, b| score_order(*a, *b));
+        assert_eq!(&scores[..3], &[0.9, 0.5, -0.2]);
+        assert!(scores[3].is_nan());
+    }
 }
 
@@ -1,4 +1,17 @@
+use crate::indexing::discovery::DiscoveredFile;
+use crate::indexing::parser::ChunkKind;
+use crate::search::calibration::ConfidenceThresholds;
+use crate::search::collections::CollectionHit;
+use crate::search::explain::ScoreBreakdown;
+use crate::search::files::FileResult;
+use crate::storage::vectors::VectorEntry;
+use crate::core::doctor::{DoctorReport, Severity};
+use crate::core::duration::{parse_date, parse_duration};
 use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
 
 /// notes2vec - Local semantic search for personal notes
 #[derive(Parser, Debug)]
@@ -26,6 +39,52 @@ pub struct Cli {
     /// Use interactive TUI mode
     #[arg(short, long)]
     pub interactive: bool,
+
+    /// Suppress status output, printing only results and errors
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Show raw cosine scores in the TUI instead of calibrated match percentages
+    #[arg(long, global = true)]
+    pub raw_scores: bool,
+
+    /// Skip the welcome screen and start on directory selection
+    #[arg(long, global = true)]
+    pub no_welcome: bool,
+
+    /// Don't paint the TUI's RGB background (for terminals that render it as solid blocks)
+    #[arg(long, global = true)]
+    pub no_background: bool,
+
+    /// Show a plain title instead of the ASCII art on the welcome screen
+    #[arg(long, global = true)]
+    pub no_ascii_art: bool,
+
+    /// Use this collection's index instead of the default one (created on first `init` or `index`).
+    /// Repeat it with `search` to search several collections together.
+    #[arg(long, global = true, value_name = "NAME", action = clap::ArgAction::Append)]
+    pub collection: Vec<String>,
+
+    /// Use the embedding model files from this revision (commit, branch or tag) of its repository
+    #[arg(long, global = true, value_name = "REV")]
+    pub model_revision: Option<String>,
+
+    /// File the TUI appends snippets to with `a` (default: <base-dir>/scratch.md)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub scratch_file: Option<String>,
+
+    /// Seed for commands that sample stored entries at random, so repeated runs pick the same ones
+    #[arg(long, global = true, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Diagnostics to log: off, error, warn (default), info, debug or trace, optionally per
+    /// module like RUST_LOG (`warn,notes2vec::indexing=debug`); overrides RUST_LOG
+    #[arg(long, global = true, value_name = "LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Also append diagnostics to this file as JSON lines
+    #[arg(long, global = true, value_name = "PATH")]
+    pub log_file: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -35,25 +94,67 @@ pub enum Commands {
         /// Custom base directory (default: ~/.notes2vec)
         #[arg(short, long)]
         base_dir: Option<String>,
+        /// Encrypt stored chunk text, embeddings and file state (passphrase from NOTES2VEC_PASSPHRASE)
+        #[arg(long, conflicts_with = "no_encrypt")]
+        encrypt: bool,
+        /// Store values unencrypted (the default)
+        #[arg(long)]
+        no_encrypt: bool,
     },
     /// Index notes from a directory
     Index {
-        /// Path to the notes directory
-        path: String,
+        /// Path to the notes directory, or a .zip of notes to index without extracting
+        /// (default: the collection's configured path)
+        path: Option<String>,
         /// Force re-indexing of all files
         #[arg(short, long)]
         force: bool,
+        /// Hash every file instead of skipping those whose timestamp and size are unchanged
+        #[arg(long)]
+        paranoid: bool,
+        /// Index even if some files were indexed from another folder under the same relative
+        /// path, replacing that folder's vectors for them
+        #[arg(long)]
+        force_overwrite: bool,
+        /// Re-embed every file when the model's files changed since the last index (same model
+        /// id, different checksums) instead of stopping
+        #[arg(long)]
+        accept_new_model: bool,
         /// Custom base directory (default: ~/.notes2vec)
         #[arg(long)]
         base_dir: Option<String>,
+        /// Print every warning instead of only the first few
+        #[arg(short, long)]
+        verbose: bool,
+        /// Output format for the indexing report
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+        /// Also index hidden files and folders (.git and .notes2vec are always skipped)
+        #[arg(long, conflicts_with = "exclude_hidden")]
+        include_hidden: bool,
+        /// Skip hidden files and folders (the default unless include_hidden is set in config)
+        #[arg(long)]
+        exclude_hidden: bool,
     },
     /// Watch a directory for changes and automatically update index
     Watch {
-        /// Path to the notes directory
-        path: String,
+        /// Path to the notes directory (default: the collection's configured path)
+        path: Option<String>,
         /// Custom base directory (default: ~/.notes2vec)
         #[arg(long)]
         base_dir: Option<String>,
+        /// Also index hidden files and folders (.git and .notes2vec are always skipped)
+        #[arg(long, conflicts_with = "exclude_hidden")]
+        include_hidden: bool,
+        /// Skip hidden files and folders (the default unless include_hidden is set in config)
+        #[arg(long)]
+        exclude_hidden: bool,
+        /// Answer `notes2vec search` over a socket in the base directory with the loaded model (unix only)
+        #[arg(long)]
+        serve_socket: bool,
+        /// Print what the running watcher last reported (watch-status.json) instead of watching
+        #[arg(long, conflicts_with_all = ["path", "include_hidden", "exclude_hidden", "serve_socket"])]
+        status: bool,
     },
     /// Search your notes
     Search {
@@ -68,6 +169,697 @@ pub enum Commands {
         /// Use interactive TUI mode
         #[arg(short, long)]
         interactive: bool,
+        /// Nearest chunks to consider before de-duplicating by file (default: sized from the index)
+        #[arg(long)]
+        candidates: Option<usize>,
+        /// Rank whole files by the mean similarity of their best chunks
+        #[arg(long)]
+        by_file: bool,
+        /// Show how each result's score was assembled (cosine, lexical, path, recency, rerank)
+        #[arg(long, conflicts_with = "by_file")]
+        explain: bool,
+        /// Search every collection and rank the results together
+        #[arg(long, conflicts_with_all = ["by_file", "interactive"])]
+        all_collections: bool,
+        /// Print only the matching files' absolute paths, one per line, best match first
+        #[arg(long, conflicts_with_all = ["by_file", "explain", "interactive", "all_collections"])]
+        files_only: bool,
+        /// With --files-only, end each path with a NUL byte instead of a newline (for xargs -0)
+        #[arg(long, requires = "files_only")]
+        null: bool,
+        /// Print each result as a JSON object on its own line, flushed as it's written (for editors)
+        #[arg(long, conflicts_with_all = ["by_file", "files_only", "interactive"])]
+        stream: bool,
+        /// Only results from notes modified within this window, e.g. 24h, 7d or 2w
+        /// (in the interactive search, type `since:7d` instead)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "interactive")]
+        changed_since: Option<Duration>,
+        /// Only results from notes dated on or after this day (YYYY-MM-DD): the frontmatter's
+        /// `modified` or `created` date, else the file's modification time
+        #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with = "interactive")]
+        after: Option<u64>,
+        /// Only results from notes dated before this day (YYYY-MM-DD), dated as for --after
+        #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with = "interactive")]
+        before: Option<u64>,
+        /// Rank files by their document vectors first, then score only the chunks of the
+        /// best `two_stage_files` of them (much faster on large indexes; may miss a stray chunk)
+        #[arg(long, conflicts_with_all = ["by_file", "interactive"])]
+        two_stage: bool,
+        /// Blend the best `expand_chunks` matches into the query and search again; helps
+        /// short queries find passages that don't share their words
+        #[arg(long, conflicts_with = "interactive")]
+        expand: bool,
+        /// Print each result's lines from the note itself, with this many lines before the match
+        #[arg(long, value_name = "N", conflicts_with_all = ["by_file", "files_only", "stream", "interactive", "all_collections"])]
+        context_lines_before: Option<usize>,
+        /// Print each result's lines from the note itself, with this many lines after the match
+        #[arg(long, value_name = "N", conflicts_with_all = ["by_file", "files_only", "stream", "interactive", "all_collections"])]
+        context_lines_after: Option<usize>,
+        /// Output format; json prints one document with the query, model, threshold and
+        /// candidate count alongside the results
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = OutputFormat::Text,
+            conflicts_with_all = ["by_file", "files_only", "stream", "interactive", "all_collections", "context_lines_before", "context_lines_after"]
+        )]
+        format: OutputFormat,
+    },
+    /// List the most recently indexed files
+    Recent {
+        /// Number of files to show
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Import a Notion export (zip or directory) or plain-text notes, then index them
+    ImportNotes {
+        /// Export zip, export directory, or a single exported note
+        source: String,
+        /// Notes directory to copy the cleaned files into
+        #[arg(long)]
+        dest: String,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+        /// Only copy the files; don't index the destination afterwards
+        #[arg(long)]
+        no_index: bool,
+    },
+    /// View or change settings stored in config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Take files out of the index without touching them: a file, every file under a folder,
+    /// or the paths matching a glob (`*` stays within a folder, `**` crosses folders)
+    Remove {
+        /// Indexed path or glob, relative to the indexed directory
+        pattern: String,
+        /// List what would be removed without removing it
+        #[arg(long)]
+        dry_run: bool,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Rebuild the vector database to reclaim space after large deletions, and re-normalize
+    /// any embeddings that aren't unit length
+    Vacuum {
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// List the files `index` would pick up, after ignore rules and the hidden-file setting
+    Files {
+        /// Notes directory (default: the collection's configured path)
+        path: Option<String>,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Diagnose common setup problems (model files, locks, mixed embeddings, empty index)
+    /// without changing anything. Exits 1 if anything warned and 2 if anything failed.
+    Doctor {
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Check that every indexed file still matches its source, without changing anything
+    Verify {
+        /// Notes directory (default: the directory last indexed)
+        path: Option<String>,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+        /// Reindex stale files and remove files that no longer exist from the index
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Find the notes most related to an indexed note
+    Similar {
+        /// Note path, relative to the indexed directory
+        file: String,
+        /// Number of similar files to show
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Chunk and embed ad-hoc text, then show its nearest indexed neighbors
+    Embed {
+        /// Text to embed (omit and pass --stdin to read from standard input)
+        text: Option<String>,
+        /// Read the text from standard input
+        #[arg(long, conflicts_with = "text")]
+        stdin: bool,
+        /// Print the embedding vectors instead of searching the index
+        #[arg(long)]
+        vectors: bool,
+        /// Number of neighbors to show per chunk
+        #[arg(short, long, default_value_t = 5)]
+        limit: usize,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
     },
 }
 
+/// `config` subcommands
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the resolved paths and effective settings (config.toml, then NOTES2VEC_* variables,
+    /// then flags), each marked with where it came from
+    Show {
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Print the location of config.toml, for scripts
+    Path {
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+    /// Write a setting to config.toml ("auto" restores the default)
+    Set {
+        /// Setting name, e.g. model_id or score_min
+        key: String,
+        /// New value
+        value: String,
+        /// Custom base directory (default: ~/.notes2vec)
+        #[arg(long)]
+        base_dir: Option<String>,
+    },
+}
+
+
+/// Output format for command reports
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// Machine-readable JSON
+    Json,
+}
+
+/// Write non-interactive search results.
+/// In quiet mode only the result blocks are written, without the header and count lines.
+pub fn write_search_results<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    quiet: bool,
+) -> std::io::Result<()> {
+    write_search_results_explained(out, query, results, &[], quiet)
+}
+
+/// Like [`write_search_results`], with a score breakdown line for each result that has one
+pub fn write_search_results_explained<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    breakdowns: &[ScoreBreakdown],
+    quiet: bool,
+) -> std::io::Result<()> {
+    write_search_results_with(out, query, results, breakdowns, None, quiet)
+}
+
+/// Like [`write_search_results_explained`], with a confidence label after each similarity
+/// when `confidence` is set
+pub fn write_search_results_with<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    breakdowns: &[ScoreBreakdown],
+    confidence: Option<ConfidenceThresholds>,
+    quiet: bool,
+) -> std::io::Result<()> {
+    let notes = ResultNotes {
+        breakdowns,
+        ..ResultNotes::default()
+    };
+    write_labelled_results(out, query, results, &notes, confidence, quiet)
+}
+
+/// Like [`write_search_results_with`], with each result's source lines in place of its preview
+/// (`--context-lines-before`/`--context-lines-after`). A result without source lines (say its
+/// note was deleted) keeps the preview.
+pub fn write_search_results_in_context<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    breakdowns: &[ScoreBreakdown],
+    sources: &[Vec<SourceLine>],
+    confidence: Option<ConfidenceThresholds>,
+    quiet: bool,
+) -> std::io::Result<()> {
+    let notes = ResultNotes {
+        breakdowns,
+        sources,
+        ..ResultNotes::default()
+    };
+    write_labelled_results(out, query, results, &notes, confidence, quiet)
+}
+
+/// Source lines around each result (`--context-lines-before`/`--context-lines-after`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextLines {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl ContextLines {
+    /// None unless at least one of the flags was given; a missing one counts as 0
+    pub fn from_flags(before: Option<usize>, after: Option<usize>) -> Option<Self> {
+        if before.is_none() && after.is_none() {
+            return None;
+        }
+        Some(Self {
+            before: before.unwrap_or(0),
+            after: after.unwrap_or(0),
+        })
+    }
+}
+
+/// One line of a note, numbered from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    pub number: usize,
+    pub text: String,
+    /// Inside the matched chunk's line range, rather than context around it
+    pub in_chunk: bool,
+}
+
+/// Lines `start_line..=end_line` of the file at `path` plus `context` lines either side,
+/// clamped to the file. Empty when the file has shrunk past `start_line` since indexing.
+pub fn read_source_lines(
+    path: &Path,
+    start_line: usize,
+    end_line: usize,
+    context: ContextLines,
+) -> std::io::Result<Vec<SourceLine>> {
+    let contents = std::fs::read_to_string(path)?;
+    let start = start_line.max(1);
+    let end = end_line.max(start);
+    let first = start.saturating_sub(context.before).max(1);
+    let last = end.saturating_add(context.after);
+    if start > contents.lines().count() {
+        return Ok(Vec::new());
+    }
+    Ok(contents
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text))
+        .skip(first - 1)
+        .take_while(|(number, _)| *number <= last)
+        .map(|(number, text)| SourceLine {
+            number,
+            text: text.to_string(),
+            in_chunk: (start..=end).contains(&number),
+        })
+        .collect())
+}
+
+/// Results of `search --all-collections`, each path prefixed with its collection
+pub fn write_collection_results<W: Write>(
+    out: &mut W,
+    query: &str,
+    hits: &[CollectionHit],
+    breakdowns: &[ScoreBreakdown],
+    confidence: Option<ConfidenceThresholds>,
+    quiet: bool,
+) -> std::io::Result<()> {
+    let results: Vec<(VectorEntry, f32)> = hits.iter().map(|hit| (hit.entry.clone(), hit.similarity)).collect();
+    let labels: Vec<&str> = hits.iter().map(|hit| hit.collection.as_str()).collect();
+    let notes = ResultNotes {
+        labels: &labels,
+        breakdowns,
+        ..ResultNotes::default()
+    };
+    write_labelled_results(out, query, &results, &notes, confidence, quiet)
+}
+
+/// What's printed with each result besides the entry itself, indexed like the results
+/// (a result past the end of a list has none)
+#[derive(Default)]
+struct ResultNotes<'a> {
+    /// Collection names
+    labels: &'a [&'a str],
+    breakdowns: &'a [ScoreBreakdown],
+    /// Lines read from the notes, shown instead of the preview
+    sources: &'a [Vec<SourceLine>],
+}
+
+fn write_labelled_results<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    notes: &ResultNotes,
+    confidence: Option<ConfidenceThresholds>,
+    quiet: bool,
+) -> std::io::Result<()> {
+    if !quiet {
+        writeln!(out, "Searching for: \"{}\"", query)?;
+        if results.is_empty() {
+            writeln!(out, "\nNo results found.")?;
+        } else {
+            writeln!(out, "\nFound {} results:", results.len())?;
+        }
+    }
+
+    for (i, (entry, similarity)) in results.iter().enumerate() {
+        if !quiet || i > 0 {
+            writeln!(out)?;
+        }
+        match notes.labels.get(i) {
+            Some(label) => write!(out, "{}. [{}] {} (similarity: {:.3})", i + 1, label, entry.file_path, similarity)?,
+            None => write!(out, "{}. {} (similarity: {:.3})", i + 1, entry.file_path, similarity)?,
+        }
+        match confidence {
+            Some(thresholds) => writeln!(out, " [{}]", thresholds.label(*similarity))?,
+            None => writeln!(out)?,
+        }
+        if let Some(breakdown) = notes.breakdowns.get(i) {
+            writeln!(out, "   Score: {}", breakdown)?;
+        }
+        if !entry.context.is_empty() {
+            writeln!(out, "   Context: {}", entry.context)?;
+        }
+        if entry.kind == ChunkKind::Metadata {
+            writeln!(out, "   (no body; matched on frontmatter)")?;
+        }
+        match notes.sources.get(i).filter(|lines| !lines.is_empty()) {
+            Some(lines) => write_source_lines(out, lines)?,
+            None => {
+                // Show preview of text (first 150 chars)
+                let preview: String = entry.text.chars().take(150).collect();
+                writeln!(out, "   Preview: {}...", preview)?;
+            }
+        }
+        writeln!(out, "   Lines: {}-{}", entry.start_line, entry.end_line)?;
+    }
+
+    Ok(())
+}
+
+/// Numbered like grep: `:` after the chunk's own lines, `-` after the context around them
+fn write_source_lines<W: Write>(out: &mut W, lines: &[SourceLine]) -> std::io::Result<()> {
+    let width = lines.last().map_or(1, |line| line.number.to_string().len());
+    for line in lines {
+        let separator = if line.in_chunk { ':' } else { '-' };
+        writeln!(out, "   {:>width$}{} {}", line.number, separator, line.text, width = width)?;
+    }
+    Ok(())
+}
+
+/// One line of `search --stream`
+#[derive(Debug, Serialize)]
+pub struct StreamedResult<'a> {
+    /// 1-based position in the ranking
+    pub rank: usize,
+    /// Only set when several collections were searched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<&'a str>,
+    pub file_path: &'a str,
+    pub similarity: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<&'static str>,
+    pub context: &'a str,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Byte range of the chunk in the note; left out for entries indexed before it was kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_offset: Option<usize>,
+    pub text: &'a str,
+    /// With `--explain`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<&'a ScoreBreakdown>,
+}
+
+/// Write ranked results as JSON lines (`search --stream`), flushing after each line so a reader
+/// gets every result as soon as it's written. `labels` are the results' collections, if any.
+pub fn write_streamed_results<W: Write>(
+    out: &mut W,
+    results: &[(VectorEntry, f32)],
+    labels: &[&str],
+    breakdowns: &[ScoreBreakdown],
+    confidence: Option<ConfidenceThresholds>,
+) -> std::io::Result<()> {
+    for record in streamed_results(results, labels, breakdowns, confidence) {
+        serde_json::to_writer(&mut *out, &record)?;
+        writeln!(out)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Ranked results as the records `search --stream` writes, one per result
+pub fn streamed_results<'a>(
+    results: &'a [(VectorEntry, f32)],
+    labels: &[&'a str],
+    breakdowns: &'a [ScoreBreakdown],
+    confidence: Option<ConfidenceThresholds>,
+) -> Vec<StreamedResult<'a>> {
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, (entry, similarity))| StreamedResult {
+            rank: i + 1,
+            collection: labels.get(i).copied(),
+            file_path: &entry.file_path,
+            similarity: *similarity,
+            confidence: confidence.map(|thresholds| thresholds.label(*similarity).as_str()),
+            context: &entry.context,
+            start_line: entry.start_line,
+            end_line: entry.end_line,
+            start_offset: entry.source_span().map(|span| span.start),
+            end_offset: entry.source_span().map(|span| span.end),
+            text: &entry.text,
+            score: breakdowns.get(i),
+        })
+        .collect()
+}
+
+/// `search --format json`: the results with what a paging UI needs to know about them
+#[derive(Debug, Serialize)]
+pub struct SearchReport<'a> {
+    pub query: &'a str,
+    pub model_id: &'a str,
+    /// Similarity at which the display score bottoms out (`score_min`, else the model's default)
+    pub threshold: f32,
+    /// Candidate chunks ranked, after filters, before keeping the best one per file
+    pub total_candidates_considered: usize,
+    /// Length of `results`
+    pub returned: usize,
+    pub results: Vec<StreamedResult<'a>>,
+}
+
+/// Write `report` as one pretty-printed JSON document
+pub fn write_search_report<W: Write>(out: &mut W, report: &SearchReport) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, report)?;
+    writeln!(out)
+}
+
+/// Write ranked results as a Markdown report: a heading with the query, then a section per
+/// result with its location, score and the chunk quoted in full
+pub fn write_markdown_report<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    breakdowns: &[ScoreBreakdown],
+    confidence: Option<ConfidenceThresholds>,
+) -> std::io::Result<()> {
+    writeln!(out, "# Search: {}", query)?;
+    writeln!(out)?;
+    match results.len() {
+        0 => writeln!(out, "No results found.")?,
+        1 => writeln!(out, "1 result.")?,
+        n => writeln!(out, "{} results.", n)?,
+    }
+
+    for (i, (entry, similarity)) in results.iter().enumerate() {
+        writeln!(out)?;
+        writeln!(out, "## {}. {}:{}-{}", i + 1, entry.file_path, entry.start_line, entry.end_line)?;
+        writeln!(out)?;
+        match confidence {
+            Some(thresholds) => writeln!(out, "Similarity: {:.3} ({})", similarity, thresholds.label(*similarity).as_str())?,
+            None => writeln!(out, "Similarity: {:.3}", similarity)?,
+        }
+        if let Some(breakdown) = breakdowns.get(i) {
+            writeln!(out, "Score: {}", breakdown)?;
+        }
+        if !entry.context.is_empty() {
+            writeln!(out, "Context: {}", entry.context)?;
+        }
+        writeln!(out)?;
+        for line in entry.text.trim_end().lines() {
+            if line.is_empty() {
+                writeln!(out, ">")?;
+            } else {
+                writeln!(out, "> {}", line)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Paths exactly as they are, each followed by a newline or a NUL byte (`search --files-only`)
+pub fn write_file_paths<W: Write>(out: &mut W, paths: &[std::path::PathBuf], null: bool) -> std::io::Result<()> {
+    let terminator: &[u8] = if null { b"\0" } else { b"\n" };
+    for path in paths {
+        out.write_all(path.as_os_str().as_encoded_bytes())?;
+        out.write_all(terminator)?;
+    }
+    Ok(())
+}
+
+/// Relative paths of discovered files, one per line, then their count unless quiet (`files`)
+pub fn write_discovered_files<W: Write>(out: &mut W, files: &[DiscoveredFile], quiet: bool) -> std::io::Result<()> {
+    for file in files {
+        writeln!(out, "{}", file.relative_path.display())?;
+    }
+    if !quiet {
+        writeln!(out, "{} file{}", files.len(), if files.len() == 1 { "" } else { "s" })?;
+    }
+    Ok(())
+}
+
+/// One line per check, with its fix indented below; then a tally unless quiet (`doctor`)
+pub fn write_doctor_report<W: Write>(out: &mut W, report: &DoctorReport, quiet: bool) -> std::io::Result<()> {
+    for check in &report.checks {
+        writeln!(out, "  {} {}: {}", check.severity.symbol(), check.name, check.detail)?;
+        if let Some(fix) = &check.fix {
+            writeln!(out, "      → {}", fix)?;
+        }
+    }
+    if !quiet {
+        let (warnings, failures) = (report.count(Severity::Warn), report.count(Severity::Fail));
+        if warnings + failures == 0 {
+            writeln!(out, "\nAll {} checks passed.", report.checks.len())?;
+        } else {
+            writeln!(out, "\n{} failed, {} warned, {} passed.", failures, warnings, report.count(Severity::Pass))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write file-level search results (`search --by-file`)
+pub fn write_file_results<W: Write>(
+    out: &mut W,
+    query: &str,
+    results: &[FileResult],
+    quiet: bool,
+) -> std::io::Result<()> {
+    if !quiet {
+        writeln!(out, "Searching for: \"{}\" (by file)", query)?;
+        if results.is_empty() {
+            writeln!(out, "\nNo results found.")?;
+        } else {
+            writeln!(out, "\nFound {} files:", results.len())?;
+        }
+    }
+    write_file_result_blocks(out, results, quiet)
+}
+
+/// Write the files most similar to `source`
+pub fn write_similar_files<W: Write>(
+    out: &mut W,
+    source: &str,
+    results: &[FileResult],
+    quiet: bool,
+) -> std::io::Result<()> {
+    if !quiet {
+        writeln!(out, "Notes similar to: {}", source)?;
+        if results.is_empty() {
+            writeln!(out, "\nNo similar notes found.")?;
+        } else {
+            writeln!(out, "\nFound {} files:", results.len())?;
+        }
+    }
+    write_file_result_blocks(out, results, quiet)
+}
+
+fn write_file_result_blocks<W: Write>(
+    out: &mut W,
+    results: &[FileResult],
+    quiet: bool,
+) -> std::io::Result<()> {
+    for (i, result) in results.iter().enumerate() {
+        if !quiet || i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "{}. {} (score: {:.3} over {} chunks)",
+            i + 1,
+            result.file_path,
+            result.score,
+            result.chunks_used
+        )?;
+        let best = &result.best_chunk;
+        let preview: String = best.text.chars().take(150).collect();
+        writeln!(out, "   Best match (similarity: {:.3}): {}...", result.best_similarity, preview)?;
+        writeln!(out, "   Lines: {}-{}", best.start_line, best.end_line)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+    use crate::search::calibration::ScoreCalibration;
+    use crate::search::files::best_chunks_by_embedding;
+    use crate::search::options::SearchOptions;
+    use crate::storage::vectors::VectorStore;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_search_report_has_paging_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("test_notes2vec"))).unwrap();
+        config.init().unwrap();
+        let store = VectorStore::open(&config).unwrap();
+        let chunk = |file: &str, index, embedding| {
+            VectorEntry::new(file.to_string(), index, embedding, format!("{} #{}", file, index), String::new(), 1, 1)
+        };
+        store.insert(&chunk("budget.md", 0, vec![1.0, 0.0])).unwrap();
+        store.insert(&chunk("budget.md", 1, vec![0.8, 0.6])).unwrap();
+        store.insert(&chunk("travel.md", 0, vec![0.6, 0.8])).unwrap();
+        store.insert(&chunk("recipes.md", 0, vec![0.0, 1.0])).unwrap();
+
+        let best = best_chunks_by_embedding(&[1.0, 0.0], &SearchOptions::new(2), &store).unwrap();
+        let report = SearchReport {
+            query: "budget",
+            model_id: &config.model_id,
+            threshold: ScoreCalibration::from_config(&config).min,
+            total_candidates_considered: best.candidates,
+            returned: best.results.len(),
+            results: streamed_results(&best.results, &[], &[], None),
+        };
+        let mut out = Vec::new();
+        write_search_report(&mut out, &report).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["query"], "budget");
+        assert_eq!(json["model_id"], config.model_id.as_str());
+        let threshold = json["threshold"].as_f64().unwrap() as f32;
+        assert!((threshold - ScoreCalibration::for_model(&config.model_id).min).abs() < 1e-6);
+        // Every chunk was a candidate; only the two best files came back
+        assert_eq!(json["total_candidates_considered"], 4);
+        assert_eq!(json["returned"], 2);
+        let results = json["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["file_path"], "budget.md");
+        assert_eq!(results[0]["rank"], 1);
+        assert_eq!(results[1]["file_path"], "travel.md");
+    }
+}
@@ -0,0 +1,172 @@
+//! Writing the TUI's current results to a file (`Ctrl+S` on the Search screen).
+//!
+//! Rendering is shared with the command line: Markdown uses the same report writer as
+//! `search`, JSON is the `search --stream` line format.
+
+use crate::core::duration::compact_timestamp;
+use crate::core::error::Result;
+use crate::search::calibration::ConfidenceThresholds;
+use crate::search::explain::ScoreBreakdown;
+use crate::storage::vectors::VectorEntry;
+use crate::ui::cli::{write_markdown_report, write_streamed_results};
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest query slug kept in an export's file name
+const MAX_SLUG_CHARS: usize = 48;
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    /// One JSON object per line, as `search --stream` writes them
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "jsonl",
+        }
+    }
+}
+
+/// Lowercase letters and digits of `query`, with every other run of characters turned into a
+/// single `-` ("results" when nothing is left)
+pub fn slugify(query: &str) -> String {
+    let mut slug = String::new();
+    for c in query.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            if slug.chars().count() >= MAX_SLUG_CHARS {
+                break;
+            }
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "results".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// `YYYYMMDD-HHMMSS-<slug>.<ext>` for an export made at Unix time `secs`
+pub fn export_file_name(query: &str, format: ExportFormat, secs: u64) -> String {
+    numbered_file_name(query, format, secs, 1)
+}
+
+/// [`export_file_name`] with `-<attempt>` before the extension after the first attempt
+fn numbered_file_name(query: &str, format: ExportFormat, secs: u64, attempt: usize) -> String {
+    let stem = format!("{}-{}", compact_timestamp(secs), slugify(query));
+    if attempt > 1 {
+        format!("{}-{}.{}", stem, attempt, format.extension())
+    } else {
+        format!("{}.{}", stem, format.extension())
+    }
+}
+
+/// Write `results` to a new timestamped file in `dir` (created if missing) and return its path.
+/// An existing file is never overwritten: a second export within the same second gets a
+/// numbered name instead.
+pub fn export_results(
+    dir: &Path,
+    query: &str,
+    results: &[(VectorEntry, f32)],
+    breakdowns: &[ScoreBreakdown],
+    confidence: Option<ConfidenceThresholds>,
+    format: ExportFormat,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut attempt = 1;
+    let (path, file) = loop {
+        let path = dir.join(numbered_file_name(query, format, now, attempt));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => break (path, file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let mut out = BufWriter::new(file);
+    let written = match format {
+        ExportFormat::Markdown => write_markdown_report(&mut out, query, results, breakdowns, confidence),
+        ExportFormat::Json => write_streamed_results(&mut out, results, &[], breakdowns, confidence),
+    };
+    let synced = written
+        .and_then(|()| out.flush())
+        .and_then(|()| out.get_ref().sync_all());
+    if let Err(e) = synced {
+        // Don't leave half a report behind (say the disk filled up)
+        drop(out);
+        let _ = std::fs::remove_file(&path);
+        return Err(e.into());
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(file: &str, text: &str) -> VectorEntry {
+        VectorEntry::new(file.to_string(), 0, vec![1.0], text.to_string(), "Plans".to_string(), 3, 4)
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Team offsite: venue & budget!"), "team-offsite-venue-budget");
+        assert_eq!(slugify("  tag:work  file:2024 "), "tag-work-file-2024");
+        assert_eq!(slugify("Café Über"), "café-über");
+        assert_eq!(slugify("???"), "results");
+        assert_eq!(slugify(&"a".repeat(100)).len(), MAX_SLUG_CHARS);
+        assert_eq!(
+            export_file_name("Weekly review", ExportFormat::Json, 1_709_251_200),
+            "20240301-000000-weekly-review.jsonl"
+        );
+    }
+
+    #[test]
+    fn test_export_writes_new_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("exports");
+        let results = vec![(entry("plans.md", "Book the venue\n\nThen the train"), 0.8123)];
+
+        let first = export_results(&dir, "venue", &results, &[], None, ExportFormat::Markdown).unwrap();
+        let second = export_results(&dir, "venue", &results, &[], None, ExportFormat::Markdown).unwrap();
+        assert_ne!(first, second);
+        assert!(first.file_name().unwrap().to_str().unwrap().ends_with("-venue.md"));
+
+        let report = std::fs::read_to_string(&first).unwrap();
+        assert_eq!(
+            report,
+            "# Search: venue\n\n1 result.\n\n## 1. plans.md:3-4\n\nSimilarity: 0.812\nContext: Plans\n\n\
+             > Book the venue\n>\n> Then the train\n"
+        );
+
+        let json = export_results(&dir, "venue", &results, &[], None, ExportFormat::Json).unwrap();
+        let line = std::fs::read_to_string(&json).unwrap();
+        let record: serde_json::Value = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(record["rank"], 1);
+        assert_eq!(record["file_path"], "plans.md");
+    }
+
+    #[test]
+    fn test_export_reports_unwritable_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let blocker = temp_dir.path().join("file");
+        std::fs::write(&blocker, "").unwrap();
+        assert!(export_results(&blocker, "q", &[], &[], None, ExportFormat::Markdown).is_err());
+    }
+}
@@ -1,4 +1,9 @@
 pub mod cli;
+pub mod export;
+pub mod progress;
+pub mod scratch;
+pub mod socket;
 pub mod tui;
 pub mod watch;
+pub mod watch_status;
 
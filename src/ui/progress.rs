@@ -0,0 +1,245 @@
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// Weight of the newest throughput sample in the moving average
+const RATE_SMOOTHING: f64 = 0.3;
+/// Bytes are averaged over at least this long, so tiny files don't produce wild rates
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+/// How often a plain progress line is printed when stdout isn't a terminal
+const PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+/// Width of the `[####----]` bar, in characters
+const BAR_WIDTH: usize = 20;
+
+/// Files and bytes processed so far, with an exponential moving average of bytes/sec
+#[derive(Debug, Clone)]
+pub struct Throughput {
+    total_files: usize,
+    total_bytes: u64,
+    files_done: usize,
+    bytes_done: u64,
+    /// Smoothed bytes/sec; `None` until the first sample interval has passed
+    rate: Option<f64>,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl Throughput {
+    pub fn new(total_files: usize, total_bytes: u64, now: Instant) -> Self {
+        Self {
+            total_files,
+            total_bytes,
+            files_done: 0,
+            bytes_done: 0,
+            rate: None,
+            window_start: now,
+            window_bytes: 0,
+        }
+    }
+
+    /// Count a finished file (indexed, skipped or failed alike)
+    pub fn record(&mut self, bytes: u64, now: Instant) {
+        self.files_done += 1;
+        self.bytes_done += bytes;
+        self.window_bytes += bytes;
+
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= SAMPLE_INTERVAL {
+            let sample = self.window_bytes as f64 / elapsed.as_secs_f64();
+            self.rate = Some(match self.rate {
+                Some(rate) => RATE_SMOOTHING * sample + (1.0 - RATE_SMOOTHING) * rate,
+                None => sample,
+            });
+            self.window_start = now;
+            self.window_bytes = 0;
+        }
+    }
+
+    /// Smoothed bytes/sec, once known
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    /// Time left at the current rate
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total_bytes.saturating_sub(self.bytes_done);
+        if remaining == 0 {
+            return Some(Duration::ZERO);
+        }
+        self.rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| Duration::from_secs_f64(remaining as f64 / rate))
+    }
+
+    /// `[####----] 1,234/4,000 files · 12.3 MB/s · ETA 2m10s`
+    pub fn line(&self) -> String {
+        let rate = self.rate.map(format_rate).unwrap_or_else(|| "-- MB/s".to_string());
+        let eta = self.eta().map(format_eta).unwrap_or_else(|| "--".to_string());
+        format!(
+            "[{}] {}/{} files · {} · ETA {}",
+            progress_bar(self.files_done, self.total_files, BAR_WIDTH),
+            format_count(self.files_done),
+            format_count(self.total_files),
+            rate,
+            eta
+        )
+    }
+}
+
+/// Progress line for `notes2vec index`: redrawn in place on a terminal, printed every
+/// few seconds otherwise so logs don't fill up with carriage returns
+pub struct ProgressLine {
+    throughput: Throughput,
+    tty: bool,
+    /// Characters currently drawn on the terminal line
+    drawn: usize,
+    last_plain: Instant,
+}
+
+impl ProgressLine {
+    pub fn new(total_files: usize, total_bytes: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            throughput: Throughput::new(total_files, total_bytes, now),
+            tty: std::io::stdout().is_terminal(),
+            drawn: 0,
+            last_plain: now,
+        }
+    }
+
+    /// Record a finished file and show the updated line
+    pub fn file_done(&mut self, bytes: u64) {
+        let now = Instant::now();
+        self.throughput.record(bytes, now);
+        let line = self.throughput.line();
+        let finished = self.throughput.files_done >= self.throughput.total_files;
+
+        if self.tty {
+            self.clear();
+            print!("{}", line);
+            self.drawn = line.chars().count();
+            let _ = std::io::stdout().flush();
+        } else if finished || now.duration_since(self.last_plain) >= PLAIN_INTERVAL {
+            println!("{}", line);
+            self.last_plain = now;
+        }
+    }
+
+    /// Erase the in-place line before other output is printed
+    pub fn clear(&mut self) {
+        if self.drawn > 0 {
+            print!("\r{}\r", " ".repeat(self.drawn));
+            let _ = std::io::stdout().flush();
+            self.drawn = 0;
+        }
+    }
+}
+
+/// Bar of `width` characters, `#` for the finished share
+pub fn progress_bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (done.min(total) * width).checked_div(total).unwrap_or(width);
+    format!("{}{}", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+/// `1234567` -> `1,234,567`
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Bytes/sec with a decimal unit: `512 B/s`, `12.3 MB/s`
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KB/s", "MB/s", "GB/s"];
+    let mut value = bytes_per_sec.max(0.0);
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// `45s`, `2m10s`, `1h05m`
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs_f64().ceil() as u64;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_is_smoothed_across_samples() {
+        let start = Instant::now();
+        let mut throughput = Throughput::new(3, 3_000, start);
+
+        // Too soon for a sample: no rate and no ETA yet
+        throughput.record(100, start + Duration::from_millis(100));
+        assert_eq!(throughput.rate(), None);
+        assert_eq!(throughput.eta(), None);
+
+        // 1,000 bytes over the first second
+        throughput.record(900, start + Duration::from_secs(1));
+        assert_eq!(throughput.rate(), Some(1000.0));
+        assert_eq!(throughput.eta(), Some(Duration::from_secs(2)));
+
+        // 2,000 bytes over the next second: 0.3 * 2000 + 0.7 * 1000
+        throughput.record(2_000, start + Duration::from_secs(2));
+        assert!((throughput.rate().unwrap() - 1300.0).abs() < 1e-6);
+        assert_eq!(throughput.eta(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_progress_line_format() {
+        let start = Instant::now();
+        let mut throughput = Throughput::new(4_000, 1_310_000_000, start);
+        assert_eq!(
+            throughput.line(),
+            "[--------------------] 0/4,000 files · -- MB/s · ETA --"
+        );
+
+        throughput.files_done = 1_233;
+        throughput.record(10_000_000, start + Duration::from_secs(1));
+        // 1.3 GB left at 10 MB/s
+        assert_eq!(
+            throughput.line(),
+            "[######--------------] 1,234/4,000 files · 10.0 MB/s · ETA 2m10s"
+        );
+    }
+
+    #[test]
+    fn test_format_helpers() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1_234), "1,234");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+
+        assert_eq!(format_rate(512.0), "512 B/s");
+        assert_eq!(format_rate(12_345_678.0), "12.3 MB/s");
+        assert_eq!(format_rate(2_500.0), "2.5 KB/s");
+
+        assert_eq!(format_eta(Duration::from_secs(45)), "45s");
+        assert_eq!(format_eta(Duration::from_secs(130)), "2m10s");
+        assert_eq!(format_eta(Duration::from_millis(3_900_500)), "1h05m");
+
+        assert_eq!(progress_bar(1, 4, 8), "##------");
+        assert_eq!(progress_bar(0, 0, 4), "####");
+    }
+}
@@ -0,0 +1,66 @@
+use crate::core::error::Result;
+use crate::storage::vectors::VectorEntry;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// Format a search result as a snippet for the scratch note
+pub fn format_snippet(entry: &VectorEntry) -> String {
+    let start_line = entry.start_line.max(1);
+    let end_line = entry.end_line.max(start_line);
+    format!(
+        "> from {}:{}-{}\n\n{}\n\n",
+        entry.file_path,
+        start_line,
+        end_line,
+        entry.text.trim_end()
+    )
+}
+
+/// Append a snippet to the scratch file, creating it (and its parent directory) if missing.
+/// The file is only ever appended to and is synced before returning, so earlier snippets
+/// survive a crash.
+pub fn append_snippet(path: &Path, entry: &VectorEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format_snippet(entry).as_bytes())?;
+    file.flush()?;
+    file.sync_data()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(file: &str, text: &str, start: usize, end: usize) -> VectorEntry {
+        VectorEntry::new(file.to_string(), 0, vec![1.0], text.to_string(), String::new(), start, end)
+    }
+
+    #[test]
+    fn test_format_snippet() {
+        let snippet = format_snippet(&entry("notes/x.md", "Some text\n", 12, 20));
+        assert_eq!(snippet, "> from notes/x.md:12-20\n\nSome text\n\n");
+    }
+
+    #[test]
+    fn test_append_snippet_creates_and_appends() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("scratch.md");
+
+        append_snippet(&path, &entry("a.md", "First", 1, 2)).unwrap();
+        append_snippet(&path, &entry("b.md", "Second", 3, 4)).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "> from a.md:1-2\n\nFirst\n\n> from b.md:3-4\n\nSecond\n\n"
+        );
+    }
+}
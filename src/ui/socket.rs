@@ -0,0 +1,382 @@
+//! Query server for `notes2vec watch --serve-socket`.
+//!
+//! A serving watcher keeps the vector store open and the model loaded, and `notes2vec search`
+//! forwards its query over a unix domain socket in the base directory instead of loading a
+//! model of its own and contending for the database lock.
+//!
+//! The protocol is newline-delimited JSON. The client sends one [`QueryRequest`]; the server
+//! answers `hello` with its protocol version, then one `result` line per hit and `done`, or a
+//! single `error` line (e.g. when the versions don't match). Both ends time out on a silent peer.
+//!
+//! Named pipes on Windows aren't implemented; there searches always run locally.
+
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use crate::search::options::SearchOptions;
+use crate::storage::vectors::VectorEntry;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Bumped whenever requests or responses change shape
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Socket file inside the base directory
+pub const SOCKET_FILE: &str = "notes2vec.sock";
+/// How long the server waits on a client's request (and each write) before dropping it
+#[cfg_attr(not(unix), allow(dead_code))]
+const SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the client waits for each response line; covers embedding the query
+#[cfg_attr(not(unix), allow(dead_code))]
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest request line the server reads
+#[cfg_attr(not(unix), allow(dead_code))]
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// A search forwarded to the watcher. `--explain` isn't sent: forwarded results rank on the
+/// cosine alone, and the client builds their breakdowns from the returned similarities.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryRequest {
+    pub version: u32,
+    pub query: String,
+    pub limit: usize,
+    #[serde(default)]
+    pub candidates: Option<usize>,
+    /// Unix time results must be modified at or after (`--changed-since`)
+    #[serde(default)]
+    pub changed_since: Option<u64>,
+    /// Unix time results must be dated at or after (`--after`)
+    #[serde(default)]
+    pub after: Option<u64>,
+    /// Unix time results must be dated before (`--before`)
+    #[serde(default)]
+    pub before: Option<u64>,
+    /// Files shortlisted by two-stage search (`--two-stage`); None scores every chunk
+    #[serde(default)]
+    pub two_stage: Option<usize>,
+    /// Best chunks blended into the query first (`--expand`); None searches as typed
+    #[serde(default)]
+    pub expand: Option<usize>,
+}
+
+impl QueryRequest {
+    pub fn new(query: &str, options: &SearchOptions) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            query: query.to_string(),
+            limit: options.limit,
+            candidates: options.candidates,
+            changed_since: options.changed_since,
+            after: options.after,
+            before: options.before,
+            two_stage: options.two_stage,
+            expand: options.expand,
+        }
+    }
+
+    /// Search options the server runs the query with
+    pub fn options(&self) -> SearchOptions {
+        SearchOptions::new(self.limit)
+            .with_candidates(self.candidates)
+            .with_changed_since(self.changed_since)
+            .with_date_range(self.after, self.before)
+            .with_two_stage(self.two_stage)
+            .with_expand(self.expand)
+    }
+}
+
+/// One line of the server's answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueryResponse {
+    Hello { version: u32 },
+    Result { entry: Box<VectorEntry>, similarity: f32 },
+    Done,
+    Error { message: String },
+}
+
+/// Runs a forwarded query: the watcher's search, or a stub in tests
+pub type QueryHandler = dyn Fn(&QueryRequest) -> Result<Vec<(VectorEntry, f32)>> + Send + Sync;
+
+/// Where a serving watcher listens for `config`'s base directory
+pub fn socket_path(config: &Config) -> PathBuf {
+    // Each collection has its own watcher, so the socket sits next to the collection's database
+    config.database_dir.parent().unwrap_or(&config.base_dir).join(SOCKET_FILE)
+}
+
+/// Write `value` as one JSON line
+#[cfg_attr(not(unix), allow(dead_code))]
+fn write_line<T: Serialize>(writer: &mut impl Write, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *writer, value)
+        .map_err(|e| Error::Server(format!("Failed to encode message: {}", e)))?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub use unix::{query_socket, QueryServer};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::io::{BufRead, BufReader, ErrorKind, Read};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    /// Answers queries on a background thread; the socket file is removed on drop
+    pub struct QueryServer {
+        path: PathBuf,
+    }
+
+    impl QueryServer {
+        /// Listen on `path`, replacing a socket left behind by a watcher that didn't exit cleanly
+        pub fn start(path: PathBuf, handler: Arc<QueryHandler>) -> Result<Self> {
+            if path.exists() {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(Error::Server(format!(
+                        "Another watcher is already serving queries on {}",
+                        path.display()
+                    )));
+                }
+                std::fs::remove_file(&path)?;
+            }
+
+            let listener = UnixListener::bind(&path)?;
+            // Results contain note text, so only the owner may connect
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let handler = Arc::clone(&handler);
+                            std::thread::spawn(move || {
+                                if let Err(e) = handle_client(&stream, handler.as_ref()) {
+                                    tracing::warn!("Query connection failed: {}", e);
+                                }
+                            });
+                        }
+                        Err(e) => tracing::warn!("Failed to accept query connection: {}", e),
+                    }
+                }
+            });
+
+            Ok(Self { path })
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    impl Drop for QueryServer {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn handle_client(stream: &UnixStream, handler: &QueryHandler) -> Result<()> {
+        stream.set_read_timeout(Some(SERVER_TIMEOUT))?;
+        stream.set_write_timeout(Some(SERVER_TIMEOUT))?;
+
+        let mut line = String::new();
+        BufReader::new(stream.take(MAX_REQUEST_BYTES)).read_line(&mut line)?;
+        let mut writer = stream;
+
+        let request: QueryRequest = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let message = format!("Malformed request: {}", e);
+                return write_line(&mut writer, &QueryResponse::Error { message });
+            }
+        };
+        if request.version != PROTOCOL_VERSION {
+            let message = format!(
+                "Protocol version {} isn't supported; this watcher speaks version {}",
+                request.version, PROTOCOL_VERSION
+            );
+            return write_line(&mut writer, &QueryResponse::Error { message });
+        }
+
+        write_line(&mut writer, &QueryResponse::Hello { version: PROTOCOL_VERSION })?;
+        match handler(&request) {
+            Ok(results) => {
+                for (entry, similarity) in results {
+                    write_line(&mut writer, &QueryResponse::Result { entry: Box::new(entry), similarity })?;
+                }
+                write_line(&mut writer, &QueryResponse::Done)
+            }
+            Err(e) => write_line(&mut writer, &QueryResponse::Error { message: e.to_string() }),
+        }
+    }
+
+    /// Send `request` to the server on `path`.
+    /// `Ok(None)` means nothing is listening (no socket, or one left by a crashed watcher).
+    pub fn query_socket(path: &Path, request: &QueryRequest) -> Result<Option<Vec<(VectorEntry, f32)>>> {
+        let stream = match UnixStream::connect(path) {
+            Ok(stream) => stream,
+            Err(_) => return Ok(None),
+        };
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        write_line(&mut &stream, request).map_err(timed_out)?;
+
+        let mut results = Vec::new();
+        let mut greeted = false;
+        for line in BufReader::new(&stream).lines() {
+            let line = line.map_err(|e| timed_out(e.into()))?;
+            let response: QueryResponse = serde_json::from_str(&line)
+                .map_err(|e| Error::Server(format!("Malformed response from watcher: {}", e)))?;
+            match response {
+                QueryResponse::Hello { version } if version == PROTOCOL_VERSION => greeted = true,
+                QueryResponse::Hello { version } => {
+                    return Err(Error::Server(format!(
+                        "Watcher speaks protocol version {} but this notes2vec speaks {}; restart 'notes2vec watch --serve-socket'",
+                        version, PROTOCOL_VERSION
+                    )))
+                }
+                QueryResponse::Result { entry, similarity } if greeted => results.push((*entry, similarity)),
+                QueryResponse::Done if greeted => return Ok(Some(results)),
+                QueryResponse::Error { message } => return Err(Error::Server(message)),
+                _ => return Err(Error::Server("Watcher sent a response out of order".to_string())),
+            }
+        }
+        Err(Error::Server("Watcher closed the connection before answering".to_string()))
+    }
+
+    /// Name a timeout after the watcher rather than as a bare I/O error
+    fn timed_out(error: Error) -> Error {
+        match error {
+            Error::Io(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                Error::Server(format!("Timed out waiting for the watcher after {}s", CLIENT_TIMEOUT.as_secs()))
+            }
+            other => other,
+        }
+    }
+}
+
+/// Unix domain sockets only; see the module docs
+#[cfg(not(unix))]
+pub struct QueryServer;
+
+#[cfg(not(unix))]
+impl QueryServer {
+    pub fn start(_path: PathBuf, _handler: std::sync::Arc<QueryHandler>) -> Result<Self> {
+        Err(Error::Server(
+            "--serve-socket needs unix domain sockets, which aren't supported on this platform yet".to_string(),
+        ))
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        std::path::Path::new("")
+    }
+}
+
+/// Run `query` on a watcher serving `config`'s base directory, if one is listening
+pub fn search_remote(config: &Config, query: &str, options: &SearchOptions) -> Result<Option<Vec<(VectorEntry, f32)>>> {
+    #[cfg(unix)]
+    {
+        query_socket(&socket_path(config), &QueryRequest::new(query, options))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (config, query, options);
+        Ok(None)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn entry(file_path: &str) -> VectorEntry {
+        VectorEntry::new(file_path.to_string(), 0, vec![1.0], "text".to_string(), "Heading".to_string(), 1, 2)
+    }
+
+    fn stub_handler() -> Arc<QueryHandler> {
+        Arc::new(|request: &QueryRequest| {
+            if request.query == "fail" {
+                return Err(Error::Model("embedding failed".to_string()));
+            }
+            Ok((0..request.limit).map(|i| (entry(&format!("note{}.md", i)), 0.9 - i as f32 * 0.1)).collect())
+        })
+    }
+
+    #[test]
+    fn test_query_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        let _server = QueryServer::start(socket_path(&config), stub_handler()).unwrap();
+
+        let results = search_remote(&config, "roadmap", &SearchOptions::new(2)).unwrap().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0.file_path, "note0.md");
+        assert!((results[1].1 - 0.8).abs() < 1e-6);
+
+        let err = search_remote(&config, "fail", &SearchOptions::new(2)).unwrap_err();
+        assert!(err.to_string().contains("embedding failed"));
+    }
+
+    #[test]
+    fn test_request_carries_the_search_flags() {
+        let options = SearchOptions::new(3)
+            .with_candidates(Some(50))
+            .with_changed_since(Some(100))
+            .with_date_range(Some(200), Some(300))
+            .with_two_stage(Some(10))
+            .with_expand(Some(2));
+        let line = serde_json::to_string(&QueryRequest::new("roadmap", &options)).unwrap();
+        let request: QueryRequest = serde_json::from_str(&line).unwrap();
+        assert_eq!(request.version, PROTOCOL_VERSION);
+
+        let forwarded = request.options();
+        assert_eq!(forwarded.limit, 3);
+        assert_eq!(forwarded.candidates, Some(50));
+        assert_eq!(forwarded.changed_since, Some(100));
+        assert_eq!((forwarded.after, forwarded.before), (Some(200), Some(300)));
+        assert_eq!(forwarded.two_stage, Some(10));
+        assert_eq!(forwarded.expand, Some(2));
+    }
+
+    #[test]
+    fn test_no_server_means_local_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().to_path_buf())).unwrap();
+        assert!(search_remote(&config, "roadmap", &SearchOptions::new(2)).unwrap().is_none());
+
+        // A socket file nobody listens on is left over from a crash
+        drop(std::os::unix::net::UnixListener::bind(socket_path(&config)).unwrap());
+        assert!(search_remote(&config, "roadmap", &SearchOptions::new(2)).unwrap().is_none());
+
+        // ...and a new server replaces it
+        let server = QueryServer::start(socket_path(&config), stub_handler()).unwrap();
+        assert!(search_remote(&config, "roadmap", &SearchOptions::new(1)).unwrap().is_some());
+        drop(server);
+        assert!(!socket_path(&config).exists());
+    }
+
+    #[test]
+    fn test_version_mismatch_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(SOCKET_FILE);
+        let _server = QueryServer::start(path.clone(), stub_handler()).unwrap();
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let request = QueryRequest { version: PROTOCOL_VERSION + 1, ..QueryRequest::new("roadmap", &SearchOptions::new(1)) };
+        write_line(&mut stream, &request).unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        match serde_json::from_str::<QueryResponse>(&line).unwrap() {
+            QueryResponse::Error { message } => assert!(message.contains("Protocol version")),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+}
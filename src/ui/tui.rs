@@ -1,29 +1,66 @@
+mod cache;
+mod console;
+mod diff;
+mod frecency;
+mod history;
+mod mouse;
+mod path_input;
 mod search;
+mod suggest;
+mod warmup;
 
 use crate::core::config::Config;
 use crate::core::error::{Error, Result};
-use crate::indexing::discovery::discover_files;
-use crate::indexing::parser::parse_markdown_file;
-use crate::search::model::{EmbeddingModel, EMBEDDING_MODEL_ID};
-use crate::storage::state::{calculate_file_hash, get_file_modified_time, StateStore};
+use crate::core::logging;
+use crate::indexing::discovery::{discover_files_with_report, is_notes_file, DiscoveryOptions};
+use crate::indexing::parser::{ChunkKind, TagNormalization};
+use crate::indexing::pipeline::index_file;
+use crate::search::calibration::{ConfidenceThresholds, ScoreCalibration};
+use crate::search::checksums::model_mismatch;
+use crate::search::collections::CollectionStore;
+use crate::search::explain::ScoreBreakdown;
+use crate::search::model::{download_model_files, missing_model_files, DownloadProgress, EmbeddingModel, MODEL_FILES};
+use crate::search::options::SearchOptions;
+use crate::ui::export::{export_results, ExportFormat};
+use crate::ui::scratch::append_snippet;
+use crate::storage::state::{FileCheck, FileSnapshot, FileState, StateStore};
 use crate::storage::vectors::{VectorEntry, VectorStore};
-use search::{perform_search, parse_file_filter_query, MAX_RESULTS_DISPLAYED};
+use cache::{CacheKey, ResultCache};
+use console::{format_age, ErrorConsole, Severity};
+use diff::{check_source, chunk_diff, DiffLine, DiffOp, SourceCheck, MAX_DIFF_FILE_BYTES};
+use frecency::FolderFrecency;
+use history::SearchHistory;
+use mouse::{area_contains, list_row_to_index, result_columns, search_chunks};
+use path_input::PathInput;
+use search::{
+    file_filter_match, parse_file_filter_query, parse_query_filters, perform_search, perform_search_collections,
+    step_result_limit, FileNames, FilterMatch, SearchHit, MAX_PER_FILE_LIMIT,
+};
+use suggest::{complete, DictionaryBuilder, Suggestion, SuggestionDictionary, SuggestionKind};
+use warmup::ModelWarmup;
 use crossterm::cursor;
-use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton,
+    MouseEvent, MouseEventKind,
+};
 use crossterm::execute;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap};
 use ratatui::Frame;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, SystemTime};
 
 // TUI configuration constants
-const MAX_PREVIEW_LINES: usize = 200;           // Maximum lines to show in details preview
+const SETUP_POLL_INTERVAL: Duration = Duration::from_millis(100); // Redraw rate while the setup thread runs
+const SETUP_TEST_SENTENCE: &str = "notes2vec setup check: semantic search for your notes.";
+const CONSOLE_PAGE: usize = 10;                 // Entries skipped by PageUp/PageDown in the error console
 
 // Warm Industrial - Claude Code Style
 mod colors {
@@ -65,16 +102,55 @@ mod colors {
     
     // Title (uses accent color)
     pub const TITLE: Color = Color::Rgb(217, 121, 95);     // #D9795F - Burnt Orange
+
+    // Diff lines in the details panel
+    pub const ADDED: Color = Color::Rgb(126, 180, 110);    // #7EB46E - Muted Green
+    pub const REMOVED: Color = Color::Rgb(214, 92, 92);    // #D65C5C - Muted Red
 }
 
 /// Screen states for the TUI flow
-#[derive(PartialEq)]
-enum Screen {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
     Welcome,
+    /// First-run setup: create directories and download the embedding model
+    Setup,
     DirectorySelection,
     Search,
 }
 
+/// Where the first-run setup flow is
+#[derive(Debug, Clone, PartialEq)]
+enum SetupState {
+    /// Asking whether to download the model
+    Prompt,
+    /// Download running on the background thread (`None` until the first progress update)
+    Downloading(Option<DownloadProgress>),
+    /// Loading the model and embedding a test sentence
+    Verifying,
+    Failed(String),
+    Declined,
+}
+
+/// Messages from the background setup thread
+enum SetupEvent {
+    Progress(DownloadProgress),
+    Verifying,
+    Ready,
+    Failed(String),
+}
+
+/// A forced reindex of the folder in scope, re-embedding one file per frame so the footer can
+/// count up while it runs
+struct ReindexJob {
+    state_store: StateStore,
+    /// Folder the scoped paths are relative to
+    root: PathBuf,
+    /// Files in scope, sorted; all were forgotten before the first is re-embedded
+    files: Vec<String>,
+    done: usize,
+    failed: usize,
+}
+
 /// Interactive TUI search interface
 pub struct SearchTui {
     // Screen state
@@ -82,57 +158,301 @@ pub struct SearchTui {
     
     // Search state
     query: String,
-    results: Vec<(VectorEntry, f32)>,
+    results: Vec<SearchHit>,
     selected: usize,
     search_mode: bool, // true = typing query, false = browsing results
+    // Query given on the command line, run as soon as a folder or file is open
+    pending_query: bool,
     
     // Directory selection state
     current_dir: PathBuf,
     dir_entries: Vec<PathBuf>,
     dir_selected: usize,
     previous_dir: Option<PathBuf>, // Track previous directory for Esc navigation
+    // Path typed after `/` or `:`, while the input is open
+    path_input: Option<PathInput>,
     
     // Core components
     config: Config,
     vector_store: Option<VectorStore>,
+    // Other collections opened for `collection:` queries, kept for the session
+    collection_stores: HashMap<String, CollectionStore>,
     model: Option<EmbeddingModel>,
+    // Model loading in the background since the TUI opened, until a folder takes it
+    model_warmup: Option<ModelWarmup>,
 
     // UI status (short-lived messages shown in directory selection footer)
     status_message: Option<String>,
 
+    // Every error and warning this session; the footer shows a count, `!` opens the list
+    console: ErrorConsole,
+    console_open: bool,
+    console_scroll: usize,
+
     // Whether the real embedding model is loaded (vs hash fallback)
     model_ready: bool,
 
     // Limit searches to the files discovered in the currently selected folder
     active_files: HashSet<String>,
+    // Frontmatter titles and aliases of the open store's files, for `file:` filters and boosting
+    file_names: FileNames,
+    // Recent searches by query, scope and model; cleared whenever the index is written
+    result_cache: ResultCache,
+    // Folders results were picked from this session, gently preferred in later searches (`b` clears)
+    folder_frecency: FolderFrecency,
+    // The folder bonus changed the order of the results shown
+    session_boosted: bool,
+
+    // Headings and tags of `active_files`, and the completions shown for the current query
+    suggestion_dict: SuggestionDictionary,
+    suggestions: Vec<Suggestion>,
+    suggestion_selected: usize,
+
+    // Past queries, recalled with Up/Down while the query field is empty
+    history: SearchHistory,
+
+    // Maps raw cosine into the displayed match percentage
+    score_calibration: ScoreCalibration,
+
+    // Thresholds for the strong/likely/weak label (None = labels off)
+    confidence: Option<ConfidenceThresholds>,
+
+    // Snippets appended to the scratch file this session
+    scratch_count: usize,
+
+    // Show how each result's score was assembled (toggled with `e`)
+    explain: bool,
+
+    // Results shown per search and chunks kept per file, changed with `+`/`-` and `[`/`]`
+    result_limit: usize,
+    per_file_limit: usize,
+
+    // Lines the details panel is scrolled down (mouse wheel over the panel)
+    details_scroll: u16,
+
+    // Content hash recorded at indexing for each file in scope, to spot notes edited since
+    indexed_hashes: HashMap<String, String>,
+    // Selected result's source file compared with its indexed hash (None = not checked)
+    source_check: Option<SourceCheck>,
+    // Selected chunk diffed against its file as it is now, while shown (toggled with `d`)
+    details_diff: Option<Vec<DiffLine>>,
+
+    // Reindex key pressed once; the footer asks for a second press before anything is removed
+    reindex_armed: bool,
+    // Forced reindex in progress (Esc stops it)
+    reindex: Option<ReindexJob>,
+
+    // First-run setup flow and the channel from its background thread
+    setup: SetupState,
+    setup_rx: Option<Receiver<SetupEvent>>,
 }
 
 impl SearchTui {
-    pub fn new(config: Config) -> Result<Self> {
-        // Start with welcome screen
+    /// Create the TUI starting on `start_screen`
+    /// (`Screen::Welcome` for first run, `Screen::DirectorySelection` to skip the banner)
+    pub fn new(config: Config, start_screen: Screen) -> Result<Self> {
         let current_dir = std::env::current_dir()
             .unwrap_or_else(|_| PathBuf::from("."));
         
         let (dir_entries, _) = Self::list_directory(&current_dir)?;
+        let score_calibration = ScoreCalibration::from_config(&config);
+        let confidence = ConfidenceThresholds::from_config(&config);
+        let history = SearchHistory::load(&config.base_dir);
+
+        // Skipping the banner goes straight to setup when the model isn't there yet
+        let start_screen = if start_screen == Screen::DirectorySelection && Self::needs_setup(&config) {
+            Screen::Setup
+        } else {
+            start_screen
+        };
 
         Ok(Self {
-            current_screen: Screen::Welcome,
+            current_screen: start_screen,
             query: String::new(),
             results: Vec::new(),
             selected: 0,
             search_mode: true,
+            pending_query: false,
             current_dir,
             dir_entries,
             dir_selected: 0,
             previous_dir: None,
+            path_input: None,
+            result_limit: config.tui_results,
+            per_file_limit: config.tui_results_per_file,
             config,
             vector_store: None,
+            collection_stores: HashMap::new(),
             model: None,
+            model_warmup: None,
             status_message: None,
+            console: ErrorConsole::default(),
+            console_open: false,
+            console_scroll: 0,
             model_ready: false,
             active_files: HashSet::new(),
+            file_names: FileNames::new(),
+            result_cache: ResultCache::default(),
+            folder_frecency: FolderFrecency::default(),
+            session_boosted: false,
+            suggestion_dict: SuggestionDictionary::default(),
+            suggestions: Vec::new(),
+            suggestion_selected: 0,
+            history,
+            score_calibration,
+            confidence,
+            scratch_count: 0,
+            explain: false,
+            details_scroll: 0,
+            indexed_hashes: HashMap::new(),
+            source_check: None,
+            details_diff: None,
+            reindex_armed: false,
+            reindex: None,
+            setup: SetupState::Prompt,
+            setup_rx: None,
         })
     }
+
+    /// Create the TUI on the welcome screen, or on directory selection when `show_welcome` is off
+    pub fn from_config(config: Config) -> Result<Self> {
+        let start_screen = if config.show_welcome {
+            Screen::Welcome
+        } else {
+            Screen::DirectorySelection
+        };
+        Self::new(config, start_screen)
+    }
+
+    /// Start with `query` in the search box; it runs as soon as a folder or file is opened
+    pub fn with_query(mut self, query: &str) -> Self {
+        self.query = query.trim().to_string();
+        self.pending_query = !self.query.is_empty();
+        self
+    }
+
+    /// Run the query from the command line once search is ready, landing on its results
+    fn run_pending_query(&mut self) {
+        if !self.pending_query || self.current_screen != Screen::Search {
+            return;
+        }
+        self.pending_query = false;
+        self.history.add(&self.query);
+        if let Err(e) = self.history.save() {
+            self.console.warn("history", format!("Could not save search history: {}", e));
+        }
+        match self.perform_search() {
+            Ok(()) => {
+                self.search_mode = false;
+                self.selected = 0;
+            }
+            Err(e) => {
+                self.console.error("search", e.to_string());
+                self.status_message = Some(format!("Search error: {}", e));
+            }
+        }
+    }
+
+    /// Whether the data directories or any model file are missing
+    fn needs_setup(config: &Config) -> bool {
+        !config.is_initialized() || !missing_model_files(config).is_empty()
+    }
+
+    /// Start loading the model on a background thread, unless it's loaded or setup must run first
+    fn start_model_warmup(&mut self) {
+        if self.model.is_none() && self.model_warmup.is_none() && !Self::needs_setup(&self.config) {
+            self.model_warmup = Some(ModelWarmup::start(&self.config));
+        }
+    }
+
+    /// The model kept from an earlier folder, else the warm-up's (waiting for it), else loaded now
+    fn load_model(&mut self) -> Result<EmbeddingModel> {
+        if let Some(model) = self.model.take() {
+            return Ok(model);
+        }
+        match self.model_warmup.take() {
+            Some(warmup) => warmup.wait(),
+            None => warmup::load_warm(&self.config),
+        }
+    }
+
+    /// Create the data directories and download + verify the model on a background thread
+    fn start_setup(&mut self) {
+        if let Err(e) = self.config.init() {
+            self.setup = SetupState::Failed(e.to_string());
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let config = self.config.clone();
+        std::thread::spawn(move || {
+            let result = if missing_model_files(&config).is_empty() {
+                Ok(())
+            } else {
+                download_model_files(&config, &mut |progress| {
+                    let _ = tx.send(SetupEvent::Progress(progress.clone()));
+                })
+            };
+            let result = result.and_then(|()| {
+                let _ = tx.send(SetupEvent::Verifying);
+                verify_model(&config)
+            });
+            let _ = tx.send(match result {
+                Ok(()) => SetupEvent::Ready,
+                Err(e) => SetupEvent::Failed(e.to_string()),
+            });
+        });
+
+        self.setup = SetupState::Downloading(None);
+        self.setup_rx = Some(rx);
+    }
+
+    /// Apply updates from the setup thread; moves on to directory selection once the model works
+    fn poll_setup(&mut self) {
+        let Some(rx) = &self.setup_rx else {
+            return;
+        };
+        let mut events = Vec::new();
+        let mut disconnected = false;
+        loop {
+            match rx.try_recv() {
+                Ok(event) => events.push(event),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        for event in events {
+            match event {
+                SetupEvent::Progress(progress) => self.setup = SetupState::Downloading(Some(progress)),
+                SetupEvent::Verifying => self.setup = SetupState::Verifying,
+                SetupEvent::Ready => {
+                    self.setup_rx = None;
+                    self.setup = SetupState::Prompt;
+                    self.previous_dir = None;
+                    self.current_screen = Screen::DirectorySelection;
+                    self.status_message = Some("Embedding model ready.".to_string());
+                    self.start_model_warmup();
+                    return;
+                }
+                SetupEvent::Failed(message) => {
+                    self.console.error("setup", message.clone());
+                    self.setup_rx = None;
+                    self.setup = SetupState::Failed(message);
+                    return;
+                }
+            }
+        }
+
+        if disconnected {
+            self.console.error("setup", "Setup thread stopped unexpectedly");
+            self.setup_rx = None;
+            self.setup = SetupState::Failed("Setup stopped unexpectedly.".to_string());
+        }
+    }
     
     /// List directory entries (directories and supported note files)
     fn list_directory(path: &Path) -> Result<(Vec<PathBuf>, usize)> {
@@ -155,10 +475,7 @@ impl SearchTui {
                             let entry_path = entry.path();
                             if entry_path.is_dir() {
                                 dirs.push(entry_path);
-                            } else if entry_path.extension()
-                                .and_then(|e| e.to_str())
-                                .map(|e| matches!(e.to_lowercase().as_str(), "md" | "markdown" | "mdown" | "mkd" | "mkdn" | "txt"))
-                                .unwrap_or(false) {
+                            } else if is_notes_file(&entry_path) {
                                 files.push(entry_path);
                             }
                         }
@@ -177,6 +494,57 @@ impl SearchTui {
         Ok((entries, selected))
     }
 
+    /// A key pressed while the path input is open: edit, complete, or open what it names.
+    /// Enter goes to a folder (or a file's folder, with the file selected); Ctrl+Enter, or
+    /// Alt+Enter where the terminal can't send it, starts searching there straight away.
+    fn handle_path_input_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(input) = self.path_input.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.path_input = None,
+            KeyCode::Tab => input.complete(&self.current_dir),
+            KeyCode::Backspace => input.backspace(),
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => input.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => input.push(c),
+            KeyCode::Enter => {
+                let target = match input.resolve(&self.current_dir) {
+                    Ok(target) => target,
+                    Err(e) => {
+                        input.error = Some(e);
+                        return Ok(());
+                    }
+                };
+                self.path_input = None;
+                let search_now = key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                let dir = if target.is_dir() {
+                    target.clone()
+                } else {
+                    target.parent().map(Path::to_path_buf).unwrap_or_else(|| self.current_dir.clone())
+                };
+                if dir != self.current_dir {
+                    self.previous_dir = Some(std::mem::replace(&mut self.current_dir, dir));
+                    if let Ok((entries, sel)) = Self::list_directory(&self.current_dir) {
+                        self.dir_entries = entries;
+                        self.dir_selected = sel;
+                    }
+                }
+                if let Some(i) = self.dir_entries.iter().position(|entry| *entry == target) {
+                    self.dir_selected = i;
+                }
+                if search_now {
+                    if target.is_dir() {
+                        self.select_directory(&target, false)?;
+                    } else {
+                        self.select_file(&target)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     pub fn run(&mut self) -> Result<()> {
         // Setup terminal
         enable_raw_mode()?;
@@ -190,20 +558,101 @@ impl SearchTui {
                 let _ = disable_raw_mode();
                 let mut stdout = io::stdout();
                 let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture, cursor::Show);
+                // Warnings logged after the console was last updated
+                for record in logging::release() {
+                    eprintln!("{}", record);
+                }
             }
         }
         let _restore = TerminalRestore;
+        // Logged warnings would be drawn over the screen; collect them for the console instead
+        logging::capture();
 
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = ratatui::Terminal::new(backend)?;
 
         let mut should_quit = false;
+        self.start_model_warmup();
 
         while !should_quit {
+            for record in logging::drain() {
+                self.console.log(record);
+            }
+            // Polled before drawing so the footer drops "loading model…" as soon as it's done
+            let warming_up = self.model_warmup.as_mut().is_some_and(ModelWarmup::poll);
             terminal.draw(|f| self.render_ui(f))?;
 
-            if let Event::Key(key) = event::read()? {
+            // A forced reindex embeds one file per pass, redrawing the progress in between
+            if self.reindex.is_some() {
+                self.reindex_step();
+                if !event::poll(Duration::ZERO)? {
+                    continue;
+                }
+            } else if self.current_screen == Screen::Setup {
+                // Keep redrawing while the setup thread reports progress
+                self.poll_setup();
+                if !event::poll(SETUP_POLL_INTERVAL)? {
+                    continue;
+                }
+            } else if warming_up && !event::poll(SETUP_POLL_INTERVAL)? {
+                continue;
+            }
+
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                self.handle_mouse(mouse, terminal.size()?);
+                continue;
+            }
+
+            if let Event::Key(key) = event {
                 if key.kind == KeyEventKind::Press {
+                    if self.console_open {
+                        match key.code {
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                should_quit = true;
+                            }
+                            KeyCode::Esc | KeyCode::Char('!') => self.console_open = false,
+                            KeyCode::Up => self.scroll_console(-1),
+                            KeyCode::Down => self.scroll_console(1),
+                            KeyCode::PageUp => self.scroll_console(-(CONSOLE_PAGE as isize)),
+                            KeyCode::PageDown => self.scroll_console(CONSOLE_PAGE as isize),
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    // `!` is typed into the query while editing, so it only opens the console elsewhere
+                    let typing = (self.current_screen == Screen::Search && self.search_mode) || self.path_input.is_some();
+                    if key.code == KeyCode::Char('!') && !typing && self.current_screen != Screen::Welcome {
+                        self.console_open = true;
+                        self.console_scroll = 0;
+                        continue;
+                    }
+                    if self.reindex.is_some() {
+                        match key.code {
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                should_quit = true;
+                            }
+                            KeyCode::Esc => {
+                                if let Some(job) = self.reindex.take() {
+                                    self.finish_reindex(job, true);
+                                }
+                            }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    // Any key but the reindex key itself cancels a pending confirmation
+                    let reindex_confirmed = std::mem::take(&mut self.reindex_armed);
+                    let reindex_key = match self.current_screen {
+                        Screen::Search => {
+                            key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL)
+                        }
+                        Screen::DirectorySelection => self.path_input.is_none() && key.code == KeyCode::Char('r'),
+                        _ => false,
+                    };
+                    if reindex_confirmed && !reindex_key {
+                        self.status_message = None;
+                    }
                     match self.current_screen {
                         Screen::Welcome => {
                             match key.code {
@@ -216,11 +665,40 @@ impl SearchTui {
                                 KeyCode::Enter => {
                                     // Reset previous_dir when entering directory selection from welcome
                                     self.previous_dir = None;
-                                    self.current_screen = Screen::DirectorySelection;
+                                    self.current_screen = if Self::needs_setup(&self.config) {
+                                        Screen::Setup
+                                    } else {
+                                        Screen::DirectorySelection
+                                    };
+                                }
+                                _ => {}
+                            }
+                        }
+                        Screen::Setup => {
+                            let busy = matches!(self.setup, SetupState::Downloading(_) | SetupState::Verifying);
+                            match key.code {
+                                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                    should_quit = true;
+                                }
+                                KeyCode::Esc => {
+                                    should_quit = true;
+                                }
+                                KeyCode::Enter | KeyCode::Char('y') if !busy => {
+                                    self.start_setup();
+                                }
+                                KeyCode::Char('n') if self.setup == SetupState::Prompt => {
+                                    self.setup = SetupState::Declined;
                                 }
                                 _ => {}
                             }
                         }
+                        Screen::DirectorySelection if self.path_input.is_some() => {
+                            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                                should_quit = true;
+                            } else {
+                                self.handle_path_input_key(key)?;
+                            }
+                        }
                         Screen::DirectorySelection => {
                             match key.code {
                                 KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
@@ -247,7 +725,14 @@ impl SearchTui {
                                     // Start searching in the current directory (only if it has note files)
                                     self.status_message = None;
                                     let current_dir_clone = self.current_dir.clone();
-                                    self.select_directory(&current_dir_clone)?;
+                                    self.select_directory(&current_dir_clone, false)?;
+                                }
+                                KeyCode::Char('r') => {
+                                    self.request_reindex(reindex_confirmed)?;
+                                }
+                                KeyCode::Char('/') | KeyCode::Char(':') => {
+                                    self.status_message = None;
+                                    self.path_input = Some(PathInput::default());
                                 }
                                 KeyCode::Enter => {
                                     self.status_message = None;
@@ -281,7 +766,7 @@ impl SearchTui {
                                     } else {
                                         // No selection - treat as "search here"
                                         let current_dir_clone = self.current_dir.clone();
-                                        self.select_directory(&current_dir_clone)?;
+                                        self.select_directory(&current_dir_clone, false)?;
                                     }
                                 }
                                 KeyCode::Up => {
@@ -305,7 +790,21 @@ impl SearchTui {
                                     // Ctrl+C always quits, even when typing
                                     should_quit = true;
                                 }
+                                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                    self.request_reindex(reindex_confirmed)?;
+                                }
+                                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                    // Ctrl+Alt+S exports JSON lines instead of the Markdown report
+                                    let format = if key.modifiers.contains(KeyModifiers::ALT) {
+                                        ExportFormat::Json
+                                    } else {
+                                        ExportFormat::Markdown
+                                    };
+                                    self.export_current_results(format);
+                                }
                                 KeyCode::Esc => {
+                                    self.suggestions.clear();
+                                    self.history.reset();
                                     if self.search_mode {
                                         // Exit search mode, clear query
                                         self.search_mode = false;
@@ -314,6 +813,7 @@ impl SearchTui {
                                     } else {
                                         // Go back to directory selection
                                         self.current_screen = Screen::DirectorySelection;
+                                        self.status_message = None;
                                         self.query.clear();
                                         self.results.clear();
                                     }
@@ -323,36 +823,93 @@ impl SearchTui {
                                     self.search_mode = true;
                                 }
                                 KeyCode::Enter if self.search_mode => {
+                                    self.status_message = None;
+                                    self.suggestions.clear();
+                                    self.history.add(&self.query);
+                                    if let Err(e) = self.history.save() {
+                                        self.console.warn("history", format!("Could not save search history: {}", e));
+                                    }
+                                    self.folder_frecency.decay();
                                     self.perform_search()?;
                                     self.search_mode = false;
                                     self.selected = 0;
                                 }
+                                KeyCode::Char('a') if !self.search_mode && !self.results.is_empty() => {
+                                    self.append_selected_to_scratch();
+                                }
+                                KeyCode::Char('e') if !self.search_mode => {
+                                    self.explain = !self.explain;
+                                    // Breakdowns are only collected when asked for, so re-run once
+                                    if self.explain && self.results.iter().any(|(_, _, b)| b.is_none()) {
+                                        let selected = self.selected;
+                                        self.perform_search()?;
+                                        self.select(selected);
+                                    }
+                                }
+                                KeyCode::Char('d') if !self.search_mode => {
+                                    self.toggle_diff();
+                                }
+                                KeyCode::Char('b') if !self.search_mode => {
+                                    self.clear_folder_boost()?;
+                                }
+                                KeyCode::Char(c @ ('+' | '=' | '-')) if !self.search_mode => {
+                                    let limit = step_result_limit(self.result_limit, c != '-');
+                                    self.set_limits(limit, self.per_file_limit)?;
+                                }
+                                KeyCode::Char(c @ ('[' | ']')) if !self.search_mode => {
+                                    let per_file = if c == ']' {
+                                        (self.per_file_limit + 1).min(MAX_PER_FILE_LIMIT)
+                                    } else {
+                                        self.per_file_limit.saturating_sub(1).max(1)
+                                    };
+                                    self.set_limits(self.result_limit, per_file)?;
+                                }
+                                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                    self.save_limits();
+                                }
                                 KeyCode::Char('u') if self.search_mode && key.modifiers.contains(KeyModifiers::CONTROL) => {
                                     // Clear query
                                     self.query.clear();
+                                    self.update_suggestions();
                                 }
                                 KeyCode::Char(c) if self.search_mode => {
                                     // Allow typing 'q' and any other character when in search mode
                                     self.query.push(c);
+                                    self.update_suggestions();
                                 }
                                 KeyCode::Char(c) if !self.search_mode => {
                                     // Start a new query quickly by just typing
                                     self.search_mode = true;
                                     self.query.clear();
                                     self.query.push(c);
+                                    self.update_suggestions();
                                 }
                                 KeyCode::Backspace if self.search_mode => {
                                     self.query.pop();
+                                    self.update_suggestions();
                                 }
-                                KeyCode::Up if !self.search_mode => {
-                                    if self.selected > 0 {
-                                        self.selected -= 1;
+                                KeyCode::Tab if self.search_mode && !self.suggestions.is_empty() => {
+                                    self.accept_suggestion();
+                                }
+                                KeyCode::Up if self.search_mode && !self.suggestions.is_empty() => {
+                                    self.suggestion_selected = self.suggestion_selected.saturating_sub(1);
+                                }
+                                KeyCode::Down if self.search_mode && !self.suggestions.is_empty() => {
+                                    self.suggestion_selected = (self.suggestion_selected + 1).min(self.suggestions.len() - 1);
+                                }
+                                KeyCode::Up if self.search_mode && (self.query.is_empty() || self.history.is_browsing()) => {
+                                    if let Some(query) = self.history.older() {
+                                        self.query = query.to_string();
                                     }
                                 }
+                                KeyCode::Down if self.search_mode && self.history.is_browsing() => {
+                                    self.query = self.history.newer().unwrap_or_default().to_string();
+                                }
+                                KeyCode::Up if !self.search_mode => {
+                                    self.select(self.selected.saturating_sub(1));
+                                }
                                 KeyCode::Down if !self.search_mode => {
-                                    if self.selected < self.results.len().saturating_sub(1) {
-                                        self.selected += 1;
-                                    }
+                                    self.select(self.selected + 1);
                                 }
                                 KeyCode::Char('r') if !self.search_mode => {
                                     self.perform_search()?;
@@ -368,18 +925,215 @@ impl SearchTui {
         Ok(())
     }
     
+    /// Move the error console view by `delta` entries (newest entry is at the top)
+    fn scroll_console(&mut self, delta: isize) {
+        let last = self.console.len().saturating_sub(1);
+        self.console_scroll = self.console_scroll.saturating_add_signed(delta).min(last);
+    }
+
+    /// Recompute completions for the query from the cached dictionary (never the database)
+    fn update_suggestions(&mut self) {
+        // Any edit ends history browsing, so Up/Down go back to the completions
+        self.history.reset();
+        self.suggestions = self.suggestion_dict.suggest(&self.query);
+        self.suggestion_selected = 0;
+    }
+
+    /// Replace the word being typed with the highlighted completion
+    fn accept_suggestion(&mut self) {
+        if let Some(suggestion) = self.suggestions.get(self.suggestion_selected) {
+            self.query = complete(&self.query, suggestion);
+        }
+        self.update_suggestions();
+    }
+
+    /// Cache the headings and tags of `active_files` for completion (best effort)
+    fn build_suggestions(&mut self, vector_store: &VectorStore) {
+        let mut builder = DictionaryBuilder::default();
+        self.suggestion_dict = match vector_store.for_each_scoped(&self.active_files, |entry| builder.add(entry)) {
+            Ok(()) => builder.finish(),
+            Err(e) => {
+                self.console.warn("suggestions", format!("Completions unavailable: {}", e));
+                SuggestionDictionary::default()
+            }
+        };
+        self.suggestions.clear();
+    }
+
+    /// Cache the frontmatter titles and aliases of the open store's files (best effort)
+    fn load_file_names(&mut self, vector_store: &VectorStore) {
+        self.file_names = match vector_store.file_meta() {
+            Ok(names) => FileNames::from([(self.config.collection.clone(), names)]),
+            Err(e) => {
+                self.console.warn("search", format!("Titles and aliases unavailable: {}", e));
+                FileNames::new()
+            }
+        };
+    }
+
+    /// Select a result (clamped to the list) and reset the details scroll
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.results.len().saturating_sub(1));
+        let changed = index != self.selected;
+        self.selected = index;
+        if changed {
+            self.details_scroll = 0;
+            self.refresh_source_check();
+        }
+    }
+
+    /// Remember the indexed hash of every file in scope (best effort)
+    fn load_indexed_hashes(&mut self, state_store: &StateStore) {
+        self.indexed_hashes = match state_store.list_states() {
+            Ok(states) => states
+                .into_iter()
+                .filter(|(path, _)| self.active_files.contains(path))
+                .map(|(path, state)| (path, state.content_hash))
+                .collect(),
+            Err(e) => {
+                self.console.warn("index", format!("Can't check results for edits since indexing: {}", e));
+                HashMap::new()
+            }
+        };
+    }
+
+    /// Compare the selected result's source file with its indexed hash and drop any diff
+    /// that was showing. Files over MAX_DIFF_FILE_BYTES aren't read.
+    fn refresh_source_check(&mut self) {
+        self.details_diff = None;
+        self.source_check = self.results.get(self.selected).and_then(|(entry, _, _)| {
+            // Other collections' notes live under roots this session doesn't know
+            if entry.collection != self.config.collection {
+                return None;
+            }
+            let indexed_hash = self.indexed_hashes.get(&entry.file_path)?;
+            Some(check_source(&self.current_dir.join(&entry.file_path), indexed_hash))
+        });
+    }
+
+    /// Show or hide the diff between the selected chunk and its changed source file
+    fn toggle_diff(&mut self) {
+        if self.details_diff.take().is_some() || self.source_check != Some(SourceCheck::Changed) {
+            return;
+        }
+        let Some((entry, _, _)) = self.results.get(self.selected) else {
+            return;
+        };
+        match chunk_diff(&self.current_dir.join(&entry.file_path), entry) {
+            Ok(diff) => {
+                self.folder_frecency.record(&entry.file_path);
+                self.details_diff = Some(diff);
+                self.details_scroll = 0;
+            }
+            Err(e) => {
+                self.console.warn("diff", e.to_string());
+                self.status_message = Some(format!("Could not diff: {}", e));
+            }
+        }
+    }
+
+    /// Wheel over the list moves the selection, wheel over details scrolls it, click selects
+    fn handle_mouse(&mut self, mouse: MouseEvent, size: Rect) {
+        if self.current_screen != Screen::Search || self.results.is_empty() {
+            return;
+        }
+        let columns = result_columns(search_chunks(size)[2]);
+        let (list_area, details_area) = (columns[0], columns[1]);
+        let over_details = area_contains(details_area, mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::ScrollDown if over_details => {
+                self.details_scroll = self.details_scroll.saturating_add(1);
+            }
+            MouseEventKind::ScrollUp if over_details => {
+                self.details_scroll = self.details_scroll.saturating_sub(1);
+            }
+            MouseEventKind::ScrollDown => self.select(self.selected + 1),
+            MouseEventKind::ScrollUp => self.select(self.selected.saturating_sub(1)),
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(index) =
+                    list_row_to_index(list_area, mouse.column, mouse.row, self.selected, self.results.len())
+                {
+                    self.search_mode = false;
+                    self.select(index);
+                    if let Some((entry, _, _)) = self.results.get(index) {
+                        self.folder_frecency.record(&entry.file_path);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Append the selected result to the scratch file and report it in the footer
+    fn append_selected_to_scratch(&mut self) {
+        let Some((entry, _, _)) = self.results.get(self.selected) else {
+            return;
+        };
+        match append_snippet(&self.config.scratch_file, entry) {
+            Ok(()) => {
+                self.folder_frecency.record(&entry.file_path);
+                self.scratch_count += 1;
+                self.status_message = Some(format!(
+                    "Appended to {} ({} this session)",
+                    self.config.scratch_file.display(),
+                    self.scratch_count
+                ));
+            }
+            Err(e) => {
+                self.console.error("scratch", e.to_string());
+                self.status_message = Some(format!("Could not append snippet: {}", e));
+            }
+        }
+    }
+
+    /// Write the current results to a new file under `export_dir` and name it in the footer
+    fn export_current_results(&mut self, format: ExportFormat) {
+        if self.results.is_empty() {
+            self.status_message = Some("No results to export".to_string());
+            return;
+        }
+        let results: Vec<(VectorEntry, f32)> = self
+            .results
+            .iter()
+            .map(|(entry, similarity, _)| (entry.clone(), *similarity))
+            .collect();
+        let breakdowns: Vec<ScoreBreakdown> = if self.explain {
+            self.results.iter().map_while(|(_, _, breakdown)| *breakdown).collect()
+        } else {
+            Vec::new()
+        };
+        match export_results(&self.config.export_dir, &self.query, &results, &breakdowns, self.confidence, format) {
+            Ok(path) => {
+                self.status_message = Some(format!("Exported {} results to {}", results.len(), path.display()));
+            }
+            Err(e) => {
+                self.console.error("export", e.to_string());
+                self.status_message = Some(format!("Could not export results: {}", e));
+            }
+        }
+    }
+
     /// Select a single file and initialize search (search only in this file)
     fn select_file(&mut self, file_path: &Path) -> Result<()> {
         // IMPORTANT: never let indexing/search setup errors kill the TUI loop.
+        let mut loaded = None;
         let res: Result<()> = (|| {
-            // TUI must not print while in raw/alternate screen mode.
-            let model = EmbeddingModel::init_quiet(&self.config)?;
+            // Missing model files: run the setup flow instead of downloading silently
+            if Self::needs_setup(&self.config) {
+                self.setup = SetupState::Prompt;
+                self.current_screen = Screen::Setup;
+                return Ok(());
+            }
+
+            // Usually loaded already by the warm-up thread started with the TUI
+            let model = &*loaded.insert(self.load_model()?);
             self.model_ready = model.is_model_loaded();
 
             if !self.model_ready {
-                let model_path = self.config.models_dir.join("model.safetensors");
-                let tokenizer_path = self.config.models_dir.join("tokenizer.json");
-                let config_path = self.config.models_dir.join("config.json");
+                let model_path = self.config.model_dir().join("model.safetensors");
+                let tokenizer_path = self.config.model_dir().join("tokenizer.json");
+                let config_path = self.config.model_dir().join("config.json");
                 self.status_message = Some(format!(
                     "Embedding model not loaded. Run: notes2vec init --base-dir {}  (missing: {}{}{})",
                     self.config.base_dir.display(),
@@ -391,6 +1145,8 @@ impl SearchTui {
             }
 
             let state_store = StateStore::open(&self.config)?;
+            // Vectors of another length can't be searched with this model
+            state_store.check_embedding_dim(model.embedding_dim())?;
             let vector_store = VectorStore::open(&self.config)?;
 
             // Get relative path - file_path is absolute, we need relative from current_dir
@@ -404,81 +1160,206 @@ impl SearchTui {
             let file_path_str = file_path_str.replace('\\', "/");
 
             // Check if file needs indexing
-            let needs_indexing = match (get_file_modified_time(file_path), calculate_file_hash(file_path)) {
-                (Ok(modified_time), Ok(hash)) => {
-                    state_store.has_file_changed(&file_path_str, modified_time, &hash).unwrap_or(true)
-                }
-                _ => true,
-            };
-
-            if needs_indexing {
-                // Index this single file
-                let doc = parse_markdown_file(file_path)?;
-                let chunk_texts: Vec<String> = doc.chunks.iter().map(|c| c.text.clone()).collect();
-                // Use embed_passages for BGE model compatibility (better search quality)
-                let embeddings = model.embed_passages(&chunk_texts)?;
-
-                // Remove old vectors for this file
-                let _ = vector_store.remove_file(&file_path_str);
-
-                // Store new vectors
-                for (chunk, embedding) in doc.chunks.iter().zip(embeddings.iter()) {
-                    let vector_entry = VectorEntry::new(
-                        file_path_str.to_string(),
-                        chunk.chunk_index,
-                        embedding.clone(),
-                        chunk.text.clone(),
-                        chunk.context.clone(),
-                        chunk.start_line,
-                        chunk.end_line,
-                    );
-                    let _ = vector_store.insert(&vector_entry);
+            let check = FileSnapshot::read(file_path)
+                .and_then(|snapshot| state_store.check_file(&file_path_str, file_path, snapshot, false));
+            match check {
+                Ok(FileCheck::Unchanged) => {}
+                Ok(FileCheck::Touched(state)) => {
+                    if let Err(e) = state_store.store_file_state(&file_path_str, &state) {
+                        self.console.warn("index", format!("{}: {}", file_path_str, e));
+                    }
                 }
-
-                // Update state
-                if let (Ok(modified_time), Ok(hash)) = (get_file_modified_time(file_path), calculate_file_hash(file_path)) {
-                    let _ = state_store.update_file_state(&file_path_str, modified_time, hash);
+                Ok(FileCheck::Changed) | Err(_) => {
+                    // Index this single file through the shared pipeline
+                    index_file(file_path, &file_path_str, &self.config, model, &vector_store)?;
+
+                    // Update state
+                    let stored = FileState::from_file(file_path)
+                        .and_then(|state| state_store.store_file_state(&file_path_str, &state));
+                    if let Err(e) = stored {
+                        self.console.warn("index", format!("{}: {}", file_path_str, e));
+                    }
                 }
             }
 
             // Set active_files to ONLY this file
             self.active_files.clear();
             self.active_files.insert(file_path_str.to_string());
+            self.load_indexed_hashes(&state_store);
 
             // Store components
-            self.model = Some(model);
+            self.build_suggestions(&vector_store);
+            self.load_file_names(&vector_store);
             self.vector_store = Some(vector_store);
+            self.result_cache.clear();
 
-            // Switch to search screen
+            // Switch to search screen (keeping a query from the command line)
             self.current_screen = Screen::Search;
-            self.query.clear();
+            if !self.pending_query {
+                self.query.clear();
+            }
             self.results.clear();
             self.search_mode = true;
 
             Ok(())
         })();
+        // Kept for the next file or folder, even if this one failed
+        if let Some(model) = loaded {
+            self.model = Some(model);
+        }
 
         if let Err(e) = res {
+            self.console.error("open file", e.to_string());
             self.status_message = Some(format!("Error: {}", e));
         }
+        self.run_pending_query();
+
+        Ok(())
+    }
+
+    /// Ask for confirmation in the footer on the first press of the reindex key; the second
+    /// press in a row re-embeds every file in scope
+    fn request_reindex(&mut self, confirmed: bool) -> Result<()> {
+        if !confirmed {
+            self.reindex_armed = true;
+            let (key, scope) = if self.current_screen == Screen::Search {
+                ("Ctrl+R", format!("all {} files in scope", self.active_files.len()))
+            } else {
+                ("r", format!("every note in {}", self.current_dir.display()))
+            };
+            self.status_message = Some(format!(
+                "Re-embed {}? This can take minutes. Press {} again to start, any other key cancels",
+                scope, key
+            ));
+            return Ok(());
+        }
+
+        if self.current_screen == Screen::DirectorySelection {
+            self.status_message = None;
+            let current_dir_clone = self.current_dir.clone();
+            return self.select_directory(&current_dir_clone, true);
+        }
+        if let Err(e) = StateStore::open(&self.config).and_then(|state_store| self.start_reindex(state_store)) {
+            self.console.error("reindex", e.to_string());
+            self.status_message = Some(format!("Could not reindex: {}", e));
+        }
+        Ok(())
+    }
+
+    /// Remove the vectors and state of every file in scope and queue them all for re-embedding
+    fn start_reindex(&mut self, state_store: StateStore) -> Result<()> {
+        let vector_store = self.vector_store.as_ref().ok_or_else(|| Error::Config("Vector store not initialized".to_string()))?;
+        let mut files: Vec<String> = self.active_files.iter().cloned().collect();
+        files.sort();
+        for file in &files {
+            vector_store.remove_file(file)?;
+            state_store.remove_file(file)?;
+        }
+        self.result_cache.clear();
 
+        self.results.clear();
+        self.selected = 0;
+        self.details_scroll = 0;
+        self.refresh_source_check();
+        self.search_mode = false;
+        self.status_message = Some(format!("Reindexing 0/{} files… (Esc: stop)", files.len()));
+        self.reindex = Some(ReindexJob {
+            state_store,
+            // Canonical, so the roots recorded in file states match `notes2vec index`
+            root: std::fs::canonicalize(&self.current_dir).unwrap_or_else(|_| self.current_dir.clone()),
+            files,
+            done: 0,
+            failed: 0,
+        });
         Ok(())
     }
 
-    /// Select a directory and initialize search (search in all files in directory)
-    fn select_directory(&mut self, dir: &Path) -> Result<()> {
+    /// Re-embed the next file of the running reindex, wrapping up after the last one
+    fn reindex_step(&mut self) {
+        let Some(mut job) = self.reindex.take() else {
+            return;
+        };
+        let (Some(model), Some(vector_store)) = (&self.model, &self.vector_store) else {
+            self.finish_reindex(job, true);
+            return;
+        };
+        if let Some(file) = job.files.get(job.done) {
+            let path = job.root.join(file);
+            let result = index_file(&path, file, &self.config, model, vector_store)
+                .and_then(|_| FileState::from_file(&path))
+                .and_then(|state| job.state_store.store_file_state(file, &state.with_root(&job.root)));
+            if let Err(e) = result {
+                self.console.error("reindex", format!("{}: {}", file, e));
+                job.failed += 1;
+            }
+            job.done += 1;
+            self.result_cache.clear();
+            self.status_message = Some(format!("Reindexing {}/{} files… (Esc: stop)", job.done, job.files.len()));
+        }
+
+        if job.done < job.files.len() {
+            self.reindex = Some(job);
+        } else {
+            self.finish_reindex(job, false);
+        }
+    }
+
+    /// Pick up the new hashes and completions once a reindex ends. Files a stopped reindex
+    /// didn't reach have no state left, so they're indexed the next time the folder is opened.
+    fn finish_reindex(&mut self, job: ReindexJob, stopped: bool) {
+        if let (false, Some(model)) = (stopped, &self.model) {
+            if let Err(e) = job.state_store.record_model(&self.config, model) {
+                self.console.warn("reindex", format!("Could not record the embedding model: {}", e));
+            }
+        }
+        self.load_indexed_hashes(&job.state_store);
+        if let Some(vector_store) = self.vector_store.take() {
+            self.build_suggestions(&vector_store);
+            self.load_file_names(&vector_store);
+            self.vector_store = Some(vector_store);
+            self.result_cache.clear();
+        }
+
+        let failed = if job.failed > 0 {
+            format!(", {} failed (! for details)", job.failed)
+        } else {
+            String::new()
+        };
+        self.status_message = Some(if stopped {
+            format!(
+                "Reindex stopped after {} of {} files{}; the rest are indexed when the folder is next opened",
+                job.done,
+                job.files.len(),
+                failed
+            )
+        } else {
+            format!("Reindexed {} files{}", job.files.len(), failed)
+        });
+    }
+
+    /// Select a directory and initialize search (search in all files in directory).
+    /// With `force`, every file found is removed from the index and re-embedded (see `reindex_step`).
+    fn select_directory(&mut self, dir: &Path, force: bool) -> Result<()> {
         // IMPORTANT: never let indexing/search setup errors kill the TUI loop.
         // We surface errors in the Directory Selection footer instead.
+        let mut loaded = None;
         let res: Result<()> = (|| {
-            // TUI must not print while in raw/alternate screen mode.
-            let model = EmbeddingModel::init_quiet(&self.config)?;
+            // Missing model files: run the setup flow instead of downloading silently
+            if Self::needs_setup(&self.config) {
+                self.setup = SetupState::Prompt;
+                self.current_screen = Screen::Setup;
+                return Ok(());
+            }
+
+            // Usually loaded already by the warm-up thread started with the TUI
+            let model = &*loaded.insert(self.load_model()?);
             self.model_ready = model.is_model_loaded();
 
             // Enforce real embeddings (no hash fallback) to keep results high-quality and avoid mixed indexes.
             if !self.model_ready {
-                let model_path = self.config.models_dir.join("model.safetensors");
-                let tokenizer_path = self.config.models_dir.join("tokenizer.json");
-                let config_path = self.config.models_dir.join("config.json");
+                let model_path = self.config.model_dir().join("model.safetensors");
+                let tokenizer_path = self.config.model_dir().join("tokenizer.json");
+                let config_path = self.config.model_dir().join("config.json");
                 self.status_message = Some(format!(
                     "Embedding model not loaded. Run: notes2vec init --base-dir {}  (missing: {}{}{})",
                     self.config.base_dir.display(),
@@ -490,21 +1371,39 @@ impl SearchTui {
             }
 
             // Open stores (after model is guaranteed)
-            // If the model id changed, wipe stale indexes so results are consistent.
+            // If the model, embedding prefixes or embedding length changed, wipe stale indexes so results are consistent.
             let state_store = StateStore::open(&self.config)?;
-            let previous_model_id = state_store.get_model_id()?.unwrap_or_default();
+            let recorded_model = state_store.get_model_checksums()?;
+            let needs_reindex = state_store.needs_reindex(&self.config)?
+                || state_store.check_embedding_dim(model.embedding_dim()).is_err()
+                || model_mismatch(recorded_model.as_ref(), model.checksums(recorded_model.as_ref())).is_some();
             drop(state_store);
-            if previous_model_id != EMBEDDING_MODEL_ID {
-                // Best-effort reset
-                let _ = std::fs::remove_file(self.config.database_dir.join("vectors.redb"));
-                let _ = std::fs::remove_file(&self.config.state_path);
+            if needs_reindex {
+                // Best-effort reset; a file that was never created is fine
+                for path in [self.config.database_dir.join("vectors.redb"), self.config.state_path.clone()] {
+                    match std::fs::remove_file(&path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                            self.console.warn("reset index", format!("{}: {}", path.display(), e));
+                        }
+                        _ => {}
+                    }
+                }
             }
 
             let state_store = StateStore::open(&self.config)?;
             let vector_store = VectorStore::open(&self.config)?;
+            // Lets `notes2vec verify` find the sources without being told
+            let notes_root = std::fs::canonicalize(dir)?;
+            state_store.set_notes_root(&notes_root)?;
 
             // Discover and index files
-            let files = discover_files(dir)?;
+            let discovery = discover_files_with_report(dir, DiscoveryOptions::from_config(&self.config))?;
+            // Counted in the footer's ⚠ badge; `!` lists them
+            for (path, reason) in &discovery.skipped {
+                let shown = path.strip_prefix(dir).unwrap_or(path);
+                self.console.warn("discovery", format!("{}: skipped ({})", shown.display(), reason.label()));
+            }
+            let files = discovery.into_files();
             if files.is_empty() {
                 self.status_message = Some("No .md or .txt files found in this folder.".to_string());
                 return Ok(());
@@ -518,6 +1417,14 @@ impl SearchTui {
                 }
             }
 
+            if force {
+                self.vector_store = Some(vector_store);
+                self.start_reindex(state_store)?;
+                self.current_screen = Screen::Search;
+                self.query.clear();
+                return Ok(());
+            }
+
             for file in &files {
                 // Convert path to string, skip if invalid UTF-8
                 let file_path_str = match file.relative_path.to_str() {
@@ -526,66 +1433,61 @@ impl SearchTui {
                 };
 
                 // Check if file has changed
-                match (get_file_modified_time(&file.path), calculate_file_hash(&file.path)) {
-                    (Ok(modified_time), Ok(hash)) => {
-                        if state_store.has_file_changed(file_path_str, modified_time, &hash)? {
-                            // Index the file
-                            match crate::indexing::parser::parse_markdown_file(&file.path) {
-                                Ok(doc) => {
-                                    // Embed context + chunk text so headings like "Agenda" affect retrieval.
-                                    let chunk_texts: Vec<String> = doc
-                                        .chunks
-                                        .iter()
-                                        .map(|c| {
-                                            if c.context.trim().is_empty() {
-                                                c.text.clone()
-                                            } else {
-                                                format!("{}\n{}", c.context, c.text)
-                                            }
-                                        })
-                                        .collect();
-
-                                    let embeddings = model.embed_passages(&chunk_texts)?;
-                                    for (chunk, embedding) in doc.chunks.iter().zip(embeddings.iter()) {
-                                        let vector_entry = VectorEntry::new(
-                                            file_path_str.to_string(),
-                                            chunk.chunk_index,
-                                            embedding.clone(),
-                                            chunk.text.clone(),
-                                            chunk.context.clone(),
-                                            chunk.start_line,
-                                            chunk.end_line,
-                                        );
-                                        let _ = vector_store.insert(&vector_entry);
-                                    }
-                                    let _ = state_store.update_file_state(file_path_str, modified_time, hash);
+                let check = FileSnapshot::read(&file.path)
+                    .and_then(|snapshot| state_store.check_file(file_path_str, &file.path, snapshot, false));
+                let result = match check {
+                    Ok(FileCheck::Touched(state)) => state_store.store_file_state(file_path_str, &state),
+                    Ok(FileCheck::Changed) => {
+                        // Index the file (same code path as `notes2vec index`)
+                        index_file(&file.path, file_path_str, &self.config, model, &vector_store)
+                            .and_then(|indexed| {
+                                if indexed.chunks_truncated > 0 {
+                                    self.console.warn("index", format!(
+                                        "{}: {} of {} chunks truncated at {} tokens",
+                                        file_path_str, indexed.chunks_truncated, indexed.chunks_stored, model.max_tokens()
+                                    ));
                                 }
-                                Err(_) => {}
-                            }
-                        }
+                                FileState::from_file(&file.path)
+                            })
+                            .and_then(|state| state_store.store_file_state(file_path_str, &state.with_root(&notes_root)))
                     }
-                    _ => {}
+                    Ok(FileCheck::Unchanged) => Ok(()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    self.console.error("index", format!("{}: {}", file_path_str, e));
                 }
             }
 
-            // Record model id used for this index
-            let _ = state_store.set_model_id(EMBEDDING_MODEL_ID);
+            // Record the model and prefixes used for this index
+            if let Err(e) = state_store.record_model(&self.config, model) {
+                self.console.warn("index", format!("Could not record the embedding model: {}", e));
+            }
+            self.load_indexed_hashes(&state_store);
 
             // Initialize search components
+            self.build_suggestions(&vector_store);
+            self.load_file_names(&vector_store);
             self.vector_store = Some(vector_store);
-            self.model = Some(model);
+            self.result_cache.clear();
             self.current_screen = Screen::Search;
             self.status_message = None;
 
             Ok(())
         })();
+        // Kept for the next file or folder, even if this one failed
+        if let Some(model) = loaded {
+            self.model = Some(model);
+        }
 
         if let Err(e) = res {
+            self.console.error("open folder", e.to_string());
             self.status_message = Some(format!("{}", e));
             // Stay in directory selection instead of exiting the whole app.
             self.current_screen = Screen::DirectorySelection;
             return Ok(());
         }
+        self.run_pending_query();
 
         Ok(())
     }
@@ -594,27 +1496,245 @@ impl SearchTui {
         let model = self.model.as_ref().ok_or_else(|| Error::Config("Model not initialized".to_string()))?;
         let vector_store = self.vector_store.as_ref().ok_or_else(|| Error::Config("Vector store not initialized".to_string()))?;
 
-        let results = perform_search(&self.query, model, vector_store, &self.active_files)?;
+        let options = SearchOptions::new(self.result_limit)
+            .with_per_file(Some(self.per_file_limit))
+            .with_merge_adjacent(self.config.merge_adjacent_chunks)
+            // Breakdowns record the session bonus each result actually got, which `reordered`
+            // needs; they're only shown with explain on
+            .with_explain(self.explain || self.folder_frecency.generation() != 0);
+        let (filters, _) = parse_query_filters(&self.query, &TagNormalization::default());
+        let searched = if filters.collections.is_empty() {
+            let key = CacheKey::new(&self.query, &self.active_files, &self.config.model_id, options)
+                .with_session(self.folder_frecency.generation());
+            let session = Some(&self.folder_frecency);
+            self.result_cache.get_or_search(key, || {
+                perform_search(&self.query, model, vector_store, &self.active_files, &self.file_names, &self.config, &options, session)
+            })
+        } else {
+            for name in &filters.collections {
+                if !self.collection_stores.contains_key(name) {
+                    match CollectionStore::open(&self.config, name) {
+                        Ok(store) => {
+                            self.collection_stores.insert(name.clone(), store);
+                        }
+                        Err(e) => {
+                            self.console.error("search", e.to_string());
+                            self.status_message = Some(e.to_string());
+                        }
+                    }
+                }
+            }
+            let stores: Vec<&CollectionStore> = filters
+                .collections
+                .iter()
+                .filter_map(|name| self.collection_stores.get(name))
+                .collect();
+            perform_search_collections(&self.query, model, &stores, &self.config, &options)
+        };
+        let results = match searched {
+            Ok(results) => results,
+            Err(e) => {
+                // Explain in the footer and the console instead of leaving the TUI
+                self.console.error("search", e.to_string());
+                self.status_message = Some(e.to_string());
+                Vec::new()
+            }
+        };
+        self.session_boosted = filters.collections.is_empty() && self.folder_frecency.reordered(&results);
         self.results = results;
         self.selected = 0;
+        self.details_scroll = 0;
+        self.refresh_source_check();
 
         Ok(())
     }
 
+    /// Forget the folders picked this session and re-run the search without their bonus (`b`)
+    fn clear_folder_boost(&mut self) -> Result<()> {
+        if !self.folder_frecency.clear() {
+            self.status_message = Some("No folder boost to clear".to_string());
+            return Ok(());
+        }
+        self.status_message = Some("Folder boost cleared".to_string());
+        let was_boosted = std::mem::take(&mut self.session_boosted);
+        if was_boosted && self.vector_store.is_some() && self.model.is_some() {
+            self.perform_search()?;
+        }
+        Ok(())
+    }
+
+    /// Use a new result limit and per-file cap for this session, re-running the current search
+    fn set_limits(&mut self, result_limit: usize, per_file_limit: usize) -> Result<()> {
+        if (result_limit, per_file_limit) == (self.result_limit, self.per_file_limit) {
+            return Ok(());
+        }
+        self.result_limit = result_limit;
+        self.per_file_limit = per_file_limit;
+        self.status_message = Some(format!(
+            "Showing up to {} results, {} per file (Ctrl+P: save)",
+            result_limit, per_file_limit
+        ));
+        if !self.query.trim().is_empty() && self.vector_store.is_some() && self.model.is_some() {
+            let selected = self.selected;
+            self.perform_search()?;
+            self.select(selected);
+        }
+        Ok(())
+    }
+
+    /// Write the session's result limit and per-file cap to config.toml (Ctrl+P)
+    fn save_limits(&mut self) {
+        let saved = self
+            .config
+            .persist_setting("tui_results", &self.result_limit.to_string())
+            .and_then(|()| {
+                self.config
+                    .persist_setting("tui_results_per_file", &self.per_file_limit.to_string())
+            });
+        match saved {
+            Ok(()) => {
+                self.config.tui_results = self.result_limit;
+                self.config.tui_results_per_file = self.per_file_limit;
+                self.status_message = Some(format!(
+                    "Saved {} results, {} per file to {}",
+                    self.result_limit,
+                    self.per_file_limit,
+                    self.config.config_file().display()
+                ));
+            }
+            Err(e) => {
+                self.console.error("settings", e.to_string());
+                self.status_message = Some(e.to_string());
+            }
+        }
+    }
+
     fn render_ui(&self, f: &mut Frame) {
         // Paint a consistent background so the UI doesn't depend on the user's terminal theme.
         // If the terminal doesn't support truecolor, this will be approximated.
         let size = f.size();
-        let background = Block::default().style(Style::default().bg(colors::BG));
-        f.render_widget(background, size);
+        if self.config.paint_background {
+            let background = Block::default().style(Style::default().bg(colors::BG));
+            f.render_widget(background, size);
+        }
 
         match self.current_screen {
             Screen::Welcome => self.render_welcome(f),
+            Screen::Setup => self.render_setup(f),
             Screen::DirectorySelection => self.render_directory_selection(f),
             Screen::Search => self.render_search(f),
         }
+
+        if self.console_open {
+            self.render_console(f);
+        }
+    }
+
+    /// Footer note while the warm-up thread is still loading the model
+    fn model_badge(&self) -> Vec<Span<'static>> {
+        if !self.model_warmup.as_ref().is_some_and(ModelWarmup::is_loading) {
+            return Vec::new();
+        }
+        vec![
+            Span::raw("  |  "),
+            Span::styled("loading model…", Style::default().fg(colors::STATUS_TEXT).add_modifier(Modifier::ITALIC)),
+        ]
+    }
+
+    /// The reindex confirmation, for the query-editing footer (which otherwise hides status messages)
+    fn reindex_prompt(&self) -> Vec<Span<'static>> {
+        match &self.status_message {
+            Some(msg) if self.reindex_armed => vec![
+                Span::raw("  |  "),
+                Span::styled(msg.clone(), Style::default().fg(colors::STATUS_TEXT).add_modifier(Modifier::BOLD)),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Footer badge with the number of logged errors and warnings, if any
+    fn console_badge(&self) -> Vec<Span<'static>> {
+        if self.console.is_empty() {
+            return Vec::new();
+        }
+        vec![
+            Span::raw("  |  "),
+            Span::styled(
+                format!("⚠ {}", self.console.len()),
+                Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" (!)"),
+        ]
+    }
+
+    /// Errors and warnings from this session, newest first, over the current screen
+    fn render_console(&self, f: &mut Frame) {
+        let size = f.size();
+        let width = (size.width * 4 / 5).max(size.width.min(40));
+        let height = (size.height * 7 / 10).max(size.height.min(8));
+        let area = Rect::new(
+            size.x + (size.width - width) / 2,
+            size.y + (size.height - height) / 2,
+            width,
+            height,
+        );
+
+        let now = SystemTime::now();
+        let mut items: Vec<ListItem> = self
+            .console
+            .entries()
+            .rev()
+            .skip(self.console_scroll)
+            .map(|entry| {
+                let (label, color) = match entry.severity {
+                    Severity::Error => ("error", colors::ACCENT),
+                    Severity::Warning => ("warn ", colors::MUTED),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:>9} ", format_age(entry.at, now)), Style::default().fg(colors::MUTED)),
+                    Span::styled(label, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                    Span::styled(format!(" {}: ", entry.source), Style::default().fg(colors::MUTED)),
+                    Span::styled(entry.message.clone(), Style::default().fg(colors::TEXT)),
+                ]))
+            })
+            .collect();
+        if items.is_empty() {
+            items.push(ListItem::new(Span::styled("No errors this session.", Style::default().fg(colors::MUTED))));
+        }
+        if self.console.dropped() > 0 {
+            items.push(ListItem::new(Span::styled(
+                format!("{} older entries dropped", self.console.dropped()),
+                Style::default().fg(colors::MUTED),
+            )));
+        }
+
+        let list = List::new(items)
+            .style(self.status_bar_style())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(colors::BORDER))
+                    .title(vec![
+                        Span::styled(
+                            format!("Errors ({})", self.console.len()),
+                            Style::default().fg(colors::TEXT).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled("  ↑↓ scroll · Esc close", Style::default().fg(colors::MUTED)),
+                    ]),
+            );
+        f.render_widget(Clear, area);
+        f.render_widget(list, area);
     }
     
+    /// Status bar background, unless background painting is disabled
+    fn status_bar_style(&self) -> Style {
+        if self.config.paint_background {
+            Style::default().bg(colors::STATUS_BG)
+        } else {
+            Style::default()
+        }
+    }
+
     fn render_welcome(&self, f: &mut Frame) {
         let size = f.size();
 
@@ -656,7 +1776,11 @@ impl SearchTui {
             "notes2vec",
             "Semantic search for your notes",
         ];
-        let logo_lines: Vec<&str> = if left_w >= 78 { big_logo.to_vec() } else { small_logo.to_vec() };
+        let logo_lines: Vec<&str> = if left_w >= 78 && self.config.ascii_art {
+            big_logo.to_vec()
+        } else {
+            small_logo.to_vec()
+        };
 
         // Clip logo lines so they don't spill when the terminal is narrow.
         let max_left = left_w.saturating_sub(1);
@@ -744,7 +1868,13 @@ impl SearchTui {
 
         let right_w = main_cols[2].width as usize;
         let right_h = main_cols[2].height as usize;
-        let character = if right_w < 62 || right_h < 18 { small_character } else { big_character };
+        let character: &[&str] = if !self.config.ascii_art {
+            &[]
+        } else if right_w < 62 || right_h < 18 {
+            small_character
+        } else {
+            big_character
+        };
 
         let available_lines = main_cols[2].height as usize;
         let mut start = 0usize;
@@ -802,7 +1932,7 @@ impl SearchTui {
             ),
             Span::styled("]", Style::default().fg(muted)),
             Span::raw(" Quit"),
-        ]))
+        ].into_iter().chain(self.model_badge()).collect::<Vec<_>>()))
         .style(Style::default().fg(muted))
         .alignment(Alignment::Center)
         .block(
@@ -814,42 +1944,244 @@ impl SearchTui {
         f.render_widget(footer, chunks[1]);
     }
     
-    fn render_directory_selection(&self, f: &mut Frame) {
+    fn render_setup(&self, f: &mut Frame) {
         let size = f.size();
+        let muted = colors::STATUS_TEXT;
+        let key_bg = colors::BORDER;
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(1), // Title
-                Constraint::Length(3), // Current path
-                Constraint::Min(0),    // Directory list
-                Constraint::Length(2), // Footer (increased for better visibility)
+                Constraint::Min(0),    // Explanation
+                Constraint::Length(3), // Download gauge
+                Constraint::Length(2), // Footer
             ])
             .split(size);
 
-        // Title (top-left) - no padding
         let title = Paragraph::new(Line::from(vec![
             Span::styled(
                 "notes2vec",
                 Style::default().fg(colors::TITLE).add_modifier(Modifier::BOLD),
             ),
+            Span::styled("  first-run setup", Style::default().fg(muted)),
         ]))
         .block(Block::default().borders(Borders::NONE))
         .alignment(Alignment::Left);
         f.render_widget(title, chunks[0]);
 
-        // Current directory path
-        let path_text = format!("Current directory: {}", self.current_dir.display());
-        let path_para = Paragraph::new(path_text)
+        let text = Style::default().fg(colors::TEXT);
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "notes2vec needs a local embedding model to index and search your notes.",
+                text.add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Data directory: ", Style::default().fg(muted)),
+                Span::styled(self.config.base_dir.display().to_string(), text),
+            ]),
+            Line::from(vec![
+                Span::styled("Model:          ", Style::default().fg(muted)),
+                Span::styled(self.config.model_id.clone(), text),
+            ]),
+            Line::from(vec![
+                Span::styled("Model files:    ", Style::default().fg(muted)),
+                Span::styled(self.config.model_dir().display().to_string(), text),
+            ]),
+            Line::from(""),
+        ];
+
+        match &self.setup {
+            SetupState::Prompt => {
+                lines.push(Line::from(Span::styled(
+                    "The model is downloaded once from the Hugging Face Hub and then runs offline.",
+                    text,
+                )));
+                lines.push(Line::from(Span::styled(
+                    "Download it now? [y/Enter] yes  [n] no",
+                    Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD),
+                )));
+            }
+            SetupState::Downloading(progress) => {
+                let status = match progress {
+                    Some(p) => format!("Downloading {} ({}/{})...", p.file, p.file_number, p.file_count),
+                    None => "Connecting to the Hugging Face Hub...".to_string(),
+                };
+                lines.push(Line::from(Span::styled(status, text)));
+            }
+            SetupState::Verifying => {
+                lines.push(Line::from(Span::styled(
+                    "Verifying the model by embedding a test sentence...",
+                    text,
+                )));
+            }
+            SetupState::Failed(message) => {
+                lines.push(Line::from(Span::styled(
+                    "Setup failed:",
+                    Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD),
+                )));
+                lines.push(Line::from(Span::styled(message.clone(), text)));
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Check your network connection and press Enter to retry.",
+                    text,
+                )));
+            }
+            SetupState::Declined => {
+                for line in [
+                    "Without the model, notes2vec can't index or search anything.",
+                    "",
+                    "There is no hash-embedding fallback: hashed vectors only match exact words,",
+                    "and mixing them with model vectors in one index would make scores meaningless.",
+                    "Notes stay unindexed until the model is available.",
+                    "",
+                    "Press Enter to download it after all, or run `notes2vec init` later.",
+                ] {
+                    lines.push(Line::from(Span::styled(line, text)));
+                }
+            }
+        }
+
+        let body = Paragraph::new(lines)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(colors::BORDER))
                     .title(vec![
-                        Span::styled("Select Notes Directory", Style::default().fg(colors::TEXT).add_modifier(Modifier::BOLD)),
-                    ])
+                        Span::styled("Setup", Style::default().fg(colors::TEXT).add_modifier(Modifier::BOLD)),
+                    ]),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(body, chunks[1]);
+
+        if let SetupState::Downloading(Some(progress)) = &self.setup {
+            let label = if progress.total > 0 {
+                format!(
+                    "{} / {} MB",
+                    progress.downloaded / (1024 * 1024),
+                    progress.total / (1024 * 1024),
+                )
+            } else {
+                progress.file.clone()
+            };
+            let gauge = Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(colors::BORDER)),
+                )
+                .gauge_style(Style::default().fg(colors::ACCENT))
+                .ratio(progress.ratio())
+                .label(label);
+            f.render_widget(gauge, chunks[2]);
+        }
+
+        let key = |label: &'static str| {
+            Span::styled(
+                label,
+                Style::default().fg(colors::KEY_ENTER).bg(key_bg).add_modifier(Modifier::BOLD),
             )
-            .style(Style::default().fg(colors::TEXT));
+        };
+        let mut footer_spans = Vec::new();
+        match &self.setup {
+            SetupState::Downloading(_) | SetupState::Verifying => {}
+            SetupState::Prompt => {
+                footer_spans.extend([
+                    Span::styled("[", Style::default().fg(muted)),
+                    key(" y "),
+                    Span::styled("]", Style::default().fg(muted)),
+                    Span::raw(" Download  "),
+                    Span::styled("[", Style::default().fg(muted)),
+                    key(" n "),
+                    Span::styled("]", Style::default().fg(muted)),
+                    Span::raw(" Skip  "),
+                ]);
+            }
+            SetupState::Failed(_) | SetupState::Declined => {
+                footer_spans.extend([
+                    Span::styled("[", Style::default().fg(muted)),
+                    key(" Enter "),
+                    Span::styled("]", Style::default().fg(muted)),
+                    Span::raw(" Download  "),
+                ]);
+            }
+        }
+        footer_spans.extend([
+            Span::styled("[", Style::default().fg(muted)),
+            key(" Esc "),
+            Span::styled("]", Style::default().fg(muted)),
+            Span::raw(" Quit"),
+        ]);
+
+        let footer = Paragraph::new(Line::from(footer_spans))
+            .style(Style::default().fg(muted))
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_style(Style::default().fg(colors::BORDER)),
+            );
+        f.render_widget(footer, chunks[3]);
+    }
+
+    fn render_directory_selection(&self, f: &mut Frame) {
+        let size = f.size();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1), // Title
+                Constraint::Length(3), // Current path
+                Constraint::Min(0),    // Directory list
+                Constraint::Length(2), // Footer (increased for better visibility)
+            ])
+            .split(size);
+
+        // Title (top-left) - no padding
+        let title = Paragraph::new(Line::from(vec![
+            Span::styled(
+                "notes2vec",
+                Style::default().fg(colors::TITLE).add_modifier(Modifier::BOLD),
+            ),
+        ]))
+        .block(Block::default().borders(Borders::NONE))
+        .alignment(Alignment::Left);
+        f.render_widget(title, chunks[0]);
+
+        // Current directory path, or the path being typed after `/`
+        let path_para = match &self.path_input {
+            Some(input) => {
+                let mut spans = if input.text.is_empty() {
+                    vec![Span::styled("Type a path (~ for home, Tab completes)", Style::default().fg(colors::MUTED))]
+                } else {
+                    vec![Span::styled(input.text.clone(), Style::default().fg(colors::TEXT))]
+                };
+                if let Some(error) = &input.error {
+                    spans.push(Span::styled(format!("  {}", error), Style::default().fg(colors::REMOVED)));
+                } else if !input.matches.is_empty() {
+                    spans.push(Span::styled(format!("  {}", input.matches.join("  ")), Style::default().fg(colors::MUTED)));
+                }
+                Paragraph::new(Line::from(spans)).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(colors::TITLE))
+                        .title(vec![
+                            Span::styled("Go to Path", Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD)),
+                        ])
+                )
+            }
+            None => Paragraph::new(format!("Current directory: {}", self.current_dir.display()))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(colors::BORDER))
+                        .title(vec![
+                            Span::styled("Select Notes Directory", Style::default().fg(colors::TEXT).add_modifier(Modifier::BOLD)),
+                        ])
+                )
+                .style(Style::default().fg(colors::TEXT)),
+        };
 
         f.render_widget(path_para, chunks[1]);
 
@@ -902,28 +2234,47 @@ impl SearchTui {
         f.render_stateful_widget(list, chunks[2], &mut list_state);
 
         // Footer (Status Bar)
-        let mut footer_spans = vec![
-            Span::styled("↑↓", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
-            Span::raw(": Navigate | "),
-            Span::styled("Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
-            Span::raw(": Open | "),
-            Span::styled("s", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
-            Span::raw(": Search here | "),
-            Span::styled("Esc", Style::default().fg(colors::KEY_ESC).add_modifier(Modifier::BOLD)),
-            Span::raw(": Back | "),
-            Span::styled("Ctrl+C", Style::default().fg(colors::KEY_QUIT).add_modifier(Modifier::BOLD)),
-            Span::raw(": Quit"),
-        ];
+        let mut footer_spans = if self.path_input.is_some() {
+            vec![
+                Span::styled("Tab", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Complete | "),
+                Span::styled("Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Go | "),
+                Span::styled("Ctrl+Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Search there | "),
+                Span::styled("Esc", Style::default().fg(colors::KEY_ESC).add_modifier(Modifier::BOLD)),
+                Span::raw(": Cancel"),
+            ]
+        } else {
+            vec![
+                Span::styled("↑↓", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Navigate | "),
+                Span::styled("Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Open | "),
+                Span::styled("/", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Go to path | "),
+                Span::styled("s", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Search here | "),
+                Span::styled("r", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Reindex | "),
+                Span::styled("Esc", Style::default().fg(colors::KEY_ESC).add_modifier(Modifier::BOLD)),
+                Span::raw(": Back | "),
+                Span::styled("Ctrl+C", Style::default().fg(colors::KEY_QUIT).add_modifier(Modifier::BOLD)),
+                Span::raw(": Quit"),
+            ]
+        };
 
         if let Some(msg) = &self.status_message {
             footer_spans.push(Span::raw("  |  "));
             footer_spans.push(Span::styled(msg.clone(), Style::default().fg(colors::STATUS_TEXT).add_modifier(Modifier::BOLD)));
         }
+        footer_spans.extend(self.model_badge());
+        footer_spans.extend(self.console_badge());
 
         let footer = Paragraph::new(Line::from(footer_spans))
         .style(Style::default().fg(colors::STATUS_TEXT))
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(colors::BORDER)).style(Style::default().bg(colors::STATUS_BG)));
+        .block(Block::default().borders(Borders::TOP).border_style(Style::default().fg(colors::BORDER)).style(self.status_bar_style()));
 
         f.render_widget(footer, chunks[3]);
     }
@@ -932,15 +2283,8 @@ impl SearchTui {
         let size = f.size();
 
         // Main layout: header with ASCII art, search bar, results, footer
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1), // Title
-                Constraint::Length(4), // Search bar
-                Constraint::Min(0),    // Results
-                Constraint::Length(2),  // Footer (border + text)
-            ])
-            .split(size);
+        // (shared with mouse hit-testing)
+        let chunks = search_chunks(size);
 
         // Title (top-left) - no padding
         let title = Paragraph::new(Line::from(vec![
@@ -1033,18 +2377,12 @@ impl SearchTui {
             f.render_widget(empty_paragraph, chunks[2]);
         } else {
             // Split results area into list and details
-            let result_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Percentage(40), // Results list
-                    Constraint::Percentage(60), // Details panel
-                ])
-                .split(chunks[2]);
+            let result_chunks = result_columns(chunks[2]);
 
             // Results list - show chunk info when multiple results from same file
             let file_counts: std::collections::HashMap<&String, usize> = self.results
                 .iter()
-                .map(|(entry, _)| &entry.file_path)
+                .map(|(entry, _, _)| &entry.file_path)
                 .fold(std::collections::HashMap::new(), |mut acc, path| {
                     *acc.entry(path).or_insert(0) += 1;
                     acc
@@ -1054,7 +2392,7 @@ impl SearchTui {
                 .results
                 .iter()
                 .enumerate()
-                .map(|(i, (entry, similarity))| {
+                .map(|(i, (entry, similarity, _))| {
                     let file_name = &entry.file_path;
                     let count = file_counts.get(file_name).copied().unwrap_or(1);
                     
@@ -1074,9 +2412,20 @@ impl SearchTui {
                         Style::default().fg(colors::TEXT)
                     };
 
-                    let similarity_pct = (similarity * 100.0) as u8;
+                    let similarity_pct = self.score_calibration.display_percent(*similarity);
+                    let label = self.confidence
+                        .map(|c| format!("{:<7}", c.label(*similarity).as_str()))
+                        .unwrap_or_default();
+                    // Hits from `collection:` searches say where they came from
+                    let collection_badge = if entry.collection != self.config.collection {
+                        format!("[{}] ", entry.collection)
+                    } else {
+                        String::new()
+                    };
                     ListItem::new(Line::from(vec![
                         Span::styled(format!("[{:3}%] ", similarity_pct), style),
+                        Span::styled(label, style),
+                        Span::styled(collection_badge, Style::default().fg(colors::MUTED)),
                         Span::styled(file_name.to_string(), style),
                         Span::styled(chunk_indicator, Style::default().fg(colors::MUTED)),
                     ]))
@@ -1105,62 +2454,127 @@ impl SearchTui {
             f.render_stateful_widget(list, result_chunks[0], &mut list_state);
 
             // Details panel
-            if let Some((entry, similarity)) = self.results.get(self.selected) {
-                let details = self.render_details(entry, *similarity);
+            if let Some((entry, similarity, breakdown)) = self.results.get(self.selected) {
+                let details = self.render_details(entry, *similarity, breakdown.as_ref());
                 f.render_widget(details, result_chunks[1]);
             }
         }
 
         // Persistent footer "buttons" (always visible)
         let (file_filter, _semantic_query) = parse_file_filter_query(&self.query);
-        let filter_note = file_filter
-            .as_ref()
-            .map(|f| format!("  Filter: {f}"))
-            .unwrap_or_default();
-        let model_note = format!("  Model: {}", EMBEDDING_MODEL_ID);
+        // How many files in scope the filter leaves, so an over-narrow filter is obvious
+        let filter_note = match &file_filter {
+            Some(f) if !self.active_files.is_empty() => {
+                let names = self.file_names.get(&self.config.collection);
+                let matches: Vec<FilterMatch> = self
+                    .active_files
+                    .iter()
+                    .filter_map(|path| file_filter_match(path, names.and_then(|names| names.get(path)), f))
+                    .collect();
+                let by_alias = matches.iter().filter(|m| **m == FilterMatch::Alias).count();
+                let alias_note = if by_alias > 0 { format!(", {} by alias", by_alias) } else { String::new() };
+                format!("  Filter: {f} ({} of {} files{})", matches.len(), self.active_files.len(), alias_note)
+            }
+            Some(f) => format!("  Filter: {f}"),
+            None => String::new(),
+        };
+        let model_note = format!("  Model: {}", self.config.model_id);
         let scope_note = format!("  Scope: {} ({} files)", self.current_dir.display(), self.active_files.len());
-        let top_note = format!("  Top {} files", MAX_RESULTS_DISPLAYED);
+        let top_note = format!("  Top {} ({} per file)", self.result_limit, self.per_file_limit);
+        // Results this session's picks moved up
+        let boost_note = match self.folder_frecency.strongest() {
+            Some(folder) if self.session_boosted => format!("  ↑ Boosting {}/", folder),
+            _ => String::new(),
+        };
 
+        let history_hint = self.suggestions.is_empty()
+            && (self.query.is_empty() || self.history.is_browsing())
+            && !self.history.entries().is_empty();
         let footer_lines = if self.search_mode {
             vec![
                 Line::from(vec![
                     Span::styled("Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
                     Span::raw(": Search  "),
+                    Span::styled(
+                        if self.suggestions.is_empty() { "" } else { "Tab" },
+                        Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(if self.suggestions.is_empty() { "" } else { ": Complete  " }),
+                    Span::styled(
+                        if history_hint { "↑↓" } else { "" },
+                        Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(if history_hint { ": History  " } else { "" }),
                     Span::styled("Esc", Style::default().fg(colors::KEY_ESC).add_modifier(Modifier::BOLD)),
                     Span::raw(": Back  "),
                     Span::styled("Ctrl+U", Style::default().fg(colors::STATUS_TEXT).add_modifier(Modifier::BOLD)),
                     Span::raw(": Clear  "),
                     Span::styled("Ctrl+C", Style::default().fg(colors::KEY_QUIT).add_modifier(Modifier::BOLD)),
                     Span::raw(": Quit"),
-                ]),
+                ].into_iter().chain(self.reindex_prompt()).chain(self.console_badge()).collect::<Vec<_>>()),
                 Line::from(vec![
-                    Span::styled("file:<name>", Style::default().fg(colors::STATUS_TEXT)),
+                    Span::styled("file:<name> tag:<tag>", Style::default().fg(colors::STATUS_TEXT)),
                     Span::raw(": filter results"),
                     Span::raw(filter_note),
                     Span::raw(model_note),
                     Span::raw(top_note),
                     Span::raw(scope_note.clone()),
+                    Span::styled(boost_note, Style::default().fg(colors::ACCENT)),
                 ]),
             ]
         } else {
+            let diffable = self.source_check == Some(SourceCheck::Changed);
+            let mut browse_spans = vec![
+                Span::styled("↑↓", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Navigate  "),
+                Span::styled("Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Edit  "),
+                Span::styled("a", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Append to scratch  "),
+                Span::styled("e", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(if self.explain { ": Hide score  " } else { ": Explain score  " }),
+                Span::styled(
+                    if diffable { "d" } else { "" },
+                    Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(match (diffable, self.details_diff.is_some()) {
+                    (false, _) => "",
+                    (true, false) => ": Diff  ",
+                    (true, true) => ": Hide diff  ",
+                }),
+                Span::styled(
+                    if self.session_boosted { "b" } else { "" },
+                    Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(if self.session_boosted { ": Clear folder boost  " } else { "" }),
+                Span::styled("+/-", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Results  "),
+                Span::styled("[/]", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Per file  "),
+                Span::styled("Ctrl+S", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Export  "),
+                Span::styled("Ctrl+R", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
+                Span::raw(": Reindex  "),
+                Span::styled("Esc", Style::default().fg(colors::KEY_ESC).add_modifier(Modifier::BOLD)),
+                Span::raw(": Back  "),
+                Span::styled("Ctrl+C", Style::default().fg(colors::KEY_QUIT).add_modifier(Modifier::BOLD)),
+                Span::raw(": Quit"),
+            ];
+            if let Some(msg) = &self.status_message {
+                browse_spans.push(Span::raw("  |  "));
+                browse_spans.push(Span::styled(msg.clone(), Style::default().fg(colors::STATUS_TEXT).add_modifier(Modifier::BOLD)));
+            }
+            browse_spans.extend(self.console_badge());
             vec![
+                Line::from(browse_spans),
                 Line::from(vec![
-                    Span::styled("↑↓", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
-                    Span::raw(": Navigate  "),
-                    Span::styled("Enter", Style::default().fg(colors::KEY_ENTER).add_modifier(Modifier::BOLD)),
-                    Span::raw(": Edit  "),
-                    Span::styled("Esc", Style::default().fg(colors::KEY_ESC).add_modifier(Modifier::BOLD)),
-                    Span::raw(": Back  "),
-                    Span::styled("Ctrl+C", Style::default().fg(colors::KEY_QUIT).add_modifier(Modifier::BOLD)),
-                    Span::raw(": Quit"),
-                ]),
-                Line::from(vec![
-                    Span::styled("file:<name>", Style::default().fg(colors::STATUS_TEXT)),
+                    Span::styled("file:<name> tag:<tag>", Style::default().fg(colors::STATUS_TEXT)),
                     Span::raw(": filter results"),
                     Span::raw(filter_note),
                     Span::raw(model_note),
                     Span::raw(top_note),
                     Span::raw(scope_note.clone()),
+                    Span::styled(boost_note, Style::default().fg(colors::ACCENT)),
                 ]),
             ]
         };
@@ -1172,14 +2586,68 @@ impl SearchTui {
                 Block::default()
                     .borders(Borders::TOP)
                     .border_style(Style::default().fg(colors::BORDER))
-                    .style(Style::default().bg(colors::STATUS_BG)),
+                    .style(self.status_bar_style()),
             );
 
         f.render_widget(footer, chunks[3]);
+
+        // Completions float over the top of the results while typing
+        if self.search_mode && !self.suggestions.is_empty() {
+            let results_area = chunks[2];
+            let area = Rect::new(
+                results_area.x + 1,
+                results_area.y,
+                results_area.width.saturating_sub(2).min(60),
+                (self.suggestions.len() as u16 + 2).min(results_area.height),
+            );
+            let items: Vec<ListItem> = self
+                .suggestions
+                .iter()
+                .enumerate()
+                .map(|(i, suggestion)| {
+                    let style = if i == self.suggestion_selected {
+                        Style::default()
+                            .fg(colors::SELECTION_TEXT)
+                            .bg(colors::SELECTION_BG)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(colors::TEXT)
+                    };
+                    let kind = match suggestion.kind {
+                        SuggestionKind::Heading => "heading",
+                        SuggestionKind::Tag => "tag",
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(suggestion.insert_text(), style),
+                        Span::styled(
+                            format!("  {} · {}", kind, suggestion.count),
+                            Style::default().fg(colors::MUTED),
+                        ),
+                    ]))
+                })
+                .collect();
+            let list = List::new(items)
+                .style(self.status_bar_style())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(colors::BORDER))
+                        .title(vec![
+                            Span::styled("Suggestions", Style::default().fg(colors::TEXT).add_modifier(Modifier::BOLD)),
+                        ]),
+                );
+            f.render_widget(Clear, area);
+            f.render_widget(list, area);
+        }
     }
 
-    fn render_details<'a>(&self, entry: &'a VectorEntry, similarity: f32) -> Paragraph<'a> {
-        let similarity_pct = (similarity * 100.0) as u8;
+    fn render_details<'a>(
+        &self,
+        entry: &'a VectorEntry,
+        similarity: f32,
+        breakdown: Option<&ScoreBreakdown>,
+    ) -> Paragraph<'a> {
+        let similarity_pct = self.score_calibration.display_percent(similarity);
         let start_line = entry.start_line.max(1);
         let end_line = entry.end_line.max(start_line);
 
@@ -1191,6 +2659,12 @@ impl SearchTui {
             Line::from(vec![
                 Span::styled("Match: ", Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD)),
                 Span::styled(format!("{}%", similarity_pct), Style::default().fg(colors::TEXT)),
+                Span::styled(
+                    self.confidence
+                        .map(|c| format!(" ({})", c.label(similarity)))
+                        .unwrap_or_default(),
+                    Style::default().fg(colors::TEXT),
+                ),
                 Span::raw("  "),
                 Span::styled("cos:", Style::default().fg(colors::MUTED)),
                 Span::styled(format!("{:.3}", similarity), Style::default().fg(colors::MUTED)),
@@ -1212,14 +2686,68 @@ impl SearchTui {
             },
             Line::from(""),
             Line::from(vec![
-                Span::styled("Content:", Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    if self.details_diff.is_some() { "Changes since indexing:" } else { "Content:" },
+                    Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD),
+                ),
+                if entry.kind == ChunkKind::Metadata {
+                    Span::styled(" (note has no body; built from frontmatter)", Style::default().fg(colors::MUTED))
+                } else {
+                    Span::raw("")
+                },
             ]),
             Line::from(""),
         ];
 
-        // Add content preview (truncate if too long)
-        // Show a lot more content so the Details panel feels useful.
-        let preview_lines: Vec<&str> = entry.text.lines().take(MAX_PREVIEW_LINES).collect();
+        if let Some(breakdown) = breakdown.filter(|_| self.explain) {
+            lines.insert(
+                2,
+                Line::from(vec![
+                    Span::styled("Score: ", Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD)),
+                    Span::styled(breakdown.to_string(), Style::default().fg(colors::MUTED)),
+                ]),
+            );
+        }
+
+        let banner = match self.source_check {
+            Some(SourceCheck::Changed) => Some(Line::from(vec![
+                Span::styled("⚠ file changed since indexing", Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    if self.details_diff.is_some() { "  (d: hide diff)" } else { "  (d: show diff)" },
+                    Style::default().fg(colors::MUTED),
+                ),
+            ])),
+            Some(SourceCheck::Missing) => Some(Line::from(Span::styled(
+                "⚠ file missing since indexing",
+                Style::default().fg(colors::ACCENT).add_modifier(Modifier::BOLD),
+            ))),
+            Some(SourceCheck::TooLarge) => Some(Line::from(Span::styled(
+                format!("(over {} KiB; not checked for edits since indexing)", MAX_DIFF_FILE_BYTES / 1024),
+                Style::default().fg(colors::MUTED),
+            ))),
+            Some(SourceCheck::Unchanged) | None => None,
+        };
+        if let Some(banner) = banner {
+            lines.insert(0, banner);
+        }
+
+        if let Some(diff) = &self.details_diff {
+            for line in diff {
+                let (prefix, color) = match line.op {
+                    DiffOp::Same => ("  ", colors::TEXT),
+                    DiffOp::Added => ("+ ", colors::ADDED),
+                    DiffOp::Removed => ("- ", colors::REMOVED),
+                };
+                lines.push(Line::from(Span::styled(
+                    format!("{}{}", prefix, line.text),
+                    Style::default().fg(color),
+                )));
+            }
+            return self.details_paragraph(lines);
+        }
+
+        // Add content preview, cut off after the configured number of lines
+        let preview_lines: Vec<&str> = entry.text.lines().take(self.config.preview_lines).collect();
         for line in preview_lines {
             lines.push(Line::from(vec![Span::styled(
                 line.to_string(),
@@ -1227,13 +2755,17 @@ impl SearchTui {
             )]));
         }
 
-        if entry.text.lines().count() > MAX_PREVIEW_LINES {
+        if entry.text.lines().nth(self.config.preview_lines).is_some() {
             lines.push(Line::from(vec![Span::styled(
                 "... (truncated)",
                 Style::default().fg(colors::MUTED),
             )]));
         }
 
+        self.details_paragraph(lines)
+    }
+
+    fn details_paragraph<'a>(&self, lines: Vec<Line<'a>>) -> Paragraph<'a> {
         Paragraph::new(lines)
             .block(
                 Block::default()
@@ -1244,5 +2776,224 @@ impl SearchTui {
                     ]),
             )
             .wrap(Wrap { trim: false })
+            .scroll((self.details_scroll, 0))
+    }
+}
+
+/// Load the model and embed a test sentence, so a broken download fails here rather than mid-index.
+/// A failed check removes the copied files so the next attempt downloads them again.
+fn verify_model(config: &Config) -> Result<()> {
+    let check = EmbeddingModel::init_quiet(config).and_then(|model| {
+        let embeddings = model.embed_passages(&[SETUP_TEST_SENTENCE.to_string()])?;
+        match embeddings.first() {
+            Some(v) if v.iter().any(|x| *x != 0.0) => Ok(()),
+            _ => Err(Error::Model("Test sentence produced an empty embedding".to_string())),
+        }
+    });
+    if check.is_err() {
+        let model_dir = config.model_dir();
+        for file in MODEL_FILES {
+            let _ = std::fs::remove_file(model_dir.join(file));
+        }
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::widgets::Widget;
+    use tempfile::TempDir;
+
+    /// Config whose data directories and model files exist, so the TUI doesn't divert to setup
+    fn ready_config(temp_dir: &TempDir) -> Config {
+        let config = Config::new(Some(temp_dir.path().join("base"))).unwrap();
+        config.init().unwrap();
+        std::fs::create_dir_all(config.model_dir()).unwrap();
+        for file in MODEL_FILES {
+            std::fs::write(config.model_dir().join(file), "").unwrap();
+        }
+        config
+    }
+
+    #[test]
+    fn test_result_limits_last_the_session_and_save_to_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ready_config(&temp_dir);
+        let mut tui = SearchTui::from_config(config.clone()).unwrap();
+        assert_eq!((tui.result_limit, tui.per_file_limit), (20, 5));
+
+        // No query yet, so nothing is searched
+        tui.set_limits(100, 2).unwrap();
+        assert!(tui.status_message.as_deref().unwrap().starts_with("Showing up to 100 results, 2 per file"));
+        assert!(!config.config_file().exists() || !std::fs::read_to_string(config.config_file()).unwrap().contains("tui_results"));
+
+        tui.save_limits();
+        let saved = std::fs::read_to_string(config.config_file()).unwrap();
+        assert!(saved.contains("tui_results = 100"));
+        assert!(saved.contains("tui_results_per_file = 2"));
+        assert_eq!(tui.config.tui_results, 100);
+    }
+
+    #[test]
+    fn test_typed_path_jumps_to_folder_and_selects_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ready_config(&temp_dir);
+        let notes = temp_dir.path().join("notes/deep/down");
+        std::fs::create_dir_all(&notes).unwrap();
+        std::fs::write(notes.join("plan.md"), "# Plan\n").unwrap();
+        let mut tui = SearchTui::from_config(config).unwrap();
+        let start = tui.current_dir.clone();
+        let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        tui.path_input = Some(PathInput { text: notes.join("missing").display().to_string(), ..Default::default() });
+        tui.handle_path_input_key(press(KeyCode::Enter)).unwrap();
+        // Still open, with the reason inline
+        assert!(tui.path_input.as_ref().unwrap().error.as_deref().unwrap().starts_with("No such"));
+        assert_eq!(tui.current_dir, start);
+
+        tui.path_input = Some(PathInput { text: format!("{}/pl", notes.display()), ..Default::default() });
+        tui.handle_path_input_key(press(KeyCode::Tab)).unwrap();
+        tui.handle_path_input_key(press(KeyCode::Enter)).unwrap();
+        assert!(tui.path_input.is_none());
+        assert_eq!(tui.current_dir, notes.canonicalize().unwrap());
+        assert_eq!(tui.dir_entries[tui.dir_selected].file_name().unwrap(), "plan.md");
+        assert_eq!(tui.previous_dir.as_ref(), Some(&start));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_skip_welcome_starts_on_directory_selection() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ready_config(&temp_dir);
+
+        let tui = SearchTui::from_config(config.clone()).unwrap();
+        assert_eq!(tui.current_screen, Screen::Welcome);
+
+        let mut config = config;
+        config.show_welcome = false;
+        let tui = SearchTui::from_config(config).unwrap();
+        assert_eq!(tui.current_screen, Screen::DirectorySelection);
+    }
+
+    #[test]
+    fn test_command_line_query_is_preloaded() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ready_config(&temp_dir);
+
+        let tui = SearchTui::from_config(config.clone()).unwrap().with_query(" kubernetes ingress ");
+        assert_eq!(tui.query, "kubernetes ingress");
+        assert!(tui.pending_query);
+        assert!(tui.search_mode);
+
+        // Nothing to run before a folder is open
+        let mut tui = tui;
+        tui.run_pending_query();
+        assert!(tui.pending_query);
+        assert!(tui.results.is_empty());
+
+        let tui = SearchTui::from_config(config).unwrap().with_query("  ");
+        assert!(tui.query.is_empty());
+        assert!(!tui.pending_query);
+    }
+
+    /// Everything `render_details` draws for `text`, as one string
+    fn rendered_details(tui: &SearchTui, text: &str) -> String {
+        let entry = VectorEntry::new("note.md".to_string(), 0, vec![1.0], text.to_string(), String::new(), 1, 1);
+        let area = Rect::new(0, 0, 60, 20);
+        let mut buffer = Buffer::empty(area);
+        tui.render_details(&entry, 0.8, None).render(area, &mut buffer);
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn test_preview_lines_truncates_long_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = ready_config(&temp_dir);
+        config.preview_lines = 3;
+        let tui = SearchTui::from_config(config).unwrap();
+
+        let long = rendered_details(&tui, "one\ntwo\nthree\nfour\nfive");
+        assert!(long.contains("three"));
+        assert!(!long.contains("four"));
+        assert!(long.contains("(truncated)"));
+
+        // Exactly the limit is not truncated
+        let short = rendered_details(&tui, "one\ntwo\nthree");
+        assert!(short.contains("three"));
+        assert!(!short.contains("(truncated)"));
+    }
+
+    #[test]
+    fn test_changed_source_shows_banner_and_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut tui = SearchTui::from_config(ready_config(&temp_dir)).unwrap();
+        tui.current_dir = temp_dir.path().to_path_buf();
+        std::fs::write(temp_dir.path().join("note.md"), "kept line\nnew line\n").unwrap();
+
+        let entry = VectorEntry::new("note.md".to_string(), 0, vec![1.0], "kept line\nold line".to_string(), String::new(), 1, 2);
+        tui.results = vec![(entry, 0.8, None)];
+        tui.indexed_hashes.insert("note.md".to_string(), "hash at indexing".to_string());
+        tui.refresh_source_check();
+        assert_eq!(tui.source_check, Some(SourceCheck::Changed));
+
+        let rendered = rendered_details(&tui, "kept line\nold line");
+        assert!(rendered.contains("file changed since indexing"));
+        assert!(!rendered.contains("+ new line"));
+
+        tui.toggle_diff();
+        let rendered = rendered_details(&tui, "kept line\nold line");
+        assert!(rendered.contains("- old line"));
+        assert!(rendered.contains("+ new line"));
+
+        // `d` again goes back to the indexed text
+        tui.toggle_diff();
+        assert!(tui.details_diff.is_none());
+    }
+
+    #[test]
+    fn test_reindex_needs_confirmation_and_forgets_files_in_scope() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ready_config(&temp_dir);
+        let notes = temp_dir.path().join("notes");
+        std::fs::create_dir_all(&notes).unwrap();
+
+        let vector_store = VectorStore::open(&config).unwrap();
+        let state_store = StateStore::open(&config).unwrap();
+        for file in ["b.md", "a.md", "other.md"] {
+            std::fs::write(notes.join(file), file).unwrap();
+            vector_store
+                .insert(&VectorEntry::new(file.to_string(), 0, vec![1.0], file.to_string(), String::new(), 1, 1))
+                .unwrap();
+            state_store.store_file_state(file, &FileState::from_file(&notes.join(file)).unwrap()).unwrap();
+        }
+        drop(state_store);
+
+        let mut tui = SearchTui::from_config(config.clone()).unwrap();
+        tui.current_screen = Screen::Search;
+        tui.current_dir = notes;
+        tui.vector_store = Some(vector_store);
+        tui.active_files = ["a.md", "b.md"].iter().map(|file| file.to_string()).collect();
+
+        // First press only asks
+        tui.request_reindex(false).unwrap();
+        assert!(tui.reindex_armed);
+        assert!(tui.status_message.as_deref().unwrap().contains("Press Ctrl+R again"));
+        assert!(tui.reindex.is_none());
+
+        tui.request_reindex(true).unwrap();
+        let job = tui.reindex.as_ref().unwrap();
+        assert_eq!(job.files, vec!["a.md", "b.md"]);
+        let vector_store = tui.vector_store.as_ref().unwrap();
+        assert!(vector_store.get_file_vectors("a.md").unwrap().is_empty());
+        assert!(vector_store.get_file_vectors("b.md").unwrap().is_empty());
+        assert_eq!(vector_store.get_file_vectors("other.md").unwrap().len(), 1);
+        assert!(job.state_store.get_file_state("a.md").unwrap().is_none());
+        assert!(job.state_store.get_file_state("other.md").unwrap().is_some());
+
+        // Stopping early leaves the rest for the next time the folder is opened
+        let job = tui.reindex.take().unwrap();
+        tui.finish_reindex(job, true);
+        assert!(tui.status_message.as_deref().unwrap().starts_with("Reindex stopped after 0 of 2 files"));
+    }
+}
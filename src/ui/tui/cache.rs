@@ -0,0 +1,155 @@
+use super::search::SearchHit;
+use crate::core::error::Result;
+use crate::search::options::SearchOptions;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Searches remembered; the oldest is dropped first
+pub const RESULT_CACHE_SIZE: usize = 32;
+
+/// How long a cached search stays valid, so relative date filters (`modified:7d`) don't go stale
+pub const RESULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// What a search's results depend on, besides the index itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    /// The query with runs of whitespace collapsed
+    query: String,
+    /// [`scope_fingerprint`] of the files searched
+    scope: u64,
+    model_id: String,
+    /// Limits and whether score breakdowns were kept
+    options: SearchOptions,
+    /// [`FolderFrecency::generation`](super::frecency::FolderFrecency::generation) the results
+    /// were boosted with
+    session: u64,
+}
+
+impl CacheKey {
+    pub fn new(query: &str, active_files: &HashSet<String>, model_id: &str, options: SearchOptions) -> Self {
+        Self {
+            query: query.split_whitespace().collect::<Vec<_>>().join(" "),
+            scope: scope_fingerprint(active_files),
+            model_id: model_id.to_string(),
+            options,
+            session: 0,
+        }
+    }
+
+    /// Key results ranked with the session's folder points as they were at `generation`
+    pub fn with_session(mut self, generation: u64) -> Self {
+        self.session = generation;
+        self
+    }
+}
+
+/// Hash of a set of files that doesn't depend on their order
+pub fn scope_fingerprint(files: &HashSet<String>) -> u64 {
+    let mut combined = files.len() as u64;
+    for file in files {
+        let mut hasher = DefaultHasher::new();
+        file.hash(&mut hasher);
+        combined = combined.wrapping_add(hasher.finish());
+    }
+    combined
+}
+
+/// Recent TUI search results, so running the same query in the same scope again is instant.
+/// Anything that writes to the index must [`clear`](Self::clear) it.
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    /// Newest last
+    entries: VecDeque<(CacheKey, Instant, Vec<SearchHit>)>,
+}
+
+impl ResultCache {
+    /// Cached results for `key`, or those of `search`, remembered when it succeeds
+    pub fn get_or_search(&mut self, key: CacheKey, search: impl FnOnce() -> Result<Vec<SearchHit>>) -> Result<Vec<SearchHit>> {
+        self.entries.retain(|(_, stored, _)| stored.elapsed() < RESULT_CACHE_TTL);
+        if let Some(i) = self.entries.iter().position(|(cached, _, _)| *cached == key) {
+            let entry = self.entries.remove(i).expect("position is in range");
+            let results = entry.2.clone();
+            self.entries.push_back(entry);
+            return Ok(results);
+        }
+
+        let results = search()?;
+        if self.entries.len() >= RESULT_CACHE_SIZE {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, Instant::now(), results.clone()));
+        Ok(results)
+    }
+
+    /// Forget every cached search
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::vectors::VectorEntry;
+
+    fn files(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_identical_query_and_scope_hit_the_cache() {
+        let mut cache = ResultCache::default();
+        let mut searches = 0;
+        let mut search = |key: CacheKey| {
+            cache
+                .get_or_search(key, || {
+                    searches += 1;
+                    Ok(vec![(VectorEntry::new("a.md".to_string(), 0, vec![1.0], "a".to_string(), String::new(), 1, 1), 0.9, None)])
+                })
+                .unwrap()
+        };
+
+        let scope = files(&["a.md", "b.md"]);
+        let plain = SearchOptions::new(20);
+        let first = search(CacheKey::new("team offsite", &scope, "bge", plain));
+        // Same query up to whitespace, same files in another order
+        let again = search(CacheKey::new("  team   offsite ", &files(&["b.md", "a.md"]), "bge", plain));
+        assert_eq!(first.len(), again.len());
+        assert_eq!(again[0].0.file_path, "a.md");
+
+        search(CacheKey::new("team offsite", &files(&["a.md"]), "bge", plain));
+        search(CacheKey::new("team offsite", &scope, "other-model", plain));
+        search(CacheKey::new("team offsite", &scope, "bge", plain.with_explain(true)));
+        search(CacheKey::new("team offsite", &scope, "bge", plain.with_per_file(Some(1))));
+        search(CacheKey::new("team offsite", &scope, "bge", plain).with_session(3));
+        assert_eq!(searches, 6);
+        assert_eq!(cache.len(), 6);
+
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_failed_search_is_not_cached_and_oldest_is_dropped() {
+        let mut cache = ResultCache::default();
+        let plain = SearchOptions::new(20);
+        let key = CacheKey::new("q", &HashSet::new(), "bge", plain);
+        assert!(cache
+            .get_or_search(key.clone(), || Err(crate::core::error::Error::Config("boom".to_string())))
+            .is_err());
+        assert_eq!(cache.len(), 0);
+
+        for i in 0..RESULT_CACHE_SIZE + 3 {
+            let key = CacheKey::new(&format!("q{}", i), &HashSet::new(), "bge", plain);
+            cache.get_or_search(key, || Ok(Vec::new())).unwrap();
+        }
+        assert_eq!(cache.len(), RESULT_CACHE_SIZE);
+    }
+}
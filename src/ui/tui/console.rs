@@ -0,0 +1,121 @@
+use crate::core::logging::LogRecord;
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime};
+
+/// Oldest entries are dropped once the console holds this many
+pub const MAX_CONSOLE_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem reported during the session
+#[derive(Debug, Clone)]
+pub struct ConsoleEntry {
+    pub at: SystemTime,
+    pub severity: Severity,
+    /// Operation that failed, e.g. `index` or `search`
+    pub source: &'static str,
+    pub message: String,
+}
+
+/// Errors and warnings from this session, newest last (opened with `!`)
+#[derive(Debug, Default)]
+pub struct ErrorConsole {
+    entries: VecDeque<ConsoleEntry>,
+    /// Entries dropped because the buffer was full
+    dropped: usize,
+}
+
+impl ErrorConsole {
+    pub fn error(&mut self, source: &'static str, message: impl Into<String>) {
+        self.push(Severity::Error, source, message.into());
+    }
+
+    pub fn warn(&mut self, source: &'static str, message: impl Into<String>) {
+        self.push(Severity::Warning, source, message.into());
+    }
+
+    /// A warning or error logged through `tracing` while the TUI had the terminal
+    pub fn log(&mut self, record: LogRecord) {
+        let severity = if record.level == tracing::Level::ERROR { Severity::Error } else { Severity::Warning };
+        self.push(severity, "log", record.message);
+    }
+
+    fn push(&mut self, severity: Severity, source: &'static str, message: String) {
+        if self.entries.len() == MAX_CONSOLE_ENTRIES {
+            self.entries.pop_front();
+            self.dropped += 1;
+        }
+        self.entries.push_back(ConsoleEntry {
+            at: SystemTime::now(),
+            severity,
+            source,
+            message,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &ConsoleEntry> {
+        self.entries.iter()
+    }
+}
+
+/// How long ago an entry was logged: `just now`, `42s ago`, `5m ago`, `2h ago`
+pub fn format_age(at: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(at).unwrap_or(Duration::ZERO).as_secs();
+    if secs < 1 {
+        "just now".to_string()
+    } else if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / 3600)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_keeps_newest_entries() {
+        let mut console = ErrorConsole::default();
+        for i in 0..MAX_CONSOLE_ENTRIES + 5 {
+            console.error("index", format!("failure {}", i));
+        }
+        console.warn("search", "last");
+
+        assert_eq!(console.len(), MAX_CONSOLE_ENTRIES);
+        assert_eq!(console.dropped(), 6);
+        let first = console.entries().next().unwrap();
+        assert_eq!(first.message, "failure 6");
+        let last = console.entries().last().unwrap();
+        assert_eq!((last.source, last.severity), ("search", Severity::Warning));
+    }
+
+    #[test]
+    fn test_format_age() {
+        let now = SystemTime::now();
+        assert_eq!(format_age(now, now), "just now");
+        assert_eq!(format_age(now - Duration::from_secs(42), now), "42s ago");
+        assert_eq!(format_age(now - Duration::from_secs(300), now), "5m ago");
+        assert_eq!(format_age(now - Duration::from_secs(7300), now), "2h ago");
+        // Clock went backwards: treat as new
+        assert_eq!(format_age(now + Duration::from_secs(5), now), "just now");
+    }
+}
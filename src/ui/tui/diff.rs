@@ -0,0 +1,192 @@
+use crate::core::error::{Error, Result};
+use crate::storage::state::calculate_file_hash;
+use crate::storage::vectors::VectorEntry;
+use std::path::Path;
+
+/// Source files larger than this aren't re-read while browsing results, so the UI never stalls
+pub const MAX_DIFF_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Line pairs compared before giving up on a minimal diff (the table is this many cells)
+const MAX_DIFF_CELLS: usize = 250_000;
+
+/// How a result's source file compares with what was indexed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceCheck {
+    Unchanged,
+    /// Contents no longer match the hash recorded at indexing
+    Changed,
+    /// Gone or unreadable since indexing
+    Missing,
+    /// Over [`MAX_DIFF_FILE_BYTES`], so not checked
+    TooLarge,
+}
+
+/// Hash the file at `path` (if it's small enough) and compare with the indexed hash
+pub fn check_source(path: &Path, indexed_hash: &str) -> SourceCheck {
+    match std::fs::metadata(path) {
+        Err(_) => return SourceCheck::Missing,
+        Ok(metadata) if metadata.len() > MAX_DIFF_FILE_BYTES => return SourceCheck::TooLarge,
+        Ok(_) => {}
+    }
+    match calculate_file_hash(path) {
+        Ok(hash) if hash == indexed_hash => SourceCheck::Unchanged,
+        Ok(_) => SourceCheck::Changed,
+        Err(_) => SourceCheck::Missing,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Same,
+    /// Only in the current file
+    Added,
+    /// Only in the indexed chunk
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Diff the indexed chunk text against the same byte range of the file as it is now, or the
+/// same line range for entries indexed without offsets (or ranges the file no longer holds)
+pub fn chunk_diff(path: &Path, entry: &VectorEntry) -> Result<Vec<DiffLine>> {
+    if std::fs::metadata(path)?.len() > MAX_DIFF_FILE_BYTES {
+        return Err(Error::Config(format!(
+            "{} is over {} KiB; not diffing it here",
+            path.display(),
+            MAX_DIFF_FILE_BYTES / 1024
+        )));
+    }
+    let contents = std::fs::read_to_string(path)?;
+    if let Some(current) = entry.source_span().and_then(|span| contents.get(span)) {
+        return Ok(line_diff(&entry.text, current));
+    }
+    let start = entry.start_line.max(1) - 1;
+    let end = entry.end_line.max(entry.start_line.max(1));
+    let current: Vec<&str> = contents.lines().skip(start).take(end - start).collect();
+    Ok(line_diff(&entry.text, &current.join("\n")))
+}
+
+/// Line diff of `old` against `new` by longest common subsequence; leading and trailing
+/// whitespace is ignored when matching lines. Inputs too large for the table come back as
+/// every old line removed followed by every new line added.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let line = |op, text: &str| DiffLine { op, text: text.to_string() };
+
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|text| line(DiffOp::Removed, text))
+            .chain(new.iter().map(|text| line(DiffOp::Added, text)))
+            .collect();
+    }
+
+    // common[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i].trim() == new[j].trim() {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i].trim() == new[j].trim() {
+            diff.push(line(DiffOp::Same, new[j]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            diff.push(line(DiffOp::Removed, old[i]));
+            i += 1;
+        } else {
+            diff.push(line(DiffOp::Added, new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|text| line(DiffOp::Removed, text)));
+    diff.extend(new[j..].iter().map(|text| line(DiffOp::Added, text)));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn ops(diff: &[DiffLine]) -> Vec<(DiffOp, &str)> {
+        diff.iter().map(|line| (line.op, line.text.as_str())).collect()
+    }
+
+    #[test]
+    fn test_line_diff_marks_added_and_removed() {
+        let diff = line_diff("alpha\nbeta\ngamma", "alpha\n  beta\ndelta\ngamma\nomega");
+        assert_eq!(
+            ops(&diff),
+            vec![
+                (DiffOp::Same, "alpha"),
+                (DiffOp::Same, "  beta"),
+                (DiffOp::Added, "delta"),
+                (DiffOp::Same, "gamma"),
+                (DiffOp::Added, "omega"),
+            ]
+        );
+
+        let diff = line_diff("one\ntwo", "two");
+        assert_eq!(ops(&diff), vec![(DiffOp::Removed, "one"), (DiffOp::Same, "two")]);
+        assert!(line_diff("", "").is_empty());
+    }
+
+    #[test]
+    fn test_check_source_and_chunk_diff() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("note.md");
+        std::fs::write(&path, "intro\nfirst line\nsecond line\noutro\n").unwrap();
+        let indexed_hash = calculate_file_hash(&path).unwrap();
+        assert_eq!(check_source(&path, &indexed_hash), SourceCheck::Unchanged);
+
+        std::fs::write(&path, "intro\nfirst line\nsecond line, edited\noutro\n").unwrap();
+        assert_eq!(check_source(&path, &indexed_hash), SourceCheck::Changed);
+
+        let entry = VectorEntry::new(
+            "note.md".to_string(),
+            0,
+            vec![1.0],
+            "first line\nsecond line".to_string(),
+            String::new(),
+            2,
+            3,
+        );
+        assert_eq!(
+            ops(&chunk_diff(&path, &entry).unwrap()),
+            vec![
+                (DiffOp::Same, "first line"),
+                (DiffOp::Removed, "second line"),
+                (DiffOp::Added, "second line, edited"),
+            ]
+        );
+
+        // With offsets, only the chunk's own bytes are compared, even mid-line
+        std::fs::write(&path, "Intro sentence. Second sentence, unchanged.\n").unwrap();
+        let entry = VectorEntry::new("note.md".to_string(), 1, vec![1.0], "Second sentence, unchanged.".to_string(), String::new(), 1, 1)
+            .with_offsets(16, 43);
+        assert_eq!(ops(&chunk_diff(&path, &entry).unwrap()), vec![(DiffOp::Same, "Second sentence, unchanged.")]);
+
+        // Large files are neither hashed nor diffed
+        std::fs::write(&path, "x".repeat(MAX_DIFF_FILE_BYTES as usize + 1)).unwrap();
+        assert_eq!(check_source(&path, &indexed_hash), SourceCheck::TooLarge);
+        assert!(chunk_diff(&path, &entry).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(check_source(&path, &indexed_hash), SourceCheck::Missing);
+    }
+}
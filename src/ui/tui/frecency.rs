@@ -0,0 +1,178 @@
+//! A gentle, session-only preference for the folders results keep being picked from.
+//!
+//! Picking a result (clicking it, appending it with `a`, diffing it with `d`) adds a point to
+//! its folder, and every new query scales all points by [`FOLDER_DECAY`], so a folder left alone
+//! fades out. Results under a folder with points get a bonus that grows with them, capped at
+//! [`SESSION_BOOST_MAX`]: enough to settle near-ties, never enough to beat a clearly better match.
+
+use super::search::SearchHit;
+use std::collections::HashMap;
+
+/// Largest bonus a result can get from the folders picked this session
+pub const SESSION_BOOST_MAX: f32 = 0.03;
+
+/// Share of its points a folder keeps at each new query
+pub const FOLDER_DECAY: f32 = 0.8;
+
+/// Points at which a folder's bonus reaches [`SESSION_BOOST_MAX`]
+const SATURATION: f32 = 3.0;
+
+/// Folders whose points fall below this are forgotten
+const MIN_POINTS: f32 = 0.05;
+
+/// Points per folder picked from this session
+#[derive(Debug, Clone, Default)]
+pub struct FolderFrecency {
+    points: HashMap<String, f32>,
+    /// Bumped on every change, so cached results ranked with older points aren't reused
+    generation: u64,
+}
+
+impl FolderFrecency {
+    /// Count a pick of `file_path`; notes at the top of the folder have no folder to prefer
+    pub fn record(&mut self, file_path: &str) {
+        let Some((folder, _)) = file_path.rsplit_once('/') else {
+            return;
+        };
+        *self.points.entry(folder.to_string()).or_insert(0.0) += 1.0;
+        self.generation += 1;
+    }
+
+    /// Scale every folder's points down for a new query
+    pub fn decay(&mut self) {
+        if self.points.is_empty() {
+            return;
+        }
+        for points in self.points.values_mut() {
+            *points *= FOLDER_DECAY;
+        }
+        self.points.retain(|_, points| *points >= MIN_POINTS);
+        self.generation += 1;
+    }
+
+    /// Forget every folder; false when there was nothing to forget
+    pub fn clear(&mut self) -> bool {
+        if self.points.is_empty() {
+            return false;
+        }
+        self.points.clear();
+        self.generation += 1;
+        true
+    }
+
+    /// What cached results ranked with these points must match (0 while nothing is boosted)
+    pub fn generation(&self) -> u64 {
+        if self.points.is_empty() {
+            0
+        } else {
+            self.generation
+        }
+    }
+
+    /// The folder with the most points
+    pub fn strongest(&self) -> Option<&str> {
+        self.points
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(folder, _)| folder.as_str())
+    }
+
+    /// Bonus for a result at `file_path`: from the best-scoring folder holding it, at any depth
+    pub fn boost(&self, file_path: &str) -> f32 {
+        let points = self
+            .points
+            .iter()
+            .filter(|(folder, _)| {
+                file_path
+                    .strip_prefix(folder.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .map(|(_, points)| *points)
+            .fold(0.0, f32::max);
+        SESSION_BOOST_MAX * (points / SATURATION).min(1.0)
+    }
+
+    /// Whether the bonus put any of the ranked `hits` above one it would otherwise trail.
+    /// Takes the bonus each hit actually got from its breakdown, so `hits` must be ranked with
+    /// `explain`; a hit below `lexical_floor` got none even in a boosted folder.
+    pub fn reordered(&self, hits: &[SearchHit]) -> bool {
+        if self.points.is_empty() {
+            return false;
+        }
+        let unboosted = |hit: &SearchHit| hit.1 - hit.2.map_or(0.0, |breakdown| breakdown.session);
+        hits.windows(2).any(|pair| unboosted(&pair[0]) < unboosted(&pair[1]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::explain::ScoreBreakdown;
+    use crate::storage::vectors::VectorEntry;
+
+    #[test]
+    fn test_boost_is_capped_and_limited_to_the_folder() {
+        let mut frecency = FolderFrecency::default();
+        assert_eq!(frecency.boost("projects/acme/plan.md"), 0.0);
+
+        frecency.record("projects/acme/plan.md");
+        let once = frecency.boost("projects/acme/notes/kickoff.md");
+        assert!(once > 0.0 && once < SESSION_BOOST_MAX);
+
+        for _ in 0..20 {
+            frecency.record("projects/acme/plan.md");
+        }
+        assert_eq!(frecency.boost("projects/acme/budget.md"), SESSION_BOOST_MAX);
+        assert_eq!(frecency.boost("projects/acme-old/budget.md"), 0.0);
+        assert_eq!(frecency.boost("projects/other.md"), 0.0);
+        assert_eq!(frecency.strongest(), Some("projects/acme"));
+
+        // Top-level notes have no folder to prefer
+        let mut root_only = FolderFrecency::default();
+        root_only.record("inbox.md");
+        assert_eq!(root_only.generation(), 0);
+    }
+
+    #[test]
+    fn test_boost_decays_with_each_query() {
+        let mut frecency = FolderFrecency::default();
+        for _ in 0..3 {
+            frecency.record("journal/2024/03.md");
+        }
+        let mut previous = frecency.boost("journal/2024/04.md");
+        assert_eq!(previous, SESSION_BOOST_MAX);
+        let mut queries = 0;
+        while frecency.generation() != 0 {
+            frecency.decay();
+            let boost = frecency.boost("journal/2024/04.md");
+            assert!(boost < previous || boost == 0.0);
+            previous = boost;
+            queries += 1;
+        }
+        assert_eq!(previous, 0.0);
+        assert!(queries < 30);
+
+        frecency.record("journal/2024/03.md");
+        assert!(frecency.clear());
+        assert!(!frecency.clear());
+        assert_eq!(frecency.boost("journal/2024/04.md"), 0.0);
+    }
+
+    #[test]
+    fn test_reordered_only_when_the_bonus_changed_places() {
+        let mut frecency = FolderFrecency::default();
+        for _ in 0..3 {
+            frecency.record("acme/plan.md");
+        }
+        let hit = |file: &str, score: f32| -> SearchHit {
+            let session = frecency.boost(file);
+            let breakdown = ScoreBreakdown::new(score - session).with_session(session);
+            (VectorEntry::new(file.to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1), score, Some(breakdown))
+        };
+        // 0.62 + 0.03 beats 0.64 only because of the bonus
+        assert!(frecency.reordered(&[hit("acme/a.md", 0.65), hit("other/b.md", 0.64)]));
+        // Boosted, but it led anyway
+        assert!(!frecency.reordered(&[hit("acme/a.md", 0.83), hit("other/b.md", 0.64)]));
+        assert!(!FolderFrecency::default().reordered(&[hit("a.md", 0.7), hit("b.md", 0.6)]));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::core::error::Result;
+use std::path::{Path, PathBuf};
+
+/// History file in the base directory, newest query first, one per line
+pub const HISTORY_FILE: &str = "search_history.txt";
+
+/// Queries kept; older ones are forgotten
+pub const MAX_HISTORY: usize = 100;
+
+/// Recent TUI queries, recalled with Up/Down like a shell history
+#[derive(Debug, Default)]
+pub struct SearchHistory {
+    /// Newest first, no duplicates
+    entries: Vec<String>,
+    /// Entry currently recalled into the query field, while browsing
+    cursor: Option<usize>,
+    path: Option<PathBuf>,
+}
+
+impl SearchHistory {
+    /// Load the history under `base_dir`; a missing or unreadable file starts an empty one
+    pub fn load(base_dir: &Path) -> Self {
+        let path = base_dir.join(HISTORY_FILE);
+        let mut history = Self {
+            path: Some(path.clone()),
+            ..Self::default()
+        };
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            // Oldest first, so re-adding keeps the order and drops any duplicates
+            for query in contents.lines().rev() {
+                history.add(query);
+            }
+        }
+        history
+    }
+
+    /// Record a query as the newest entry, moving it up if it was already there
+    pub fn add(&mut self, query: &str) {
+        self.cursor = None;
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != query);
+        self.entries.insert(0, query.to_string());
+        self.entries.truncate(MAX_HISTORY);
+    }
+
+    /// Write the history back to its file
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut contents = self.entries.join("\n");
+        contents.push('\n');
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Step to an older query (Up); stays on the oldest once reached
+    pub fn older(&mut self) -> Option<&str> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let index = match self.cursor {
+            None => 0,
+            Some(i) => (i + 1).min(self.entries.len() - 1),
+        };
+        self.cursor = Some(index);
+        Some(&self.entries[index])
+    }
+
+    /// Step to a newer query (Down); `None` once past the newest, back to an empty field
+    pub fn newer(&mut self) -> Option<&str> {
+        match self.cursor {
+            None | Some(0) => {
+                self.cursor = None;
+                None
+            }
+            Some(i) => {
+                self.cursor = Some(i - 1);
+                Some(&self.entries[i - 1])
+            }
+        }
+    }
+
+    /// Whether Up/Down are currently recalling entries
+    pub fn is_browsing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Stop browsing, e.g. because the recalled query was edited
+    pub fn reset(&mut self) {
+        self.cursor = None;
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_dedups_and_bounds() {
+        let mut history = SearchHistory::default();
+        history.add("rust ownership");
+        history.add("  tax documents ");
+        history.add("");
+        history.add("rust ownership");
+        assert_eq!(history.entries(), ["rust ownership", "tax documents"]);
+
+        for i in 0..MAX_HISTORY + 10 {
+            history.add(&format!("query {}", i));
+        }
+        assert_eq!(history.entries().len(), MAX_HISTORY);
+        assert_eq!(history.entries()[0], format!("query {}", MAX_HISTORY + 9));
+    }
+
+    #[test]
+    fn test_cycle_through_entries() {
+        let mut history = SearchHistory::default();
+        assert_eq!(history.older(), None);
+        for query in ["first", "second", "third"] {
+            history.add(query);
+        }
+
+        assert!(!history.is_browsing());
+        assert_eq!(history.older(), Some("third"));
+        assert_eq!(history.older(), Some("second"));
+        assert_eq!(history.older(), Some("first"));
+        // Oldest entry stays put
+        assert_eq!(history.older(), Some("first"));
+        assert_eq!(history.newer(), Some("second"));
+        assert_eq!(history.newer(), Some("third"));
+        // Past the newest: back to an empty query
+        assert_eq!(history.newer(), None);
+        assert!(!history.is_browsing());
+
+        history.older();
+        history.reset();
+        assert_eq!(history.older(), Some("third"));
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().join("base");
+
+        let mut history = SearchHistory::load(&base);
+        assert!(history.entries().is_empty());
+        for query in ["alpha", "beta", "alpha", "gamma"] {
+            history.add(query);
+        }
+        history.save().unwrap();
+
+        let reloaded = SearchHistory::load(&base);
+        assert_eq!(reloaded.entries(), ["gamma", "alpha", "beta"]);
+    }
+}
@@ -0,0 +1,95 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use std::rc::Rc;
+
+/// Vertical layout of the search screen: title, search bar, results, footer
+pub fn search_chunks(size: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Title
+            Constraint::Length(4), // Search bar
+            Constraint::Min(0),    // Results
+            Constraint::Length(2),  // Footer (border + text)
+        ])
+        .split(size)
+}
+
+/// Results area split into the results list and the details panel
+pub fn result_columns(results_area: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(40), // Results list
+            Constraint::Percentage(60), // Details panel
+        ])
+        .split(results_area)
+}
+
+/// Whether a screen cell lies inside `area`
+pub fn area_contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.x + area.width && row >= area.y && row < area.y + area.height
+}
+
+/// Result index under a click at (`column`, `row`) in a bordered list drawn in `area`.
+///
+/// The list is rendered from a fresh `ListState` each frame, so ratatui scrolls just far
+/// enough to keep `selected` on the last visible row; the same offset is applied here.
+pub fn list_row_to_index(area: Rect, column: u16, row: u16, selected: usize, len: usize) -> Option<usize> {
+    if area.width < 2 || area.height < 2 {
+        return None;
+    }
+    let inner = Rect::new(area.x + 1, area.y + 1, area.width - 2, area.height - 2);
+    if inner.height == 0 || !area_contains(inner, column, row) {
+        return None;
+    }
+
+    let visible = inner.height as usize;
+    let offset = (selected + 1).saturating_sub(visible);
+    let index = offset + (row - inner.y) as usize;
+    (index < len).then_some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_maps_to_row() {
+        // Border on row 10, items on rows 11..=18
+        let area = Rect::new(0, 10, 30, 10);
+        assert_eq!(list_row_to_index(area, 5, 11, 0, 20), Some(0));
+        assert_eq!(list_row_to_index(area, 5, 14, 0, 20), Some(3));
+        assert_eq!(list_row_to_index(area, 5, 18, 0, 20), Some(7));
+    }
+
+    #[test]
+    fn test_click_outside_items() {
+        let area = Rect::new(0, 10, 30, 10);
+        // Borders
+        assert_eq!(list_row_to_index(area, 5, 10, 0, 20), None);
+        assert_eq!(list_row_to_index(area, 5, 19, 0, 20), None);
+        assert_eq!(list_row_to_index(area, 0, 12, 0, 20), None);
+        // Right of the list
+        assert_eq!(list_row_to_index(area, 40, 12, 0, 20), None);
+        // Below the last result
+        assert_eq!(list_row_to_index(area, 5, 14, 0, 2), None);
+    }
+
+    #[test]
+    fn test_click_accounts_for_scroll() {
+        // 8 visible rows; selecting item 12 scrolls so it sits on the last row
+        let area = Rect::new(0, 10, 30, 10);
+        assert_eq!(list_row_to_index(area, 5, 18, 12, 20), Some(12));
+        assert_eq!(list_row_to_index(area, 5, 11, 12, 20), Some(5));
+    }
+
+    #[test]
+    fn test_result_columns_split() {
+        let chunks = search_chunks(Rect::new(0, 0, 100, 40));
+        let columns = result_columns(chunks[2]);
+        assert_eq!(chunks[2].y, 5);
+        assert_eq!(columns[0].width + columns[1].width, 100);
+        assert!(area_contains(columns[1], columns[1].x, columns[1].y));
+        assert!(!area_contains(columns[1], columns[0].x, columns[0].y));
+    }
+}
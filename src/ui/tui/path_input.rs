@@ -0,0 +1,223 @@
+//! Typing a path on the directory screen (`/` or `:`) instead of stepping through folders.
+//!
+//! `~` expands to the home directory and relative paths start at the folder being shown. Tab
+//! completes against the folders and note files on disk, as far as the matches agree.
+
+use crate::indexing::discovery::is_notes_file;
+use std::path::{Path, PathBuf};
+
+/// Matches listed under the input after an ambiguous Tab
+const MAX_SHOWN_MATCHES: usize = 8;
+
+/// The one-line path input and what it last had to say
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathInput {
+    pub text: String,
+    /// Why the path can't be opened, shown inline until the next edit
+    pub error: Option<String>,
+    /// Names the last Tab could have completed to
+    pub matches: Vec<String>,
+}
+
+impl PathInput {
+    pub fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.edited();
+    }
+
+    pub fn backspace(&mut self) {
+        self.text.pop();
+        self.edited();
+    }
+
+    /// Clear the line (Ctrl+U)
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.edited();
+    }
+
+    fn edited(&mut self) {
+        self.error = None;
+        self.matches.clear();
+    }
+
+    /// Extend the text to the longest prefix every match shares; list the matches when that's
+    /// not a single one
+    pub fn complete(&mut self, current_dir: &Path) {
+        let found = completions(&self.text, current_dir);
+        self.edited();
+        match found.as_slice() {
+            [] => self.error = Some("No matches".to_string()),
+            [only] => self.text = only.clone(),
+            _ => {
+                let common = common_prefix(&found);
+                if common.len() > self.text.len() {
+                    self.text = common.to_string();
+                }
+                let (dir_part, _) = split_last(&self.text);
+                self.matches = found
+                    .iter()
+                    .take(MAX_SHOWN_MATCHES)
+                    .map(|m| m[dir_part.len()..].to_string())
+                    .collect();
+            }
+        }
+    }
+
+    /// The folder or note file the text names, or why it can't be opened
+    pub fn resolve(&self, current_dir: &Path) -> std::result::Result<PathBuf, String> {
+        let text = self.text.trim();
+        if text.is_empty() {
+            return Err("Type a path".to_string());
+        }
+        let path = current_dir.join(expand_home(text));
+        if !path.exists() {
+            return Err(format!("No such folder or file: {}", path.display()));
+        }
+        if !path.is_dir() && !is_notes_file(&path) {
+            return Err(format!("Not a folder or note file: {}", path.display()));
+        }
+        // Fold `..` and symlinks so the parent row and Esc behave as when browsing there
+        Ok(std::fs::canonicalize(&path).unwrap_or(path))
+    }
+}
+
+/// `input` with a leading `~` replaced by the home directory
+pub fn expand_home(input: &str) -> PathBuf {
+    let rest = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', std::path::MAIN_SEPARATOR]) => rest,
+        _ => return PathBuf::from(input),
+    };
+    match dirs::home_dir() {
+        Some(home) => home.join(rest.trim_start_matches(['/', std::path::MAIN_SEPARATOR])),
+        None => PathBuf::from(input),
+    }
+}
+
+/// `input` split after its last separator: the folder typed so far and the name being typed
+fn split_last(input: &str) -> (&str, &str) {
+    match input.rfind(['/', std::path::MAIN_SEPARATOR]) {
+        Some(i) => input.split_at(i + 1),
+        None => ("", input),
+    }
+}
+
+/// Every way to finish the last name in `input` with a folder (ending in `/`) or note file in
+/// the folder it names, sorted. Hidden entries only match a name that starts with `.`.
+pub fn completions(input: &str, current_dir: &Path) -> Vec<String> {
+    if input == "~" {
+        return vec!["~/".to_string()];
+    }
+    let (dir_part, prefix) = split_last(input);
+    let dir = current_dir.join(expand_home(dir_part));
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                Some(format!("{}{}/", dir_part, name))
+            } else if is_notes_file(&path) {
+                Some(format!("{}{}", dir_part, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Longest prefix of every string in `items`, cut on a character boundary
+fn common_prefix(items: &[String]) -> &str {
+    let Some(first) = items.first() else {
+        return "";
+    };
+    let mut end = first.len();
+    for item in &items[1..] {
+        end = first
+            .char_indices()
+            .zip(item.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(end.min(item.len()), |((i, _), _)| i.min(end));
+    }
+    &first[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn notes_tree() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("projects/roadmap")).unwrap();
+        std::fs::create_dir_all(root.join("personal")).unwrap();
+        std::fs::create_dir_all(root.join(".obsidian")).unwrap();
+        std::fs::write(root.join("projects/plan.md"), "# Plan\n").unwrap();
+        std::fs::write(root.join("projects/photo.png"), "").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_tab_completes_as_far_as_matches_agree() {
+        let temp_dir = notes_tree();
+        let root = temp_dir.path();
+
+        let mut input = PathInput { text: "pro".to_string(), ..Default::default() };
+        input.complete(root);
+        assert_eq!(input.text, "projects/");
+
+        // Two matches starting with "p": nothing to add, both listed
+        input.text = "projects/".to_string();
+        input.complete(root);
+        assert_eq!(input.text, "projects/");
+        assert_eq!(input.matches, vec!["plan.md", "roadmap/"]);
+
+        input.push('r');
+        assert!(input.matches.is_empty());
+        input.complete(root);
+        assert_eq!(input.text, "projects/roadmap/");
+
+        // Absolute paths work too, and hidden folders need a leading dot
+        let absolute = format!("{}/", root.display());
+        assert_eq!(completions(&absolute, Path::new("/")).len(), 2);
+        assert_eq!(completions(&format!("{}.o", absolute), Path::new("/")), vec![format!("{}.obsidian/", absolute)]);
+
+        input.text = "nothing-here".to_string();
+        input.complete(root);
+        assert_eq!(input.error.as_deref(), Some("No matches"));
+    }
+
+    #[test]
+    fn test_resolve_reports_bad_paths_inline() {
+        let temp_dir = notes_tree();
+        let root = temp_dir.path();
+        let resolve = |text: &str| PathInput { text: text.to_string(), ..Default::default() }.resolve(root);
+
+        assert_eq!(resolve("projects/roadmap/..").unwrap(), root.join("projects").canonicalize().unwrap());
+        assert!(resolve("projects/plan.md").unwrap().is_file());
+        assert!(resolve("missing").unwrap_err().starts_with("No such folder or file"));
+        assert!(resolve("projects/photo.png").unwrap_err().starts_with("Not a folder or note file"));
+        assert!(resolve("  ").is_err());
+    }
+
+    #[test]
+    fn test_expand_home() {
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_home("~"), home);
+            assert_eq!(expand_home("~/notes"), home.join("notes"));
+        }
+        assert_eq!(expand_home("~other/notes"), PathBuf::from("~other/notes"));
+        assert_eq!(expand_home("/tmp/~"), PathBuf::from("/tmp/~"));
+        assert_eq!(common_prefix(&["ab/".to_string(), "abc".to_string()]), "ab");
+    }
+}
@@ -1,129 +1,496 @@
+use crate::core::config::Config;
+use crate::core::duration::{parse_date, parse_duration, unix_cutoff};
 use crate::core::error::{Error, Result};
+use crate::indexing::parser::TagNormalization;
+use crate::search::calibration::ScoreCalibration;
+use crate::search::collections::CollectionStore;
+use crate::search::explain::ScoreBreakdown;
 use crate::search::model::EmbeddingModel;
-use crate::storage::vectors::{VectorEntry, VectorStore};
+use crate::search::options::SearchOptions;
+use crate::storage::vectors::{rank_order, FileMeta, VectorEntry, VectorStore};
+use super::frecency::FolderFrecency;
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::time::Duration;
 
-// Search configuration constants
-const SEARCH_CANDIDATES_LIMIT: usize = 200;      // Number of candidates to fetch for unconstrained search
-const SCOPED_SEARCH_CANDIDATES_LIMIT: usize = 500; // Number of candidates for scoped search
-pub const MAX_RESULTS_DISPLAYED: usize = 20;     // Maximum number of results to display (top 20 passages)
-const MAX_RESULTS_PER_FILE: usize = 5;           // Maximum results per file (allows multiple chunks from same file)
+// Steps `+`/`-` move the result limit through, and the largest per-file cap `]` reaches
+pub const RESULT_LIMIT_STEPS: [usize; 6] = [5, 10, 20, 50, 100, 200];
+pub const MAX_PER_FILE_LIMIT: usize = 20;
 
 // Lexical boost values for search results
 const LEXICAL_BOOST_PATH: f32 = 0.05;   // Boost for filename matches
 const LEXICAL_BOOST_CONTEXT: f32 = 0.10; // Boost for context matches
 const LEXICAL_BOOST_TEXT: f32 = 0.15;    // Boost for text content matches
+const LEXICAL_BOOST_ALIAS: f32 = 0.10;   // Boost for frontmatter title/alias matches
 
-/// Perform semantic search with lexical boosting and deduplication
+// Share of the score taken from the path's similarity when it beats the chunk's (path_signal)
+const PATH_SIGNAL_BLEND: f32 = 0.3;
+
+/// A ranked result; the breakdown is only kept when `SearchOptions::explain` is set
+pub type SearchHit = (VectorEntry, f32, Option<ScoreBreakdown>);
+
+/// Frontmatter names (title and aliases) of the indexed files, by collection and then path
+pub type FileNames = HashMap<String, HashMap<String, FileMeta>>;
+
+fn names_of<'a>(names: &'a FileNames, entry: &VectorEntry) -> Option<&'a FileMeta> {
+    names.get(&entry.collection)?.get(&entry.file_path)
+}
+
+/// Perform semantic search with lexical boosting and deduplication.
+/// With `session`, results in folders picked from earlier get its small bonus.
+#[allow(clippy::too_many_arguments)]
 pub fn perform_search(
     query: &str,
     model: &EmbeddingModel,
     vector_store: &VectorStore,
     active_files: &HashSet<String>,
-) -> Result<Vec<(VectorEntry, f32)>> {
-    let (file_filter, semantic_query) = parse_file_filter_query(query);
+    names: &FileNames,
+    config: &Config,
+    options: &SearchOptions,
+    session: Option<&FolderFrecency>,
+) -> Result<Vec<SearchHit>> {
+    let Some(prepared) = PreparedQuery::new(query, model, config)? else {
+        return Ok(Vec::new());
+    };
+    let candidates = prepared.candidates(vector_store, active_files, names, options)?;
+    prepared.rank(candidates, model, names, config, options, session)
+}
 
-    if semantic_query.trim().is_empty() {
+/// Like [`perform_search`] over several collections (`collection:` in the query), ranked together.
+/// Each collection's similarities are scaled by its weight; searches aren't scoped to a folder.
+pub fn perform_search_collections(
+    query: &str,
+    model: &EmbeddingModel,
+    stores: &[&CollectionStore],
+    config: &Config,
+    options: &SearchOptions,
+) -> Result<Vec<SearchHit>> {
+    let Some(prepared) = PreparedQuery::new(query, model, config)? else {
         return Ok(Vec::new());
+    };
+    let mut names = FileNames::new();
+    let mut candidates = Vec::new();
+    for collection in stores {
+        names.insert(collection.name.clone(), collection.store.file_meta()?);
+        let found = prepared.candidates(&collection.store, &HashSet::new(), &names, options)?;
+        candidates.extend(
+            found
+                .into_iter()
+                .map(|(entry, cosine)| (entry, cosine * collection.weight)),
+        );
     }
+    prepared.rank(candidates, model, &names, config, options, None)
+}
 
-    // Keep a lowercase copy for small lexical boosting before we move the String.
-    let q_lower = semantic_query.to_lowercase();
-    let query_texts = vec![semantic_query];
-    let query_embeddings = model.embed_queries(&query_texts)?;
+/// A query with its filters split off and its embedding computed, ready to search stores with
+struct PreparedQuery {
+    filters: QueryFilters,
+    /// Lowercase semantic query, for lexical boosting
+    q_lower: String,
+    embedding: Vec<f32>,
+}
 
-    if query_embeddings.is_empty() {
-        return Err(Error::Model("Failed to generate query embedding".to_string()));
-    }
+impl PreparedQuery {
+    /// `None` when only filters are left, so there's nothing to search for
+    fn new(query: &str, model: &EmbeddingModel, config: &Config) -> Result<Option<Self>> {
+        let (filters, semantic_query) = parse_query_filters(query, &TagNormalization::from_config(config));
 
-    let query_embedding = &query_embeddings[0];
-    // Get more candidates, then scope + boost + dedupe to top results (better UX).
-    // For scoped searches, fetch even more candidates to ensure we get enough results
-    let mut results = if active_files.is_empty() {
-        vector_store.search(query_embedding, SEARCH_CANDIDATES_LIMIT)?
-    } else {
-        // For scoped search, fetch enough candidates to get top passages
-        // Multiply by MAX_RESULTS_PER_FILE to ensure we get multiple chunks per file
-        let candidate_limit = (MAX_RESULTS_DISPLAYED * MAX_RESULTS_PER_FILE).max(SCOPED_SEARCH_CANDIDATES_LIMIT);
-        vector_store.search_scoped(query_embedding, candidate_limit, active_files)?
-    };
+        if semantic_query.trim().is_empty() {
+            return Ok(None);
+        }
 
-    // Optional: limit results to a specific file (or partial filename).
-    if let Some(filter) = file_filter {
-        results.retain(|(entry, _)| path_matches_filter(&entry.file_path, &filter));
+        // Keep a lowercase copy for small lexical boosting before we move the String.
+        let q_lower = semantic_query.to_lowercase();
+        let query_texts = vec![semantic_query];
+        let embedding = model
+            .embed_queries(&query_texts)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Model("Failed to generate query embedding".to_string()))?;
+
+        Ok(Some(Self { filters, q_lower, embedding }))
     }
 
-    // Small lexical boost for obvious matches (helps short queries like "Agenda")
-    // Optimized: Use case-insensitive matching helper to reduce allocations
-    if !q_lower.is_empty() {
-        for (entry, sim) in results.iter_mut() {
-            let mut bonus = 0.0f32;
-            // Use efficient case-insensitive contains (only allocates when needed)
-            if contains_case_insensitive(&entry.file_path, &q_lower) {
-                bonus += LEXICAL_BOOST_PATH;
-            }
-            if contains_case_insensitive(&entry.context, &q_lower) {
-                bonus += LEXICAL_BOOST_CONTEXT;
-            }
-            if contains_case_insensitive(&entry.text, &q_lower) {
-                bonus += LEXICAL_BOOST_TEXT;
-            }
-            *sim = (*sim + bonus).min(1.0);
+    /// Nearest chunks in `vector_store` that pass the query's filters
+    fn candidates(
+        &self,
+        vector_store: &VectorStore,
+        active_files: &HashSet<String>,
+        names: &FileNames,
+        options: &SearchOptions,
+    ) -> Result<Vec<(VectorEntry, f32)>> {
+        // Get more candidates, then scope + boost + dedupe to top results (better UX).
+        // The pool is sized from the store unless the caller fixed it.
+        let candidate_limit = options.candidate_limit(vector_store.chunk_count());
+        let mut candidates = if active_files.is_empty() {
+            vector_store.search(&self.embedding, candidate_limit)?
+        } else {
+            // For scoped search, fetch enough candidates to get top passages
+            // Multiply by the per-file cap to ensure we get multiple chunks per file
+            let candidate_limit = candidate_limit.max(options.limit * options.per_file.unwrap_or(1));
+            vector_store.search_scoped(&self.embedding, candidate_limit, active_files)?
+        };
+
+        // Optional: limit results to a specific file (or partial filename, title or alias).
+        if let Some(filter) = &self.filters.file {
+            candidates.retain(|(entry, _)| {
+                file_filter_match(&entry.file_path, names_of(names, entry), filter).is_some()
+            });
         }
+
+        // Optional: only keep chunks carrying every requested tag.
+        if !self.filters.tags.is_empty() {
+            candidates.retain(|(entry, _)| entry_has_tags(entry, &self.filters.tags));
+        }
+
+        // Optional: only keep chunks whose custom frontmatter fields match.
+        if !self.filters.meta.is_empty() {
+            candidates.retain(|(entry, _)| entry_has_meta(entry, &self.filters.meta));
+        }
+
+        // Optional: only keep notes changed recently.
+        if let Some(window) = self.filters.changed_within {
+            let recent = options.with_changed_since(Some(unix_cutoff(window)));
+            candidates.retain(|(entry, _)| recent.admits(entry));
+        }
+
+        // Optional: only keep notes dated within a range.
+        if self.filters.after.is_some() || self.filters.before.is_some() {
+            let dated = options.with_date_range(self.filters.after, self.filters.before);
+            candidates.retain(|(entry, _)| dated.admits(entry));
+        }
+
+        Ok(candidates)
+    }
+
+    fn rank(
+        &self,
+        candidates: Vec<(VectorEntry, f32)>,
+        model: &EmbeddingModel,
+        names: &FileNames,
+        config: &Config,
+        options: &SearchOptions,
+        session: Option<&FolderFrecency>,
+    ) -> Result<Vec<SearchHit>> {
+        // Descriptive file names carry meaning even when the body is sparse
+        let path_scores = if config.path_signal {
+            path_similarities(model, &self.embedding, &candidates)?
+        } else {
+            HashMap::new()
+        };
+
+        let ranked = rank_candidates(candidates, &self.q_lower, &path_scores, names, lexical_floor(config), options, session);
+        Ok(if options.merge_adjacent { merge_adjacent(ranked) } else { ranked })
     }
+}
+
+/// Cosine a chunk needs before the lexical boost applies: `lexical_floor`, else the level the
+/// model gives unrelated text (see [`ScoreCalibration::for_model`])
+fn lexical_floor(config: &Config) -> f32 {
+    config
+        .lexical_floor
+        .unwrap_or_else(|| ScoreCalibration::for_model(&config.model_id).min)
+}
+
+/// Boost, group per file and truncate scored candidates.
+/// `path_scores` maps file paths to their query similarity; files missing from it get no path bonus.
+/// Files with a title or alias matching the query get the alias bonus on every chunk.
+/// Chunks below `lexical_floor` get no lexical or session bonus: boosts refine the semantic
+/// ranking, they don't override it.
+fn rank_candidates(
+    candidates: Vec<(VectorEntry, f32)>,
+    q_lower: &str,
+    path_scores: &HashMap<String, f32>,
+    names: &FileNames,
+    lexical_floor: f32,
+    options: &SearchOptions,
+    session: Option<&FolderFrecency>,
+) -> Vec<SearchHit> {
+    // Each stage records its contribution in a breakdown; the breakdown is a small Copy
+    // value, and is only kept on the result when explaining
+    let results = candidates.into_iter().map(|(entry, cosine)| {
+        let path = path_scores
+            .get(&entry.file_path)
+            .map_or(0.0, |&similarity| path_bonus(cosine, similarity));
+        // Small lexical boost for obvious matches (helps short queries like "Agenda")
+        let lexical = if cosine >= lexical_floor {
+            lexical_bonus(&entry, q_lower, names_of(names, &entry))
+        } else {
+            0.0
+        };
+        // Folders picked from this session, by a capped few hundredths
+        let session = session
+            .filter(|_| cosine >= lexical_floor)
+            .map_or(0.0, |frecency| frecency.boost(&entry.file_path));
+        let breakdown = ScoreBreakdown::new(cosine)
+            .with_lexical(lexical)
+            .with_path(path)
+            .with_session(session);
+        (entry, breakdown.final_score, options.explain.then_some(breakdown))
+    });
 
-    // Smart deduplication: allow multiple results per file (up to MAX_RESULTS_PER_FILE)
+    // Smart deduplication: allow multiple results per file (up to `options.per_file`)
     // This allows users to see multiple relevant chunks from the same file
     // Group results by file, keep top N per file, then take overall top results
-    let mut results_by_file: HashMap<String, Vec<(VectorEntry, f32)>> = HashMap::new();
+    // (the same path in two collections is two files)
+    let mut results_by_file: HashMap<(String, String), Vec<SearchHit>> = HashMap::new();
     
-    for (entry, sim) in results {
+    for hit in results {
         results_by_file
-            .entry(entry.file_path.clone())
+            .entry((hit.0.collection.clone(), hit.0.file_path.clone()))
             .or_insert_with(Vec::new)
-            .push((entry, sim));
+            .push(hit);
     }
     
     // Sort each file's results by similarity (descending) and keep top N per file
     for file_results in results_by_file.values_mut() {
-        file_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        file_results.truncate(MAX_RESULTS_PER_FILE);
+        file_results.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
+        if let Some(per_file) = options.per_file {
+            file_results.truncate(per_file);
+        }
     }
     
     // Flatten and sort all results by similarity
-    let mut all_results: Vec<(VectorEntry, f32)> = results_by_file
+    let mut all_results: Vec<SearchHit> = results_by_file
         .into_values()
         .flatten()
         .collect();
     
-    all_results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    all_results.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
     
-    // Return top 20 passages (or all if less than 20)
-    all_results.truncate(MAX_RESULTS_DISPLAYED);
+    // Return the top passages
+    all_results.truncate(options.limit);
 
-    Ok(all_results)
+    all_results
 }
 
-/// Parse query string to extract file filter and semantic query
-pub fn parse_file_filter_query(raw: &str) -> (Option<String>, String) {
-    let mut filter: Option<String> = None;
+/// Coalesce ranked hits on consecutive chunks of one file into a single hit: the chunks' texts
+/// joined in file order, spanning from the first chunk's start line to the last one's end.
+/// A merged hit keeps the score (and breakdown) of its best chunk and is re-ranked by it.
+pub fn merge_adjacent(hits: Vec<SearchHit>) -> Vec<SearchHit> {
+    let mut by_file: HashMap<(String, String), Vec<SearchHit>> = HashMap::new();
+    for hit in hits {
+        by_file
+            .entry((hit.0.collection.clone(), hit.0.file_path.clone()))
+            .or_default()
+            .push(hit);
+    }
+
+    let mut merged: Vec<SearchHit> = Vec::new();
+    for mut file_hits in by_file.into_values() {
+        file_hits.sort_by_key(|hit| hit.0.chunk_index);
+        // Each run with the chunk index it has reached so far
+        let mut runs: Vec<(SearchHit, usize)> = Vec::new();
+        for hit in file_hits {
+            match runs.last_mut() {
+                Some((run, last)) if hit.0.chunk_index == *last + 1 => {
+                    *last = hit.0.chunk_index;
+                    run.0.text = format!("{}\n\n{}", run.0.text, hit.0.text);
+                    run.0.start_line = run.0.start_line.min(hit.0.start_line);
+                    run.0.end_line = run.0.end_line.max(hit.0.end_line);
+                    // Byte range too, unless either chunk lacks one
+                    let span = run.0.source_span().zip(hit.0.source_span());
+                    run.0.start_offset = span.as_ref().map_or(0, |(a, b)| a.start.min(b.start));
+                    run.0.end_offset = span.as_ref().map_or(0, |(a, b)| a.end.max(b.end));
+                    if hit.1 > run.1 {
+                        run.1 = hit.1;
+                        run.2 = hit.2;
+                    }
+                }
+                _ => {
+                    let last = hit.0.chunk_index;
+                    runs.push((hit, last));
+                }
+            }
+        }
+        merged.extend(runs.into_iter().map(|(run, _)| run));
+    }
+    merged.sort_by(|a, b| rank_order(&a.0, a.1, &b.0, b.1));
+    merged
+}
+
+/// The result limit one step up or down [`RESULT_LIMIT_STEPS`] from `current`
+pub fn step_result_limit(current: usize, up: bool) -> usize {
+    let steps = RESULT_LIMIT_STEPS;
+    if up {
+        steps.into_iter().find(|&step| step > current).unwrap_or(steps[steps.len() - 1])
+    } else {
+        steps.into_iter().rev().find(|&step| step < current).unwrap_or(steps[0])
+    }
+}
+
+/// Words of a relative path, for embedding: `projects/2024/db-migration.md` -> `projects 2024 db migration`
+pub fn path_words(file_path: &str) -> String {
+    let stem = match file_path.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.contains(['/', '\\']) => stem,
+        _ => file_path,
+    };
+    stem.split(['/', '\\', '-', '_'])
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Similarity between the query and the words of each distinct candidate path (one batch)
+fn path_similarities(
+    model: &EmbeddingModel,
+    query_embedding: &[f32],
+    candidates: &[(VectorEntry, f32)],
+) -> Result<HashMap<String, f32>> {
+    let mut paths: Vec<&str> = candidates.iter().map(|(entry, _)| entry.file_path.as_str()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+    if paths.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let texts: Vec<String> = paths.iter().map(|path| path_words(path)).collect();
+    let embeddings = model.embed_passages(&texts)?;
+    // Embeddings are L2-normalized, so the dot product is the cosine
+    Ok(paths
+        .into_iter()
+        .zip(embeddings)
+        .map(|(path, embedding)| {
+            let similarity: f32 = query_embedding.iter().zip(&embedding).map(|(a, b)| a * b).sum();
+            (path.to_string(), similarity)
+        })
+        .collect())
+}
+
+/// Blend in the path's similarity where it beats the chunk's; a path never lowers a score
+fn path_bonus(cosine: f32, path_similarity: f32) -> f32 {
+    ((path_similarity - cosine) * PATH_SIGNAL_BLEND).max(0.0)
+}
+
+/// Bonus for the (lowercased) query appearing verbatim in the path, heading context or text,
+/// or matching one of the file's frontmatter names
+fn lexical_bonus(entry: &VectorEntry, q_lower: &str, names: Option<&FileMeta>) -> f32 {
+    if q_lower.is_empty() {
+        return 0.0;
+    }
+    let mut bonus = 0.0f32;
+    // Use efficient case-insensitive contains (only allocates when needed)
+    if contains_case_insensitive(&entry.file_path, q_lower) {
+        bonus += LEXICAL_BOOST_PATH;
+    }
+    if contains_case_insensitive(&entry.context, q_lower) {
+        bonus += LEXICAL_BOOST_CONTEXT;
+    }
+    if contains_case_insensitive(&entry.text, q_lower) {
+        bonus += LEXICAL_BOOST_TEXT;
+    }
+    if names.is_some_and(|names| names.names().any(|name| name_matches_query(name, q_lower))) {
+        bonus += LEXICAL_BOOST_ALIAS;
+    }
+    bonus
+}
+
+/// A title or alias matches when either one contains the other: "standup" finds "Standup notes",
+/// and "standup notes march" still finds it
+fn name_matches_query(name: &str, q_lower: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty() && (contains_case_insensitive(name, q_lower) || q_lower.contains(&name.to_lowercase()))
+}
+
+/// Filters parsed out of a raw query string
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryFilters {
+    /// `file:<name>` filter
+    pub file: Option<String>,
+    /// `tag:<name>` filters, already normalized
+    pub tags: Vec<String>,
+    /// `meta:<key>=<value>` filters on custom frontmatter fields
+    pub meta: Vec<(String, String)>,
+    /// `collection:<name>` filters: search these collections instead of the open folder
+    pub collections: Vec<String>,
+    /// `since:<duration>` filter (e.g. `since:7d`): only notes modified within this window
+    pub changed_within: Option<Duration>,
+    /// `after:<date>` filter (e.g. `after:2024-03-01`): only notes dated on or after that day
+    pub after: Option<u64>,
+    /// `before:<date>` filter: only notes dated before that day
+    pub before: Option<u64>,
+}
+
+/// Parse query string to extract filters (`file:`, `tag:`, `meta:`, `collection:`, `since:`,
+/// `after:`, `before:`) and the semantic query
+pub fn parse_query_filters(raw: &str, tag_normalization: &TagNormalization) -> (QueryFilters, String) {
+    let mut filters = QueryFilters::default();
     let mut parts: Vec<&str> = Vec::new();
 
     for token in raw.split_whitespace() {
         if let Some(rest) = token.strip_prefix("file:") {
             if !rest.is_empty() {
                 // Allow file:"name.md" and strip trailing punctuation like commas.
-                let cleaned = rest
-                    .trim_matches(|c: char| c == '"' || c == '\'' || c == ',' || c == ';' || c == '.');
-                filter = Some(cleaned.to_string());
+                filters.file = Some(clean_filter_value(rest).to_string());
                 continue;
             }
         }
+        if let Some(rest) = token.strip_prefix("tag:") {
+            let tag = tag_normalization.normalize(clean_filter_value(rest));
+            if !tag.is_empty() {
+                filters.tags.push(tag);
+                continue;
+            }
+        }
+        // `meta:status=draft`; without a key and value it stays part of the query
+        if let Some((key, value)) = token.strip_prefix("meta:").and_then(|rest| clean_filter_value(rest).split_once('=')) {
+            let (key, value) = (key.trim(), clean_filter_value(value));
+            if !key.is_empty() && !value.is_empty() {
+                filters.meta.push((key.to_string(), value.to_string()));
+                continue;
+            }
+        }
+        if let Some(rest) = token.strip_prefix("collection:") {
+            let name = clean_filter_value(rest);
+            if !name.is_empty() {
+                if !filters.collections.iter().any(|c| c == name) {
+                    filters.collections.push(name.to_string());
+                }
+                continue;
+            }
+        }
+        // A malformed duration stays part of the query, like an empty filter
+        if let Some(window) = token.strip_prefix("since:").and_then(|rest| parse_duration(clean_filter_value(rest)).ok()) {
+            filters.changed_within = Some(window);
+            continue;
+        }
+        if let Some(date) = token.strip_prefix("after:").and_then(|rest| parse_date(clean_filter_value(rest)).ok()) {
+            filters.after = Some(date);
+            continue;
+        }
+        if let Some(date) = token.strip_prefix("before:").and_then(|rest| parse_date(clean_filter_value(rest)).ok()) {
+            filters.before = Some(date);
+            continue;
+        }
         parts.push(token);
     }
 
-    (filter, parts.join(" "))
+    (filters, parts.join(" "))
+}
+
+/// Parse query string to extract file filter and semantic query
+pub fn parse_file_filter_query(raw: &str) -> (Option<String>, String) {
+    let (filters, semantic_query) = parse_query_filters(raw, &TagNormalization::default());
+    (filters.file, semantic_query)
+}
+
+fn clean_filter_value(value: &str) -> &str {
+    value.trim_matches(|c: char| c == '"' || c == '\'' || c == ',' || c == ';' || c == '.')
+}
+
+/// Check that an entry carries every one of the (normalized) tags
+pub fn entry_has_tags(entry: &VectorEntry, tags: &[String]) -> bool {
+    tags.iter().all(|t| entry.tags.contains(t))
+}
+
+/// Check that an entry has every `key=value` pair among its custom frontmatter fields.
+/// Keys match exactly, as written in YAML; values ignore case.
+pub fn entry_has_meta(entry: &VectorEntry, meta: &[(String, String)]) -> bool {
+    meta.iter().all(|(key, value)| {
+        entry
+            .metadata
+            .get(key)
+            .is_some_and(|stored| stored.trim().to_lowercase() == value.to_lowercase())
+    })
 }
 
 /// Case-insensitive contains check (optimized for ASCII, falls back to allocation for Unicode)
@@ -145,17 +512,437 @@ fn contains_case_insensitive(haystack: &str, needle: &str) -> bool {
     }
 }
 
-/// Check if a file path matches a filter string
-pub fn path_matches_filter(file_path: &str, filter: &str) -> bool {
+/// How a `file:` filter picked a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMatch {
+    Path,
+    /// Through the file's frontmatter title or an alias, not its path
+    Alias,
+}
+
+/// Check a file against a `file:` filter: its path first (see [`path_matches_filter`]), then its
+/// frontmatter title and aliases, which match on a case-insensitive substring, or the whole name
+/// with a trailing `$`
+pub fn file_filter_match(file_path: &str, names: Option<&FileMeta>, filter: &str) -> Option<FilterMatch> {
+    if path_matches_filter(file_path, filter) {
+        return Some(FilterMatch::Path);
+    }
     let filter_lower = filter.to_lowercase();
-    if contains_case_insensitive(file_path, &filter_lower) {
-        return true;
+    let name_matches = |name: &str| match filter_lower.strip_suffix('$') {
+        Some(exact) => name.trim().to_lowercase() == exact,
+        None => contains_case_insensitive(name, &filter_lower),
+    };
+    names?.names().any(name_matches).then_some(FilterMatch::Alias)
+}
+
+/// Check if a file path matches a `file:` filter (case-insensitive, `/` or `\` as separator):
+/// - `todo` matches any path containing it, like `work/todo.md` or `mytodo.txt`
+/// - `work/todo` matches the path's last components: the final one within the file name and
+///   every earlier one exactly, so `homework/todo.md` doesn't match
+/// - a trailing `$` makes the file name exact: `todo.md$` matches `work/todo.md` but not `todo.md.bak`
+pub fn path_matches_filter(file_path: &str, filter: &str) -> bool {
+    let (filter, anchored) = match filter.strip_suffix('$') {
+        Some(filter) => (filter, true),
+        None => (filter, false),
+    };
+    let filter_lower = filter.to_lowercase().replace('\\', "/");
+    let wanted: Vec<&str> = filter_lower.split('/').filter(|c| !c.is_empty()).collect();
+    if !anchored && wanted.len() <= 1 {
+        return contains_case_insensitive(file_path, &filter_lower);
     }
 
-    Path::new(file_path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .map(|name| contains_case_insensitive(name, &filter_lower))
-        .unwrap_or(false)
+    let file_path = file_path.replace('\\', "/");
+    let components: Vec<&str> = file_path.split('/').filter(|c| !c.is_empty()).collect();
+    let (Some((name_wanted, dirs_wanted)), Some(start)) =
+        (wanted.split_last(), components.len().checked_sub(wanted.len()))
+    else {
+        return false;
+    };
+    let Some((name, dirs)) = components[start..].split_last() else {
+        return false;
+    };
+    let name_matches = if anchored {
+        name.to_lowercase() == *name_wanted
+    } else {
+        contains_case_insensitive(name, name_wanted)
+    };
+    name_matches && dirs.iter().zip(dirs_wanted).all(|(dir, wanted)| dir.to_lowercase() == *wanted)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::DEFAULT_COLLECTION;
+    use crate::indexing::parser::TextChunk;
+    use crate::indexing::pipeline::chunk_tags;
+
+    #[test]
+    fn test_parse_query_filters() {
+        let norm = TagNormalization::default();
+        let (filters, query) = parse_query_filters("file:notes.md tag:#Work standup agenda", &norm);
+        assert_eq!(filters.file, Some("notes.md".to_string()));
+        assert_eq!(filters.tags, vec!["work".to_string()]);
+        assert_eq!(query, "standup agenda");
+    }
+
+    #[test]
+    fn test_parse_since_filter() {
+        let norm = TagNormalization::default();
+        let (filters, query) = parse_query_filters("since:7d standup notes", &norm);
+        assert_eq!(filters.changed_within, Some(Duration::from_secs(7 * 86400)));
+        assert_eq!(query, "standup notes");
+
+        // Not a duration: searched as text
+        let (filters, query) = parse_query_filters("since:yesterday standup", &norm);
+        assert_eq!(filters.changed_within, None);
+        assert_eq!(query, "since:yesterday standup");
+    }
+
+    #[test]
+    fn test_parse_date_filters() {
+        let norm = TagNormalization::default();
+        let (filters, query) = parse_query_filters("after:2024-03-01 retro before:2024-04-01", &norm);
+        assert_eq!(filters.after, Some(1_709_251_200));
+        assert_eq!(filters.before, Some(1_711_929_600));
+        assert_eq!(query, "retro");
+
+        let (filters, query) = parse_query_filters("after:march retro", &norm);
+        assert_eq!(filters.after, None);
+        assert_eq!(query, "after:march retro");
+    }
+
+    #[test]
+    fn test_parse_collection_filters() {
+        let norm = TagNormalization::default();
+        let (filters, query) = parse_query_filters("collection:work roadmap collection:personal, collection:work", &norm);
+        assert_eq!(filters.collections, vec!["work".to_string(), "personal".to_string()]);
+        assert_eq!(query, "roadmap");
+    }
+
+    #[test]
+    fn test_meta_filter() {
+        let norm = TagNormalization::default();
+        let (filters, query) = parse_query_filters("meta:status=draft roadmap meta:owner= meta:oops", &norm);
+        assert_eq!(filters.meta, vec![("status".to_string(), "draft".to_string())]);
+        assert_eq!(query, "roadmap meta:owner= meta:oops");
+
+        let note = |status: &str| {
+            let metadata = [("status".to_string(), status.to_string())].into_iter().collect();
+            VectorEntry::new("plan.md".to_string(), 0, vec![1.0], "Plan".to_string(), String::new(), 1, 1).with_metadata(metadata)
+        };
+        assert!(entry_has_meta(&note("draft"), &filters.meta));
+        assert!(entry_has_meta(&note("Draft"), &filters.meta));
+        assert!(!entry_has_meta(&note("published"), &filters.meta));
+        let untagged = VectorEntry::new("plan.md".to_string(), 0, vec![1.0], "Plan".to_string(), String::new(), 1, 1);
+        assert!(!entry_has_meta(&untagged, &filters.meta));
+        assert!(entry_has_meta(&untagged, &[]));
+    }
+
+    #[test]
+    fn test_same_path_in_two_collections_is_kept_apart() {
+        let note = |collection: &str, similarity: f32| {
+            (
+                VectorEntry::new("plan.md".to_string(), 0, vec![1.0], "Plan".to_string(), String::new(), 1, 1)
+                    .with_collection(collection),
+                similarity,
+            )
+        };
+        let candidates = vec![note("work", 0.8), note("personal", 0.7)];
+        let ranked = rank_candidates(candidates, "", &HashMap::new(), &FileNames::new(), 0.0, &SearchOptions::new(5), None);
+        let collections: Vec<&str> = ranked.iter().map(|hit| hit.0.collection.as_str()).collect();
+        assert_eq!(collections, vec!["work", "personal"]);
+    }
+
+    #[test]
+    fn test_per_file_cap_and_limit_steps() {
+        let chunk = |file: &str, cosine: f32| {
+            (VectorEntry::new(file.to_string(), 0, vec![1.0], file.to_string(), String::new(), 1, 1), cosine)
+        };
+        let candidates = vec![chunk("a.md", 0.9), chunk("a.md", 0.8), chunk("a.md", 0.7), chunk("b.md", 0.6)];
+        let files = |options: SearchOptions| -> Vec<String> {
+            rank_candidates(candidates.clone(), "", &HashMap::new(), &FileNames::new(), 0.0, &options, None)
+                .into_iter()
+                .map(|hit| hit.0.file_path)
+                .collect()
+        };
+        assert_eq!(files(SearchOptions::new(10)), vec!["a.md", "a.md", "a.md", "b.md"]);
+        assert_eq!(files(SearchOptions::new(10).with_per_file(Some(1))), vec!["a.md", "b.md"]);
+        assert_eq!(files(SearchOptions::new(2).with_per_file(Some(2))), vec!["a.md", "a.md"]);
+
+        assert_eq!(step_result_limit(20, true), 50);
+        assert_eq!(step_result_limit(20, false), 10);
+        // Off-step values snap to the neighbouring steps, and the ends hold
+        assert_eq!(step_result_limit(30, true), 50);
+        assert_eq!(step_result_limit(30, false), 20);
+        assert_eq!(step_result_limit(200, true), 200);
+        assert_eq!(step_result_limit(5, false), 5);
+    }
+
+    #[test]
+    fn test_adjacent_chunks_merge_into_one_result() {
+        let chunk = |file: &str, index: usize, lines: (usize, usize), score: f32| {
+            let text = format!("{} part {}", file, index);
+            (VectorEntry::new(file.to_string(), index, vec![1.0], text, String::new(), lines.0, lines.1), score, None)
+        };
+        let hits = vec![
+            chunk("plan.md", 3, (12, 20), 0.9),
+            chunk("other.md", 0, (1, 4), 0.85),
+            chunk("plan.md", 2, (5, 11), 0.8),
+            chunk("plan.md", 5, (30, 34), 0.7),
+        ];
+
+        let merged = merge_adjacent(hits);
+        assert_eq!(merged.len(), 3);
+        let (entry, score, _) = &merged[0];
+        assert_eq!((entry.file_path.as_str(), entry.chunk_index), ("plan.md", 2));
+        assert_eq!((entry.start_line, entry.end_line), (5, 20));
+        assert_eq!(entry.text, "plan.md part 2\n\nplan.md part 3");
+        assert_eq!(*score, 0.9);
+        // Chunk 5 isn't next to 3, so it stays on its own
+        assert_eq!(merged[1].0.file_path, "other.md");
+        assert_eq!((merged[2].0.chunk_index, merged[2].0.start_line), (5, 30));
+    }
+
+    #[test]
+    fn test_lexical_boost_needs_the_floor() {
+        let note = |file: &str, text: &str, cosine: f32| {
+            (VectorEntry::new(file.to_string(), 0, vec![1.0], text.to_string(), String::new(), 1, 1), cosine)
+        };
+        // The off-topic chunk says "meeting"; the relevant one doesn't
+        let candidates = vec![
+            note("offsite.md", "Agenda for the quarterly offsite", 0.55),
+            note("lunch.md", "The meeting about lunch orders", 0.45),
+        ];
+        let ranked = |floor| -> Vec<String> {
+            rank_candidates(candidates.clone(), "meeting", &HashMap::new(), &FileNames::new(), floor, &SearchOptions::new(5), None)
+                .into_iter()
+                .map(|hit| hit.0.file_path)
+                .collect()
+        };
+        assert_eq!(ranked(0.0), vec!["lunch.md", "offsite.md"]);
+        assert_eq!(ranked(0.5), vec!["offsite.md", "lunch.md"]);
+    }
+
+    #[test]
+    fn test_session_boost_settles_near_ties_only() {
+        let note = |file: &str, cosine: f32| {
+            (VectorEntry::new(file.to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1), cosine)
+        };
+        let mut session = FolderFrecency::default();
+        for _ in 0..5 {
+            session.record("projects/acme/plan.md");
+        }
+        let ranked = |candidates: Vec<(VectorEntry, f32)>| -> Vec<String> {
+            rank_candidates(candidates, "", &HashMap::new(), &FileNames::new(), 0.0, &SearchOptions::new(5), Some(&session))
+                .into_iter()
+                .map(|hit| hit.0.file_path)
+                .collect()
+        };
+        let near_tie = vec![note("other/budget.md", 0.62), note("projects/acme/budget.md", 0.61)];
+        assert_eq!(ranked(near_tie), vec!["projects/acme/budget.md", "other/budget.md"]);
+        let clear_gap = vec![note("other/budget.md", 0.72), note("projects/acme/budget.md", 0.61)];
+        assert_eq!(ranked(clear_gap), vec!["other/budget.md", "projects/acme/budget.md"]);
+    }
+
+    #[test]
+    fn test_hits_under_the_floor_are_not_counted_as_reordered() {
+        let note = |file: &str, cosine: f32| {
+            (VectorEntry::new(file.to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1), cosine)
+        };
+        let mut session = FolderFrecency::default();
+        for _ in 0..5 {
+            session.record("projects/acme/plan.md");
+        }
+        let options = SearchOptions::new(5).with_explain(true);
+        let rank = |candidates| rank_candidates(candidates, "", &HashMap::new(), &FileNames::new(), 0.5, &options, Some(&session));
+
+        // Leads on its own cosine; under the floor it got no bonus to take away
+        let under_floor = rank(vec![note("other/budget.md", 0.46), note("projects/acme/budget.md", 0.47)]);
+        assert_eq!(under_floor[0].0.file_path, "projects/acme/budget.md");
+        assert_eq!(under_floor[0].2.unwrap().session, 0.0);
+        assert!(!session.reordered(&under_floor));
+
+        let near_tie = rank(vec![note("other/budget.md", 0.62), note("projects/acme/budget.md", 0.61)]);
+        assert_eq!(near_tie[0].0.file_path, "projects/acme/budget.md");
+        assert!(session.reordered(&near_tie));
+    }
+
+    #[test]
+    fn test_tag_filter_matches_normalized_inline_tag() {
+        let norm = TagNormalization::default();
+        let chunk = TextChunk {
+            text: "Quarterly planning #Work".to_string(),
+            context: String::new(),
+            headings: Vec::new(),
+            chunk_index: 0,
+            start_line: 1,
+            end_line: 1,
+            start_offset: 0,
+            end_offset: 24,
+            kind: crate::indexing::parser::ChunkKind::Content,
+        };
+        let entry = VectorEntry::new("plan.md".to_string(), 0, vec![1.0], chunk.text.clone(), String::new(), 1, 1)
+            .with_tags(chunk_tags(&[], &chunk, &norm));
+
+        let (filters, _) = parse_query_filters("tag:work planning", &norm);
+        assert!(entry_has_tags(&entry, &filters.tags));
+
+        let (filters, _) = parse_query_filters("tag:WORK planning", &norm);
+        assert!(entry_has_tags(&entry, &filters.tags));
+    }
+
+    #[test]
+    fn test_lexical_bonus_components() {
+        let entry = VectorEntry::new(
+            "agenda.md".to_string(),
+            0,
+            vec![1.0],
+            "Weekly agenda items".to_string(),
+            "Meetings".to_string(),
+            1,
+            1,
+        );
+        let bonus = lexical_bonus(&entry, "agenda", None);
+        assert!((bonus - (LEXICAL_BOOST_PATH + LEXICAL_BOOST_TEXT)).abs() < 1e-6);
+        assert_eq!(lexical_bonus(&entry, "", None), 0.0);
+
+        let breakdown = ScoreBreakdown::new(0.5).with_lexical(bonus);
+        assert!((breakdown.final_score - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_path_words() {
+        assert_eq!(path_words("projects/2024/db-migration.md"), "projects 2024 db migration");
+        assert_eq!(path_words("meeting_notes/Q3-review"), "meeting notes Q3 review");
+        assert_eq!(path_words("v1.2/readme"), "v1.2 readme");
+    }
+
+    #[test]
+    fn test_path_signal_ranks_descriptive_sparse_file() {
+        // "database migration": the sparse file's body barely matches, its path matches well
+        let candidates = vec![
+            (
+                VectorEntry::new("projects/2024/db-migration.md".to_string(), 0, vec![1.0], "TODO".to_string(), String::new(), 1, 1),
+                0.78,
+            ),
+            (
+                VectorEntry::new("journal/monday.md".to_string(), 0, vec![1.0], "Moved some data around".to_string(), String::new(), 1, 1),
+                0.80,
+            ),
+        ];
+        let options = SearchOptions::new(2).with_explain(true);
+
+        let plain = rank_candidates(candidates.clone(), "database migration", &HashMap::new(), &FileNames::new(), 0.0, &options, None);
+        assert_eq!(plain[0].0.file_path, "journal/monday.md");
+        assert_eq!(plain[0].2.unwrap().path, 0.0);
+
+        let path_scores = HashMap::from([
+            ("projects/2024/db-migration.md".to_string(), 0.90),
+            ("journal/monday.md".to_string(), 0.72),
+        ]);
+        let ranked = rank_candidates(candidates, "database migration", &path_scores, &FileNames::new(), 0.0, &options, None);
+        assert_eq!(ranked[0].0.file_path, "projects/2024/db-migration.md");
+        let breakdown = ranked[0].2.unwrap();
+        assert!((breakdown.path - 0.12 * PATH_SIGNAL_BLEND).abs() < 1e-6);
+        // A poorly matching path costs nothing
+        assert_eq!(ranked[1].2.unwrap().path, 0.0);
+        assert!((ranked[1].1 - 0.80).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_file_filter_matches_path_components() {
+        // A bare name still matches anywhere in the path
+        assert!(path_matches_filter("work/todo.md", "todo"));
+        assert!(path_matches_filter("personal/mytodo.txt", "TODO"));
+
+        // With a separator, earlier components must match whole
+        assert!(path_matches_filter("work/todo.md", "work/todo.md"));
+        assert!(path_matches_filter("notes/work/todo.md", "work/todo"));
+        assert!(!path_matches_filter("homework/todo.md", "work/todo.md"));
+        assert!(!path_matches_filter("personal/todo.md", "work/todo.md"));
+        assert!(!path_matches_filter("todo.md", "work/todo.md"));
+        assert!(path_matches_filter("2024/q1/work/plan.md", "q1/work/plan"));
+        assert!(!path_matches_filter("2024/q1/work/plan.md", "q1/plan"));
+    }
+
+    #[test]
+    fn test_file_filter_windows_separators() {
+        assert!(path_matches_filter("work/todo.md", "work\\todo.md"));
+        assert!(path_matches_filter("work\\todo.md", "work/todo.md"));
+        assert!(path_matches_filter("Notes\\Work\\todo.md", "work\\TODO.md$"));
+        assert!(!path_matches_filter("homework\\todo.md", "work\\todo.md"));
+    }
+
+    #[test]
+    fn test_file_filter_anchored_name() {
+        assert!(path_matches_filter("work/todo.md", "todo.md$"));
+        assert!(path_matches_filter("todo.md", "todo.md$"));
+        assert!(!path_matches_filter("work/todo.md.bak", "todo.md$"));
+        assert!(!path_matches_filter("work/mytodo.md", "todo.md$"));
+        assert!(path_matches_filter("a/work/todo.md", "work/todo.md$"));
+        assert!(!path_matches_filter("a/work/todo.md", "work/todo$"));
+        assert!(!path_matches_filter("todo.md", "$"));
+
+        let (filters, _) = parse_query_filters("file:work/todo.md$ groceries", &TagNormalization::default());
+        assert_eq!(filters.file.as_deref(), Some("work/todo.md$"));
+    }
+
+    fn standup_names() -> FileMeta {
+        FileMeta {
+            title: Some("Team sync".to_string()),
+            aliases: vec!["Standup notes".to_string(), "Daily sync".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_file_filter_matches_aliases() {
+        let names = standup_names();
+        assert_eq!(file_filter_match("journal/2024-03-04.md", Some(&names), "standup"), Some(FilterMatch::Alias));
+        assert_eq!(file_filter_match("journal/2024-03-04.md", Some(&names), "team sync$"), Some(FilterMatch::Alias));
+        assert_eq!(file_filter_match("journal/2024-03-04.md", Some(&names), "daily$"), None);
+        assert_eq!(file_filter_match("journal/2024-03-04.md", None, "standup"), None);
+        // The path is checked first
+        assert_eq!(file_filter_match("journal/2024-03-04.md", Some(&names), "journal"), Some(FilterMatch::Path));
+    }
+
+    #[test]
+    fn test_alias_query_ranks_aliased_file_first() {
+        let entry = |path: &str, text: &str| {
+            VectorEntry::new(path.to_string(), 0, vec![1.0], text.to_string(), String::new(), 1, 1)
+        };
+        let candidates = vec![
+            (entry("meetings/weekly.md", "Went over the roadmap and hiring"), 0.74),
+            (entry("journal/2024-03-04.md", "Blocked on review, pairing after lunch"), 0.70),
+        ];
+        let options = SearchOptions::new(2).with_explain(true);
+
+        let plain = rank_candidates(candidates.clone(), "standup notes", &HashMap::new(), &FileNames::new(), 0.0, &options, None);
+        assert_eq!(plain[0].0.file_path, "meetings/weekly.md");
+
+        let names = FileNames::from([(
+            DEFAULT_COLLECTION.to_string(),
+            HashMap::from([("journal/2024-03-04.md".to_string(), standup_names())]),
+        )]);
+        for query in ["standup notes", "daily sync", "standup notes from march"] {
+            let ranked = rank_candidates(candidates.clone(), query, &HashMap::new(), &names, 0.0, &options, None);
+            assert_eq!(ranked[0].0.file_path, "journal/2024-03-04.md", "{}", query);
+            assert!((ranked[0].2.unwrap().lexical - LEXICAL_BOOST_ALIAS).abs() < 1e-6);
+        }
+        // Another collection's file with the same path has its own names
+        let other = FileNames::from([("work".to_string(), names[DEFAULT_COLLECTION].clone())]);
+        let ranked = rank_candidates(candidates, "standup notes", &HashMap::new(), &other, 0.0, &options, None);
+        assert_eq!(ranked[0].0.file_path, "meetings/weekly.md");
+    }
+
+    #[test]
+    fn test_tag_filter_case_sensitive() {
+        let norm = TagNormalization { case_sensitive: true, strip_hash: true };
+        let entry = VectorEntry::new("plan.md".to_string(), 0, vec![1.0], String::new(), String::new(), 1, 1)
+            .with_tags(vec!["Work".to_string()]);
+
+        let (filters, _) = parse_query_filters("tag:work", &norm);
+        assert!(!entry_has_tags(&entry, &filters.tags));
+        let (filters, _) = parse_query_filters("tag:Work", &norm);
+        assert!(entry_has_tags(&entry, &filters.tags));
+    }
+}
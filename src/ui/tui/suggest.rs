@@ -0,0 +1,206 @@
+use crate::storage::vectors::VectorEntry;
+use std::collections::HashMap;
+
+/// Distinct headings and tags kept for completion; once full, only known terms are counted
+pub const MAX_DICTIONARY_TERMS: usize = 5000;
+/// Suggestions shown under the search box
+pub const MAX_SUGGESTIONS: usize = 5;
+/// Characters of the current word typed before suggestions appear
+pub const MIN_PREFIX_CHARS: usize = 3;
+
+/// Where a suggestion came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SuggestionKind {
+    /// A segment of a chunk's heading breadcrumb
+    Heading,
+    Tag,
+}
+
+/// A completion candidate and how many chunks it appears on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    pub term: String,
+    pub count: usize,
+}
+
+impl Suggestion {
+    /// Text put into the query: headings as written, tags as a `tag:` filter
+    pub fn insert_text(&self) -> String {
+        match self.kind {
+            SuggestionKind::Heading => self.term.clone(),
+            SuggestionKind::Tag => format!("tag:{}", self.term),
+        }
+    }
+}
+
+/// Headings and tags of the files in scope, most frequent first.
+/// Built once per scope selection so completing never touches the database.
+#[derive(Debug, Default, Clone)]
+pub struct SuggestionDictionary {
+    terms: Vec<Suggestion>,
+    /// Lowercased `terms`, in the same order
+    lowered: Vec<String>,
+}
+
+/// Counts terms from stored entries; `finish` ranks them into a dictionary
+#[derive(Debug, Default)]
+pub struct DictionaryBuilder {
+    /// (kind, lowercased term) -> (first spelling seen, count)
+    counts: HashMap<(SuggestionKind, String), (String, usize)>,
+}
+
+impl DictionaryBuilder {
+    pub fn add(&mut self, entry: &VectorEntry) {
+        for segment in entry.context.split(" > ") {
+            self.count(SuggestionKind::Heading, segment);
+        }
+        for tag in &entry.tags {
+            // `tag:` filters are split on whitespace, so such tags couldn't be inserted
+            if !tag.contains(char::is_whitespace) {
+                self.count(SuggestionKind::Tag, tag);
+            }
+        }
+    }
+
+    fn count(&mut self, kind: SuggestionKind, term: &str) {
+        let term = term.trim();
+        if term.is_empty() {
+            return;
+        }
+        let key = (kind, term.to_lowercase());
+        if let Some((_, count)) = self.counts.get_mut(&key) {
+            *count += 1;
+        } else if self.counts.len() < MAX_DICTIONARY_TERMS {
+            self.counts.insert(key, (term.to_string(), 1));
+        }
+    }
+
+    pub fn finish(self) -> SuggestionDictionary {
+        let mut terms: Vec<(String, Suggestion)> = self
+            .counts
+            .into_iter()
+            .map(|((kind, lower), (term, count))| (lower, Suggestion { kind, term, count }))
+            .collect();
+        terms.sort_by(|a, b| {
+            b.1.count
+                .cmp(&a.1.count)
+                .then(a.1.kind.cmp(&b.1.kind))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        let (lowered, terms) = terms.into_iter().unzip();
+        SuggestionDictionary { terms, lowered }
+    }
+}
+
+impl SuggestionDictionary {
+    /// Up to `MAX_SUGGESTIONS` terms extending the word being typed, most frequent first.
+    /// A word starting with `tag:` only completes tags.
+    pub fn suggest(&self, query: &str) -> Vec<Suggestion> {
+        let word = current_word(query);
+        let (word, tags_only) = match word.strip_prefix("tag:") {
+            Some(rest) => (rest, true),
+            None => (word, false),
+        };
+        let word = word.trim_start_matches('#');
+        if word.chars().count() < MIN_PREFIX_CHARS {
+            return Vec::new();
+        }
+
+        let prefix = word.to_lowercase();
+        self.terms
+            .iter()
+            .zip(&self.lowered)
+            .filter(|(s, lower)| {
+                (!tags_only || s.kind == SuggestionKind::Tag)
+                    && lower.starts_with(&prefix)
+                    && **lower != prefix
+            })
+            .map(|(s, _)| s.clone())
+            .take(MAX_SUGGESTIONS)
+            .collect()
+    }
+}
+
+/// Replace the word being typed with `suggestion`, leaving the cursor after a space
+pub fn complete(query: &str, suggestion: &Suggestion) -> String {
+    let word = current_word(query);
+    let head = &query[..query.len() - word.len()];
+    format!("{}{} ", head, suggestion.insert_text())
+}
+
+/// Text after the last whitespace (empty right after a space)
+fn current_word(query: &str) -> &str {
+    query.rsplit(char::is_whitespace).next().unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(context: &str, tags: &[&str]) -> VectorEntry {
+        VectorEntry::new("notes.md".to_string(), 0, vec![1.0], "text".to_string(), context.to_string(), 1, 1)
+            .with_tags(tags.iter().map(|t| t.to_string()).collect())
+    }
+
+    fn dictionary(entries: &[VectorEntry]) -> SuggestionDictionary {
+        let mut builder = DictionaryBuilder::default();
+        for e in entries {
+            builder.add(e);
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn test_suggestions_ranked_by_frequency() {
+        let dict = dictionary(&[
+            entry("Projects > Roadmap", &["project"]),
+            entry("Projects > Retro", &["project"]),
+            entry("Projects", &[]),
+            entry("Programming", &[]),
+        ]);
+
+        let terms: Vec<String> = dict.suggest("plan pro").into_iter().map(|s| s.term).collect();
+        assert_eq!(terms, vec!["Projects", "project", "Programming"]);
+    }
+
+    #[test]
+    fn test_short_or_finished_words_get_no_suggestions() {
+        let dict = dictionary(&[entry("Projects", &[])]);
+        assert!(dict.suggest("pr").is_empty());
+        assert!(dict.suggest("projects").is_empty());
+        assert!(dict.suggest("pro ").is_empty());
+    }
+
+    #[test]
+    fn test_tag_prefix_only_completes_tags() {
+        let dict = dictionary(&[entry("Work log", &["work"]), entry("Work log", &[])]);
+        let suggestions = dict.suggest("tag:wo");
+        assert_eq!(suggestions.len(), 0, "needs three characters after tag:");
+
+        let suggestions = dict.suggest("notes tag:#wor");
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].kind, SuggestionKind::Tag);
+        assert_eq!(complete("notes tag:#wor", &suggestions[0]), "notes tag:work ");
+    }
+
+    #[test]
+    fn test_complete_replaces_current_word() {
+        let heading = Suggestion { kind: SuggestionKind::Heading, term: "Roadmap".to_string(), count: 1 };
+        assert_eq!(complete("q3 road", &heading), "q3 Roadmap ");
+        assert_eq!(complete("roa", &heading), "Roadmap ");
+    }
+
+    #[test]
+    fn test_dictionary_size_is_capped() {
+        let mut builder = DictionaryBuilder::default();
+        for i in 0..MAX_DICTIONARY_TERMS + 10 {
+            builder.add(&entry(&format!("Heading {}", i), &[]));
+        }
+        // Known terms are still counted once the cap is reached
+        builder.add(&entry("Heading 0", &[]));
+        let dict = builder.finish();
+        assert_eq!(dict.terms.len(), MAX_DICTIONARY_TERMS);
+        assert_eq!(dict.suggest("head")[0].term, "Heading 0");
+    }
+}
@@ -0,0 +1,98 @@
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use crate::search::model::EmbeddingModel;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread::JoinHandle;
+
+/// The embedding model loading on a background thread, started when the TUI opens so the
+/// first folder doesn't wait for it
+pub struct ModelWarmup {
+    handle: Option<JoinHandle<()>>,
+    rx: Receiver<Result<EmbeddingModel>>,
+    /// The thread's result, once it has arrived
+    result: Option<Result<EmbeddingModel>>,
+}
+
+impl ModelWarmup {
+    pub fn start(config: &Config) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let config = config.clone();
+        let handle = std::thread::spawn(move || {
+            // TUI must not print while in raw/alternate screen mode
+            let _ = tx.send(load_warm(&config));
+        });
+        Self {
+            handle: Some(handle),
+            rx,
+            result: None,
+        }
+    }
+
+    /// Pick up the result if the thread has finished; true while still loading
+    pub fn poll(&mut self) -> bool {
+        if self.result.is_none() {
+            match self.rx.try_recv() {
+                Ok(result) => self.result = Some(result),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.result = Some(Err(stopped())),
+            }
+        }
+        self.is_loading()
+    }
+
+    /// Whether the result hasn't been picked up yet (see [`poll`](Self::poll))
+    pub fn is_loading(&self) -> bool {
+        self.result.is_none()
+    }
+
+    /// Block until the model is loaded, then join the thread
+    pub fn wait(mut self) -> Result<EmbeddingModel> {
+        let result = match self.result.take() {
+            Some(result) => result,
+            None => self.rx.recv().unwrap_or_else(|_| Err(stopped())),
+        };
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                return Err(stopped());
+            }
+        }
+        result
+    }
+}
+
+/// Load the model and run a first embedding, so the first search is as fast as the rest
+pub fn load_warm(config: &Config) -> Result<EmbeddingModel> {
+    let model = EmbeddingModel::init_quiet(config)?;
+    model.warmup()?;
+    Ok(model)
+}
+
+fn stopped() -> Error {
+    Error::Model("Model loading stopped unexpectedly".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::model::MODEL_FILES;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_failure_is_returned_by_wait() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config::new(Some(temp_dir.path().join("base"))).unwrap();
+        config.init().unwrap();
+        std::fs::create_dir_all(config.model_dir()).unwrap();
+        // Present but unreadable, so loading fails without trying to download
+        for file in MODEL_FILES {
+            std::fs::write(config.model_dir().join(file), "").unwrap();
+        }
+
+        let mut warmup = ModelWarmup::start(&config);
+        while warmup.poll() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(!warmup.is_loading());
+        assert!(warmup.wait().is_err());
+    }
+}
@@ -1,23 +1,35 @@
 use crate::core::config::Config;
 use crate::core::error::{Error, Result};
-use crate::indexing::discovery::is_notes_file;
-use crate::indexing::parser::parse_markdown_file;
+use crate::indexing::discovery::{discover_files_with, is_excluded, is_notes_file, DiscoveryOptions};
+use crate::indexing::pipeline::index_file;
+use crate::search::files::best_chunk_per_file;
 use crate::search::model::EmbeddingModel;
-use crate::storage::state::{calculate_file_hash, get_file_modified_time, StateStore};
-use crate::storage::vectors::{VectorEntry, VectorStore};
+use crate::ui::socket::{socket_path, QueryHandler, QueryRequest, QueryServer};
+use crate::ui::watch_status::{status_path, WatchStatus, STATUS_INTERVAL};
+use crate::storage::state::{FileCheck, FileSnapshot, FileState, StateStore};
+use crate::storage::vectors::VectorStore;
 use notify_debouncer_full::{
     new_debouncer,
     notify::{RecursiveMode, Watcher},
     DebounceEventResult,
 };
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
 use std::time::Duration;
 
 /// File watcher for automatic indexing
 pub struct FileWatcher {
     root_path: PathBuf,
     config: Config,
+    quiet: bool,
+    serve_socket: bool,
+}
+
+/// Vector store and model kept open for the whole watch while serving queries
+struct Session {
+    vector_store: Arc<VectorStore>,
+    model: Arc<EmbeddingModel>,
 }
 
 impl FileWatcher {
@@ -26,17 +38,43 @@ impl FileWatcher {
         Ok(Self {
             root_path: root_path.to_path_buf(),
             config,
+            quiet: false,
+            serve_socket: false,
         })
     }
 
+    /// Suppress the startup banner and model loading output
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Also answer searches over a socket in the base directory (see `ui::socket`)
+    pub fn serve_socket(mut self, serve_socket: bool) -> Self {
+        self.serve_socket = serve_socket;
+        self
+    }
+
     /// Start watching and processing file changes
     pub fn watch(&mut self) -> Result<()> {
-        println!("Watching directory: {}", self.root_path.display());
-        println!("Press Ctrl+C to stop watching...\n");
+        if !self.quiet {
+            println!("Watching directory: {}", self.root_path.display());
+            println!("Press Ctrl+C to stop watching...\n");
+        }
+
+        // Serving keeps the store and model open, so searches and batches share them;
+        // otherwise each batch opens its own and releases the database lock afterwards
+        let (session, _server) = if self.serve_socket {
+            let (session, server) = self.start_server()?;
+            (Some(session), Some(server))
+        } else {
+            (None, None)
+        };
 
         let (tx, rx) = mpsc::channel();
         let root_path = self.root_path.clone();
         let config = self.config.clone();
+        let quiet = self.quiet;
         
         // Create debouncer with callback
         let mut debouncer = new_debouncer(
@@ -61,13 +99,26 @@ impl FileWatcher {
                 format!("Failed to watch directory: {}", e),
             )))?;
 
+        // Rewritten after every batch, and periodically while idle so monitoring can tell
+        // a quiet watcher from a dead one
+        let status_file = status_path(&self.config);
+        let mut status = WatchStatus::new(&self.root_path);
+        status.save(&status_file);
+
         // Process events
         loop {
-            match rx.recv() {
+            match rx.recv_timeout(STATUS_INTERVAL) {
                 Ok((events, root_path, config)) => {
-                    Self::process_events_static(&events, &root_path, &config)?;
+                    status.record_event();
+                    let processed = Self::process_events_static(&events, &root_path, &config, quiet, session.as_ref(), &mut status);
+                    if let Err(e) = &processed {
+                        status.last_error = Some(e.to_string());
+                    }
+                    status.save(&status_file);
+                    processed?;
                 }
-                Err(_) => {
+                Err(RecvTimeoutError::Timeout) => status.save(&status_file),
+                Err(RecvTimeoutError::Disconnected) => {
                     // Channel closed
                     break;
                 }
@@ -77,108 +128,175 @@ impl FileWatcher {
         Ok(())
     }
 
+    /// Open the store and load the model for the whole watch, and listen for searches
+    fn start_server(&self) -> Result<(Session, QueryServer)> {
+        let session = Session {
+            vector_store: Arc::new(VectorStore::open(&self.config)?),
+            model: Arc::new(if self.quiet {
+                EmbeddingModel::init_quiet(&self.config)?
+            } else {
+                EmbeddingModel::init_verbose(&self.config)?
+            }),
+        };
+
+        let vector_store = Arc::clone(&session.vector_store);
+        let model = Arc::clone(&session.model);
+        let handler: Arc<QueryHandler> = Arc::new(move |request: &QueryRequest| {
+            best_chunk_per_file(&request.query, &request.options(), &model, &vector_store)
+        });
+        let server = QueryServer::start(socket_path(&self.config), handler)?;
+        if !self.quiet {
+            println!("Serving searches on {}\n", server.path().display());
+        }
+        Ok((session, server))
+    }
+
     /// Process file change events (static version for use in closure)
     fn process_events_static(
         events: &[notify_debouncer_full::DebouncedEvent],
         root_path: &Path,
         config: &Config,
+        quiet: bool,
+        session: Option<&Session>,
+        status: &mut WatchStatus,
     ) -> Result<()> {
         let state_store = StateStore::open(config)?;
-        let vector_store = VectorStore::open(config)?;
-        
-        // Initialize model once for all files in this batch
-        // This avoids expensive re-initialization on every file change
-        let model = match EmbeddingModel::init_verbose(config) {
-            Ok(m) => m,
-            Err(e) => {
-                eprintln!("⚠ Warning: Failed to initialize embedding model: {}", e);
-                eprintln!("  Skipping file indexing in this batch.");
-                return Ok(());
+        // Recorded with each file, as `notes2vec index` does
+        let notes_root = std::fs::canonicalize(root_path).unwrap_or_else(|_| root_path.to_path_buf());
+
+        let opened;
+        let (vector_store, model) = match session {
+            Some(session) => (session.vector_store.as_ref(), session.model.as_ref()),
+            None => {
+                let vector_store = VectorStore::open(config)?;
+
+                // Initialize model once for all files in this batch
+                // This avoids expensive re-initialization on every file change
+                let model_init = if quiet {
+                    EmbeddingModel::init_quiet(config)
+                } else {
+                    EmbeddingModel::init_verbose(config)
+                };
+                let model = match model_init {
+                    Ok(m) => m,
+                    Err(e) => {
+                        tracing::warn!("Failed to initialize embedding model: {}. Skipping file indexing in this batch.", e);
+                        status.last_error = Some(format!("Failed to initialize embedding model: {}", e));
+                        return Ok(());
+                    }
+                };
+                opened = (vector_store, model);
+                (&opened.0, &opened.1)
             }
         };
+        // New vectors of another length would be mixed into the index
+        state_store.check_embedding_dim(model.embedding_dim())?;
 
+        // Expand directories (e.g. a folder moved into the tree) into the notes they contain.
+        // Processing order doesn't matter here, so skip the sort.
+        let options = DiscoveryOptions { sort: false, ..DiscoveryOptions::from_config(config) };
+        let mut paths: Vec<PathBuf> = Vec::new();
         for event in events {
             // DebouncedEvent contains paths (plural) - iterate through them
             for path in &event.paths {
-                // Only process supported notes files
-                if !is_notes_file(path) {
-                    continue;
+                if path.is_dir() {
+                    match discover_files_with(path, options.clone()) {
+                        Ok(files) => paths.extend(files.into_iter().map(|f| f.path)),
+                        Err(e) => tracing::warn!("Could not scan directory {}: {}", path.display(), e),
+                    }
+                } else if !paths.contains(path) {
+                    paths.push(path.clone());
                 }
+            }
+        }
 
-                // Check if file exists (might have been deleted)
-                if !path.exists() {
-                    // File was deleted - remove from index
-                    if let Ok(relative_path) = path.strip_prefix(root_path) {
-                        let file_path_str = match relative_path.to_str() {
-                            Some(s) => s,
-                            None => {
-                                eprintln!("⚠ Warning: Skipping deleted file with invalid UTF-8 path: {}", relative_path.display());
-                                continue;
-                            }
-                        };
-                        if let Err(e) = vector_store.remove_file(file_path_str) {
-                            eprintln!("⚠ Warning: Failed to remove deleted file from index ({}): {}", relative_path.display(), e);
+        for path in &paths {
+            // Only process supported notes files
+            if !is_notes_file(path) {
+                continue;
+            }
+
+            // Same exclusions as a full index: hidden files unless enabled, never .git or .notes2vec
+            if path.strip_prefix(root_path).map(|p| is_excluded(p, &options)).unwrap_or(false) {
+                continue;
+            }
+
+            // Check if file exists (might have been deleted)
+            if !path.exists() {
+                // File was deleted - remove from index
+                if let Ok(relative_path) = path.strip_prefix(root_path) {
+                    let file_path_str = match relative_path.to_str() {
+                        Some(s) => s,
+                        None => {
+                            tracing::warn!("Skipping deleted file with invalid UTF-8 path: {}", relative_path.display());
+                            continue;
                         }
-                        if let Err(e) = state_store.remove_file(file_path_str) {
-                            eprintln!("⚠ Warning: Failed to remove deleted file from state ({}): {}", relative_path.display(), e);
+                    };
+                    let from_index = vector_store.remove_file(file_path_str)
+                        .map_err(|e| format!("Failed to remove deleted file from index ({}): {}", relative_path.display(), e));
+                    let from_state = state_store.remove_file(file_path_str)
+                        .map_err(|e| format!("Failed to remove deleted file from state ({}): {}", relative_path.display(), e));
+                    match from_index.and(from_state) {
+                        Ok(()) => status.removed += 1,
+                        Err(message) => {
+                            tracing::warn!("{}", message);
+                            status.record_failure(message);
                         }
-                        println!("  ✗ Removed deleted file: {}", relative_path.display());
                     }
-                    continue;
+                    tracing::info!("✗ Removed deleted file: {}", relative_path.display());
                 }
+                continue;
+            }
 
-                // Process file
-                match path.strip_prefix(root_path) {
-                    Ok(relative_path) => {
-                        let file_path_str = match relative_path.to_str() {
-                            Some(s) => s,
-                            None => {
-                                eprintln!("⚠ Warning: Skipping file with invalid UTF-8 path: {}", relative_path.display());
-                                continue;
+            // Process file
+            match path.strip_prefix(root_path) {
+                Ok(relative_path) => {
+                    let file_path_str = match relative_path.to_str() {
+                        Some(s) => s,
+                        None => {
+                            tracing::warn!("Skipping file with invalid UTF-8 path: {}", relative_path.display());
+                            continue;
+                        }
+                    };
+                    
+                    // Check if file has changed
+                    let check = FileSnapshot::read(path)
+                        .and_then(|snapshot| state_store.check_file(file_path_str, path, snapshot, false));
+                    match check {
+                        Ok(FileCheck::Unchanged) => {}
+                        Ok(FileCheck::Touched(state)) => {
+                            // Same content under a new timestamp; nothing to reindex
+                            if let Err(e) = state_store.store_file_state(file_path_str, &state) {
+                                tracing::warn!(file = file_path_str, "Failed to update state: {}", e);
                             }
-                        };
-                        
-                        // Check if file has changed
-                        match (get_file_modified_time(path), calculate_file_hash(path)) {
-                            (Ok(modified_time), Ok(hash)) => {
-                                if let Ok(false) = state_store.has_file_changed(
-                                    file_path_str,
-                                    modified_time,
-                                    &hash,
-                                ) {
-                                    // File hasn't changed, skip
-                                    continue;
-                                }
-
-                                // Index the file
-                                match Self::index_file_static(path, file_path_str, &state_store, &vector_store, &model) {
-                                    Ok(_) => {
-                                        // Update state
-                                        if let Err(e) = state_store.update_file_state(
-                                            file_path_str,
-                                            modified_time,
-                                            hash,
-                                        ) {
-                                            eprintln!("  ⚠ Warning: Failed to update state: {}", e);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        eprintln!("  ✗ Failed to index {}: {}", relative_path.display(), e);
+                        }
+                        Ok(FileCheck::Changed) => {
+                            // Index the file
+                            match Self::index_file_static(path, file_path_str, config, vector_store, model) {
+                                Ok(_) => {
+                                    status.indexed += 1;
+                                    // Update state
+                                    let stored = FileState::from_file(path)
+                                        .map(|state| state.with_root(&notes_root))
+                                        .and_then(|state| state_store.store_file_state(file_path_str, &state));
+                                    if let Err(e) = stored {
+                                        tracing::warn!(file = file_path_str, "Failed to update state: {}", e);
                                     }
                                 }
-                            }
-                            (Err(e), _) => {
-                                eprintln!("  ⚠ Warning: Could not get modification time for {}: {}", relative_path.display(), e);
-                            }
-                            (_, Err(e)) => {
-                                eprintln!("  ⚠ Warning: Could not calculate hash for {}: {}", relative_path.display(), e);
+                                Err(e) => {
+                                    tracing::error!("Failed to index {}: {}", relative_path.display(), e);
+                                    status.record_failure(format!("Failed to index {}: {}", relative_path.display(), e));
+                                }
                             }
                         }
+                        Err(e) => {
+                            tracing::warn!("Could not check {} for changes: {}", relative_path.display(), e);
+                        }
                     }
-                    Err(_) => {
-                        // File is outside root path, skip
-                        continue;
-                    }
+                }
+                Err(_) => {
+                    // File is outside root path, skip
+                    continue;
                 }
             }
         }
@@ -190,43 +308,26 @@ impl FileWatcher {
     fn index_file_static(
         path: &Path,
         file_path_str: &str,
-        _state_store: &StateStore,
+        config: &Config,
         vector_store: &VectorStore,
         model: &EmbeddingModel,
     ) -> Result<()> {
-        // Remove old vectors
-        let _ = vector_store.remove_file(file_path_str);
-
-        // Parse file
-        let doc = parse_markdown_file(path)?;
-
-        // Process chunks (model is already initialized and passed in)
-        let chunks_to_embed: Vec<String> = doc.chunks.iter().map(|c| c.text.clone()).collect();
-        // Use embed_passages for BGE model compatibility (better search quality)
-        let embeddings = model.embed_passages(&chunks_to_embed)?;
-
-        // Store vectors - pre-allocate entries for better performance
-        let mut entries_to_insert = Vec::with_capacity(doc.chunks.len());
-        for (chunk, embedding) in doc.chunks.iter().zip(embeddings.iter()) {
-            entries_to_insert.push(VectorEntry::new(
-                file_path_str.to_string(),
-                chunk.chunk_index,
-                embedding.clone(),
-                chunk.text.clone(),
-                chunk.context.clone(),
-                chunk.start_line,
-                chunk.end_line,
-            ));
-        }
+        // Shared pipeline: parse, embed (model is already initialized and passed in) and store
+        let indexed = index_file(path, file_path_str, config, model, vector_store)?;
 
-        // Insert all entries
-        for (i, entry) in entries_to_insert.iter().enumerate() {
-            if let Err(e) = vector_store.insert(entry) {
-                eprintln!("  ⚠ Warning: Failed to store vector for chunk {}: {}", i, e);
-            }
+        tracing::info!("✓ Indexed: {} ({} chunks)", file_path_str, indexed.chunks_stored);
+        if indexed.truncated() {
+            tracing::warn!(
+                "{} exceeded max_chunks_per_file; {} chunks were not indexed",
+                file_path_str, indexed.chunks_dropped
+            );
+        }
+        if let Some(error) = &indexed.frontmatter_error {
+            tracing::warn!(
+                "{} has invalid frontmatter (metadata ignored): {}",
+                file_path_str, error
+            );
         }
-
-        println!("  ✓ Indexed: {} ({} chunks)", file_path_str, doc.chunks.len());
         Ok(())
     }
 }
@@ -0,0 +1,151 @@
+//! Status file kept by `notes2vec watch` for monitoring (`notes2vec watch --status`).
+//!
+//! The watcher rewrites it after every batch and every [`STATUS_INTERVAL`] while idle, so a
+//! stale `updated_at` means the watcher is gone or stuck. Writes go to a temporary file that
+//! is renamed over the old one, so readers never see half a file. A failed write is logged
+//! and otherwise ignored: monitoring must never take the watcher down.
+
+use crate::core::config::Config;
+use crate::core::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Status file inside the base directory
+pub const STATUS_FILE: &str = "watch-status.json";
+/// How often an idle watcher rewrites its status
+pub const STATUS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What a running watcher has been doing; times are Unix seconds
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchStatus {
+    pub pid: u32,
+    /// Notes directory being watched
+    pub root: PathBuf,
+    pub started_at: u64,
+    /// Last time the file was written
+    pub updated_at: u64,
+    /// Last batch of file changes, if any arrived yet
+    pub last_event_at: Option<u64>,
+    pub indexed: u64,
+    pub removed: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+impl WatchStatus {
+    /// Status of a watcher in this process starting now
+    pub fn new(root: &Path) -> Self {
+        let now = unix_now();
+        Self {
+            pid: std::process::id(),
+            root: root.to_path_buf(),
+            started_at: now,
+            updated_at: now,
+            last_event_at: None,
+            indexed: 0,
+            removed: 0,
+            failed: 0,
+            last_error: None,
+        }
+    }
+
+    /// Note that a batch of changes arrived
+    pub fn record_event(&mut self) {
+        self.last_event_at = Some(unix_now());
+    }
+
+    /// Count a failure and keep its message
+    pub fn record_failure(&mut self, error: impl ToString) {
+        self.failed += 1;
+        self.last_error = Some(error.to_string());
+    }
+
+    /// Write the status to `path` atomically (temporary file, then rename)
+    pub fn write(&mut self, path: &Path) -> Result<()> {
+        self.updated_at = unix_now();
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Config(format!("Failed to serialize watch status: {}", e)))?;
+        let tmp_path = path.with_extension("json.tmp");
+        let written = std::fs::File::create(&tmp_path).and_then(|mut file| {
+            file.write_all(json.as_bytes())?;
+            file.write_all(b"\n")?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| std::fs::rename(&tmp_path, path)) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// [`write`](Self::write), logging instead of failing
+    pub fn save(&mut self, path: &Path) {
+        if let Err(e) = self.write(path) {
+            tracing::warn!("Could not write watch status to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Status last written to `path`; None when no watcher has written one
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| Error::Config(format!("Unreadable watch status in {}: {}", path.display(), e)))
+    }
+}
+
+/// Where the watcher for `config`'s collection keeps its status
+pub fn status_path(config: &Config) -> PathBuf {
+    // Next to the collection's database, like the socket, so each collection's watcher has one
+    config.database_dir.parent().unwrap_or(&config.base_dir).join(STATUS_FILE)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_status_round_trips_and_replaces_the_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(STATUS_FILE);
+        assert_eq!(WatchStatus::read(&path).unwrap(), None);
+
+        let mut status = WatchStatus::new(Path::new("/notes"));
+        status.write(&path).unwrap();
+        status.record_event();
+        status.indexed = 3;
+        status.record_failure("disk on fire");
+        status.write(&path).unwrap();
+
+        let read = WatchStatus::read(&path).unwrap().unwrap();
+        assert_eq!(read, status);
+        assert_eq!(read.pid, std::process::id());
+        assert_eq!((read.indexed, read.failed), (3, 1));
+        assert_eq!(read.last_error.as_deref(), Some("disk on fire"));
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn test_failed_save_is_not_fatal() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing").join(STATUS_FILE);
+        let mut status = WatchStatus::new(temp_dir.path());
+        assert!(status.write(&path).is_err());
+        status.save(&path);
+        assert!(!path.exists());
+    }
+}
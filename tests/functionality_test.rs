@@ -257,3 +257,277 @@ fn test_config_edge_cases() -> Result<()> {
     Ok(())
 }
 
+
+/// The shared, batched pipeline stores what the old per-path indexing loops embedded:
+/// the TUI joined context and text with a newline, `index` and watch embedded the text alone
+#[test]
+fn test_indexing_paths_produce_identical_embeddings() -> Result<()> {
+    use notes2vec::indexing::pipeline::index_file;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let notes_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(&notes_dir)?;
+    let note = notes_dir.join("meeting.md");
+    fs::write(
+        &note,
+        "# Meeting\n\n## Agenda\n\nDiscuss the quarterly roadmap and hiring plan.\n\n## Notes\n\nBudget review moves to Friday.\n",
+    )?;
+
+    let config = Config::new(Some(base_dir))?;
+    config.init()?;
+    let model = EmbeddingModel::init(&config)?;
+    let doc = parse_markdown_file(&note)?;
+    assert!(doc.chunks.len() > 1);
+
+    let assert_close = |stored: &[notes2vec::VectorEntry], expected: &[Vec<f32>]| {
+        assert_eq!(stored.len(), expected.len());
+        for (entry, expected) in stored.iter().zip(expected) {
+            assert_eq!(entry.embedding.len(), expected.len());
+            for (a, b) in entry.embedding.iter().zip(expected) {
+                assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+            }
+        }
+    };
+
+    // Default settings reproduce the TUI's old recipe
+    let tui_store = VectorStore::open(&config)?;
+    index_file(&note, "meeting.md", &config, &model, &tui_store)?;
+    let tui_texts: Vec<String> = doc
+        .chunks
+        .iter()
+        .map(|c| {
+            if c.context.trim().is_empty() {
+                c.text.clone()
+            } else {
+                format!("{}\n{}", c.context, c.text)
+            }
+        })
+        .collect();
+    assert_close(&tui_store.get_file_vectors("meeting.md")?, &model.embed_passages(&tui_texts)?);
+    drop(tui_store);
+
+    // Without embed_context it reproduces the CLI and watch loops
+    let mut cli_config = config.clone();
+    cli_config.embed_context = false;
+    cli_config.database_dir = temp_dir.path().join("cli_database");
+    let cli_store = VectorStore::open(&cli_config)?;
+    index_file(&note, "meeting.md", &cli_config, &model, &cli_store)?;
+    let cli_texts: Vec<String> = doc.chunks.iter().map(|c| c.text.clone()).collect();
+    assert_close(&cli_store.get_file_vectors("meeting.md")?, &model.embed_passages(&cli_texts)?);
+
+    Ok(())
+}
+
+/// Notes that are only frontmatter still get one searchable chunk
+#[test]
+fn test_frontmatter_only_note_is_searchable() -> Result<()> {
+    use notes2vec::indexing::parser::ChunkKind;
+    use notes2vec::indexing::pipeline::index_file;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let notes_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(&notes_dir)?;
+    let stub = notes_dir.join("stub.md");
+    fs::write(
+        &stub,
+        "---\ntitle: Sourdough bread recipe\ntags: [baking, cooking]\nsource: https://example.com/bread\n---\n",
+    )?;
+    let other = notes_dir.join("other.md");
+    fs::write(&other, "# Servers\n\nRotate the TLS certificates on the load balancer every quarter.\n")?;
+
+    let config = Config::new(Some(base_dir))?;
+    config.init()?;
+    let model = EmbeddingModel::init(&config)?;
+    let vector_store = VectorStore::open(&config)?;
+
+    assert_eq!(index_file(&stub, "stub.md", &config, &model, &vector_store)?.chunks_stored, 1);
+    index_file(&other, "other.md", &config, &model, &vector_store)?;
+
+    let query = model.embed_queries(&["how to bake bread".to_string()])?;
+    let results = vector_store.search(&query[0], 1)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0.file_path, "stub.md");
+    assert_eq!(results[0].0.kind, ChunkKind::Metadata);
+
+    Ok(())
+}
+
+/// A file with more chunks than `max_chunks_per_file` stores exactly the cap and reports it
+#[test]
+fn test_max_chunks_per_file_caps_storage() -> Result<()> {
+    use notes2vec::indexing::pipeline::index_file;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let notes_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(&notes_dir)?;
+
+    let mut content = String::from("# Huge\n\n");
+    for i in 0..12 {
+        content.push_str(&format!("## Section {}\n\nParagraph number {} with some text.\n\n", i, i));
+    }
+    let huge = notes_dir.join("huge.md");
+    fs::write(&huge, content)?;
+
+    let mut config = Config::new(Some(base_dir))?;
+    config.init()?;
+    config.max_chunks_per_file = Some(5);
+    let model = EmbeddingModel::init(&config)?;
+    let vector_store = VectorStore::open(&config)?;
+
+    let indexed = index_file(&huge, "huge.md", &config, &model, &vector_store)?;
+    assert_eq!(indexed.chunks_stored, 5);
+    assert!(indexed.truncated());
+    assert_eq!(vector_store.get_file_vectors("huge.md")?.len(), 5);
+
+    Ok(())
+}
+
+/// Invalid frontmatter is reported by the pipeline, and the body is indexed without its metadata
+#[test]
+fn test_invalid_frontmatter_is_reported_and_body_indexed() -> Result<()> {
+    use notes2vec::indexing::pipeline::index_file;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let notes_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(&notes_dir)?;
+
+    let note = notes_dir.join("typo.md");
+    fs::write(&note, "---\ntitle: Typo\ntags: [work\n---\n\n# Standup\n\nDiscussed the release checklist.\n")?;
+
+    let config = Config::new(Some(base_dir))?;
+    config.init()?;
+    let model = EmbeddingModel::init(&config)?;
+    let vector_store = VectorStore::open(&config)?;
+
+    let indexed = index_file(&note, "typo.md", &config, &model, &vector_store)?;
+    assert!(indexed.frontmatter_error.is_some());
+    assert!(indexed.chunks_stored > 0);
+
+    let entries = vector_store.get_file_vectors("typo.md")?;
+    assert!(entries.iter().any(|e| e.text.contains("release checklist")));
+    assert!(entries.iter().all(|e| e.tags.is_empty()));
+
+    Ok(())
+}
+
+/// A file of one-word bullets is flagged low-content and doesn't crowd out real notes
+#[test]
+fn test_stub_bullets_do_not_dominate_results() -> Result<()> {
+    use notes2vec::indexing::pipeline::index_file;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let notes_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(&notes_dir)?;
+
+    let stubs = notes_dir.join("stubs.md");
+    fs::write(&stubs, "## Today\n\n- milk\n\n## Later\n\n- call\n\n## Someday\n\n- gym\n")?;
+    let garden = notes_dir.join("garden.md");
+    fs::write(
+        &garden,
+        "# Garden\n\nTomatoes need full sun, regular watering and a stake once the first trusses form.\n",
+    )?;
+
+    let config = Config::new(Some(base_dir))?;
+    config.init()?;
+    let model = EmbeddingModel::init(&config)?;
+    let vector_store = VectorStore::open(&config)?;
+    index_file(&stubs, "stubs.md", &config, &model, &vector_store)?;
+    index_file(&garden, "garden.md", &config, &model, &vector_store)?;
+
+    // Stubs are still stored for previews, just flagged
+    let stub_vectors = vector_store.get_file_vectors("stubs.md")?;
+    assert!(!stub_vectors.is_empty());
+    assert!(stub_vectors.iter().all(|v| v.low_content));
+
+    let query = model.embed_queries(&["how often should I water tomato plants".to_string()])?;
+    let results = vector_store.search(&query[0], 5)?;
+    assert_eq!(results[0].0.file_path, "garden.md");
+    for (entry, similarity) in results.iter().filter(|(e, _)| e.file_path == "stubs.md") {
+        assert!(*similarity < results[0].1, "stub chunk {:?} outranked a real note", entry.text);
+    }
+
+    Ok(())
+}
+
+/// Search opens the index read-only, so it works once base_dir can no longer be written
+#[cfg(unix)]
+#[test]
+fn test_search_works_on_read_only_base_dir() -> Result<()> {
+    use notes2vec::indexing::pipeline::index_file;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    fn set_read_only(path: &Path, read_only: bool) -> std::io::Result<()> {
+        let is_dir = path.is_dir();
+        if is_dir && !read_only {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        }
+        if is_dir {
+            for entry in fs::read_dir(path)? {
+                set_read_only(&entry?.path(), read_only)?;
+            }
+        }
+        let mode = match (is_dir, read_only) {
+            (true, true) => 0o555,
+            (true, false) => 0o755,
+            (false, true) => 0o444,
+            (false, false) => 0o644,
+        };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let notes_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(&notes_dir)?;
+    let note = notes_dir.join("bread.md");
+    fs::write(&note, "# Bread\n\nSourdough needs a lively starter and a long, cool overnight proof.\n")?;
+
+    let config = Config::new(Some(base_dir.clone()))?;
+    config.init()?;
+    {
+        let model = EmbeddingModel::init(&config)?;
+        let vector_store = VectorStore::open(&config)?;
+        index_file(&note, "bread.md", &config, &model, &vector_store)?;
+    }
+
+    set_read_only(&base_dir, true)?;
+    let db_path = config.database_dir.join("vectors.redb");
+    let before = fs::read(&db_path)?;
+
+    let result = (|| -> Result<Vec<(notes2vec::VectorEntry, f32)>> {
+        let vector_store = VectorStore::open_read_only(&config)?;
+        let model = EmbeddingModel::init(&config)?;
+        let query = model.embed_queries(&["how do I bake sourdough".to_string()])?;
+        vector_store.search(&query[0], 5)
+    })();
+    let after = fs::read(&db_path)?;
+    set_read_only(&base_dir, false)?;
+
+    let results = result?;
+    assert_eq!(results[0].0.file_path, "bread.md");
+    assert_eq!(before, after, "search must not modify the index");
+
+    Ok(())
+}
+
+/// Warming up a loaded model runs a real embedding and leaves the model usable
+#[test]
+fn test_warmup_on_loaded_model() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let config = Config::new(Some(temp_dir.path().join("test_notes2vec")))?;
+    config.init()?;
+    let model = EmbeddingModel::init(&config)?;
+
+    model.warmup()?;
+    model.warmup()?;
+    let embeddings = model.embed_queries(&["after warmup".to_string()])?;
+    assert_eq!(embeddings.len(), 1);
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use notes2vec::{Config, discover_files, Result};
 use notes2vec::EmbeddingModel;
-use notes2vec::indexing::parser::parse_markdown_file;
+use notes2vec::indexing::parser::{parse_markdown_file, parse_note_file};
 use notes2vec::{StateStore, calculate_file_hash, get_file_modified_time};
 use notes2vec::VectorStore;
 use std::fs;
@@ -171,3 +171,77 @@ fn test_multiple_files_indexing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_real_mdx_doc_page() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let notes_dir = temp_dir.path().join("docs");
+    fs::create_dir_all(&notes_dir)?;
+
+    // A docs-site page as written for Nextra/Docusaurus-style MDX
+    let page = r#"---
+title: Getting started
+sidebar_position: 1
+---
+import { Callout, Tabs } from 'nextra/components'
+import InstallCommand from '../components/InstallCommand'
+export const meta = {
+  description: 'Install the CLI and index a folder'
+}
+
+# Getting started
+
+Install the command line tool with your package manager, then point it at a folder of notes.
+
+<Callout type="warning" emoji="⚠️">
+  The first run downloads the embedding model, which needs a network connection.
+</Callout>
+
+<Tabs items={['macOS', 'Linux']} defaultIndex={0}>
+  <Tabs.Tab>
+    Homebrew installs the binary and keeps it up to date.
+  </Tabs.Tab>
+  <Tabs.Tab>
+    Cargo builds it from source on any distribution.
+  </Tabs.Tab>
+</Tabs>
+
+{/* TODO: screenshot of the first index run */}
+
+## Indexing a folder
+
+Run the index command once; later runs only embed files that changed. <InstallCommand />
+
+```jsx
+<Callout>Code samples keep their tags.</Callout>
+```
+"#;
+    let page_path = notes_dir.join("getting-started.mdx");
+    fs::write(&page_path, page)?;
+
+    let files = discover_files(&notes_dir)?;
+    assert_eq!(files.len(), 1);
+
+    let doc = parse_note_file(&page_path)?;
+    assert_eq!(doc.metadata.title.as_deref(), Some("Getting started"));
+    assert_eq!(doc.title, "Getting started");
+    let text: Vec<&str> = doc.chunks.iter().map(|c| c.text.as_str()).collect();
+    let all = text.join("\n");
+    for prose in ["Install the command line tool", "downloads the embedding model", "Homebrew installs", "Cargo builds", "only embed files that changed"] {
+        assert!(all.contains(prose), "missing {:?} in {:?}", prose, text);
+    }
+    for chunk in &doc.chunks {
+        if chunk.text.contains("Code samples") {
+            continue;
+        }
+        for jsx in ["<", "/>", "import ", "export ", "{/*", "nextra", "defaultIndex"] {
+            assert!(!chunk.text.contains(jsx), "{:?} in {:?}", jsx, chunk.text);
+        }
+        // Offsets still point into the file as written
+        let source = &page[chunk.start_offset..chunk.end_offset];
+        let first_sentence = chunk.text.split(". ").next().unwrap();
+        assert!(source.starts_with(first_sentence), "{:?} vs {:?}", source, first_sentence);
+    }
+    assert!(all.contains("<Callout>Code samples keep their tags.</Callout>"));
+
+    Ok(())
+}
@@ -1,10 +1,10 @@
 use notes2vec::{Config, Result};
-use notes2vec::{VectorEntry, VectorStore};
+use notes2vec::{Error, VectorEntry, VectorStore};
 use notes2vec::{StateStore, calculate_file_hash, get_file_modified_time};
 use notes2vec::discover_files;
 use notes2vec::indexing::parser::parse_markdown;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 
 /// Test VectorEntry serialization and deserialization
@@ -32,6 +32,127 @@ fn test_vector_entry_roundtrip() -> Result<()> {
     Ok(())
 }
 
+/// NOTES2VEC_HOME takes precedence over XDG_DATA_HOME, which takes precedence over the home directory
+#[test]
+fn test_notes2vec_home_overrides_base_dir() -> Result<()> {
+    let custom = PathBuf::from("/srv/custom_home");
+    let data_home = PathBuf::from("/srv/data");
+    let home = PathBuf::from("/home/someone");
+
+    assert_eq!(
+        Config::base_dir_from(Some(custom.clone()), Some(data_home.clone()), Some(home.clone())),
+        Some(custom)
+    );
+    assert_eq!(
+        Config::base_dir_from(None, Some(data_home.clone()), Some(home.clone())),
+        Some(data_home.join("notes2vec"))
+    );
+    assert_eq!(Config::base_dir_from(None, None, Some(home.clone())), Some(home.join(".notes2vec")));
+    assert_eq!(Config::base_dir_from(None, None, None), None);
+
+    // An explicit base dir is used as given
+    let temp_dir = TempDir::new().unwrap();
+    let explicit = temp_dir.path().join("explicit");
+    assert_eq!(Config::new(Some(explicit.clone()))?.base_dir, explicit);
+
+    Ok(())
+}
+
+/// `config set` persists to config.toml and `Config::load` reflects it
+#[test]
+fn test_config_set_persists() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+
+    let config = Config::load(Some(base_dir.clone()))?;
+    config.persist_setting("model_id", "sentence-transformers/all-MiniLM-L6-v2")?;
+    config.persist_setting("max_chunks_per_file", "0")?;
+
+    let reloaded = Config::load(Some(base_dir.clone()))?;
+    assert_eq!(reloaded.model_id, "sentence-transformers/all-MiniLM-L6-v2");
+    assert_eq!(reloaded.max_chunks_per_file, None);
+    let shown = reloaded.settings();
+    assert!(shown.contains(&("model_id", "\"sentence-transformers/all-MiniLM-L6-v2\"".to_string())));
+
+    // The non-default model gets its own cache directory
+    assert_ne!(reloaded.model_dir(), reloaded.models_dir);
+
+    // "auto" drops the key again
+    reloaded.persist_setting("model_id", "auto")?;
+    let reset = Config::load(Some(base_dir))?;
+    assert_eq!(reset.model_id, Config::new(None)?.model_id);
+    assert_eq!(reset.model_dir(), reset.models_dir);
+
+    Ok(())
+}
+
+/// `ignore_dirs` is a comma-separated list on the command line and a TOML array in the file
+#[test]
+fn test_config_set_ignore_dirs() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+
+    let config = Config::load(Some(base_dir.clone()))?;
+    assert!(config.ignore_dirs.contains(&"node_modules".to_string()));
+    config.persist_setting("ignore_dirs", "node_modules, templates")?;
+    let reloaded = Config::load(Some(base_dir.clone()))?;
+    assert_eq!(reloaded.ignore_dirs, vec!["node_modules", "templates"]);
+
+    assert!(matches!(reloaded.persist_setting("ignore_dirs", "notes/templates"), Err(Error::Config(_))));
+    reloaded.persist_setting("ignore_dirs", "")?;
+    assert!(Config::load(Some(base_dir))?.ignore_dirs.is_empty());
+
+    Ok(())
+}
+
+/// Unknown keys and mistyped values are rejected without touching config.toml
+#[test]
+fn test_config_set_rejects_invalid() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let config = Config::load(Some(temp_dir.path().join("test_notes2vec")))?;
+
+    assert!(matches!(config.persist_setting("no_such_key", "1"), Err(Error::Config(_))));
+    assert!(matches!(config.persist_setting("raw_scores", "maybe"), Err(Error::Config(_))));
+    assert!(matches!(config.persist_setting("max_chunks_per_file", "-3"), Err(Error::Config(_))));
+    assert!(!config.config_file().exists());
+
+    // A hand-edited file with an unknown key fails to load
+    fs::create_dir_all(&config.base_dir).unwrap();
+    fs::write(config.config_file(), "colour = \"blue\"\n").unwrap();
+    assert!(matches!(Config::load(Some(config.base_dir.clone())), Err(Error::Config(_))));
+
+    Ok(())
+}
+
+/// `[collections.<name>]` tables set each collection's path and search weight
+#[test]
+fn test_collection_tables_load() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    fs::create_dir_all(&base_dir).unwrap();
+    let config_file = base_dir.join("config.toml");
+    fs::write(
+        &config_file,
+        "[collections.work]\npath = \"/home/me/work-notes\"\n\n[collections.archive]\nweight = 0.5\n",
+    )
+    .unwrap();
+
+    let config = Config::load(Some(base_dir.clone()))?;
+    assert_eq!(config.collection_weight("archive"), 0.5);
+    assert_eq!(config.collection_weight("work"), 1.0);
+    // Unconfigured collections weigh the same as configured ones without a weight
+    assert_eq!(config.collection_weight("personal"), 1.0);
+    let work = config.with_collection("work")?;
+    assert_eq!(work.collection_path(), Some(Path::new("/home/me/work-notes")));
+
+    fs::write(&config_file, "[collections.archive]\nweight = 0\n").unwrap();
+    assert!(matches!(Config::load(Some(base_dir.clone())), Err(Error::Config(_))));
+    fs::write(&config_file, "[collections.archive]\ncolour = \"grey\"\n").unwrap();
+    assert!(matches!(Config::load(Some(base_dir)), Err(Error::Config(_))));
+
+    Ok(())
+}
+
 /// Test VectorStore operations
 #[test]
 fn test_vector_store_operations() -> Result<()> {
@@ -72,6 +193,42 @@ fn test_vector_store_operations() -> Result<()> {
     Ok(())
 }
 
+/// Hash-fallback and model vectors must not be ranked together
+#[test]
+fn test_vector_store_refuses_mixed_embedding_sources() -> Result<()> {
+    use notes2vec::storage::vectors::EmbeddingSource;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let config = Config::new(Some(base_dir))?;
+    config.init()?;
+
+    let store = VectorStore::open(&config)?;
+    let model_entry = VectorEntry::new("real.md".to_string(), 0, vec![1.0, 0.0], "Real".to_string(), String::new(), 1, 1);
+    store.insert(&model_entry)?;
+
+    // Model-only store searches normally
+    assert_eq!(store.search(&[1.0, 0.0], 5)?.len(), 1);
+
+    let hash_entry = VectorEntry::new("stale.md".to_string(), 0, vec![0.0, 1.0], "Stale".to_string(), String::new(), 1, 1)
+        .with_embedding_source(EmbeddingSource::Hash);
+    store.insert(&hash_entry)?;
+
+    match store.search(&[1.0, 0.0], 5) {
+        Err(Error::EmbeddingSourceMismatch { found, expected }) => {
+            assert_eq!(found, "hash");
+            assert_eq!(expected, "model");
+        }
+        other => panic!("expected embedding source mismatch, got {:?}", other.map(|r| r.len())),
+    }
+
+    // Reindexing the stale file with the model clears the mismatch
+    store.remove_file("stale.md")?;
+    assert_eq!(store.search(&[1.0, 0.0], 5)?.len(), 1);
+
+    Ok(())
+}
+
 /// Test StateStore file change detection
 #[test]
 fn test_state_store_change_detection() -> Result<()> {
@@ -174,6 +331,47 @@ fn test_file_discovery_edge_cases() -> Result<()> {
     Ok(())
 }
 
+/// `files` lists exactly what discovery returns, in order, then the count
+#[test]
+fn test_files_command_lists_discovered_files() -> Result<()> {
+    use notes2vec::indexing::discovery::{discover_files_with, DiscoveryOptions};
+    use notes2vec::ui::cli::write_discovered_files;
+
+    let temp_dir = TempDir::new().unwrap();
+    let test_dir = temp_dir.path().join("notes");
+    fs::create_dir_all(test_dir.join("work/drafts"))?;
+    fs::create_dir_all(test_dir.join(".obsidian"))?;
+    fs::create_dir_all(test_dir.join("node_modules"))?;
+    fs::write(test_dir.join("inbox.md"), "# Inbox")?;
+    fs::write(test_dir.join("work/plan.md"), "# Plan")?;
+    fs::write(test_dir.join("work/drafts/ideas.txt"), "Ideas")?;
+    fs::write(test_dir.join("work/script.py"), "print()")?;
+    fs::write(test_dir.join(".obsidian/workspace.md"), "# Hidden")?;
+    fs::write(test_dir.join("node_modules/readme.md"), "# Ignored")?;
+
+    let config = Config::new(Some(temp_dir.path().join("base")))?;
+    let files = discover_files_with(&test_dir, DiscoveryOptions::from_config(&config))?;
+    let mut out = Vec::new();
+    write_discovered_files(&mut out, &files, false)?;
+    let text = String::from_utf8(out).unwrap();
+
+    let expected: Vec<String> = discover_files(&test_dir)?
+        .iter()
+        .map(|file| file.relative_path.display().to_string())
+        .collect();
+    assert_eq!(expected.len(), 3);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[..expected.len()], expected);
+    assert_eq!(lines[expected.len()..], ["3 files"]);
+
+    // Quiet leaves just the paths, for piping
+    let mut out = Vec::new();
+    write_discovered_files(&mut out, &files, true)?;
+    assert_eq!(String::from_utf8(out).unwrap().lines().count(), expected.len());
+
+    Ok(())
+}
+
 /// Test file hash calculation
 #[test]
 fn test_file_hash() -> Result<()> {
@@ -213,6 +411,83 @@ fn test_config_initialization() -> Result<()> {
     Ok(())
 }
 
+/// A half-deleted base directory is not initialized, and every missing piece is listed
+#[test]
+fn test_partial_base_dir_is_not_initialized() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let config = Config::new(Some(temp_dir.path().join("test_notes2vec")))?;
+    config.init()?;
+    config.ensure_initialized(true)?;
+
+    fs::remove_dir_all(&config.models_dir)?;
+    fs::remove_dir_all(config.state_path.parent().unwrap())?;
+    assert!(!config.is_initialized());
+    assert_eq!(config.initialization_problems(false).len(), 2);
+    match config.ensure_initialized(false) {
+        Err(Error::Config(msg)) => {
+            assert!(msg.contains("models directory is missing"), "{}", msg);
+            assert!(msg.contains("state directory is missing"), "{}", msg);
+        }
+        other => panic!("expected a config error, got {:?}", other),
+    }
+
+    // init is idempotent and fills in what's missing
+    config.init()?;
+    assert!(config.is_initialized());
+
+    Ok(())
+}
+
+/// Every invalid setting in config.toml is reported at once, including settings that conflict
+#[test]
+fn test_config_load_lists_every_problem() -> Result<()> {
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    fs::create_dir_all(&base_dir).unwrap();
+    let config_file = base_dir.join("config.toml");
+
+    fs::write(&config_file, "raw_scores = \"maybe\"\ncolour = \"blue\"\n").unwrap();
+    match Config::load(Some(base_dir.clone())) {
+        Err(Error::Config(msg)) => {
+            assert!(msg.contains("'raw_scores' must be true or false"), "{}", msg);
+            assert!(msg.contains("Unknown config key 'colour'"), "{}", msg);
+        }
+        other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+    }
+
+    fs::write(&config_file, "confidence_strong = 0.5\nconfidence_likely = 0.7\n").unwrap();
+    assert!(matches!(Config::load(Some(base_dir.clone())), Err(Error::Config(_))));
+
+    // `config set` refuses to write a conflicting value
+    fs::write(&config_file, "score_max = 0.8\n").unwrap();
+    let config = Config::load(Some(base_dir))?;
+    assert!(matches!(config.persist_setting("score_min", "0.9"), Err(Error::Config(_))));
+    config.persist_setting("score_min", "0.3")?;
+
+    Ok(())
+}
+
+/// `config show` marks each setting with where its value came from
+#[test]
+fn test_setting_sources() -> Result<()> {
+    use notes2vec::core::config::SettingSource;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    fs::create_dir_all(&base_dir).unwrap();
+    fs::write(base_dir.join("config.toml"), "min_chunk_chars = 20\n").unwrap();
+
+    let mut config = Config::load(Some(base_dir))?;
+    assert_eq!(config.source("min_chunk_chars"), SettingSource::File);
+    assert_eq!(config.source("preview_lines"), SettingSource::Default);
+    config.set_source("raw_scores", SettingSource::Flag);
+    assert_eq!(config.source("raw_scores"), SettingSource::Flag);
+    config.set_source("no_such_key", SettingSource::Flag);
+    assert_eq!(config.source("no_such_key"), SettingSource::Default);
+
+    Ok(())
+}
+
 /// Test vector store search with empty database
 #[test]
 fn test_vector_store_search_empty() -> Result<()> {
@@ -259,3 +534,232 @@ fn test_state_store_remove_nonexistent() -> Result<()> {
     Ok(())
 }
 
+
+/// Test that quiet search output contains only result lines
+#[test]
+fn test_search_output_quiet_only_results() -> Result<()> {
+    use notes2vec::ui::cli::write_search_results;
+
+    let results = vec![
+        (
+            VectorEntry::new(
+                "rust.md".to_string(),
+                0,
+                vec![1.0, 0.0],
+                "Ownership keeps memory safe".to_string(),
+                "Rust > Ownership".to_string(),
+                3,
+                5,
+            ),
+            0.91,
+        ),
+        (
+            VectorEntry::new(
+                "db.md".to_string(),
+                1,
+                vec![0.0, 1.0],
+                "Indexes speed up queries".to_string(),
+                String::new(),
+                7,
+                9,
+            ),
+            0.42,
+        ),
+    ];
+
+    let mut out = Vec::new();
+    write_search_results(&mut out, "memory", &results, true)?;
+    let text = String::from_utf8(out).unwrap();
+
+    assert!(!text.contains("Searching for"));
+    assert!(!text.contains("Found"));
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let is_result_line = line.starts_with("1. ")
+            || line.starts_with("2. ")
+            || line.starts_with("   Context: ")
+            || line.starts_with("   Preview: ")
+            || line.starts_with("   Lines: ");
+        assert!(is_result_line, "unexpected line in quiet output: {}", line);
+    }
+    assert!(text.contains("1. rust.md (similarity: 0.910)"));
+
+    // Without quiet the header is present
+    let mut out = Vec::new();
+    write_search_results(&mut out, "memory", &results, false)?;
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("Searching for: \"memory\""));
+
+    Ok(())
+}
+
+/// `--explain` adds one score breakdown line per result
+#[test]
+fn test_search_output_explain() -> Result<()> {
+    use notes2vec::search::explain::ScoreBreakdown;
+    use notes2vec::ui::cli::write_search_results_explained;
+
+    let results = vec![(
+        VectorEntry::new("rust.md".to_string(), 0, vec![1.0], "Ownership".to_string(), String::new(), 1, 2),
+        0.75,
+    )];
+    let breakdowns = vec![ScoreBreakdown::new(0.6).with_lexical(0.15)];
+
+    let mut out = Vec::new();
+    write_search_results_explained(&mut out, "ownership", &results, &breakdowns, true)?;
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("   Score: cosine 0.600 + lexical 0.150 + path 0.000 + recency 0.000 + rerank 0.000 = 0.750"));
+
+    Ok(())
+}
+
+/// `--stream` writes one JSON object per line, one line per result
+#[test]
+fn test_search_output_stream() -> Result<()> {
+    use notes2vec::search::explain::ScoreBreakdown;
+    use notes2vec::ui::cli::write_streamed_results;
+
+    let results = vec![
+        (
+            VectorEntry::new("rust.md".to_string(), 0, vec![1.0], "Ownership\nand borrowing".to_string(), "Rust".to_string(), 3, 5),
+            0.91,
+        ),
+        (
+            VectorEntry::new("db \"notes\".md".to_string(), 1, vec![1.0], "Indexes".to_string(), String::new(), 7, 9),
+            0.42,
+        ),
+        (
+            VectorEntry::new("misc.md".to_string(), 0, vec![1.0], "Other".to_string(), String::new(), 1, 1),
+            0.10,
+        ),
+    ];
+    let breakdowns: Vec<ScoreBreakdown> = results.iter().map(|(_, sim)| ScoreBreakdown::new(*sim)).collect();
+
+    let mut out = Vec::new();
+    write_streamed_results(&mut out, &results, &["work", "work", "personal"], &breakdowns, None)?;
+    let text = String::from_utf8(out).unwrap();
+
+    let records: Vec<serde_json::Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is a JSON object"))
+        .collect();
+    assert_eq!(records.len(), results.len());
+    assert_eq!(records[0]["rank"], 1);
+    assert_eq!(records[0]["text"], "Ownership\nand borrowing");
+    assert_eq!(records[1]["file_path"], "db \"notes\".md");
+    assert_eq!(records[2]["collection"], "personal");
+    assert!(records[2]["score"]["cosine"].is_number());
+    assert!(records[0].get("confidence").is_none());
+
+    Ok(())
+}
+
+/// `--files-only` prints raw paths (spaces included) with nothing else on the line
+#[test]
+fn test_search_output_files_only() -> Result<()> {
+    use notes2vec::ui::cli::write_file_paths;
+    use std::path::PathBuf;
+
+    let paths = vec![PathBuf::from("/notes/tax documents/2023.md"), PathBuf::from("/notes/receipts.md")];
+
+    let mut out = Vec::new();
+    write_file_paths(&mut out, &paths, false)?;
+    assert_eq!(String::from_utf8(out).unwrap(), "/notes/tax documents/2023.md\n/notes/receipts.md\n");
+
+    let mut out = Vec::new();
+    write_file_paths(&mut out, &paths, true)?;
+    assert_eq!(out, b"/notes/tax documents/2023.md\0/notes/receipts.md\0");
+
+    Ok(())
+}
+
+/// `--context-lines-before 1 --context-lines-after 3` prints exactly those lines around the
+/// chunk, numbered grep-style and clamped to the file
+#[test]
+fn test_search_output_asymmetric_context() -> Result<()> {
+    use notes2vec::ui::cli::{read_source_lines, write_search_results_in_context, ContextLines};
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("plan.md");
+    let lines: Vec<String> = (1..=12).map(|n| format!("line {}", n)).collect();
+    fs::write(&path, lines.join("\n"))?;
+    let context = ContextLines::from_flags(Some(1), Some(3)).unwrap();
+
+    let entry = VectorEntry::new("plan.md".to_string(), 0, vec![1.0], "line 5\nline 6".to_string(), String::new(), 5, 6);
+    let sources = vec![read_source_lines(&path, entry.start_line, entry.end_line, context)?];
+    let mut out = Vec::new();
+    write_search_results_in_context(&mut out, "plan", &[(entry, 0.8)], &[], &sources, None, true)?;
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "1. plan.md (similarity: 0.800)\n\
+         \x20  4- line 4\n\
+         \x20  5: line 5\n\
+         \x20  6: line 6\n\
+         \x20  7- line 7\n\
+         \x20  8- line 8\n\
+         \x20  9- line 9\n\
+         \x20  Lines: 5-6\n"
+    );
+
+    // Clamped at both ends of the file
+    let numbers = |start, end| -> Result<Vec<usize>> {
+        Ok(read_source_lines(&path, start, end, context)?.iter().map(|line| line.number).collect())
+    };
+    assert_eq!(numbers(1, 1)?, vec![1, 2, 3, 4]);
+    assert_eq!(numbers(11, 12)?, vec![10, 11, 12]);
+    // A chunk past the end of a file that has since shrunk has nothing to show
+    assert!(numbers(20, 22)?.is_empty());
+    assert_eq!(ContextLines::from_flags(None, Some(2)), Some(ContextLines { before: 0, after: 2 }));
+    assert_eq!(ContextLines::from_flags(None, None), None);
+
+    Ok(())
+}
+
+/// Encrypted stores keep values unreadable on disk and reject a wrong passphrase
+#[test]
+fn test_encrypted_vector_store() -> Result<()> {
+    use notes2vec::storage::crypto::PASSPHRASE_ENV;
+
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("test_notes2vec");
+    let mut config = Config::new(Some(base_dir))?;
+    config.init()?;
+    config.encrypt = true;
+
+    std::env::set_var(PASSPHRASE_ENV, "correct horse battery staple");
+    {
+        let store = VectorStore::open(&config)?;
+        let entry = VectorEntry::new(
+            "diary.md".to_string(),
+            0,
+            vec![1.0, 0.0],
+            "extremely private diary entry".to_string(),
+            String::new(),
+            1,
+            1,
+        );
+        store.insert(&entry)?;
+        assert_eq!(store.get("diary.md:0")?.unwrap().text, "extremely private diary entry");
+    }
+
+    let raw = fs::read(config.database_dir.join("vectors.redb"))?;
+    let needle = b"extremely private";
+    assert!(!raw.windows(needle.len()).any(|w| w == needle));
+
+    // Wrong passphrase fails when opening, not on first read
+    std::env::set_var(PASSPHRASE_ENV, "wrong");
+    assert!(matches!(VectorStore::open(&config), Err(Error::Encryption(_))));
+
+    // Encryption is detected from the store even when the config doesn't ask for it
+    config.encrypt = false;
+    std::env::set_var(PASSPHRASE_ENV, "correct horse battery staple");
+    let store = VectorStore::open(&config)?;
+    let results = store.search(&[1.0, 0.0], 5)?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0.file_path, "diary.md");
+    drop(store);
+
+    std::env::remove_var(PASSPHRASE_ENV);
+    assert!(matches!(VectorStore::open(&config), Err(Error::Encryption(_))));
+
+    Ok(())
+}